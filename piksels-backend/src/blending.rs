@@ -1,5 +1,6 @@
 /// Blending equation. Used to state how blending factors and pixel data should be blended.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Equation {
   /// `Additive` represents the following blending equation:
   ///
@@ -31,6 +32,7 @@ pub enum Equation {
 /// Blending factors. Pixel data are multiplied by these factors to achieve several effects driven
 /// by *blending equations*.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Factor {
   /// `1 * color = color`
   One,
@@ -68,6 +70,7 @@ pub enum Factor {
 
 /// Basic blending configuration.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Blending {
   /// Blending equation to use.
   pub equation: Equation,
@@ -81,6 +84,7 @@ pub struct Blending {
 
 /// Blending configuration to represent combined or separate options.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BlendingMode {
   /// Blending is disabled.
   Off,
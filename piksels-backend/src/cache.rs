@@ -96,6 +96,11 @@ where
     self.0.is_some()
   }
 
+  /// Cached value, if any.
+  pub fn get(&self) -> Option<&T> {
+    self.0.as_ref()
+  }
+
   /// Check whether the cached value is invalid regarding a value.
   ///
   /// A value is invalid if it was never set, or if it’s different from the parameter one.
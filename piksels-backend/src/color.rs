@@ -2,6 +2,7 @@ macro_rules! mk_color_type {
   ($ty:ident : $field_ty:ty, $($field_name:ident),*) => {
     #[repr(C)]
     #[derive(Clone, Debug, PartialEq)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     pub struct $ty {
       $(
         pub $field_name: $field_ty
@@ -1,6 +1,7 @@
 /// Comparison to perform for depth / stencil operations. `a` is the incoming fragment’s data and b is the fragment’s
 /// data that is already stored.
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Comparison {
   /// Test never succeeds.
   Never,
@@ -24,6 +25,7 @@ pub enum Comparison {
 ///
 /// If you disable depth test, fragments will always be blended, whatever the order in which they are written.
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DepthTest {
   /// Depth test is disabled.
   Off,
@@ -34,6 +36,7 @@ pub enum DepthTest {
 
 /// Depth write mode.
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DepthWrite {
   /// Will write depth data.
   On,
@@ -47,6 +50,7 @@ pub enum DepthWrite {
 ///
 /// If you disable depth test, fragments will always be blended, whatever the order in which they are written.
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum StencilTest {
   /// Stencil test is disabled.
   Off,
@@ -56,6 +60,7 @@ pub enum StencilTest {
 }
 
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StencilFunc {
   /// Comparison to apply to make a fragment pass the test.
   comparison: Comparison,
@@ -78,6 +83,7 @@ pub struct StencilFunc {
 
 /// Possible stencil operations.
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum StencilOp {
   /// Keep the current value.
   Keep,
@@ -2,6 +2,8 @@ use std::sync::PoisonError;
 
 use thiserror::Error;
 
+use crate::{render_targets::RenderTargetsError, shader::UniformType};
+
 /// Backend common errors.
 ///
 /// Backend errors are specific for each technology they wrap. However, they are some overlapping kind of errors that
@@ -11,11 +13,128 @@ pub enum Error {
   #[error("no more units available on device")]
   NoMoreUnits,
 
+  #[error("no more units available on device; units in use: {owners}")]
+  NoMoreUnitsInUse { owners: String },
+
   #[error("thread is poisoned")]
   PoisonedThread,
 
+  #[error("incompatible depth/stencil attachment: {reason}")]
+  IncompatibleDepthStencilAttachment { reason: String },
+
+  #[error("incomplete render targets: {0}")]
+  IncompleteRenderTargets(RenderTargetsError),
+
+  #[error("invalid render targets resize: {reason}")]
+  InvalidRenderTargetsResize { reason: String },
+
+  #[error("invalid attachment point: {reason}")]
+  InvalidAttachmentPoint { reason: String },
+
+  #[error("invalid readback: {reason}")]
+  InvalidReadback { reason: String },
+
+  #[error("attachment cannot be sampled: {reason}")]
+  NonSampleableAttachment { reason: String },
+
+  #[error("incompatible resolve: {reason}")]
+  IncompatibleResolve { reason: String },
+
+  #[error("invalid blit: {reason}")]
+  InvalidBlit { reason: String },
+
+  #[error("stale render targets: {reason}")]
+  StaleRenderTargets { reason: String },
+
+  #[error("invalid swap chain: {reason}")]
+  InvalidSwapChain { reason: String },
+
+  /// The swap chain doesn’t match its surface anymore, e.g. because the window was resized or minimized.
+  ///
+  /// Resize the swap chain to recover.
+  #[error("swap chain out of date")]
+  SwapChainOutOfDate,
+
+  /// The surface of the swap chain is gone, e.g. because the display it was on was disconnected.
+  ///
+  /// The swap chain cannot be used anymore; drop it and create a new one to recover.
+  #[error("surface lost")]
+  SurfaceLost,
+
+  #[error("invalid swap chain acquire: {reason}")]
+  InvalidAcquire { reason: String },
+
+  #[error("invalid present: {reason}")]
+  InvalidPresent { reason: String },
+
+  #[error("cannot apply coordinate convention: {reason}")]
+  CoordinateConvention { reason: String },
+
+  #[error("invalid vertex data: {reason}")]
+  InvalidVertexData { reason: String },
+
+  #[error("invalid texture levels: {reason}")]
+  InvalidTextureLevels { reason: String },
+
+  #[error("invalid texels upload: {reason}")]
+  InvalidTexelsUpload { reason: String },
+
+  #[error("invalid vertex array update: {reason}")]
+  InvalidVertexArrayUpdate { reason: String },
+
+  #[error("streaming overflow: {reason}")]
+  StreamingOverflow { reason: String },
+
+  #[error("invalid vertex array view: {reason}")]
+  InvalidVertexArrayView { reason: String },
+
+  #[error("invalid storage buffer range: {reason}")]
+  InvalidStorageBufferRange { reason: String },
+
+  #[error("invalid map range: {reason}")]
+  InvalidMapRange { reason: String },
+
+  #[error("invalid trace: {reason}")]
+  InvalidTrace { reason: String },
+
+  #[error("video export error: {reason}")]
+  VideoExport { reason: String },
+
+  #[error("headless loop error: {reason}")]
+  HeadlessLoop { reason: String },
+
   #[error("extension check failed: {reason}")]
   ExtensionCheck { reason: String },
+
+  #[error("invalid shader sources: {reason}")]
+  InvalidShaderSources { reason: String },
+
+  #[error("shader preprocessing error: {reason}")]
+  ShaderPreprocessing { reason: String },
+
+  #[error("shader translation error: {reason}")]
+  ShaderTranslation { reason: String },
+
+  #[error("uniform type mismatch: expected {expected:?}, got {got:?}")]
+  UniformTypeMismatch {
+    expected: UniformType,
+    got: UniformType,
+  },
+
+  #[error("invalid uniform array range: {reason}")]
+  InvalidUniformArrayRange { reason: String },
+
+  #[error("uniform block layout mismatch: {reason}")]
+  UniformBlockLayoutMismatch { reason: String },
+
+  #[error("invalid frame: {reason}")]
+  InvalidFrame { reason: String },
+
+  #[error("invalid command buffer execution: {reason}")]
+  InvalidExecute { reason: String },
+
+  #[error("recording budget exceeded: {reason}")]
+  RecordingBudgetExceeded { reason: String },
 }
 
 impl<T> From<PoisonError<T>> for Error {
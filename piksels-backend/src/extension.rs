@@ -1,20 +1,127 @@
-use self::logger::LoggerExt;
+//! Backend extensions.
+//!
+//! Extensions are optional capabilities backends might implement. Some of them are configured when building a backend
+//! (e.g. [`LoggerExt`]), others are exposed as traits backends implement (e.g. `BackendReadback`). Either way, they
+//! are negotiated: [`Extensions`] lists the extensions requested when building a backend, and the backend accepts the
+//! ones it supports. The extensions left unaccepted are rejected.
 
+use std::collections::BTreeSet;
+
+use self::logger::{Logger, LoggerExt};
+
+#[cfg(feature = "ext-frame-stats")]
+pub mod frame_stats;
 #[cfg(feature = "ext-logger")]
 pub mod logger;
+#[cfg(feature = "ext-profiler")]
+pub mod profiler;
+#[cfg(feature = "ext-readback")]
+pub mod readback;
+#[cfg(feature = "ext-sparse-texture")]
+pub mod sparse_texture;
+#[cfg(feature = "ext-trace")]
+pub mod trace;
+
+/// Kind of extension.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ExtensionKind {
+  FrameStats,
+  Logger,
+  Profiler,
+  Readback,
+  SparseTexture,
+  Trace,
+}
 
-pub struct ExtensionsBuilder<ExtLogger> {
-  pub logger: ExtLogger,
+/// Extensions requested when building a backend.
+///
+/// Backends accept the extensions they support with [`Extensions::accept`] or [`Extensions::accept_logger`] while
+/// being built; [`Extensions::negotiated`] then reports which extensions were accepted and which were rejected.
+#[derive(Default)]
+pub struct Extensions {
+  requested: BTreeSet<ExtensionKind>,
+  accepted: BTreeSet<ExtensionKind>,
+  logger: Option<LoggerExt<Box<dyn 'static + Logger + Send + Sync>>>,
 }
 
-impl Default for ExtensionsBuilder<()> {
-  fn default() -> Self {
-    Self { logger: () }
+impl Extensions {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Request an extension.
+  ///
+  /// Requesting [`ExtensionKind::Logger`] this way is pointless, since it needs a logger; use [`Extensions::logger`].
+  pub fn with(mut self, kind: ExtensionKind) -> Self {
+    self.requested.insert(kind);
+    self
+  }
+
+  /// Request the logger extension, replacing any previously requested logger.
+  ///
+  /// Loggers must be thread-safe, since backends can be shared across threads.
+  pub fn logger(mut self, logger: LoggerExt<impl 'static + Logger + Send + Sync>) -> Self {
+    self.requested.insert(ExtensionKind::Logger);
+    self.logger = Some(LoggerExt::new(logger.level_filter, Box::new(logger.logger)));
+    self
+  }
+
+  pub fn is_requested(&self, kind: ExtensionKind) -> bool {
+    self.requested.contains(&kind)
+  }
+
+  /// Accept an extension, and return whether it was requested.
+  ///
+  /// Accepting an extension that was not requested has no effect. Prefer [`Extensions::accept_logger`] to accept the
+  /// logger extension, which also hands out the logger.
+  pub fn accept(&mut self, kind: ExtensionKind) -> bool {
+    let requested =
+      self.is_requested(kind) && (kind != ExtensionKind::Logger || self.logger.is_some());
+
+    if requested {
+      self.accepted.insert(kind);
+    }
+
+    requested
+  }
+
+  /// Accept the logger extension, and take the requested logger, if any.
+  pub fn accept_logger(&mut self) -> Option<LoggerExt<Box<dyn 'static + Logger + Send + Sync>>> {
+    let logger = self.logger.take()?;
+    self.accepted.insert(ExtensionKind::Logger);
+    Some(logger)
+  }
+
+  /// Extensions accepted and rejected so far.
+  pub fn negotiated(&self) -> NegotiatedExtensions {
+    NegotiatedExtensions {
+      accepted: self.accepted.clone(),
+      rejected: self.requested.difference(&self.accepted).copied().collect(),
+    }
   }
 }
 
-impl ExtensionsBuilder<()> {
-  pub fn logger<F>(self, logger: LoggerExt<F>) -> ExtensionsBuilder<LoggerExt<F>> {
-    ExtensionsBuilder { logger }
+/// Outcome of negotiating extensions with a backend.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct NegotiatedExtensions {
+  accepted: BTreeSet<ExtensionKind>,
+  rejected: BTreeSet<ExtensionKind>,
+}
+
+impl NegotiatedExtensions {
+  pub fn is_accepted(&self, kind: ExtensionKind) -> bool {
+    self.accepted.contains(&kind)
+  }
+
+  /// Accepted extensions, in order.
+  pub fn accepted(&self) -> impl Iterator<Item = ExtensionKind> + '_ {
+    self.accepted.iter().copied()
+  }
+
+  /// Requested extensions the backend didn’t accept, in order.
+  pub fn rejected(&self) -> impl Iterator<Item = ExtensionKind> + '_ {
+    self.rejected.iter().copied()
   }
 }
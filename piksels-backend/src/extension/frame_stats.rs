@@ -0,0 +1,31 @@
+//! Frame statistics extension.
+//!
+//! Presentation engines know when presented images actually reach the display. Frame statistics expose that
+//! information, so that applications can pace their frames — e.g. start recording frames as late as possible to reduce
+//! latency, or lower the quality of rendering when vertical blanks are missed.
+
+use std::time::Duration;
+
+use crate::Backend;
+
+/// Presentation statistics of a swap chain.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FrameStats {
+  /// Times at which the last presented images reached the display, oldest first.
+  ///
+  /// Timestamps are relative to an arbitrary, monotonic origin; only their differences are meaningful.
+  pub present_timestamps: Vec<Duration>,
+
+  /// Number of vertical blanks missed since the swap chain was created, i.e. number of times an image was displayed
+  /// for more than one refresh period because the next one was not ready.
+  pub missed_vblanks: u64,
+
+  /// Refresh rate of the display, in Hz, if known.
+  pub refresh_rate: Option<f64>,
+}
+
+/// Backends supporting frame statistics.
+pub trait BackendFrameStats: Backend {
+  /// Presentation statistics of a swap chain.
+  fn swap_chain_frame_stats(swap_chain: &Self::SwapChain) -> Result<FrameStats, Self::Err>;
+}
@@ -57,7 +57,6 @@ pub struct LogEntry {
 #[macro_export]
 macro_rules! log {
   ($backend:expr, $lvl:ident, $($msg:tt)*) => {
-    #[cfg(feature = "ext-logger")]
     $backend.log($crate::extension::logger::LogEntry {
       level: $crate::extension::logger::LogLevel::$lvl,
       file: file!(),
@@ -0,0 +1,28 @@
+//! Profiler extension.
+//!
+//! Timer queries measure how long the device takes to execute the commands recorded in a command buffer between the
+//! beginning and the end of the query. Their result is only known once the device has executed the commands, so it is
+//! polled later, typically a few frames after recording.
+
+use std::{fmt::Debug, time::Duration};
+
+use crate::Backend;
+
+/// Backends supporting timer queries.
+pub trait BackendProfiler: Backend {
+  type TimerQuery: Debug + Send;
+
+  /// Start timing the commands recorded next in a command buffer.
+  fn cmd_buf_begin_timer_query(cmd_buf: &Self::CmdBuf) -> Result<Self::TimerQuery, Self::Err>;
+
+  /// Stop timing the commands recorded in a command buffer.
+  fn cmd_buf_end_timer_query(
+    cmd_buf: &Self::CmdBuf,
+    timer_query: &Self::TimerQuery,
+  ) -> Result<(), Self::Err>;
+
+  /// Time the device spent executing the commands of an ended timer query, or [`None`] if it is not known yet.
+  fn timer_query_elapsed(timer_query: &Self::TimerQuery) -> Result<Option<Duration>, Self::Err>;
+
+  fn drop_timer_query(timer_query: &Self::TimerQuery);
+}
@@ -0,0 +1,46 @@
+//! Readback extension.
+//!
+//! Readbacks copy the texels of a color attachment back to the CPU. They are recorded in command buffers and complete
+//! asynchronously: a readback is gated by a fence that gets signaled once the GPU is done with it, so that reading
+//! its texels doesn’t stall the pipeline.
+//!
+//! Color attachments can also be read back synchronously, which stalls the pipeline but is convenient in tests and
+//! tools. So can the last image presented to a swap chain, e.g. to take screenshots.
+
+use crate::{texture::Rect, Backend, Scarce};
+
+/// Backends supporting readbacks.
+pub trait BackendReadback: Backend {
+  /// A pending readback.
+  type Readback: Scarce<Self>;
+
+  /// Record reading back the texels of a color attachment.
+  ///
+  /// Texels are read back as 8-bit RGBA, with rows in the backend order (bottom row first).
+  fn cmd_buf_read_color_attachment(
+    cmd_buf: &Self::CmdBuf,
+    color_attachment: &Self::ColorAttachment,
+  ) -> Result<Self::Readback, Self::Err>;
+
+  /// Read back the texels of a rect of a color attachment, blocking until they are available.
+  ///
+  /// Texels are read back as 8-bit RGBA, with rows in the backend order (bottom row first).
+  fn read_color_attachment_texels(
+    color_attachment: &Self::ColorAttachment,
+    rect: Rect,
+  ) -> Result<Vec<u8>, Self::Err>;
+
+  /// Read back the texels of the last image presented to a swap chain, blocking until they are available.
+  ///
+  /// Texels are read back as 8-bit RGBA, with rows in the backend order (bottom row first).
+  fn read_swap_chain_texels(swap_chain: &Self::SwapChain) -> Result<Vec<u8>, Self::Err>;
+
+  /// Drop a readback.
+  fn drop_readback(readback: &Self::Readback);
+
+  /// Check whether the fence of a readback is signaled, without blocking.
+  fn is_readback_ready(readback: &Self::Readback) -> Result<bool, Self::Err>;
+
+  /// Wait for the fence of a readback to be signaled and copy its texels into `texels`.
+  fn readback_texels(readback: &Self::Readback, texels: &mut [u8]) -> Result<(), Self::Err>;
+}
@@ -0,0 +1,50 @@
+//! Sparse texture extension.
+//!
+//! Sparse (or partially resident) textures have a virtual size, but only the regions explicitly committed are backed by
+//! physical memory. This is the basic building block of texture streaming techniques, such as megatextures or virtual
+//! texturing.
+//!
+//! Regions are committed and decommitted in pages. The size of a page depends on the storage of the texture and is
+//! given by [`BackendSparseTexture::sparse_page_size`].
+
+use crate::{
+  pixel::Pixel,
+  texture::{Rect, Sampling, Size, Storage},
+  Backend,
+};
+
+/// Backends supporting sparse textures.
+pub trait BackendSparseTexture: Backend {
+  /// Size of a page for the given storage.
+  ///
+  /// Return [`None`] if sparse textures are not supported with such a storage.
+  fn sparse_page_size(&self, storage: Storage) -> Result<Option<Size>, Self::Err>;
+
+  /// Create a new sparse [`Texture`].
+  ///
+  /// No region is resident when the texture is created.
+  fn new_sparse_texture(
+    &self,
+    storage: Storage,
+    pixel: Pixel,
+    sampling: Sampling,
+  ) -> Result<Self::Texture, Self::Err>;
+
+  /// Make a region of a sparse texture resident.
+  ///
+  /// The region must be aligned on the page size.
+  fn commit_texture_region(
+    texture: &Self::Texture,
+    rect: Rect,
+    level: usize,
+  ) -> Result<(), Self::Err>;
+
+  /// Release the physical memory of a region of a sparse texture.
+  ///
+  /// The region must be aligned on the page size.
+  fn decommit_texture_region(
+    texture: &Self::Texture,
+    rect: Rect,
+    level: usize,
+  ) -> Result<(), Self::Err>;
+}
@@ -0,0 +1,247 @@
+//! Trace extension.
+//!
+//! A [`Trace`] is a backend-agnostic capture of the commands recorded in command buffers: pipeline state changes,
+//! bindings and draws, split in frames. Resources are referred to by [`TraceId`]s rather than by the handles of the
+//! backend that recorded them, so that a trace captured with a backend can be replayed with another one against
+//! identical workloads, e.g. to benchmark backend implementations.
+//!
+//! Replaying a trace is not an extension by itself; measuring it is. [`BackendTrace`] lets backends wait for the
+//! commands of a command buffer to be executed, so that the time spent executing a frame can be measured, and not only
+//! the time spent recording it.
+//!
+//! With the `serde` feature, traces can be serialized and deserialized, e.g. to capture them in an application and
+//! replay them in a benchmark.
+
+use crate::{
+  blending::BlendingMode,
+  color::RGBA32F,
+  depth_stencil::{DepthTest, DepthWrite, StencilTest},
+  face_culling::FaceCulling,
+  scissor::Scissor,
+  viewport::Viewport,
+  Backend,
+};
+
+/// Identifier of a resource in a [`Trace`].
+///
+/// Trace identifiers are given to resources in the order they are first used while capturing, starting at `0`, and
+/// are unique among all the resources of a trace, whatever their type.
+pub type TraceId = u64;
+
+/// A command captured in a [`Trace`].
+///
+/// Commands are captured as issued to the backend: viewports and scissors are in the backend coordinate convention.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TraceCall {
+  Blending(BlendingMode),
+  DepthTest(DepthTest),
+  DepthWrite(DepthWrite),
+  StencilTest(StencilTest),
+  FaceCulling(FaceCulling),
+  Viewport(Viewport),
+  Scissor(Scissor),
+  ClearColor(RGBA32F),
+  ClearDepth(f32),
+  Srgb(bool),
+  PrimitiveRestart(bool),
+  RenderTargets(TraceId),
+  Shader(TraceId),
+
+  /// Set a uniform to already converted bytes.
+  Uniform {
+    uniform: TraceId,
+    bytes: Vec<u8>,
+  },
+
+  /// Set some elements of a uniform array to already converted bytes.
+  UniformArray {
+    uniform: TraceId,
+    first_element: usize,
+    bytes: Vec<u8>,
+  },
+
+  /// Bind a texture to a texture binding point.
+  Texture {
+    texture: TraceId,
+    binding_point: TraceId,
+  },
+
+  /// Bind a color attachment to a texture binding point.
+  ColorAttachment {
+    color_attachment: TraceId,
+    binding_point: TraceId,
+  },
+
+  /// Bind a depth/stencil attachment to a texture binding point.
+  DepthStencilAttachment {
+    depth_stencil_attachment: TraceId,
+    binding_point: TraceId,
+  },
+
+  /// Associate a texture binding point with a shader texture binding point.
+  AssociateTexture {
+    binding_point: TraceId,
+    shader_binding_point: TraceId,
+  },
+
+  /// Bind a uniform buffer to a uniform buffer binding point.
+  UniformBuffer {
+    uniform_buffer: TraceId,
+    binding_point: TraceId,
+  },
+
+  /// Associate a uniform buffer binding point with a shader uniform buffer binding point.
+  AssociateUniformBuffer {
+    binding_point: TraceId,
+    shader_binding_point: TraceId,
+  },
+
+  /// Bind a storage buffer to a storage buffer binding point.
+  StorageBuffer {
+    storage_buffer: TraceId,
+    binding_point: TraceId,
+  },
+
+  /// Associate a storage buffer binding point with a shader storage buffer binding point.
+  AssociateStorageBuffer {
+    binding_point: TraceId,
+    shader_binding_point: TraceId,
+  },
+
+  /// Read back the texels of a color attachment.
+  ReadColorAttachment(TraceId),
+
+  /// Resolve a multisample texture into a single-sample texture.
+  ResolveTexture {
+    src: TraceId,
+    dst: TraceId,
+  },
+
+  /// Resolve multisample render targets into single-sample render targets.
+  ResolveRenderTargets {
+    src: TraceId,
+    dst: TraceId,
+  },
+
+  /// Draw a whole vertex array.
+  Draw {
+    vertex_array: TraceId,
+    restart_index: u32,
+  },
+
+  /// Draw a range of the vertices and instances of a vertex array.
+  DrawRange {
+    vertex_array: TraceId,
+    start_vertex: usize,
+    vertex_count: usize,
+    start_instance: usize,
+    instance_count: usize,
+    restart_index: u32,
+  },
+
+  /// Execute a deferred command buffer.
+  Execute(TraceId),
+
+  /// Finish the command buffer, ending a frame.
+  Finish,
+}
+
+impl TraceCall {
+  pub fn kind(&self) -> TraceCallKind {
+    match self {
+      TraceCall::Blending(_) => TraceCallKind::Blending,
+      TraceCall::DepthTest(_) => TraceCallKind::DepthTest,
+      TraceCall::DepthWrite(_) => TraceCallKind::DepthWrite,
+      TraceCall::StencilTest(_) => TraceCallKind::StencilTest,
+      TraceCall::FaceCulling(_) => TraceCallKind::FaceCulling,
+      TraceCall::Viewport(_) => TraceCallKind::Viewport,
+      TraceCall::Scissor(_) => TraceCallKind::Scissor,
+      TraceCall::ClearColor(_) => TraceCallKind::ClearColor,
+      TraceCall::ClearDepth(_) => TraceCallKind::ClearDepth,
+      TraceCall::Srgb(_) => TraceCallKind::Srgb,
+      TraceCall::PrimitiveRestart(_) => TraceCallKind::PrimitiveRestart,
+      TraceCall::RenderTargets(_) => TraceCallKind::RenderTargets,
+      TraceCall::Shader(_) => TraceCallKind::Shader,
+      TraceCall::Uniform { .. } => TraceCallKind::Uniform,
+      TraceCall::UniformArray { .. } => TraceCallKind::UniformArray,
+      TraceCall::Texture { .. } => TraceCallKind::Texture,
+      TraceCall::ColorAttachment { .. } => TraceCallKind::ColorAttachment,
+      TraceCall::DepthStencilAttachment { .. } => TraceCallKind::DepthStencilAttachment,
+      TraceCall::AssociateTexture { .. } => TraceCallKind::AssociateTexture,
+      TraceCall::UniformBuffer { .. } => TraceCallKind::UniformBuffer,
+      TraceCall::AssociateUniformBuffer { .. } => TraceCallKind::AssociateUniformBuffer,
+      TraceCall::StorageBuffer { .. } => TraceCallKind::StorageBuffer,
+      TraceCall::AssociateStorageBuffer { .. } => TraceCallKind::AssociateStorageBuffer,
+      TraceCall::ReadColorAttachment(_) => TraceCallKind::ReadColorAttachment,
+      TraceCall::ResolveTexture { .. } => TraceCallKind::ResolveTexture,
+      TraceCall::ResolveRenderTargets { .. } => TraceCallKind::ResolveRenderTargets,
+      TraceCall::Draw { .. } => TraceCallKind::Draw,
+      TraceCall::DrawRange { .. } => TraceCallKind::DrawRange,
+      TraceCall::Execute(_) => TraceCallKind::Execute,
+      TraceCall::Finish => TraceCallKind::Finish,
+    }
+  }
+}
+
+/// Kind of [`TraceCall`], without its arguments.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TraceCallKind {
+  Blending,
+  DepthTest,
+  DepthWrite,
+  StencilTest,
+  FaceCulling,
+  Viewport,
+  Scissor,
+  ClearColor,
+  ClearDepth,
+  Srgb,
+  PrimitiveRestart,
+  RenderTargets,
+  Shader,
+  Uniform,
+  UniformArray,
+  Texture,
+  ColorAttachment,
+  DepthStencilAttachment,
+  AssociateTexture,
+  UniformBuffer,
+  AssociateUniformBuffer,
+  StorageBuffer,
+  AssociateStorageBuffer,
+  ReadColorAttachment,
+  ResolveTexture,
+  ResolveRenderTargets,
+  Draw,
+  DrawRange,
+  Execute,
+  Finish,
+}
+
+/// Commands captured from command buffers, in order.
+///
+/// Every [`TraceCall::Finish`] ends a frame.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Trace {
+  pub calls: Vec<TraceCall>,
+}
+
+impl Trace {
+  /// Number of frames of the trace, i.e. number of finished command buffers.
+  pub fn frame_count(&self) -> usize {
+    self
+      .calls
+      .iter()
+      .filter(|call| **call == TraceCall::Finish)
+      .count()
+  }
+}
+
+/// Backends able to measure replayed traces.
+pub trait BackendTrace: Backend {
+  /// Wait until the commands of a finished command buffer are executed by the device.
+  fn cmd_buf_wait_idle(cmd_buf: &Self::CmdBuf) -> Result<(), Self::Err>;
+}
@@ -1,5 +1,6 @@
 /// Face culling setup.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FaceCulling {
   Off,
 
@@ -17,6 +18,7 @@ pub enum FaceCulling {
 /// wind up in the same direction as the `FaceCullingOrder`, it’s assigned the front side,
 /// otherwise, it’s the back side.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FaceCullingOrder {
   /// Clockwise order.
   CW,
@@ -26,6 +28,7 @@ pub enum FaceCullingOrder {
 
 /// Side to show and side to cull.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FaceCullingFace {
   /// Cull the front side only.
   Front,
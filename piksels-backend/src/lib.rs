@@ -1,24 +1,31 @@
-use std::{collections::HashSet, fmt::Debug, hash::Hash};
+use std::{
+  collections::{BTreeMap, HashSet},
+  fmt::{Debug, Display},
+  hash::Hash,
+  ops::Range,
+};
 
 use blending::BlendingMode;
 use color::RGBA32F;
 use depth_stencil::{DepthTest, DepthWrite, StencilTest};
 use error::Error;
-use extension::{
-  logger::{Logger, LoggerExt},
-  ExtensionsBuilder,
-};
+use extension::{logger::BackendLogger, Extensions};
 use face_culling::FaceCulling;
-use render_targets::{ColorAttachmentPoint, DepthStencilAttachmentPoint};
+use pixel::Pixel;
+use render_targets::{
+  BlitFilter, BlitMask, ColorAttachmentPoint, DepthStencilAttachmentPoint, RenderTargetsFormat,
+};
 use scissor::Scissor;
-use swap_chain::SwapChainMode;
+use swap_chain::{PresentStatus, SwapChainCapabilities, SwapChainFormat, SwapChainMode};
 use texture::{Sampling, Storage};
-use vertex_array::DataSelector;
+use uniform_block::UniformBlockLayout;
+use unit::Unit;
+use vertex::VertexAttr;
+use vertex_array::{DataSelector, MapAccess, VertexArrayUpdate, VertexStream};
 use viewport::Viewport;
 
-use crate::{
-  shader::{ShaderSources, UniformType},
-  vertex_array::VertexArrayData,
+use crate::shader::{
+  ShaderBindings, ShaderDialect, ShaderInterface, ShaderSources, Stage, UniformType,
 };
 
 /// A macro to help creating backend types methods.
@@ -46,30 +53,101 @@ pub mod face_culling;
 pub mod pixel;
 pub mod primitive;
 pub mod render_targets;
+pub mod scarce;
 pub mod scissor;
 pub mod shader;
 pub mod swap_chain;
 pub mod texture;
+pub mod uniform_block;
+pub mod unit;
 pub mod vertex;
 pub mod vertex_array;
 pub mod viewport;
 
 #[derive(Clone, Debug, Hash, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct BackendInfo {
   pub version: &'static str,
   pub git_commit_hash: &'static str,
 }
 
+/// Limits of the graphics device a backend runs on.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DeviceLimits {
+  /// Maximum width and height of 2D textures, in texels.
+  pub max_texture_size: u32,
+
+  /// Maximum number of color attachments of a [`RenderTargets`](Backend::RenderTargets).
+  pub max_color_attachments: usize,
+
+  /// Maximum number of textures bound at once.
+  pub max_texture_units: usize,
+
+  /// Maximum number of textures bound at once for a given stage.
+  ///
+  /// Some devices have separate limits per stage — typically, fewer texture units for the vertex stage. Stages not
+  /// listed are only limited by [`DeviceLimits::max_texture_units`].
+  pub max_stage_texture_units: BTreeMap<Stage, usize>,
+
+  /// Maximum number of uniform buffers bound at once.
+  pub max_uniform_buffer_units: usize,
+
+  /// Maximum number of samples of multisample storages.
+  pub max_samples: usize,
+
+  /// Maximum size of a uniform buffer, in bytes.
+  pub max_uniform_buffer_size: usize,
+
+  /// Maximum number of vertex attributes read by the vertex stage.
+  pub max_vertex_attributes: usize,
+
+  /// Maximum number of workgroups of a compute dispatch, along each dimension.
+  pub max_workgroup_count: [u32; 3],
+
+  /// Maximum size of a workgroup, along each dimension.
+  pub max_workgroup_size: [u32; 3],
+
+  /// Maximum number of invocations of a workgroup, all dimensions included.
+  pub max_workgroup_invocations: u32,
+}
+
+/// Optional capabilities of the graphics device a backend runs on.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DeviceFeatures {
+  /// Compute stages.
+  pub compute: bool,
+
+  /// Tessellation control and evaluation stages.
+  pub tessellation: bool,
+
+  /// Geometry stages.
+  pub geometry_shaders: bool,
+
+  /// Anisotropic texture filtering.
+  pub anisotropy: bool,
+
+  /// sRGB-encoded color attachments and textures.
+  pub srgb: bool,
+
+  /// Issuing several indirect draws with a single command.
+  pub multi_draw_indirect: bool,
+
+  /// Accessing textures from shaders without binding them to units first.
+  pub bindless: bool,
+}
+
 pub trait Scarce<B>: Debug
 where
-  B: Backend + ?Sized,
+  B: Backend,
 {
   fn scarce_index(&self) -> B::ScarceIndex;
   fn scarce_clone(&self) -> Self;
 }
 
-pub trait Backend: Sized {
-  type Err: From<Error>;
+pub trait Backend: BackendLogger + Sized {
+  type Err: Display + From<Error>;
 
   type CmdBuf: Scarce<Self>;
   type ColorAttachment: Scarce<Self>;
@@ -77,21 +155,38 @@ pub trait Backend: Sized {
   type RenderTargets: Scarce<Self>;
   type ScarceIndex: Clone + Debug + Eq + Hash + Ord + PartialEq + PartialOrd;
   type Shader: Scarce<Self>;
+  type ShaderStorageBufferBindingPoint: Scarce<Self>;
   type ShaderTextureBindingPoint: Scarce<Self>;
   type ShaderUniformBufferBindingPoint: Scarce<Self>;
+  type StorageBuffer: Scarce<Self>;
+  type StorageBufferBindingPoint: Scarce<Self>;
+  type StorageBufferMappedBytes;
   type SwapChain: Scarce<Self>;
   type Texture: Scarce<Self>;
   type TextureBindingPoint: Scarce<Self>;
+  type TextureUnit: Unit;
   type Uniform: Scarce<Self>;
   type UniformBuffer: Scarce<Self>;
   type UniformBufferBindingPoint: Scarce<Self>;
+  type UniformBufferUnit: Unit;
   type VertexArray: Scarce<Self>;
   type VertexArrayMappedBytes;
 
-  /// Initialize the backend from extensions.
-  fn build(
-    extensions: ExtensionsBuilder<LoggerExt<impl 'static + Logger>>,
-  ) -> Result<Self, Self::Err>;
+  /// Initialize the backend from requested extensions.
+  ///
+  /// Backends must accept the extensions they support (see [`Extensions::accept`]); the other ones are rejected.
+  fn build(extensions: &mut Extensions) -> Result<Self, Self::Err>;
+
+  /// Make the graphics context of the backend current on the calling thread.
+  ///
+  /// Backends with a notion of current context (e.g. OpenGL) must make their context current, so that the calling
+  /// thread can use the backend until [`Backend::release_current`] is called. Other backends do nothing.
+  fn make_current(&self) -> Result<(), Self::Err>;
+
+  /// Release the graphics context of the backend from the calling thread.
+  ///
+  /// See [`Backend::make_current`].
+  fn release_current(&self) -> Result<(), Self::Err>;
 
   /// Backend author.
   fn author(&self) -> Result<String, Self::Err>;
@@ -108,16 +203,45 @@ pub trait Backend: Sized {
   /// More information about the backend (git hash, etc.).
   fn info(&self) -> Result<BackendInfo, Self::Err>;
 
-  /// Create a new [`VertexArray`].
+  /// Shading language dialect the sources of shaders must be written in.
+  fn shader_dialect(&self) -> ShaderDialect;
+
+  /// Limits of the graphics device.
+  ///
+  /// Limits don’t change during the lifetime of the backend.
+  fn limits(&self) -> Result<DeviceLimits, Self::Err>;
+
+  /// Optional features supported by the graphics device.
+  ///
+  /// Features don’t change during the lifetime of the backend.
+  fn features(&self) -> Result<DeviceFeatures, Self::Err>;
+
+  /// Create a new [`VertexArray`] from vertex streams.
+  ///
+  /// Streams are given in order; [`DataSelector::InterleavedStream`] and [`DataSelector::DeinterleavedStream`] refer
+  /// to them by their position in `streams`.
   fn new_vertex_array(
     &self,
-    vertices: &VertexArrayData,
-    instances: &VertexArrayData,
+    streams: &[VertexStream],
     indices: &[u32],
   ) -> Result<Self::VertexArray, Self::Err>;
 
+  /// Create a new [`VertexArray`] without any vertex data.
+  ///
+  /// Drawing it runs the vertex stage `vertex_count` times; vertices are expected to be generated from their index or
+  /// pulled from other resources by the shader.
+  fn new_empty_vertex_array(&self, vertex_count: usize) -> Result<Self::VertexArray, Self::Err>;
+
   /// Drop a [`VertexArray`].
-  fn drop_vertex_array(vertex_array: &Self::VertexArray);
+  fn drop_vertex_array(vertex_array: &Self::VertexArray) -> Result<(), Self::Err>;
+
+  /// Partially update the data of a [`VertexArray`].
+  ///
+  /// Updates going past the end of the selected data must fail.
+  fn update_vertex_array(
+    vertex_array: &Self::VertexArray,
+    update: VertexArrayUpdate,
+  ) -> Result<(), Self::Err>;
 
   /// Map bytes from a [`VertexArray`].
   fn map_vertex_array_bytes(
@@ -125,6 +249,17 @@ pub trait Backend: Sized {
     data_selector: DataSelector,
   ) -> Result<Self::VertexArrayMappedBytes, Self::Err>;
 
+  /// Map a range of bytes from a [`VertexArray`].
+  ///
+  /// `byte_range` is relative to the start of the data selected by `data_selector`. Only the mapped range is flushed
+  /// when unmapping.
+  fn map_vertex_array_bytes_range(
+    vertex_array: &Self::VertexArray,
+    data_selector: DataSelector,
+    byte_range: Range<usize>,
+    access: MapAccess,
+  ) -> Result<Self::VertexArrayMappedBytes, Self::Err>;
+
   /// Unmap bytes from a [`VertexArray`].
   fn unmap_vertex_array_bytes(
     mapped_vertices: &Self::VertexArrayMappedBytes,
@@ -136,6 +271,10 @@ pub trait Backend: Sized {
   /// Obtain a mutable pointer and the size in bytes of the underlying memory region.
   fn vertex_array_bytes_data_mut(bytes: &mut Self::VertexArrayMappedBytes) -> (*mut u8, usize);
 
+  /// Create a new [`RenderTargets`].
+  ///
+  /// If the backend cannot create the render targets, it should fail with [`Error::IncompleteRenderTargets`],
+  /// describing the offending attachment.
   fn new_render_targets(
     &self,
     color_attachment_points: HashSet<ColorAttachmentPoint>,
@@ -143,8 +282,69 @@ pub trait Backend: Sized {
     storage: Storage,
   ) -> Result<Self::RenderTargets, Self::Err>;
 
+  /// Create a new [`RenderTargets`] sharing the depth/stencil attachment of another [`RenderTargets`].
+  ///
+  /// The storage must be the same as the one of the render targets the depth/stencil attachment comes from.
+  fn new_render_targets_with_depth_stencil_attachment(
+    &self,
+    color_attachment_points: HashSet<ColorAttachmentPoint>,
+    depth_stencil_attachment: &Self::DepthStencilAttachment,
+    storage: Storage,
+  ) -> Result<Self::RenderTargets, Self::Err>;
+
   /// Drop a [`RenderTargets`].
-  fn drop_render_targets(render_targets: &Self::RenderTargets);
+  fn drop_render_targets(render_targets: &Self::RenderTargets) -> Result<(), Self::Err>;
+
+  /// Label a [`RenderTargets`] with a name, e.g. for graphics debuggers and driver messages.
+  ///
+  /// Backends without debug labels do nothing.
+  fn set_render_targets_name(
+    render_targets: &Self::RenderTargets,
+    name: &str,
+  ) -> Result<(), Self::Err>;
+
+  /// Restrict the color attachments of a [`RenderTargets`] written to by draws to `indices`, sorted and without
+  /// duplicates.
+  ///
+  /// Fragment outputs keep their locations; outputs of inactive attachments are discarded.
+  fn set_render_targets_active_attachments(
+    render_targets: &Self::RenderTargets,
+    indices: &[usize],
+  ) -> Result<(), Self::Err>;
+
+  /// Reallocate all the attachments of a [`RenderTargets`] with `storage`, keeping the render targets and attachments
+  /// handles valid.
+  fn resize_render_targets(
+    render_targets: &Self::RenderTargets,
+    storage: Storage,
+  ) -> Result<(), Self::Err>;
+
+  /// Copy the `src_rect` region of the attachments of `src` selected by `mask` to the `dst_rect` region of `dst`,
+  /// scaling with `filter` if the regions have different sizes.
+  ///
+  /// Color attachments are copied from the attachment at the lowest index of `src` to the active attachments of `dst`.
+  fn blit_render_targets(
+    src: &Self::RenderTargets,
+    dst: &Self::RenderTargets,
+    src_rect: texture::Rect,
+    dst_rect: texture::Rect,
+    filter: BlitFilter,
+    mask: BlitMask,
+  ) -> Result<(), Self::Err>;
+
+  /// Render the color attachment at `index` of a [`RenderTargets`] to `texture` instead of the attachment it was
+  /// created with.
+  fn attach_render_targets_texture(
+    render_targets: &Self::RenderTargets,
+    index: usize,
+    texture: &Self::Texture,
+  ) -> Result<(), Self::Err>;
+
+  /// Render the color attachment at `index` of a [`RenderTargets`] to the attachment it was created with again.
+  fn detach_render_targets_texture(
+    render_targets: &Self::RenderTargets,
+    index: usize,
+  ) -> Result<(), Self::Err>;
 
   /// Obtain the indexed color attachment.
   fn get_color_attachment(
@@ -152,6 +352,12 @@ pub trait Backend: Sized {
     index: usize,
   ) -> Result<Self::ColorAttachment, Self::Err>;
 
+  /// Set how the depth/stencil attachment of some render targets is sampled when bound as a texture.
+  fn set_depth_stencil_attachment_sampling(
+    depth_stencil_attachment: &Self::DepthStencilAttachment,
+    sampling: Sampling,
+  ) -> Result<(), Self::Err>;
+
   /// Obtain the indexed depth/stencil attachment.
   fn get_depth_stencil_attachment(
     render_targets: &Self::RenderTargets,
@@ -162,7 +368,29 @@ pub trait Backend: Sized {
   fn new_shader(&self, sources: ShaderSources) -> Result<Self::Shader, Self::Err>;
 
   /// Drop a [`Shader`].
-  fn drop_shader(shader: &Self::Shader);
+  fn drop_shader(shader: &Self::Shader) -> Result<(), Self::Err>;
+
+  /// Label a [`Shader`] with a name.
+  ///
+  /// See [`Backend::set_render_targets_name`].
+  fn set_shader_name(shader: &Self::Shader, name: &str) -> Result<(), Self::Err>;
+
+  /// Reflect the texture and uniform buffer bindings declared by a [`Shader`].
+  fn reflect_shader_bindings(shader: &Self::Shader) -> Result<ShaderBindings, Self::Err>;
+
+  /// Reflect the vertex attributes of a [`Shader`], and the variables passed to its fragment stage.
+  fn reflect_shader_interface(shader: &Self::Shader) -> Result<ShaderInterface, Self::Err>;
+
+  /// Prepare drawing with a [`Shader`] and vertices laid out as `vertex_attrs` to render targets of a given `format`.
+  ///
+  /// Backends compiling or linking pipeline variants lazily — e.g. on the first draw — must do it here instead, so
+  /// that first-use hitches happen at load time. Other backends do nothing.
+  fn warm_up(
+    &self,
+    shader: &Self::Shader,
+    vertex_attrs: &[VertexAttr],
+    format: &RenderTargetsFormat,
+  ) -> Result<(), Self::Err>;
 
   /// Create a new [`Uniform`].
   fn get_uniform(
@@ -177,6 +405,17 @@ pub trait Backend: Sized {
     name: &str,
   ) -> Result<Self::UniformBuffer, Self::Err>;
 
+  /// Replace the content of a [`UniformBuffer`] with `bytes`, laid out according to the std140 rules.
+  fn update_uniform_buffer(
+    uniform_buffer: &Self::UniformBuffer,
+    bytes: &[u8],
+  ) -> Result<(), Self::Err>;
+
+  /// Query the layout of the block backing a [`UniformBuffer`], as laid out by the driver.
+  fn uniform_buffer_layout(
+    uniform_buffer: &Self::UniformBuffer,
+  ) -> Result<UniformBlockLayout, Self::Err>;
+
   /// Get a texture binding point.
   fn get_texture_binding_point(&self, index: usize)
     -> Result<Self::TextureBindingPoint, Self::Err>;
@@ -187,6 +426,12 @@ pub trait Backend: Sized {
     index: usize,
   ) -> Result<Self::UniformBufferBindingPoint, Self::Err>;
 
+  /// Get a storage buffer binding point.
+  fn get_storage_buffer_binding_point(
+    &self,
+    index: usize,
+  ) -> Result<Self::StorageBufferBindingPoint, Self::Err>;
+
   /// Get a shader texture binding point from a shader.
   fn get_shader_texture_binding_point(
     shader: &Self::Shader,
@@ -199,12 +444,67 @@ pub trait Backend: Sized {
     name: &str,
   ) -> Result<Self::ShaderUniformBufferBindingPoint, Self::Err>;
 
-  fn new_texture(&self, storage: Storage, sampling: Sampling) -> Result<Self::Texture, Self::Err>;
+  /// Get a storage buffer binding point from a shader.
+  fn get_shader_storage_buffer_binding_point(
+    shader: &Self::Shader,
+    name: &str,
+  ) -> Result<Self::ShaderStorageBufferBindingPoint, Self::Err>;
+
+  /// Create a new [`StorageBuffer`] of `size` bytes, initialized to zero.
+  fn new_storage_buffer(&self, size: usize) -> Result<Self::StorageBuffer, Self::Err>;
 
-  fn drop_texture(texture: &Self::Texture);
+  fn drop_storage_buffer(storage_buffer: &Self::StorageBuffer) -> Result<(), Self::Err>;
+
+  /// Write `bytes` to a [`StorageBuffer`], starting at byte `offset`.
+  fn update_storage_buffer(
+    storage_buffer: &Self::StorageBuffer,
+    offset: usize,
+    bytes: &[u8],
+  ) -> Result<(), Self::Err>;
+
+  /// Map a range of bytes from a [`StorageBuffer`].
+  ///
+  /// Only the mapped range is flushed when unmapped.
+  fn map_storage_buffer_bytes(
+    storage_buffer: &Self::StorageBuffer,
+    byte_range: Range<usize>,
+    access: MapAccess,
+  ) -> Result<Self::StorageBufferMappedBytes, Self::Err>;
+
+  /// Unmap bytes from a [`StorageBuffer`].
+  fn unmap_storage_buffer_bytes(
+    mapped_bytes: &Self::StorageBufferMappedBytes,
+  ) -> Result<(), Self::Err>;
+
+  /// Obtain a pointer and the size in bytes of the underlying memory region.
+  fn storage_buffer_bytes_data(bytes: &Self::StorageBufferMappedBytes) -> (*const u8, usize);
+
+  /// Obtain a mutable pointer and the size in bytes of the underlying memory region.
+  fn storage_buffer_bytes_data_mut(bytes: &mut Self::StorageBufferMappedBytes) -> (*mut u8, usize);
+
+  fn new_texture(
+    &self,
+    storage: Storage,
+    pixel: Pixel,
+    sampling: Sampling,
+  ) -> Result<Self::Texture, Self::Err>;
+
+  fn drop_texture(texture: &Self::Texture) -> Result<(), Self::Err>;
+
+  /// Label a [`Texture`] with a name.
+  ///
+  /// See [`Backend::set_render_targets_name`].
+  fn set_texture_name(texture: &Self::Texture, name: &str) -> Result<(), Self::Err>;
 
   fn resize_texture(texture: &Self::Texture, size: texture::Size) -> Result<(), Self::Err>;
 
+  /// Restrict the mipmap levels a [`Texture`] can be sampled from to `base_level..=max_level`.
+  fn set_texture_levels(
+    texture: &Self::Texture,
+    base_level: usize,
+    max_level: usize,
+  ) -> Result<(), Self::Err>;
+
   fn set_texels(
     texture: &Self::Texture,
     rect: texture::Rect,
@@ -222,7 +522,18 @@ pub trait Backend: Sized {
 
   fn new_cmd_buf(&self) -> Result<Self::CmdBuf, Self::Err>;
 
-  fn drop_cmd_buf(cmd_buf: &Self::CmdBuf);
+  /// Create a new deferred [`CmdBuf`], recording commands without executing them.
+  ///
+  /// Deferred command buffers can be recorded from any thread, even if the graphics context is current on another
+  /// one. They are executed with [`Backend::cmd_buf_execute`].
+  fn new_deferred_cmd_buf(&self) -> Result<Self::CmdBuf, Self::Err>;
+
+  fn drop_cmd_buf(cmd_buf: &Self::CmdBuf) -> Result<(), Self::Err>;
+
+  /// Label a [`CmdBuf`] with a name.
+  ///
+  /// See [`Backend::set_render_targets_name`].
+  fn set_cmd_buf_name(cmd_buf: &Self::CmdBuf, name: &str) -> Result<(), Self::Err>;
 
   fn cmd_buf_blending(cmd_buf: &Self::CmdBuf, blending: BlendingMode) -> Result<(), Self::Err>;
 
@@ -250,10 +561,31 @@ pub trait Backend: Sized {
 
   fn cmd_buf_srgb(cmd_buf: &Self::CmdBuf, srgb: bool) -> Result<(), Self::Err>;
 
+  /// Enable or disable primitive restart for indexed draws.
+  fn cmd_buf_primitive_restart(
+    cmd_buf: &Self::CmdBuf,
+    primitive_restart: bool,
+  ) -> Result<(), Self::Err>;
+
+  /// Set the value of a uniform.
+  ///
+  /// `value` holds the bytes of the value, already converted to the type the uniform was obtained with (see
+  /// [`UniformType::coerce`]).
   fn cmd_buf_set_uniform(
     cmd_buf: &Self::CmdBuf,
     uniform: &Self::Uniform,
-    value: *const u8, // TODO: type with UniformValue trait
+    value: &[u8],
+  ) -> Result<(), Self::Err>;
+
+  /// Set some elements of a uniform array, starting at element `first_element`.
+  ///
+  /// `values` holds the bytes of the elements, already converted to the element type of the uniform; elements outside
+  /// of the range must be left untouched.
+  fn cmd_buf_set_uniform_array(
+    cmd_buf: &Self::CmdBuf,
+    uniform: &Self::Uniform,
+    first_element: usize,
+    values: &[u8],
   ) -> Result<(), Self::Err>;
 
   /// Bind a texture.
@@ -263,6 +595,20 @@ pub trait Backend: Sized {
     binding_point: &Self::TextureBindingPoint,
   ) -> Result<(), Self::Err>;
 
+  /// Bind the color attachment of some render targets as a texture, so that it can be sampled by later passes.
+  fn cmd_buf_bind_color_attachment(
+    cmd_buf: &Self::CmdBuf,
+    color_attachment: &Self::ColorAttachment,
+    binding_point: &Self::TextureBindingPoint,
+  ) -> Result<(), Self::Err>;
+
+  /// Bind the depth/stencil attachment of some render targets as a texture, e.g. to sample a shadow map.
+  fn cmd_buf_bind_depth_stencil_attachment(
+    cmd_buf: &Self::CmdBuf,
+    depth_stencil_attachment: &Self::DepthStencilAttachment,
+    binding_point: &Self::TextureBindingPoint,
+  ) -> Result<(), Self::Err>;
+
   /// Associate a texture binding point to a shader texture binding point.
   fn cmd_buf_associate_texture_binding_point(
     cmd_buf: &Self::CmdBuf,
@@ -284,6 +630,35 @@ pub trait Backend: Sized {
     shader_uniform_buffer_binding_point: &Self::ShaderUniformBufferBindingPoint,
   ) -> Result<(), Self::Err>;
 
+  /// Bind a storage buffer.
+  fn cmd_buf_bind_storage_buffer(
+    cmd_buf: &Self::CmdBuf,
+    storage_buffer: &Self::StorageBuffer,
+    binding_point: &Self::StorageBufferBindingPoint,
+  ) -> Result<(), Self::Err>;
+
+  /// Associate a storage buffer binding point to a shader storage buffer binding point.
+  fn cmd_buf_associate_storage_buffer_binding_point(
+    cmd_buf: &Self::CmdBuf,
+    storage_buffer_binding_point: &Self::StorageBufferBindingPoint,
+    shader_storage_buffer_binding_point: &Self::ShaderStorageBufferBindingPoint,
+  ) -> Result<(), Self::Err>;
+
+  /// Resolve a multisample texture into a single-sample texture.
+  fn cmd_buf_resolve_texture(
+    cmd_buf: &Self::CmdBuf,
+    src: &Self::Texture,
+    dst: &Self::Texture,
+  ) -> Result<(), Self::Err>;
+
+  /// Resolve the attachments of multisample render targets into the matching attachments of single-sample render
+  /// targets.
+  fn cmd_buf_resolve_render_targets(
+    cmd_buf: &Self::CmdBuf,
+    src: &Self::RenderTargets,
+    dst: &Self::RenderTargets,
+  ) -> Result<(), Self::Err>;
+
   fn cmd_buf_bind_render_targets(
     cmd_buf: &Self::CmdBuf,
     render_targets: &Self::RenderTargets,
@@ -291,28 +666,76 @@ pub trait Backend: Sized {
 
   fn cmd_buf_bind_shader(cmd_buf: &Self::CmdBuf, shader: &Self::Shader) -> Result<(), Self::Err>;
 
+  /// Draw a vertex array.
+  ///
+  /// If primitive restart is enabled and the vertex array is indexed, primitives restart at `restart_index`.
   fn cmd_buf_draw_vertex_array(
     cmd_buf: &Self::CmdBuf,
     vertex_array: &Self::VertexArray,
+    restart_index: u32,
+  ) -> Result<(), Self::Err>;
+
+  /// Draw a range of vertices of a vertex array, possibly instanced.
+  ///
+  /// If primitive restart is enabled and the vertex array is indexed, primitives restart at `restart_index`.
+  fn cmd_buf_draw_vertex_array_range(
+    cmd_buf: &Self::CmdBuf,
+    vertex_array: &Self::VertexArray,
+    start_vertex: usize,
+    vertex_count: usize,
+    start_instance: usize,
+    instance_count: usize,
+    restart_index: u32,
   ) -> Result<(), Self::Err>;
 
   fn cmd_buf_finish(cmd_buf: &Self::CmdBuf) -> Result<(), Self::Err>;
 
+  /// Execute the commands recorded in a deferred [`CmdBuf`] as part of `cmd_buf`.
+  ///
+  /// Backends without secondary command buffers replay the recorded commands. A deferred command buffer can be
+  /// executed several times.
+  fn cmd_buf_execute(cmd_buf: &Self::CmdBuf, deferred: &Self::CmdBuf) -> Result<(), Self::Err>;
+
+  /// Parameters swap chains can be created with.
+  fn swap_chain_capabilities(&self) -> Result<SwapChainCapabilities, Self::Err>;
+
+  /// Create a swap chain of `image_count` images, i.e. with at most `image_count` frames in flight, in `format`.
   fn new_swap_chain(
     &self,
     width: u32,
     height: u32,
     mode: SwapChainMode,
+    image_count: usize,
+    format: SwapChainFormat,
   ) -> Result<Self::SwapChain, Self::Err>;
 
-  fn drop_swap_chain(swap_chain: &Self::SwapChain);
+  fn drop_swap_chain(swap_chain: &Self::SwapChain) -> Result<(), Self::Err>;
+
+  /// Block until every image presented to a swap chain is displayed.
+  fn swap_chain_wait_idle(swap_chain: &Self::SwapChain) -> Result<(), Self::Err>;
+
+  /// Recreate the surfaces of a swap chain with a width and height of `width` and `height`.
+  ///
+  /// Render targets previously returned by [`Backend::swap_chain_render_targets`] must not be used anymore.
+  fn resize_swap_chain(
+    swap_chain: &Self::SwapChain,
+    width: u32,
+    height: u32,
+  ) -> Result<(), Self::Err>;
 
+  /// Acquire the render targets of the next image of a swap chain.
+  ///
+  /// Swap chains are independent: images of several swap chains can be acquired at once, and presented in any order.
   fn swap_chain_render_targets(
     swap_chain: &Self::SwapChain,
   ) -> Result<Self::RenderTargets, Self::Err>;
 
+  /// Present the render targets of an image of a swap chain.
+  ///
+  /// A swap chain out of date or a lost surface is not an error, but a [`PresentStatus`]: the image is dropped either
+  /// way, and callers decide how to recover.
   fn present_render_targets(
     swap_chain: &Self::SwapChain,
     render_targets: &Self::RenderTargets,
-  ) -> Result<(), Self::Err>;
+  ) -> Result<PresentStatus, Self::Err>;
 }
@@ -1,3 +1,8 @@
+/// Default index at which primitives restart when primitive restart is enabled.
+///
+/// This is the largest index representable with 32-bit indices.
+pub const DEFAULT_RESTART_INDEX: u32 = u32::MAX;
+
 /// Primitive class, like points, lines, triangles, etc.
 pub trait Primitive {
   /// Connector used to link vertices together, if any.
@@ -1,16 +1,74 @@
+use std::{
+  fmt::{self, Display},
+  ops::BitOr,
+};
+
+use crate::texture::Storage;
+
 /// A color image attachment point.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct ColorAttachmentPoint {
   index: usize,
   name: &'static str,
   ty: ColorType,
+  level: usize,
+  layer: Option<usize>,
+  usage: AttachmentUsage,
+  srgb_encoding: bool,
+}
+
+impl ColorAttachmentPoint {
+  /// Create a color attachment point rendering to the base level, and to all layers if the storage is layered.
+  pub fn new(index: usize, name: &'static str, ty: ColorType) -> Self {
+    Self {
+      index,
+      name,
+      ty,
+      level: 0,
+      layer: None,
+      usage: AttachmentUsage::Sampled,
+      srgb_encoding: false,
+    }
+  }
+
+  /// Render to the mipmap level `level` instead of the base level.
+  pub fn with_level(self, level: usize) -> Self {
+    Self { level, ..self }
+  }
+
+  /// Render to a single layer — see [`Storage::layers`](crate::texture::Storage::layers) — instead of all layers.
+  pub fn with_layer(self, layer: usize) -> Self {
+    Self {
+      layer: Some(layer),
+      ..self
+    }
+  }
+
+  /// Set how the attachment is used once rendered to.
+  pub fn with_usage(self, usage: AttachmentUsage) -> Self {
+    Self { usage, ..self }
+  }
+
+  /// Encode the linear colors written to the attachment to sRGB.
+  ///
+  /// Only sRGB attachments (see [`ColorType::is_srgb`]) can encode; others always store colors as written.
+  pub fn with_srgb_encoding(self, srgb_encoding: bool) -> Self {
+    Self {
+      srgb_encoding,
+      ..self
+    }
+  }
 }
 
 mk_bckd_type_getters!(
   ColorAttachmentPoint,
   index -> usize,
   name -> &'static str,
-  ty -> ColorType
+  ty -> ColorType,
+  level -> usize,
+  layer -> Option<usize>,
+  usage -> AttachmentUsage,
+  srgb_encoding -> bool
 );
 
 /// A depth-stencil attachment point.
@@ -19,15 +77,188 @@ pub struct DepthStencilAttachmentPoint {
   index: usize,
   name: &'static str,
   ty: DepthStencilType,
+  level: usize,
+  layer: Option<usize>,
+  usage: AttachmentUsage,
+}
+
+impl DepthStencilAttachmentPoint {
+  /// Create a depth-stencil attachment point rendering to the base level, and to all layers if the storage is layered.
+  pub fn new(index: usize, name: &'static str, ty: DepthStencilType) -> Self {
+    Self {
+      index,
+      name,
+      ty,
+      level: 0,
+      layer: None,
+      usage: AttachmentUsage::Sampled,
+    }
+  }
+
+  /// Render to the mipmap level `level` instead of the base level.
+  pub fn with_level(self, level: usize) -> Self {
+    Self { level, ..self }
+  }
+
+  /// Render to a single layer — see [`Storage::layers`](crate::texture::Storage::layers) — instead of all layers.
+  pub fn with_layer(self, layer: usize) -> Self {
+    Self {
+      layer: Some(layer),
+      ..self
+    }
+  }
+
+  /// Set how the attachment is used once rendered to.
+  pub fn with_usage(self, usage: AttachmentUsage) -> Self {
+    Self { usage, ..self }
+  }
 }
 
 mk_bckd_type_getters!(
   DepthStencilAttachmentPoint,
   index -> usize,
   name -> &'static str,
-  ty -> DepthStencilType
+  ty -> DepthStencilType,
+  level -> usize,
+  layer -> Option<usize>,
+  usage -> AttachmentUsage
 );
 
+/// Reason render targets cannot be created, pointing at the offending attachment.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum RenderTargetsError {
+  /// The backend cannot render to the type of an attachment.
+  UnsupportedFormat { attachment: &'static str },
+
+  /// An attachment doesn’t have the same size as the other attachments.
+  MismatchedSizes {
+    attachment: &'static str,
+    expected: Storage,
+    got: Storage,
+  },
+
+  /// More color attachments than the backend supports.
+  TooManyAttachments { count: usize, max: usize },
+
+  /// Several color attachments have the same index.
+  DuplicateIndex {
+    index: usize,
+    attachments: [&'static str; 2],
+  },
+}
+
+impl Display for RenderTargetsError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::UnsupportedFormat { attachment } => {
+        write!(f, "cannot render to the type of {attachment:?}")
+      }
+
+      Self::MismatchedSizes {
+        attachment,
+        expected,
+        got,
+      } => write!(
+        f,
+        "{attachment:?} is {got:?}, but other attachments are {expected:?}"
+      ),
+
+      Self::TooManyAttachments { count, max } => {
+        write!(
+          f,
+          "{count} color attachments, but at most {max} are supported"
+        )
+      }
+
+      Self::DuplicateIndex {
+        index,
+        attachments: [a, b],
+      } => write!(f, "{a:?} and {b:?} are both at index {index}"),
+    }
+  }
+}
+
+/// How an attachment is used once rendered to.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum AttachmentUsage {
+  /// The attachment can be sampled by later passes.
+  #[default]
+  Sampled,
+
+  /// The attachment is only rendered to, and never sampled — e.g. a depth/stencil attachment only used for depth and
+  /// stencil tests.
+  ///
+  /// Backends can allocate such attachments in cheaper memory than textures, such as renderbuffers or
+  /// lazily-allocated memory. Render-only attachments have a single level, and their storage cannot have layers.
+  RenderOnly,
+}
+
+/// Filter used when a blit stretches or shrinks the copied region.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum BlitFilter {
+  /// Copy the closest texel.
+  #[default]
+  Nearest,
+
+  /// Linearly interpolate between surrounding texels.
+  ///
+  /// Only color attachments can be linearly filtered.
+  Linear,
+}
+
+/// Attachments copied by a blit.
+///
+/// Masks can be combined with `|`, e.g. `BlitMask::COLOR | BlitMask::DEPTH`.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct BlitMask {
+  pub color: bool,
+  pub depth: bool,
+  pub stencil: bool,
+}
+
+impl BlitMask {
+  pub const COLOR: Self = Self {
+    color: true,
+    depth: false,
+    stencil: false,
+  };
+
+  pub const DEPTH: Self = Self {
+    color: false,
+    depth: true,
+    stencil: false,
+  };
+
+  pub const STENCIL: Self = Self {
+    color: false,
+    depth: false,
+    stencil: true,
+  };
+
+  pub const DEPTH_STENCIL: Self = Self {
+    color: false,
+    depth: true,
+    stencil: true,
+  };
+
+  /// Whether nothing is copied.
+  pub fn is_empty(self) -> bool {
+    !(self.color || self.depth || self.stencil)
+  }
+}
+
+impl BitOr for BlitMask {
+  type Output = Self;
+
+  fn bitor(self, rhs: Self) -> Self::Output {
+    Self {
+      color: self.color || rhs.color,
+      depth: self.depth || rhs.depth,
+      stencil: self.stencil || rhs.stencil,
+    }
+  }
+}
+
 /// Color attachment type.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum ColorType {
@@ -109,6 +340,27 @@ pub enum ColorType {
   },
 }
 
+impl ColorType {
+  /// Whether texels are stored in the sRGB color space.
+  pub fn is_srgb(self) -> bool {
+    matches!(
+      self,
+      ColorType::ISRGB { .. }
+        | ColorType::ISRGBA { .. }
+        | ColorType::UintSRGB { .. }
+        | ColorType::UintSRGBA { .. }
+    )
+  }
+}
+
+/// Format of render targets: the type of their color attachments, and of their depth/stencil attachment, if any.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct RenderTargetsFormat {
+  /// Color attachment types, along with their indices, sorted by index.
+  pub color_attachments: Vec<(usize, ColorType)>,
+  pub depth_stencil_attachment: Option<DepthStencilType>,
+}
+
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum DepthStencilType {
   Depth {
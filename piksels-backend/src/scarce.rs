@@ -0,0 +1,76 @@
+//! Scarce index allocation.
+//!
+//! Backends identify their resources with scarce indices (see [`Scarce`](crate::Scarce)), which must be unique among
+//! the live resources of a given type. [`ScarceIndexAllocator`] mints such indices, so that backends don’t have to.
+
+use std::{
+  collections::VecDeque,
+  sync::{
+    atomic::{AtomicUsize, Ordering},
+    Mutex, MutexGuard, PoisonError,
+  },
+};
+
+/// Thread-safe allocator of unique `usize` scarce indices.
+///
+/// Indices are minted from a counter, and can be given back with [`ScarceIndexAllocator::free`] once the resource
+/// they identify is destroyed. Freed indices are reused oldest first, so that an index is reused as late as possible.
+///
+/// ```
+/// use piksels_backend::scarce::ScarceIndexAllocator;
+///
+/// let allocator = ScarceIndexAllocator::new();
+/// let a = allocator.allocate();
+/// let b = allocator.allocate();
+/// assert_ne!(a, b);
+///
+/// allocator.free(a);
+/// assert_eq!(allocator.allocate(), a);
+/// ```
+#[derive(Debug, Default)]
+pub struct ScarceIndexAllocator {
+  next: AtomicUsize,
+  free: Mutex<VecDeque<usize>>,
+}
+
+impl ScarceIndexAllocator {
+  pub const fn new() -> Self {
+    Self {
+      next: AtomicUsize::new(0),
+      free: Mutex::new(VecDeque::new()),
+    }
+  }
+
+  /// Allocate an index, reusing a freed one if any.
+  pub fn allocate(&self) -> usize {
+    self
+      .free_list()
+      .pop_front()
+      .unwrap_or_else(|| self.next.fetch_add(1, Ordering::Relaxed))
+  }
+
+  /// Give an index back, so that it can be reused.
+  ///
+  /// Only free the index of a destroyed resource, and only once: handles still referring to it would otherwise be
+  /// mistaken for the resource the index is reused for.
+  pub fn free(&self, index: usize) {
+    let mut free = self.free_list();
+    debug_assert!(
+      index < self.next.load(Ordering::Relaxed),
+      "scarce index {index} was never allocated"
+    );
+    debug_assert!(!free.contains(&index), "scarce index {index} freed twice");
+    free.push_back(index);
+  }
+
+  /// Number of indices currently allocated.
+  pub fn allocated(&self) -> usize {
+    let free = self.free_list();
+    self.next.load(Ordering::Relaxed) - free.len()
+  }
+
+  /// The free-list is always left consistent, so a poisoned lock is still usable.
+  fn free_list(&self) -> MutexGuard<'_, VecDeque<usize>> {
+    self.free.lock().unwrap_or_else(PoisonError::into_inner)
+  }
+}
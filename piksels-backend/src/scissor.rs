@@ -1,5 +1,6 @@
 /// Scissor mode.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Scissor {
   Off,
   On(ScissorRegion),
@@ -7,6 +8,7 @@ pub enum Scissor {
 
 /// The region outside of which fragments will be discarded.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ScissorRegion {
   /// The x screen position of the scissor region.
   x: u32,
@@ -20,3 +22,22 @@ pub struct ScissorRegion {
   /// The screen height of the scissor region.
   height: u32,
 }
+
+impl ScissorRegion {
+  pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+    Self {
+      x,
+      y,
+      width,
+      height,
+    }
+  }
+}
+
+mk_bckd_type_getters!(
+  ScissorRegion,
+  x -> u32,
+  y -> u32,
+  width -> u32,
+  height -> u32
+);
@@ -1,3 +1,37 @@
+use crate::{error::Error, vertex::Type};
+
+/// Shading language dialects.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ShaderDialect {
+  /// GLSL 330 core, for desktop OpenGL.
+  Glsl330,
+
+  /// ESSL 300 (GLSL ES 3.00), for OpenGL ES 3 and WebGL 2.
+  Essl300,
+
+  /// WGSL, for WebGPU.
+  Wgsl,
+}
+
+/// Stages of a shader program.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Stage {
+  Vertex,
+  TessCtrl,
+  TessEval,
+  Geometry,
+  Fragment,
+  Compute,
+}
+
+/// Sources of the stages of a shader program.
+///
+/// A program is either made of rasterization stages (vertex, tessellation, geometry and fragment), or of a single
+/// compute stage. Stages left empty are absent.
+///
+/// Sources can be specialized with `#define`s (see [`ShaderSources::define`]); backends don’t see them, as they are
+/// injected in the stages before the shader is created.
 #[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct ShaderSources<'a> {
   tess_ctrl_stage: &'a str,
@@ -5,6 +39,8 @@ pub struct ShaderSources<'a> {
   vertex_stage: &'a str,
   geometry_stage: &'a str,
   fragment_stage: &'a str,
+  compute_stage: &'a str,
+  defines: Vec<(&'a str, &'a str)>,
 }
 
 impl<'a> ShaderSources<'a> {
@@ -32,6 +68,116 @@ impl<'a> ShaderSources<'a> {
     self.fragment_stage = fragment_stage;
     self
   }
+
+  /// Compute stage; cannot be mixed with the rasterization stages.
+  pub fn compute(mut self, compute_stage: &'a str) -> Self {
+    self.compute_stage = compute_stage;
+    self
+  }
+
+  /// Inject `#define name value` in every stage, right after its `#version` line.
+  ///
+  /// Defines are injected in the order they were added. `value` can be empty.
+  pub fn define(mut self, name: &'a str, value: &'a str) -> Self {
+    self.defines.push((name, value));
+    self
+  }
+
+  pub fn tess_ctrl_stage(&self) -> &'a str {
+    self.tess_ctrl_stage
+  }
+
+  pub fn tess_eval_stage(&self) -> &'a str {
+    self.tess_eval_stage
+  }
+
+  pub fn vertex_stage(&self) -> &'a str {
+    self.vertex_stage
+  }
+
+  pub fn geometry_stage(&self) -> &'a str {
+    self.geometry_stage
+  }
+
+  pub fn fragment_stage(&self) -> &'a str {
+    self.fragment_stage
+  }
+
+  pub fn compute_stage(&self) -> &'a str {
+    self.compute_stage
+  }
+
+  pub fn defines(&self) -> &[(&'a str, &'a str)] {
+    &self.defines
+  }
+
+  /// Whether these are the sources of a compute program.
+  pub fn is_compute(&self) -> bool {
+    !self.compute_stage.is_empty()
+  }
+
+  /// Check that the compute stage is not mixed with rasterization stages.
+  pub fn validate(&self) -> Result<(), Error> {
+    if !self.is_compute() {
+      return Ok(());
+    }
+
+    let stages = [
+      ("tessellation control", self.tess_ctrl_stage),
+      ("tessellation evaluation", self.tess_eval_stage),
+      ("vertex", self.vertex_stage),
+      ("geometry", self.geometry_stage),
+      ("fragment", self.fragment_stage),
+    ];
+    let mixed = stages
+      .iter()
+      .filter(|(_, src)| !src.is_empty())
+      .map(|(name, _)| *name)
+      .collect::<Vec<_>>();
+
+    if mixed.is_empty() {
+      Ok(())
+    } else {
+      Err(Error::InvalidShaderSources {
+        reason: format!(
+          "compute stage mixed with rasterization stages: {}",
+          mixed.join(", ")
+        ),
+      })
+    }
+  }
+}
+
+/// Inject `#define`s in `src`, right after the `#version` line if any, or at the top otherwise.
+pub fn inject_defines<'a>(
+  src: &str,
+  defines: impl IntoIterator<Item = (&'a str, &'a str)>,
+) -> String {
+  let mut injected = String::new();
+  for (name, value) in defines {
+    injected.push_str("#define ");
+    injected.push_str(name);
+
+    if !value.is_empty() {
+      injected.push(' ');
+      injected.push_str(value);
+    }
+
+    injected.push('\n');
+  }
+
+  let mut offset = 0;
+  for line in src.split_inclusive('\n') {
+    offset += line.len();
+
+    if line.trim_start().starts_with("#version") {
+      let (version, rest) = src.split_at(offset);
+      let newline = if version.ends_with('\n') { "" } else { "\n" };
+      return format!("{version}{newline}{injected}{rest}");
+    }
+  }
+
+  format!("{injected}{src}")
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
@@ -58,6 +204,69 @@ impl UniformType {
     self.array = Some(array);
     self
   }
+
+  pub fn base(&self) -> UniformTypeBase {
+    self.base
+  }
+
+  pub fn array_len(&self) -> Option<usize> {
+    self.array
+  }
+
+  /// Bytes to set a uniform of this type to `value`, converted according to the coercion rules.
+  ///
+  /// A value can be set to a uniform if:
+  ///
+  /// - Their types are the same.
+  /// - Their types have the same shape (scalar, vector or matrix dimensions) and the components of the value convert
+  ///   implicitly to the components of the uniform:
+  ///   - `bool` converts to `int`, `uint` and `float` (`false` is `0` and `true` is `1`).
+  ///   - `int` and `uint` convert to `float`.
+  ///   - `int`, `uint` and `float` convert to `double`.
+  /// - For arrays, the value is an array (a slice) of at most as many elements as the uniform, and the types of the
+  ///   elements follow the rules above.
+  ///
+  /// Any other combination — e.g. setting a `Float3` uniform from `[f32; 4]`, an `Int` uniform from `f32` or a `Bool`
+  /// uniform from `i32` — fails with [`Error::UniformTypeMismatch`].
+  pub fn coerce<T>(&self, value: &T) -> Result<Vec<u8>, Error>
+  where
+    T: ?Sized + UniformValue,
+  {
+    let got = value.uniform_type();
+    let mismatch = || Error::UniformTypeMismatch {
+      expected: *self,
+      got,
+    };
+
+    let array_fits = match (self.array, got.array) {
+      (None, None) => true,
+      (Some(expected_len), Some(got_len)) => got_len <= expected_len,
+      _ => false,
+    };
+
+    if !array_fits || self.base.shape() != got.base.shape() {
+      return Err(mismatch());
+    }
+
+    let (from, to) = (got.base.component(), self.base.component());
+    if !from.converts_to(to) {
+      return Err(mismatch());
+    }
+
+    let mut bytes = Vec::new();
+    value.write_bytes(&mut bytes);
+
+    if from == to {
+      return Ok(bytes);
+    }
+
+    Ok(
+      bytes
+        .chunks_exact(from.size())
+        .flat_map(|component| to.convert_from(from, component))
+        .collect(),
+    )
+  }
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
@@ -103,3 +312,293 @@ pub enum UniformTypeBase {
   // TODO: texture types
   // TODO: shader storage types (like UBO, SSBO, etc.?); -> buffer
 }
+
+impl UniformTypeBase {
+  /// Type of the components.
+  fn component(self) -> Component {
+    match self {
+      Self::Int | Self::Int2 | Self::Int3 | Self::Int4 => Component::Int,
+      Self::Uint | Self::Uint2 | Self::Uint3 | Self::Uint4 => Component::Uint,
+      Self::Bool | Self::Bool2 | Self::Bool3 | Self::Bool4 => Component::Bool,
+      Self::Float
+      | Self::Float2
+      | Self::Float3
+      | Self::Float4
+      | Self::FloatMat22
+      | Self::FloatMat23
+      | Self::FloatMat24
+      | Self::FloatMat32
+      | Self::FloatMat33
+      | Self::FloatMat34
+      | Self::FloatMat42
+      | Self::FloatMat43
+      | Self::FloatMat44 => Component::Float,
+      Self::Double
+      | Self::Double2
+      | Self::Double3
+      | Self::Double4
+      | Self::DoubleMat22
+      | Self::DoubleMat23
+      | Self::DoubleMat24
+      | Self::DoubleMat32
+      | Self::DoubleMat33
+      | Self::DoubleMat34
+      | Self::DoubleMat42
+      | Self::DoubleMat43
+      | Self::DoubleMat44 => Component::Double,
+    }
+  }
+
+  /// Number of columns and rows; vectors are single columns and scalars are `(1, 1)`.
+  fn shape(self) -> (usize, usize) {
+    match self {
+      Self::Int | Self::Uint | Self::Bool | Self::Float | Self::Double => (1, 1),
+      Self::Int2 | Self::Uint2 | Self::Bool2 | Self::Float2 | Self::Double2 => (1, 2),
+      Self::Int3 | Self::Uint3 | Self::Bool3 | Self::Float3 | Self::Double3 => (1, 3),
+      Self::Int4 | Self::Uint4 | Self::Bool4 | Self::Float4 | Self::Double4 => (1, 4),
+      Self::FloatMat22 | Self::DoubleMat22 => (2, 2),
+      Self::FloatMat23 | Self::DoubleMat23 => (2, 3),
+      Self::FloatMat24 | Self::DoubleMat24 => (2, 4),
+      Self::FloatMat32 | Self::DoubleMat32 => (3, 2),
+      Self::FloatMat33 | Self::DoubleMat33 => (3, 3),
+      Self::FloatMat34 | Self::DoubleMat34 => (3, 4),
+      Self::FloatMat42 | Self::DoubleMat42 => (4, 2),
+      Self::FloatMat43 | Self::DoubleMat43 => (4, 3),
+      Self::FloatMat44 | Self::DoubleMat44 => (4, 4),
+    }
+  }
+}
+
+/// Type of the components of uniform values.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Component {
+  Bool,
+  Int,
+  Uint,
+  Float,
+  Double,
+}
+
+impl Component {
+  /// Size in bytes; booleans are 32-bit.
+  fn size(self) -> usize {
+    match self {
+      Self::Double => 8,
+      _ => 4,
+    }
+  }
+
+  fn converts_to(self, to: Self) -> bool {
+    matches!(
+      (self, to),
+      (
+        Self::Bool,
+        Self::Bool | Self::Int | Self::Uint | Self::Float
+      ) | (Self::Int, Self::Int | Self::Float | Self::Double)
+        | (Self::Uint, Self::Uint | Self::Float | Self::Double)
+        | (Self::Float, Self::Float | Self::Double)
+        | (Self::Double, Self::Double)
+    )
+  }
+
+  /// Convert the native-endian bytes of a component of type `from`.
+  fn convert_from(self, from: Self, bytes: &[u8]) -> Vec<u8> {
+    let value = match from {
+      Self::Bool | Self::Uint => u32::from_ne_bytes(bytes.try_into().unwrap()) as f64,
+      Self::Int => i32::from_ne_bytes(bytes.try_into().unwrap()) as f64,
+      Self::Float => f32::from_ne_bytes(bytes.try_into().unwrap()) as f64,
+      Self::Double => f64::from_ne_bytes(bytes.try_into().unwrap()),
+    };
+
+    match self {
+      Self::Bool | Self::Uint => (value as u32).to_ne_bytes().to_vec(),
+      Self::Int => (value as i32).to_ne_bytes().to_vec(),
+      Self::Float => (value as f32).to_ne_bytes().to_vec(),
+      Self::Double => value.to_ne_bytes().to_vec(),
+    }
+  }
+}
+
+/// Rust types that can be used as uniform values.
+///
+/// See [`UniformType::coerce`] for the rules deciding which uniforms a value can be set to.
+pub trait UniformValue {
+  /// Base type of the value: its type, or the type of its elements for arrays.
+  const BASE: UniformTypeBase;
+
+  /// Type of the value.
+  fn uniform_type(&self) -> UniformType;
+
+  /// Append the native-endian bytes of the components of the value; booleans are written as 32-bit integers.
+  fn write_bytes(&self, bytes: &mut Vec<u8>);
+}
+
+/// Components of uniform values, as Rust types.
+trait RawComponent: Copy {
+  fn write(self, bytes: &mut Vec<u8>);
+}
+
+impl RawComponent for bool {
+  fn write(self, bytes: &mut Vec<u8>) {
+    bytes.extend_from_slice(&u32::from(self).to_ne_bytes());
+  }
+}
+
+macro_rules! impl_raw_component {
+  ($($t:ty),+) => {
+    $(
+      impl RawComponent for $t {
+        fn write(self, bytes: &mut Vec<u8>) {
+          bytes.extend_from_slice(&self.to_ne_bytes());
+        }
+      }
+    )+
+  };
+}
+
+impl_raw_component!(i32, u32, f32, f64);
+
+macro_rules! impl_uniform_value {
+  ($($t:ty => $base:ident, |$value:ident, $bytes:ident| $write:expr);+ $(;)?) => {
+    $(
+      impl UniformValue for $t {
+        const BASE: UniformTypeBase = UniformTypeBase::$base;
+
+        fn uniform_type(&self) -> UniformType {
+          Self::BASE.into()
+        }
+
+        fn write_bytes(&self, $bytes: &mut Vec<u8>) {
+          let $value = self;
+          $write
+        }
+      }
+    )+
+  };
+}
+
+macro_rules! impl_uniform_value_scalars {
+  ($($t:ty => $base:ident),+ $(,)?) => {
+    impl_uniform_value!($($t => $base, |value, bytes| value.write(bytes));+);
+  };
+}
+
+macro_rules! impl_uniform_value_vectors {
+  ($($t:ty => $base:ident),+ $(,)?) => {
+    impl_uniform_value!($($t => $base, |value, bytes| value.iter().for_each(|c| c.write(bytes)));+);
+  };
+}
+
+macro_rules! impl_uniform_value_matrices {
+  ($($t:ty => $base:ident),+ $(,)?) => {
+    impl_uniform_value!(
+      $($t => $base, |value, bytes| value.iter().flatten().for_each(|c| c.write(bytes)));+
+    );
+  };
+}
+
+impl_uniform_value_scalars!(
+  i32 => Int,
+  u32 => Uint,
+  bool => Bool,
+  f32 => Float,
+  f64 => Double,
+);
+
+impl_uniform_value_vectors!(
+  [i32; 2] => Int2,
+  [i32; 3] => Int3,
+  [i32; 4] => Int4,
+  [u32; 2] => Uint2,
+  [u32; 3] => Uint3,
+  [u32; 4] => Uint4,
+  [bool; 2] => Bool2,
+  [bool; 3] => Bool3,
+  [bool; 4] => Bool4,
+  [f32; 2] => Float2,
+  [f32; 3] => Float3,
+  [f32; 4] => Float4,
+  [f64; 2] => Double2,
+  [f64; 3] => Double3,
+  [f64; 4] => Double4,
+);
+
+// matrices are arrays of columns
+impl_uniform_value_matrices!(
+  [[f32; 2]; 2] => FloatMat22,
+  [[f32; 3]; 2] => FloatMat23,
+  [[f32; 4]; 2] => FloatMat24,
+  [[f32; 2]; 3] => FloatMat32,
+  [[f32; 3]; 3] => FloatMat33,
+  [[f32; 4]; 3] => FloatMat34,
+  [[f32; 2]; 4] => FloatMat42,
+  [[f32; 3]; 4] => FloatMat43,
+  [[f32; 4]; 4] => FloatMat44,
+  [[f64; 2]; 2] => DoubleMat22,
+  [[f64; 3]; 2] => DoubleMat23,
+  [[f64; 4]; 2] => DoubleMat24,
+  [[f64; 2]; 3] => DoubleMat32,
+  [[f64; 3]; 3] => DoubleMat33,
+  [[f64; 4]; 3] => DoubleMat34,
+  [[f64; 2]; 4] => DoubleMat42,
+  [[f64; 3]; 4] => DoubleMat43,
+  [[f64; 4]; 4] => DoubleMat44,
+);
+
+/// Slices are uniform arrays.
+impl<T> UniformValue for [T]
+where
+  T: UniformValue,
+{
+  const BASE: UniformTypeBase = T::BASE;
+
+  fn uniform_type(&self) -> UniformType {
+    UniformType::new(T::BASE, self.len())
+  }
+
+  fn write_bytes(&self, bytes: &mut Vec<u8>) {
+    for value in self {
+      value.write_bytes(bytes);
+    }
+  }
+}
+
+/// Bindings declared by a shader, as found by reflection.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ShaderBindings {
+  /// Names of the texture (sampler) bindings.
+  pub textures: Vec<String>,
+
+  /// Names of the uniform buffer (block) bindings.
+  pub uniform_buffers: Vec<String>,
+
+  /// Names of the storage buffer (block) bindings.
+  pub storage_buffers: Vec<String>,
+}
+
+/// Interfaces between the stages of a shader, as found by reflection.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ShaderInterface {
+  /// Inputs of the vertex stage, i.e. vertex attributes.
+  pub attributes: Vec<InterfaceVariable>,
+
+  /// Outputs of the last stage before the fragment stage.
+  pub varying_outputs: Vec<InterfaceVariable>,
+
+  /// Inputs of the fragment stage.
+  pub varying_inputs: Vec<InterfaceVariable>,
+}
+
+/// Input or output variable of a shader stage.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct InterfaceVariable {
+  pub name: String,
+
+  /// Location, if explicitly set with `layout(location = …)`.
+  pub location: Option<usize>,
+
+  /// Type, as declared in the shader; integral types are never normalized.
+  pub ty: Type,
+
+  pub array: Option<usize>,
+}
@@ -11,3 +11,54 @@ pub enum SwapChainMode {
   /// Same thing as [`SwapChainMode::Fifo`] but whenever the FIFO is full, new images replaces old ones.
   Mailbox,
 }
+
+/// Format of the images of a swap chain.
+///
+/// Not every platform can present every format; see [`SwapChainCapabilities::formats`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum SwapChainFormat {
+  /// 8-bit red/green/blue/alpha channels, sRGB encoded — the standard dynamic range format every platform supports.
+  SRGBA8,
+
+  /// 10-bit red/green/blue channels and 2-bit alpha channel, for HDR10 output.
+  RGB10A2,
+
+  /// 16-bit floating-point red/green/blue/alpha channels, for scRGB HDR output.
+  RGBA16F,
+}
+
+/// Swap chain parameters supported by the surface swap chains are presented to.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct SwapChainCapabilities {
+  /// Minimum width and height of swap chains.
+  pub min_extent: (u32, u32),
+
+  /// Maximum width and height of swap chains.
+  pub max_extent: (u32, u32),
+
+  /// Minimum number of images of swap chains.
+  pub min_image_count: usize,
+
+  /// Maximum number of images of swap chains.
+  pub max_image_count: usize,
+
+  /// Supported modes. [`SwapChainMode::Fifo`] is always supported.
+  pub modes: Vec<SwapChainMode>,
+
+  /// Supported formats, in order of preference of the platform. [`SwapChainFormat::SRGBA8`] is always supported.
+  pub formats: Vec<SwapChainFormat>,
+}
+
+/// Outcome of presenting an image to a swap chain.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum PresentStatus {
+  /// The image was presented.
+  #[default]
+  Presented,
+
+  /// The image was not presented because the swap chain doesn’t match its surface anymore.
+  OutOfDate,
+
+  /// The image was not presented because the surface of the swap chain is gone.
+  SurfaceLost,
+}
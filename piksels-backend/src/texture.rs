@@ -82,6 +82,24 @@ pub struct Sampling {
   pub depth_comparison: Option<Comparison>,
 }
 
+impl Default for Sampling {
+  /// Default sampling.
+  ///
+  /// - Coordinates clamped to edges.
+  /// - Trilinear filtering.
+  /// - No depth comparison.
+  fn default() -> Self {
+    Self {
+      wrap_r: Wrap::ClampToEdge,
+      wrap_s: Wrap::ClampToEdge,
+      wrap_t: Wrap::ClampToEdge,
+      min_filter: MinFilter::LinearMipmapLinear,
+      mag_filter: MagFilter::Linear,
+      depth_comparison: None,
+    }
+  }
+}
+
 /// Texture storage data.
 ///
 /// A texture can be flat or layered. Flat textures hold a single collection of texels in each of their mipmaps. Layered
@@ -127,17 +145,165 @@ pub enum Storage {
     layers: u32,
   },
 
-  /// Layered 2D texture.
+  /// Multisample layered 2D texture.
   Layered2DMultiSample {
     width: u32,
     height: u32,
     layers: u32,
+    samples: u32,
   },
 
   /// Layered cubemap texture.
   LayeredCubemap { size: u32, layers: u32 },
 }
 
+impl Storage {
+  /// Number of samples per texel for multisample storage.
+  ///
+  /// Return [`None`] for single-sample storage.
+  pub fn samples(self) -> Option<u32> {
+    match self {
+      Storage::Flat2DMultiSample { samples, .. }
+      | Storage::Layered2DMultiSample { samples, .. } => Some(samples),
+      _ => None,
+    }
+  }
+
+  /// Single-sample storage a multisample storage resolves to.
+  ///
+  /// Return [`None`] for single-sample storage.
+  pub fn resolved(self) -> Option<Storage> {
+    match self {
+      Storage::Flat2DMultiSample { width, height, .. } => Some(Storage::Flat2D { width, height }),
+      Storage::Layered2DMultiSample {
+        width,
+        height,
+        layers,
+        ..
+      } => Some(Storage::Layered2D {
+        width,
+        height,
+        layers,
+      }),
+      _ => None,
+    }
+  }
+
+  /// Number of mipmap levels of a full mipmap chain, including the base level.
+  ///
+  /// Multisample storage has a single level.
+  pub fn levels(self) -> usize {
+    let largest = match self {
+      Storage::Flat1D { width } | Storage::Layered1D { width, .. } => width,
+      Storage::Flat2D { width, height } | Storage::Layered2D { width, height, .. } => {
+        width.max(height)
+      }
+      Storage::Flat3D {
+        width,
+        height,
+        depth,
+      } => width.max(height).max(depth),
+      Storage::FlatCubemap { size } | Storage::LayeredCubemap { size, .. } => size,
+      Storage::Flat2DMultiSample { .. } | Storage::Layered2DMultiSample { .. } => return 1,
+    };
+
+    (u32::BITS - largest.max(1).leading_zeros()) as usize
+  }
+
+  /// Multisample storage with `samples` samples per texel a single-sample storage resolves from.
+  ///
+  /// This is the converse of [`Storage::resolved`]. Return [`None`] for storage that cannot be multisampled — only 2D
+  /// and layered 2D storage can.
+  pub fn multisampled(self, samples: u32) -> Option<Storage> {
+    match self {
+      Storage::Flat2D { width, height } | Storage::Flat2DMultiSample { width, height, .. } => {
+        Some(Storage::Flat2DMultiSample {
+          width,
+          height,
+          samples,
+        })
+      }
+      Storage::Layered2D {
+        width,
+        height,
+        layers,
+      }
+      | Storage::Layered2DMultiSample {
+        width,
+        height,
+        layers,
+        ..
+      } => Some(Storage::Layered2DMultiSample {
+        width,
+        height,
+        layers,
+        samples,
+      }),
+      _ => None,
+    }
+  }
+
+  /// Same storage, with a width and height of `width` and `height`.
+  ///
+  /// Depth, layers and samples are kept. Return [`None`] if the size doesn’t fit the storage: 1D storage must have a
+  /// height of `1`, and cubemaps must be square.
+  pub fn resized(self, width: u32, height: u32) -> Option<Storage> {
+    let storage = match self {
+      Storage::Flat1D { .. } if height == 1 => Storage::Flat1D { width },
+      Storage::Flat2D { .. } => Storage::Flat2D { width, height },
+      Storage::Flat2DMultiSample { samples, .. } => Storage::Flat2DMultiSample {
+        width,
+        height,
+        samples,
+      },
+      Storage::Flat3D { depth, .. } => Storage::Flat3D {
+        width,
+        height,
+        depth,
+      },
+      Storage::FlatCubemap { .. } if width == height => Storage::FlatCubemap { size: width },
+      Storage::Layered1D { layers, .. } if height == 1 => Storage::Layered1D { width, layers },
+      Storage::Layered2D { layers, .. } => Storage::Layered2D {
+        width,
+        height,
+        layers,
+      },
+      Storage::Layered2DMultiSample {
+        layers, samples, ..
+      } => Storage::Layered2DMultiSample {
+        width,
+        height,
+        layers,
+        samples,
+      },
+      Storage::LayeredCubemap { layers, .. } if width == height => Storage::LayeredCubemap {
+        size: width,
+        layers,
+      },
+      _ => return None,
+    };
+
+    Some(storage)
+  }
+
+  /// Number of layers a render target attachment can select.
+  ///
+  /// Layers are the depth slices of 3D textures, the layers of layered textures, the faces of cubemaps, and the faces of
+  /// every layer of layered cubemaps — face `f` of layer `l` being layer `6 * l + f`. Return [`None`] for storage without
+  /// layers.
+  pub fn layers(self) -> Option<usize> {
+    match self {
+      Storage::Flat3D { depth, .. } => Some(depth as usize),
+      Storage::FlatCubemap { .. } => Some(6),
+      Storage::Layered1D { layers, .. }
+      | Storage::Layered2D { layers, .. }
+      | Storage::Layered2DMultiSample { layers, .. } => Some(layers as usize),
+      Storage::LayeredCubemap { layers, .. } => Some(6 * layers as usize),
+      Storage::Flat1D { .. } | Storage::Flat2D { .. } | Storage::Flat2DMultiSample { .. } => None,
+    }
+  }
+}
+
 /// Cube face of a cubemap.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum CubeFace {
@@ -160,6 +326,23 @@ pub enum CubeFace {
   NegZ,
 }
 
+impl CubeFace {
+  /// Layer of the face, in order +X, -X, +Y, -Y, +Z, -Z.
+  ///
+  /// This is the value to write to `gl_Layer` to render to the face of a layered cubemap render target; for layered
+  /// cubemaps, add `6 * layer`.
+  pub fn layer(self) -> usize {
+    match self {
+      CubeFace::PosX => 0,
+      CubeFace::NegX => 1,
+      CubeFace::PosY => 2,
+      CubeFace::NegY => 3,
+      CubeFace::PosZ => 4,
+      CubeFace::NegZ => 5,
+    }
+  }
+}
+
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum Size {
   Dim1 { width: u32 },
@@ -187,3 +370,15 @@ pub struct Rect {
   offset: Offset,
   size: Size,
 }
+
+impl Rect {
+  pub fn new(offset: Offset, size: Size) -> Self {
+    Self { offset, size }
+  }
+}
+
+mk_bckd_type_getters!(
+  Rect,
+  offset -> Offset,
+  size -> Size
+);
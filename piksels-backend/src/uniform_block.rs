@@ -0,0 +1,214 @@
+//! Uniform blocks and the std140 layout.
+//!
+//! The content of a uniform buffer must follow the std140 layout rules, which add padding in places Rust doesn’t —
+//! e.g. a `vec3` is aligned on 16 bytes, and every matrix column is padded to a `vec4`. [`Std140`] describes how a
+//! type is laid out according to these rules, and [`UniformBlock`] — derived with `#[derive(UniformBlock)]` from
+//! `piksels-derive` — lays out structs field by field, so that their bytes can be written to uniform buffers.
+//!
+//! Supported field types are:
+//!
+//! - Scalars: `f32`, `i32`, `u32` and `bool`.
+//! - Vectors: `[f32; N]`, `[i32; N]` and `[u32; N]`, with `N` in `2..=4`.
+//! - Matrices: `[[f32; R]; C]`, column-major, with `R` and `C` in `2..=4`.
+//! - Other uniform blocks, and arrays of them.
+//!
+//! The layout the driver actually uses for a block can be queried as a [`UniformBlockLayout`], to check it matches the
+//! one of a Rust struct with [`UniformBlockLayout::check`].
+
+use crate::error::Error;
+
+/// Round `offset` up to the next multiple of `align`, which must be a power of two.
+pub const fn align_up(offset: usize, align: usize) -> usize {
+  (offset + align - 1) & !(align - 1)
+}
+
+/// Types with a std140 layout.
+pub trait Std140 {
+  /// Base alignment, in bytes.
+  const ALIGN: usize;
+
+  /// Size, in bytes.
+  const SIZE: usize;
+
+  /// Write the std140 bytes of the value at the beginning of `bytes`, which must be at least [`Std140::SIZE`] long.
+  fn write_std140(&self, bytes: &mut [u8]);
+}
+
+/// Structs usable as the content of a uniform buffer.
+///
+/// You should not implement this trait by hand but use `#[derive(UniformBlock)]` from `piksels-derive` instead.
+pub trait UniformBlock: Std140 {
+  /// Name and std140 offset of every field, in declaration order.
+  fn field_offsets() -> Vec<(&'static str, usize)>;
+
+  /// Write the std140 bytes of the block to `bytes`.
+  ///
+  /// # Panics
+  ///
+  /// Panics if `bytes` is shorter than [`Std140::SIZE`].
+  fn write_to(&self, bytes: &mut [u8]) {
+    assert!(
+      bytes.len() >= Self::SIZE,
+      "{} bytes are not enough for a uniform block of {} bytes",
+      bytes.len(),
+      Self::SIZE
+    );
+    bytes[..Self::SIZE].fill(0);
+    self.write_std140(bytes);
+  }
+
+  /// Get the std140 bytes of the block.
+  fn to_std140_bytes(&self) -> Vec<u8> {
+    let mut bytes = vec![0; Self::SIZE];
+    self.write_std140(&mut bytes);
+    bytes
+  }
+}
+
+/// Layout of a uniform block, as laid out by the driver.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct UniformBlockLayout {
+  /// Size of the block, in bytes.
+  pub size: usize,
+
+  /// Members of the block.
+  pub members: Vec<UniformBlockMember>,
+}
+
+impl UniformBlockLayout {
+  pub fn member(&self, name: &str) -> Option<&UniformBlockMember> {
+    self.members.iter().find(|member| member.name == name)
+  }
+
+  /// Check that `T` is laid out like the block: same size, and every field of `T` at the offset of the member of the
+  /// same name.
+  pub fn check<T>(&self) -> Result<(), Error>
+  where
+    T: UniformBlock,
+  {
+    let mismatch = |reason| Err(Error::UniformBlockLayoutMismatch { reason });
+
+    if T::SIZE != self.size {
+      return mismatch(format!(
+        "block is {} bytes, but {} bytes on the CPU side",
+        self.size,
+        T::SIZE
+      ));
+    }
+
+    for (name, offset) in T::field_offsets() {
+      let Some(member) = self.member(name) else {
+        return mismatch(format!("no member {name:?} in block"));
+      };
+
+      if member.offset != offset {
+        return mismatch(format!(
+          "member {name:?} is at offset {}, but at offset {offset} on the CPU side",
+          member.offset
+        ));
+      }
+    }
+
+    Ok(())
+  }
+}
+
+/// Member of a uniform block.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct UniformBlockMember {
+  pub name: String,
+
+  /// Offset from the beginning of the block, in bytes.
+  pub offset: usize,
+
+  /// Stride between the elements of an array member, in bytes; `None` if the member is not an array.
+  pub array_stride: Option<usize>,
+
+  /// Stride between the columns of a matrix member, in bytes; `None` if the member is not a matrix.
+  pub matrix_stride: Option<usize>,
+}
+
+macro_rules! impl_std140_scalar {
+  ($($ty:ty => $as:ty),*) => {
+    $(
+      impl Std140 for $ty {
+        const ALIGN: usize = 4;
+        const SIZE: usize = 4;
+
+        fn write_std140(&self, bytes: &mut [u8]) {
+          bytes[..4].copy_from_slice(&(*self as $as).to_ne_bytes());
+        }
+      }
+    )*
+  };
+}
+
+impl_std140_scalar!(f32 => f32, i32 => i32, u32 => u32, bool => u32);
+
+macro_rules! impl_std140_vector {
+  ($($ty:ty),*) => {
+    $(
+      impl_std140_vector!(@impl $ty, 2, 8);
+      impl_std140_vector!(@impl $ty, 3, 16);
+      impl_std140_vector!(@impl $ty, 4, 16);
+    )*
+  };
+
+  (@impl $ty:ty, $n:literal, $align:literal) => {
+    impl Std140 for [$ty; $n] {
+      const ALIGN: usize = $align;
+      const SIZE: usize = 4 * $n;
+
+      fn write_std140(&self, bytes: &mut [u8]) {
+        for (component, bytes) in self.iter().zip(bytes.chunks_exact_mut(4)) {
+          component.write_std140(bytes);
+        }
+      }
+    }
+  };
+}
+
+impl_std140_vector!(f32, i32, u32);
+
+macro_rules! impl_std140_matrix {
+  ($($c:literal),*) => {
+    $(
+      impl_std140_matrix!(@impl $c, 2);
+      impl_std140_matrix!(@impl $c, 3);
+      impl_std140_matrix!(@impl $c, 4);
+    )*
+  };
+
+  (@impl $c:literal, $r:literal) => {
+    // columns are laid out as an array of vectors, whose stride is rounded up to a vec4
+    impl Std140 for [[f32; $r]; $c] {
+      const ALIGN: usize = 16;
+      const SIZE: usize = 16 * $c;
+
+      fn write_std140(&self, bytes: &mut [u8]) {
+        for (column, bytes) in self.iter().zip(bytes.chunks_mut(16)) {
+          column.write_std140(bytes);
+        }
+      }
+    }
+  };
+}
+
+impl_std140_matrix!(2, 3, 4);
+
+/// Arrays of uniform blocks.
+impl<T, const N: usize> Std140 for [T; N]
+where
+  T: UniformBlock,
+{
+  const ALIGN: usize = T::ALIGN;
+  const SIZE: usize = align_up(T::SIZE, T::ALIGN) * N;
+
+  fn write_std140(&self, bytes: &mut [u8]) {
+    let stride = align_up(T::SIZE, T::ALIGN);
+
+    for (i, block) in self.iter().enumerate() {
+      block.write_std140(&mut bytes[i * stride..]);
+    }
+  }
+}
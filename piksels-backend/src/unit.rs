@@ -0,0 +1,58 @@
+//! Units.
+//!
+//! Some backends have the concept of « units » resources are bound to, such as texture units or uniform buffer units.
+//! Backends pick the type of their units with [`Backend::TextureUnit`](crate::Backend::TextureUnit) and
+//! [`Backend::UniformBufferUnit`](crate::Backend::UniformBufferUnit); both must implement [`Unit`].
+
+use std::{fmt::Debug, hash::Hash};
+
+/// A unit resources can be bound to.
+///
+/// Units are ordered, starting at [`Default::default`], each unit being followed by [`Unit::next_unit`].
+pub trait Unit: Clone + Debug + Default + Eq + Hash + Ord + PartialEq + PartialOrd {
+  /// Unit following this one.
+  fn next_unit(&self) -> Self;
+
+  /// Unit at `index`, starting at `0`.
+  ///
+  /// This is typically used to get the first unit past the maximum number of units of a device, e.g. from
+  /// [`DeviceLimits::max_texture_units`](crate::DeviceLimits::max_texture_units). Indices that cannot be represented
+  /// are clamped to the maximum unit.
+  fn from_index(index: usize) -> Self;
+
+  /// Index of the unit, starting at `0`.
+  fn index(&self) -> usize;
+}
+
+/// Units as indices of binding points.
+impl Unit for usize {
+  fn next_unit(&self) -> Self {
+    self + 1
+  }
+
+  fn from_index(index: usize) -> Self {
+    index
+  }
+
+  fn index(&self) -> usize {
+    *self
+  }
+}
+
+/// Units as 32-bit indices, as found in most graphics APIs.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct U32Unit(pub u32);
+
+impl Unit for U32Unit {
+  fn next_unit(&self) -> Self {
+    U32Unit(self.0 + 1)
+  }
+
+  fn from_index(index: usize) -> Self {
+    U32Unit(u32::try_from(index).unwrap_or(u32::MAX))
+  }
+
+  fn index(&self) -> usize {
+    self.0 as usize
+  }
+}
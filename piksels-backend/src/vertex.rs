@@ -8,8 +8,19 @@ pub struct VertexAttr {
 
 impl VertexAttr {
   /// Size in bytes of a vertex attribute.
-  pub fn size(&self) -> usize {
-    self.ty.size() * self.array.unwrap_or(1)
+  pub const fn size(&self) -> usize {
+    match self.array {
+      Some(len) => self.ty.size() * len,
+      None => self.ty.size(),
+    }
+  }
+
+  /// Number of consecutive attribute locations (slots) used by a vertex attribute.
+  pub const fn locations(&self) -> usize {
+    match self.array {
+      Some(len) => self.ty.locations() * len,
+      None => self.ty.locations(),
+    }
   }
 }
 
@@ -36,11 +47,20 @@ pub enum Type {
   Bool2,
   Bool3,
   Bool4,
+
+  /// 2×2 matrix, made of two [`Type::Float2`] columns.
+  FloatMat2,
+
+  /// 3×3 matrix, made of three [`Type::Float3`] columns.
+  FloatMat3,
+
+  /// 4×4 matrix, made of four [`Type::Float4`] columns.
+  FloatMat4,
 }
 
 impl Type {
   /// Size in bytes.
-  pub fn size(&self) -> usize {
+  pub const fn size(&self) -> usize {
     match self {
       Self::Int(_) | Self::Uint(_) | Self::Float | Self::Bool => 4,
       Self::Int2(_) | Self::Uint2(_) | Self::Float2 | Self::Bool2 => 4 * 2,
@@ -50,26 +70,77 @@ impl Type {
       Self::Double2 => 8 * 2,
       Self::Double3 => 8 * 3,
       Self::Double4 => 8 * 4,
+      Self::FloatMat2 => 4 * 2 * 2,
+      Self::FloatMat3 => 4 * 3 * 3,
+      Self::FloatMat4 => 4 * 4 * 4,
+    }
+  }
+
+  /// Alignment in bytes, which is the size of a single component.
+  pub const fn align(&self) -> usize {
+    match self {
+      Self::Double | Self::Double2 | Self::Double3 | Self::Double4 => 8,
+      _ => 4,
     }
   }
 
   /// Vector dimension.
   ///
-  /// This makes sense only for vectors. Scalars always have a dimension of `1`.
-  pub fn vector_dim(&self) -> usize {
+  /// This makes sense only for vectors. Scalars always have a dimension of `1`. For matrices, this is the dimension of
+  /// a column.
+  pub const fn vector_dim(&self) -> usize {
     match self {
-      Self::Int2(_) | Self::Uint2(_) | Self::Float2 | Self::Double2 | Self::Bool2 => 2,
-      Self::Int3(_) | Self::Uint3(_) | Self::Float3 | Self::Double3 | Self::Bool3 => 3,
-      Self::Int4(_) | Self::Uint4(_) | Self::Float4 | Self::Double4 | Self::Bool4 => 4,
+      Self::Int2(_)
+      | Self::Uint2(_)
+      | Self::Float2
+      | Self::Double2
+      | Self::Bool2
+      | Self::FloatMat2 => 2,
+      Self::Int3(_)
+      | Self::Uint3(_)
+      | Self::Float3
+      | Self::Double3
+      | Self::Bool3
+      | Self::FloatMat3 => 3,
+      Self::Int4(_)
+      | Self::Uint4(_)
+      | Self::Float4
+      | Self::Double4
+      | Self::Bool4
+      | Self::FloatMat4 => 4,
       _ => 1,
     }
   }
 
+  /// Number of consecutive attribute locations (slots) used.
+  ///
+  /// Matrices use one location per column; all the other types use a single location.
+  pub const fn locations(&self) -> usize {
+    match self {
+      Self::FloatMat2 => 2,
+      Self::FloatMat3 => 3,
+      Self::FloatMat4 => 4,
+      _ => 1,
+    }
+  }
+
+  /// Type of a single attribute location.
+  ///
+  /// This is the type of a column for matrices, and the type itself otherwise.
+  pub const fn location_type(self) -> Self {
+    match self {
+      Self::FloatMat2 => Self::Float2,
+      Self::FloatMat3 => Self::Float3,
+      Self::FloatMat4 => Self::Float4,
+      _ => self,
+    }
+  }
+
   /// Normalize a vertex attribute type if it’s integral.
   ///
   /// Return the normalized integer vertex attribute type if non-normalized. Otherwise, return the
   /// vertex attribute type directly.
-  pub fn normalize(self) -> Self {
+  pub const fn normalize(self) -> Self {
     match self {
       Self::Int(Normalized::No) => Self::Int(Normalized::Yes),
       Self::Int2(Normalized::No) => Self::Int2(Normalized::Yes),
@@ -92,3 +163,145 @@ pub enum Normalized {
   /// Do not perform any normalization and hence leave integral values as-is.
   No,
 }
+
+/// Types that can be used as interleaved vertices.
+///
+/// You should not implement this trait by hand but use `#[derive(Vertex)]` from `piksels-derive` instead, which
+/// checks the requirements below at compile-time.
+///
+/// # Safety
+///
+/// The type must be `#[repr(C)]`, without padding, and [`Vertex::vertex_attrs`] must describe its fields in order.
+pub unsafe trait Vertex: Copy {
+  /// Vertex attributes, one per field.
+  fn vertex_attrs() -> Vec<VertexAttr>;
+
+  /// Bytes of a vertex.
+  fn as_bytes(&self) -> &[u8] {
+    // SAFETY: the type has no padding, so all of its bytes are initialized
+    unsafe {
+      std::slice::from_raw_parts(
+        self as *const Self as *const u8,
+        std::mem::size_of::<Self>(),
+      )
+    }
+  }
+
+  /// Bytes of a slice of vertices, ready for interleaved upload.
+  fn slice_as_bytes(vertices: &[Self]) -> &[u8] {
+    // SAFETY: the type has no padding, so all of its bytes are initialized
+    unsafe {
+      std::slice::from_raw_parts(
+        vertices.as_ptr() as *const u8,
+        std::mem::size_of_val(vertices),
+      )
+    }
+  }
+}
+
+/// Rust types that can be used as vertex attributes.
+pub trait VertexAttrType {
+  /// Vertex attribute type the Rust type maps to.
+  const TYPE: Type;
+}
+
+macro_rules! impl_vertex_attr_type {
+  ($($t:ty => $v:expr),+ $(,)?) => {
+    $(
+      impl VertexAttrType for $t {
+        const TYPE: Type = $v;
+      }
+    )+
+  };
+}
+
+impl_vertex_attr_type!(
+  i32 => Type::Int(Normalized::No),
+  [i32; 2] => Type::Int2(Normalized::No),
+  [i32; 3] => Type::Int3(Normalized::No),
+  [i32; 4] => Type::Int4(Normalized::No),
+  u32 => Type::Uint(Normalized::No),
+  [u32; 2] => Type::Uint2(Normalized::No),
+  [u32; 3] => Type::Uint3(Normalized::No),
+  [u32; 4] => Type::Uint4(Normalized::No),
+  f32 => Type::Float,
+  [f32; 2] => Type::Float2,
+  [f32; 3] => Type::Float3,
+  [f32; 4] => Type::Float4,
+  f64 => Type::Double,
+  [f64; 2] => Type::Double2,
+  [f64; 3] => Type::Double3,
+  [f64; 4] => Type::Double4,
+  [[f32; 2]; 2] => Type::FloatMat2,
+  [[f32; 3]; 3] => Type::FloatMat3,
+  [[f32; 4]; 4] => Type::FloatMat4,
+);
+
+/// Offset in bytes of a field in a struct, usable in const contexts.
+///
+/// ```
+/// # use piksels_backend::field_offset;
+/// #[repr(C)]
+/// struct Vertex {
+///   position: [f32; 3],
+///   color: [f32; 4],
+/// }
+///
+/// const COLOR_OFFSET: usize = field_offset!(Vertex, color);
+/// assert_eq!(COLOR_OFFSET, 12);
+/// ```
+#[macro_export]
+macro_rules! field_offset {
+  ($ty:ty, $field:ident) => {{
+    let uninit = ::std::mem::MaybeUninit::<$ty>::uninit();
+    let base = uninit.as_ptr();
+
+    // SAFETY: only the address of the field is computed; the uninitialized memory is never read
+    unsafe {
+      (::std::ptr::addr_of!((*base).$field) as *const u8).offset_from(base as *const u8) as usize
+    }
+  }};
+}
+
+/// Assert at compile-time that the layout of a struct matches vertex attribute types.
+///
+/// Fields must be listed in declaration order, along with the [`Type`] they are declared with. Each field must start
+/// right where the previous one ends, and the size of the struct must be the sum of the sizes of the attributes.
+/// Any mismatch — a reordered field, a padding byte, a field which Rust type doesn’t match its vertex attribute type
+/// — is a compile error.
+///
+/// ```
+/// # use piksels_backend::{assert_vertex_layout, vertex::Type};
+/// #[repr(C)]
+/// struct Vertex {
+///   position: [f32; 3],
+///   color: [f32; 4],
+/// }
+///
+/// assert_vertex_layout!(Vertex {
+///   position: Type::Float3,
+///   color: Type::Float4,
+/// });
+/// ```
+#[macro_export]
+macro_rules! assert_vertex_layout {
+  ($ty:ty { $($field:ident : $attr_ty:expr),* $(,)? }) => {
+    const _: () = {
+      #[allow(unused_mut)]
+      let mut offset = 0;
+
+      $(
+        assert!(
+          $crate::field_offset!($ty, $field) == offset,
+          concat!("unexpected offset of ", stringify!($ty), "::", stringify!($field))
+        );
+        offset += $crate::vertex::Type::size(&$attr_ty);
+      )*
+
+      assert!(
+        ::std::mem::size_of::<$ty>() == offset,
+        concat!("size of ", stringify!($ty), " doesn’t match its vertex attributes")
+      );
+    };
+  };
+}
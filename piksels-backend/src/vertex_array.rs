@@ -1,4 +1,12 @@
-use crate::vertex::VertexAttr;
+#[cfg(feature = "bytemuck")]
+use bytemuck::Pod;
+
+use crate::vertex::{Vertex, VertexAttr};
+#[cfg(feature = "bytemuck")]
+use crate::{
+  error::Error,
+  vertex::{Type, VertexAttrType},
+};
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct VertexArrayData {
@@ -11,6 +19,50 @@ impl VertexArrayData {
     VertexArrayData { attrs, layout }
   }
 
+  /// Interleaved data from a slice of vertices.
+  pub fn from_vertices<V>(vertices: &[V]) -> Self
+  where
+    V: Vertex,
+  {
+    Self::new(
+      V::vertex_attrs(),
+      MemoryLayout::Interleaved {
+        data: V::slice_as_bytes(vertices).to_vec(),
+      },
+    )
+  }
+
+  /// Interleaved data from a slice of vertices.
+  ///
+  /// Unlike [`VertexArrayData::from_vertices`], the size of the vertex type is checked against the size of its
+  /// attributes.
+  #[cfg(feature = "bytemuck")]
+  pub fn from_slice<V>(vertices: &[V]) -> Result<Self, Error>
+  where
+    V: Vertex + Pod,
+  {
+    let attrs = V::vertex_attrs();
+    let vertex_len: usize = attrs.iter().map(VertexAttr::size).sum();
+
+    if vertex_len != std::mem::size_of::<V>() {
+      return Err(Error::InvalidVertexData {
+        reason: format!(
+          "vertex is {} bytes but its attributes are {vertex_len} bytes",
+          std::mem::size_of::<V>()
+        ),
+      });
+    }
+
+    let data = bytemuck::cast_slice(vertices).to_vec();
+    Ok(Self::new(attrs, MemoryLayout::Interleaved { data }))
+  }
+
+  /// Build deinterleaved data, one slice per attribute.
+  #[cfg(feature = "bytemuck")]
+  pub fn deinterleaved() -> DeinterleavedBuilder {
+    DeinterleavedBuilder::default()
+  }
+
   pub fn attrs(&self) -> &[VertexAttr] {
     &self.attrs
   }
@@ -26,12 +78,7 @@ impl VertexArrayData {
       // and simply divide the data length by this value
       MemoryLayout::Interleaved { ref data } => {
         let vertex_len: usize = self.attrs.iter().map(VertexAttr::size).sum();
-
-        if vertex_len == 0 {
-          0
-        } else {
-          data.len() / vertex_len
-        }
+        data.len().checked_div(vertex_len).unwrap_or(0)
       }
 
       // for deinterleaved memory, we are supposed to have the same number of vertices in each array, so we can simply just
@@ -54,6 +101,94 @@ impl VertexArrayData {
   }
 }
 
+/// Builder of deinterleaved [`VertexArrayData`].
+///
+/// Attributes are indexed in the order they are added; an attribute using several locations, such as a matrix, shifts
+/// the index of the next ones accordingly.
+#[cfg(feature = "bytemuck")]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DeinterleavedBuilder {
+  attrs: Vec<VertexAttr>,
+  data_per_attr: Vec<Vec<u8>>,
+}
+
+#[cfg(feature = "bytemuck")]
+impl DeinterleavedBuilder {
+  /// Add an attribute along with its data.
+  pub fn attr<T>(self, name: &'static str, data: &[T]) -> Self
+  where
+    T: Pod + VertexAttrType,
+  {
+    self.push_attr(name, T::TYPE, data)
+  }
+
+  /// Add a normalized integral attribute along with its data.
+  pub fn normalized_attr<T>(self, name: &'static str, data: &[T]) -> Self
+  where
+    T: Pod + VertexAttrType,
+  {
+    self.push_attr(name, T::TYPE.normalize(), data)
+  }
+
+  fn push_attr<T>(mut self, name: &'static str, ty: Type, data: &[T]) -> Self
+  where
+    T: Pod,
+  {
+    let index = self
+      .attrs
+      .last()
+      .map_or(0, |attr| attr.index + attr.locations());
+
+    self.attrs.push(VertexAttr {
+      index,
+      name,
+      ty,
+      array: None,
+    });
+    self.data_per_attr.push(bytemuck::cast_slice(data).to_vec());
+    self
+  }
+
+  /// Build the data, checking that all the attributes have the same number of vertices.
+  pub fn build(self) -> Result<VertexArrayData, Error> {
+    let mut vertex_count = None;
+
+    for (attr, data) in self.attrs.iter().zip(&self.data_per_attr) {
+      if data.len() % attr.size() != 0 {
+        return Err(Error::InvalidVertexData {
+          reason: format!(
+            "{} bytes of data for attribute {} of {} bytes",
+            data.len(),
+            attr.name,
+            attr.size()
+          ),
+        });
+      }
+
+      let count = data.len() / attr.size();
+      match vertex_count {
+        Some(vertex_count) if vertex_count != count => {
+          return Err(Error::InvalidVertexData {
+            reason: format!(
+              "attribute {} has {count} vertices, expected {vertex_count}",
+              attr.name
+            ),
+          });
+        }
+
+        _ => vertex_count = Some(count),
+      }
+    }
+
+    Ok(VertexArrayData::new(
+      self.attrs,
+      MemoryLayout::Deinterleaved {
+        data_per_attr: self.data_per_attr,
+      },
+    ))
+  }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum MemoryLayout {
   /// Memory is interleaved; i.e. { x0, y0, r0, g0, b0, x1, y1, r1, g1, b1 }.
@@ -63,20 +198,164 @@ pub enum MemoryLayout {
   Deinterleaved { data_per_attr: Vec<Vec<u8>> },
 }
 
+/// Rate at which a [`VertexStream`] advances.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum StepRate {
+  /// Advance once per vertex.
+  PerVertex,
+
+  /// Advance once every `divisor` instances.
+  PerInstance { divisor: u32 },
+}
+
+/// A stream of vertex data, along with the rate at which it advances.
+///
+/// A vertex array is made of several streams — e.g. static attributes, skinning data and morph targets each in their
+/// own buffer, and per-instance data. Attribute indices must be unique across all the streams of a vertex array.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VertexStream {
+  data: VertexArrayData,
+  rate: StepRate,
+}
+
+impl VertexStream {
+  pub fn new(data: VertexArrayData, rate: StepRate) -> Self {
+    Self { data, rate }
+  }
+
+  /// Stream advancing once per vertex.
+  pub fn per_vertex(data: VertexArrayData) -> Self {
+    Self::new(data, StepRate::PerVertex)
+  }
+
+  /// Stream advancing once per instance.
+  pub fn per_instance(data: VertexArrayData) -> Self {
+    Self::new(data, StepRate::PerInstance { divisor: 1 })
+  }
+
+  pub fn data(&self) -> &VertexArrayData {
+    &self.data
+  }
+
+  pub fn rate(&self) -> StepRate {
+    self.rate
+  }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum DataSelector {
-  /// Select interleaved vertices.
+  /// Select interleaved vertices (first per-vertex stream).
   InterleavedVertices,
 
-  /// Select interleaved vertex instances.
+  /// Select interleaved vertex instances (first per-instance stream).
   InterleavedVertexInstances,
 
-  /// Select deinterleaved vertices (field identified by `index`).
+  /// Select deinterleaved vertices (field identified by `index`, in the first per-vertex stream).
   DeinterleavedVertices { index: usize },
 
-  /// Select deinterleaved vertex instances (field identified by `index`).
+  /// Select deinterleaved vertex instances (field identified by `index`, in the first per-instance stream).
   DeinterleavedVertexInstances { index: usize },
 
+  /// Select the interleaved data of the stream at index `stream`.
+  InterleavedStream { stream: usize },
+
+  /// Select deinterleaved data (field identified by `index`) of the stream at index `stream`.
+  DeinterleavedStream { stream: usize, index: usize },
+
   /// Select indices.
   Indices,
 }
+
+/// Partial update of the data of a vertex array.
+///
+/// `bytes` are written `offset` bytes after the start of the data selected by the [`DataSelector`]; the rest of the
+/// data is left untouched. With deinterleaved memory, this allows to update a sub-range of a single attribute stream.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VertexArrayUpdate<'a> {
+  selector: DataSelector,
+  offset: usize,
+  bytes: &'a [u8],
+}
+
+impl<'a> VertexArrayUpdate<'a> {
+  pub fn new(selector: DataSelector, offset: usize, bytes: &'a [u8]) -> Self {
+    Self {
+      selector,
+      offset,
+      bytes,
+    }
+  }
+
+  /// Update the interleaved data of the stream at index `stream`.
+  pub fn stream(stream: usize, offset: usize, bytes: &'a [u8]) -> Self {
+    Self::new(DataSelector::InterleavedStream { stream }, offset, bytes)
+  }
+
+  /// Update a single attribute of the deinterleaved stream at index `stream`.
+  pub fn stream_attr(stream: usize, index: usize, offset: usize, bytes: &'a [u8]) -> Self {
+    Self::new(
+      DataSelector::DeinterleavedStream { stream, index },
+      offset,
+      bytes,
+    )
+  }
+
+  /// Update interleaved vertices.
+  pub fn vertices(offset: usize, bytes: &'a [u8]) -> Self {
+    Self::new(DataSelector::InterleavedVertices, offset, bytes)
+  }
+
+  /// Update interleaved vertex instances.
+  pub fn instances(offset: usize, bytes: &'a [u8]) -> Self {
+    Self::new(DataSelector::InterleavedVertexInstances, offset, bytes)
+  }
+
+  /// Update the deinterleaved vertex attribute identified by `index`.
+  pub fn vertex_attr(index: usize, offset: usize, bytes: &'a [u8]) -> Self {
+    Self::new(DataSelector::DeinterleavedVertices { index }, offset, bytes)
+  }
+
+  /// Update the deinterleaved vertex instance attribute identified by `index`.
+  pub fn instance_attr(index: usize, offset: usize, bytes: &'a [u8]) -> Self {
+    Self::new(
+      DataSelector::DeinterleavedVertexInstances { index },
+      offset,
+      bytes,
+    )
+  }
+
+  /// Update indices.
+  pub fn indices(offset: usize, indices: &'a [u32]) -> Self {
+    // SAFETY: u32 has no padding, and u8 has no alignment requirement
+    let bytes = unsafe {
+      std::slice::from_raw_parts(indices.as_ptr().cast(), std::mem::size_of_val(indices))
+    };
+
+    Self::new(DataSelector::Indices, offset, bytes)
+  }
+
+  pub fn selector(&self) -> &DataSelector {
+    &self.selector
+  }
+
+  pub fn offset(&self) -> usize {
+    self.offset
+  }
+
+  pub fn bytes(&self) -> &'a [u8] {
+    self.bytes
+  }
+}
+
+/// Access requested when mapping vertex array data.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MapAccess {
+  /// Mapped bytes are only read from.
+  Read,
+
+  /// Mapped bytes are only written to; their previous content is undefined.
+  Write,
+
+  /// Mapped bytes are read from and written to.
+  ReadWrite,
+}
@@ -1,4 +1,5 @@
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Viewport {
   /// The whole viewport is used. The position and dimension of the viewport rectangle are
   /// extracted from the framebuffer.
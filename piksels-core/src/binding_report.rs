@@ -0,0 +1,219 @@
+//! Binding reports.
+//!
+//! A [`BindingReport`] records, for each draw of a command buffer, which textures, uniform buffers and storage buffers
+//! were bound to which binding points (units). Displaying it prints a table in which the bindings that changed since
+//! the previous draw are marked with a `*`, which helps diagnosing wrong-texture-bound bugs without an external GPU
+//! debugger.
+//!
+//! Use [`CmdBuf::record_bindings`](crate::cmd_buf::CmdBuf::record_bindings) to start recording.
+
+use std::{
+  collections::BTreeMap,
+  fmt::{self, Display},
+};
+
+use piksels_backend::Backend;
+
+use crate::cache::ResourceId;
+
+/// Bindings in effect for a draw.
+#[derive(Debug)]
+pub struct DrawBindings<B>
+where
+  B: Backend,
+{
+  /// Vertex array drawn.
+  pub vertex_array: ResourceId<B>,
+
+  /// Shader in use, if any was bound while recording.
+  pub shader: Option<ResourceId<B>>,
+
+  /// Textures, by texture binding point.
+  pub textures: BTreeMap<ResourceId<B>, ResourceId<B>>,
+
+  /// Uniform buffers, by uniform buffer binding point.
+  pub uniform_buffers: BTreeMap<ResourceId<B>, ResourceId<B>>,
+
+  /// Storage buffers, by storage buffer binding point.
+  pub storage_buffers: BTreeMap<ResourceId<B>, ResourceId<B>>,
+}
+
+/// Bindings of all the draws recorded in a command buffer.
+#[derive(Debug)]
+pub struct BindingReport<B>
+where
+  B: Backend,
+{
+  draws: Vec<DrawBindings<B>>,
+}
+
+impl<B> Default for BindingReport<B>
+where
+  B: Backend,
+{
+  fn default() -> Self {
+    Self { draws: Vec::new() }
+  }
+}
+
+impl<B> BindingReport<B>
+where
+  B: Backend,
+{
+  pub fn draws(&self) -> &[DrawBindings<B>] {
+    &self.draws
+  }
+}
+
+impl<B> Display for BindingReport<B>
+where
+  B: Backend,
+{
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    const HEADER: [&str; 6] = [
+      "draw",
+      "vertex array",
+      "shader",
+      "textures",
+      "uniform buffers",
+      "storage buffers",
+    ];
+
+    let mut rows = vec![HEADER.map(str::to_owned)];
+    let mut previous: Option<&DrawBindings<B>> = None;
+
+    for (i, draw) in self.draws.iter().enumerate() {
+      let shader = match draw.shader {
+        Some(ref shader) => format!("{shader:?}"),
+        None => "-".to_owned(),
+      };
+      let shader_changed = previous.is_some_and(|previous| previous.shader != draw.shader);
+
+      rows.push([
+        i.to_string(),
+        format!("{:?}", draw.vertex_array),
+        format!("{shader}{}", if shader_changed { "*" } else { "" }),
+        fmt_bindings(&draw.textures, previous.map(|previous| &previous.textures)),
+        fmt_bindings(
+          &draw.uniform_buffers,
+          previous.map(|previous| &previous.uniform_buffers),
+        ),
+        fmt_bindings(
+          &draw.storage_buffers,
+          previous.map(|previous| &previous.storage_buffers),
+        ),
+      ]);
+
+      previous = Some(draw);
+    }
+
+    let mut widths = [0; HEADER.len()];
+    for row in &rows {
+      for (width, cell) in widths.iter_mut().zip(row) {
+        *width = (*width).max(cell.chars().count());
+      }
+    }
+
+    for row in &rows {
+      let line = row
+        .iter()
+        .zip(widths)
+        .map(|(cell, width)| format!("{cell:<width$}"))
+        .collect::<Vec<_>>()
+        .join(" | ");
+      writeln!(f, "{}", line.trim_end())?;
+    }
+
+    Ok(())
+  }
+}
+
+/// Format bindings as `point: resource`, marking with a `*` those which changed since the previous draw.
+fn fmt_bindings<I>(bindings: &BTreeMap<I, I>, previous: Option<&BTreeMap<I, I>>) -> String
+where
+  I: fmt::Debug + Ord,
+{
+  if bindings.is_empty() {
+    return "-".to_owned();
+  }
+
+  bindings
+    .iter()
+    .map(|(point, resource)| {
+      let changed = previous.is_some_and(|previous| previous.get(point) != Some(resource));
+      format!("{point:?}: {resource:?}{}", if changed { "*" } else { "" })
+    })
+    .collect::<Vec<_>>()
+    .join(", ")
+}
+
+/// Binding state of a command buffer being recorded.
+#[derive(Debug)]
+pub(crate) struct BindingRecorder<B>
+where
+  B: Backend,
+{
+  shader: Option<ResourceId<B>>,
+  textures: BTreeMap<ResourceId<B>, ResourceId<B>>,
+  uniform_buffers: BTreeMap<ResourceId<B>, ResourceId<B>>,
+  storage_buffers: BTreeMap<ResourceId<B>, ResourceId<B>>,
+  report: BindingReport<B>,
+}
+
+impl<B> Default for BindingRecorder<B>
+where
+  B: Backend,
+{
+  fn default() -> Self {
+    Self {
+      shader: None,
+      textures: BTreeMap::new(),
+      uniform_buffers: BTreeMap::new(),
+      storage_buffers: BTreeMap::new(),
+      report: BindingReport::default(),
+    }
+  }
+}
+
+impl<B> BindingRecorder<B>
+where
+  B: Backend,
+{
+  pub(crate) fn bind_shader(&mut self, shader: ResourceId<B>) {
+    self.shader = Some(shader);
+  }
+
+  pub(crate) fn bind_texture(&mut self, binding_point: ResourceId<B>, texture: ResourceId<B>) {
+    self.textures.insert(binding_point, texture);
+  }
+
+  pub(crate) fn bind_uniform_buffer(
+    &mut self,
+    binding_point: ResourceId<B>,
+    uniform_buffer: ResourceId<B>,
+  ) {
+    self.uniform_buffers.insert(binding_point, uniform_buffer);
+  }
+
+  pub(crate) fn bind_storage_buffer(
+    &mut self,
+    binding_point: ResourceId<B>,
+    storage_buffer: ResourceId<B>,
+  ) {
+    self.storage_buffers.insert(binding_point, storage_buffer);
+  }
+
+  pub(crate) fn draw(&mut self, vertex_array: ResourceId<B>) {
+    self.report.draws.push(DrawBindings {
+      vertex_array,
+      shader: self.shader.clone(),
+      textures: self.textures.clone(),
+      uniform_buffers: self.uniform_buffers.clone(),
+      storage_buffers: self.storage_buffers.clone(),
+    });
+  }
+
+  pub(crate) fn into_report(self) -> BindingReport<B> {
+    self.report
+  }
+}
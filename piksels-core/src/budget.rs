@@ -0,0 +1,55 @@
+//! Recording budgets of command buffers.
+//!
+//! A [`RecordingBudget`] limits the number of draws and state changes a [`CmdBuf`](crate::cmd_buf::CmdBuf) can
+//! record before being finished. It catches accidental unbounded loops — e.g. a scene traversal visiting the same nodes
+//! over and over — which otherwise only show up as a frame that never ends. By default, exceeding a limit emits a
+//! warning through the backend logger, once per limit and per recording; in strict mode, the offending command is
+//! rejected with [`Error::RecordingBudgetExceeded`](piksels_backend::error::Error::RecordingBudgetExceeded) instead.
+
+/// Limits on what a command buffer can record before being finished.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct RecordingBudget {
+  max_draws: Option<usize>,
+  max_state_changes: Option<usize>,
+  strict: bool,
+}
+
+impl RecordingBudget {
+  /// Budget without limits.
+  pub fn unlimited() -> Self {
+    Self::default()
+  }
+
+  /// Record at most `max_draws` draws.
+  pub fn draws(mut self, max_draws: usize) -> Self {
+    self.max_draws = Some(max_draws);
+    self
+  }
+
+  /// Record at most `max_state_changes` state changes.
+  ///
+  /// State changes are changes of pipeline variables (blending, depth test, viewport, etc.) and bindings of render
+  /// targets, shaders, textures, color attachments, uniform buffers and storage buffers.
+  pub fn state_changes(mut self, max_state_changes: usize) -> Self {
+    self.max_state_changes = Some(max_state_changes);
+    self
+  }
+
+  /// Reject commands exceeding the budget with an error, instead of warning.
+  pub fn strict(mut self) -> Self {
+    self.strict = true;
+    self
+  }
+
+  pub fn max_draws(&self) -> Option<usize> {
+    self.max_draws
+  }
+
+  pub fn max_state_changes(&self) -> Option<usize> {
+    self.max_state_changes
+  }
+
+  pub fn is_strict(&self) -> bool {
+    self.strict
+  }
+}
@@ -0,0 +1,964 @@
+//! Resource tracking.
+//!
+//! Every resource created through a [`Device`](crate::device::Device) is tracked by its scarce index in the
+//! [`Cache`]. This allows to refer to resources by [`ResourceId`], for instance in render graphs or editors, and to get
+//! back a handle on them later.
+//!
+//! The cache also tracks the dropped resources waiting to be destroyed (see [`gc`](crate::gc)). The pipeline state is
+//! cached by each command buffer, the cache only telling them when to forget it (see [`StateGenerations`]).
+
+use std::{
+  collections::{HashMap, VecDeque},
+  fmt::{self, Display},
+  sync::Arc,
+};
+
+use piksels_backend::{
+  blending::BlendingMode,
+  cache::Cached,
+  color::RGBA32F,
+  depth_stencil::{DepthTest, DepthWrite, StencilTest},
+  error::Error,
+  face_culling::FaceCulling,
+  pixel::Pixel,
+  render_targets::ColorAttachmentPoint,
+  scissor::Scissor,
+  swap_chain::SwapChainFormat,
+  texture::Storage,
+  viewport::Viewport,
+  Backend, DeviceLimits, Scarce,
+};
+
+#[cfg(feature = "ext-profiler")]
+use crate::profiler::{PendingTiming, Timing};
+use crate::{
+  frame::FrameIndex,
+  gc::{DropErrors, Garbage, GcMode},
+  name::NamedKind,
+  pipeline::{PipelineDefaults, PipelineState, PipelineStateDiff},
+  shader::{ShaderBindingPoints, ShaderKey},
+  units::{Unit, UnitBinding, Units},
+};
+
+/// Unique identifier of a resource.
+///
+/// A resource identifier is the scarce index of the resource, and is unique for a given resource type.
+pub type ResourceId<B> = <B as Backend>::ScarceIndex;
+
+/// Tag telling apart the resources tracked with the same [`ResourceId`] over time.
+///
+/// Backends reuse the scarce indices of destroyed resources. Handles keep the tag their resource was tracked with, so
+/// that dropping a handle that outlived its resource doesn’t untrack another resource that got the same index.
+///
+/// The default tag is never given to a tracked resource: handles with it (e.g. internal copies of handles, or the
+/// render targets of swap chain images) don’t count as handles on their resource.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct TrackingTag(u64);
+
+/// Generations of the state cached by command buffers.
+///
+/// Each [`CmdBuf`](crate::cmd_buf::CmdBuf) caches the state it set itself. A generation is bumped whenever that state
+/// might have changed behind the back of command buffers, so that they forget it.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct StateGenerations {
+  pub pipeline: u64,
+  pub shader: u64,
+  pub render_targets: u64,
+  pub uniforms: u64,
+}
+
+/// Structured description of everything a [`Cache`] tracks, to diagnose issues such as resources not being bound.
+///
+/// Get one with [`Cache::debug_dump`], or [`Device::debug_dump`](crate::device::Device::debug_dump). The pipeline
+/// state is cached by command buffers, not by the cache; see [`CmdBuf::snapshot`](crate::cmd_buf::CmdBuf::snapshot).
+/// The [`Display`] implementation lists everything in a readable way.
+#[derive(Debug)]
+pub struct CacheDump<B>
+where
+  B: Backend,
+{
+  pub cmd_bufs: Vec<TrackedDump<B>>,
+  pub render_targets: Vec<TrackedDump<B>>,
+  pub shaders: Vec<TrackedDump<B>>,
+  pub storage_buffers: Vec<TrackedDump<B>>,
+  pub swap_chains: Vec<TrackedDump<B>>,
+  pub textures: Vec<TrackedDump<B>>,
+  pub vertex_arrays: Vec<TrackedDump<B>>,
+  /// Textures bound to texture units.
+  pub texture_units: Vec<UnitBinding<B, B::TextureUnit>>,
+  /// Uniform buffers bound to uniform buffer units.
+  pub uniform_buffer_units: Vec<UnitBinding<B, B::UniformBufferUnit>>,
+  pub state_generations: StateGenerations,
+  /// Number of dropped resources waiting to be destroyed.
+  pub garbage_len: usize,
+  pub frame: FrameIndex,
+}
+
+/// Description of a tracked resource.
+#[derive(Debug)]
+pub struct TrackedDump<B>
+where
+  B: Backend,
+{
+  pub id: ResourceId<B>,
+  /// Name given to the resource, if any.
+  pub name: Option<String>,
+  /// Information tracked about the resource, e.g. its storage.
+  pub info: String,
+}
+
+impl<B> Display for CacheDump<B>
+where
+  B: Backend,
+{
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    fn resources<B>(
+      f: &mut fmt::Formatter<'_>,
+      title: &str,
+      resources: &[TrackedDump<B>],
+    ) -> fmt::Result
+    where
+      B: Backend,
+    {
+      writeln!(f, "{title}: {}", resources.len())?;
+
+      for resource in resources {
+        write!(f, "  {:?}", resource.id)?;
+
+        if let Some(name) = &resource.name {
+          write!(f, " {name:?}")?;
+        }
+
+        if resource.info.is_empty() {
+          writeln!(f)?;
+        } else {
+          writeln!(f, ": {}", resource.info)?;
+        }
+      }
+
+      Ok(())
+    }
+
+    fn units<B, U>(
+      f: &mut fmt::Formatter<'_>,
+      title: &str,
+      units: &[UnitBinding<B, U>],
+    ) -> fmt::Result
+    where
+      B: Backend,
+      U: Unit,
+    {
+      writeln!(f, "{title}: {}", units.len())?;
+
+      for binding in units {
+        let state = if binding.in_use { "in use" } else { "idle" };
+        writeln!(
+          f,
+          "  {:?}: {:?} ({state})",
+          binding.unit, binding.scarce_index
+        )?;
+      }
+
+      Ok(())
+    }
+
+    writeln!(
+      f,
+      "{}, {} dropped resources waiting",
+      self.frame, self.garbage_len
+    )?;
+    resources(f, "command buffers", &self.cmd_bufs)?;
+    resources(f, "render targets", &self.render_targets)?;
+    resources(f, "shaders", &self.shaders)?;
+    resources(f, "storage buffers", &self.storage_buffers)?;
+    resources(f, "swap chains", &self.swap_chains)?;
+    resources(f, "textures", &self.textures)?;
+    resources(f, "vertex arrays", &self.vertex_arrays)?;
+    units(f, "texture units", &self.texture_units)?;
+    units(f, "uniform buffer units", &self.uniform_buffer_units)?;
+    writeln!(f, "state generations: {:?}", self.state_generations)
+  }
+}
+
+/// A tracked resource, along with information about it.
+#[derive(Debug)]
+struct Tracked<R, I> {
+  raw: R,
+  info: I,
+  tag: TrackingTag,
+  /// Number of handles on the resource.
+  handles: usize,
+}
+
+/// Information tracked for render targets: storage, color attachment points sorted by index, and number of layers if
+/// layered.
+type RenderTargetsInfo = (Storage, Arc<[ColorAttachmentPoint]>, Option<usize>);
+
+/// Information tracked for shaders.
+type ShaderInfo<B> = Arc<ShaderBindingPoints<B>>;
+
+/// Information tracked for swap chains.
+#[derive(Clone, Copy, Debug)]
+pub struct SwapChainInfo {
+  pub(crate) storage: Storage,
+  pub(crate) image_count: usize,
+  pub(crate) format: SwapChainFormat,
+  /// Number of times the swap chain was resized.
+  pub(crate) generation: u64,
+  /// Whether an image is acquired and not presented yet.
+  pub(crate) acquired: bool,
+  /// Whether an image was presented since the swap chain was created or resized.
+  pub(crate) presented: bool,
+  /// Whether the surface of the swap chain is gone.
+  pub(crate) lost: bool,
+}
+
+/// Information tracked for textures.
+type TextureInfo = (Storage, Pixel);
+
+/// Information tracked for vertex arrays: number of vertices, and maximum number of instances, if limited.
+type VertexArrayInfo = (usize, Option<usize>);
+
+#[derive(Debug)]
+pub struct Cache<B>
+where
+  B: Backend,
+{
+  cmd_bufs: HashMap<B::ScarceIndex, Tracked<B::CmdBuf, ()>>,
+  render_targets: HashMap<B::ScarceIndex, Tracked<B::RenderTargets, RenderTargetsInfo>>,
+  /// Color attachments render targets write to, when restricted.
+  active_attachments: HashMap<B::ScarceIndex, Cached<Vec<usize>>>,
+  shaders: HashMap<B::ScarceIndex, Tracked<B::Shader, ShaderInfo<B>>>,
+  /// Shader variants.
+  shader_variants: HashMap<ShaderKey, B::ScarceIndex>,
+  /// Keys of the shader variants.
+  shader_variant_keys: HashMap<B::ScarceIndex, ShaderKey>,
+  storage_buffers: HashMap<B::ScarceIndex, Tracked<B::StorageBuffer, usize>>,
+  swap_chains: HashMap<B::ScarceIndex, Tracked<B::SwapChain, SwapChainInfo>>,
+  textures: HashMap<B::ScarceIndex, Tracked<B::Texture, TextureInfo>>,
+  vertex_arrays: HashMap<B::ScarceIndex, Tracked<B::VertexArray, VertexArrayInfo>>,
+  /// Generations of the state cached by command buffers.
+  state_generations: StateGenerations,
+  /// Texture units, shared by every layer.
+  texture_units: Units<B, B::TextureUnit>,
+  /// Uniform buffer units, shared by every layer.
+  uniform_buffer_units: Units<B, B::UniformBufferUnit>,
+  /// Command buffer the resources still bound to idle units were bound by.
+  units_cmd_buf: Option<B::ScarceIndex>,
+  /// Queued garbage, tagged with the frame it was dropped in, and with the submissions made when it was dropped if it
+  /// must wait for another submission.
+  garbage: VecDeque<(FrameIndex, Option<u64>, Garbage<B>)>,
+  /// Number of command buffers finished and images presented.
+  submissions: u64,
+  /// Defaults set on the device, if any.
+  defaults: Option<PipelineDefaults>,
+  /// Names given to resources.
+  names: HashMap<(NamedKind, B::ScarceIndex), String>,
+  /// Last tag given to a tracked resource.
+  last_tag: u64,
+  /// How resources whose last handle is dropped are destroyed.
+  gc_mode: GcMode,
+  /// Errors destroying resources whose last handle was dropped, not reported by the device yet.
+  drop_errors: DropErrors<B>,
+  /// Ended timer queries, oldest first.
+  #[cfg(feature = "ext-profiler")]
+  pending_timings: Vec<PendingTiming<B>>,
+  frame: FrameIndex,
+  in_frame: bool,
+}
+
+impl<B> Default for Cache<B>
+where
+  B: Backend,
+{
+  fn default() -> Self {
+    Self {
+      cmd_bufs: HashMap::default(),
+      render_targets: HashMap::default(),
+      active_attachments: HashMap::default(),
+      shaders: HashMap::default(),
+      shader_variants: HashMap::default(),
+      shader_variant_keys: HashMap::default(),
+      storage_buffers: HashMap::default(),
+      swap_chains: HashMap::default(),
+      textures: HashMap::default(),
+      vertex_arrays: HashMap::default(),
+      state_generations: StateGenerations::default(),
+      texture_units: Units::new(Unit::from_index(0)),
+      uniform_buffer_units: Units::new(Unit::from_index(0)),
+      units_cmd_buf: None,
+      garbage: VecDeque::default(),
+      submissions: 0,
+      defaults: None,
+      names: HashMap::default(),
+      last_tag: 0,
+      gc_mode: GcMode::default(),
+      drop_errors: DropErrors::default(),
+      #[cfg(feature = "ext-profiler")]
+      pending_timings: Vec::new(),
+      frame: FrameIndex::default(),
+      in_frame: false,
+    }
+  }
+}
+
+/// Create the tracking methods for a given resource type.
+///
+/// Resources can be tracked along with information about them (`with $info`). Getting such a resource back returns
+/// the information as well.
+///
+/// Tracked resources count their handles: tracking a resource gives its first handle, sharing it gives another one,
+/// and releasing a handle untracks the resource if it was the last one.
+macro_rules! mk_tracking_methods {
+  ($field:ident : $ty:ident => $track:ident, $share:ident, $release:ident, $get:ident) => {
+    mk_tracking_methods!(@release $field : $ty => $release);
+
+    impl<B> Cache<B>
+    where
+      B: Backend,
+    {
+      pub fn $track(&mut self, raw: &B::$ty) -> TrackingTag {
+        let tag = self.next_tag();
+        self.$field.insert(
+          raw.scarce_index(),
+          Tracked {
+            raw: raw.scarce_clone(),
+            info: (),
+            tag,
+            handles: 1,
+          },
+        );
+        tag
+      }
+
+      pub fn $get(&self, id: &ResourceId<B>) -> Option<(B::$ty, TrackingTag)> {
+        self
+          .$field
+          .get(id)
+          .map(|tracked| (tracked.raw.scarce_clone(), tracked.tag))
+      }
+
+      /// Get another handle on the resource `id`.
+      pub fn $share(&mut self, id: &ResourceId<B>) -> Option<(B::$ty, TrackingTag)> {
+        let tracked = self.$field.get_mut(id)?;
+        tracked.handles += 1;
+        Some((tracked.raw.scarce_clone(), tracked.tag))
+      }
+    }
+  };
+
+  ($field:ident : $ty:ident with $info:ty => $track:ident, $share:ident, $release:ident, $get:ident) => {
+    mk_tracking_methods!(@release $field : $ty => $release);
+
+    impl<B> Cache<B>
+    where
+      B: Backend,
+    {
+      pub fn $track(&mut self, raw: &B::$ty, info: $info) -> TrackingTag {
+        let tag = self.next_tag();
+        self.$field.insert(
+          raw.scarce_index(),
+          Tracked {
+            raw: raw.scarce_clone(),
+            info,
+            tag,
+            handles: 1,
+          },
+        );
+        tag
+      }
+
+      pub fn $get(&self, id: &ResourceId<B>) -> Option<(B::$ty, $info, TrackingTag)> {
+        self.$field.get(id).map(|tracked| {
+          (
+            tracked.raw.scarce_clone(),
+            tracked.info.clone(),
+            tracked.tag,
+          )
+        })
+      }
+
+      /// Get another handle on the resource `id`.
+      pub fn $share(&mut self, id: &ResourceId<B>) -> Option<(B::$ty, $info, TrackingTag)> {
+        let tracked = self.$field.get_mut(id)?;
+        tracked.handles += 1;
+        Some((
+          tracked.raw.scarce_clone(),
+          tracked.info.clone(),
+          tracked.tag,
+        ))
+      }
+    }
+  };
+
+  (@release $field:ident : $ty:ident => $release:ident) => {
+    impl<B> Cache<B>
+    where
+      B: Backend,
+    {
+      /// Release a handle on the resource `id`, if it is still the one tracked with `tag`.
+      ///
+      /// Return the resource, untracked, if it was its last handle.
+      pub fn $release(&mut self, id: &ResourceId<B>, tag: TrackingTag) -> Option<B::$ty> {
+        let tracked = self.$field.get_mut(id)?;
+        if tracked.tag != tag {
+          return None;
+        }
+
+        tracked.handles -= 1;
+        if tracked.handles > 0 {
+          return None;
+        }
+
+        self.$field.remove(id).map(|tracked| tracked.raw)
+      }
+    }
+  };
+}
+
+impl<B> Cache<B>
+where
+  B: Backend,
+{
+  /// Cache with unit pools sized by the texture and uniform buffer units limits.
+  pub(crate) fn with_units(limits: &DeviceLimits) -> Self {
+    let mut texture_units = Units::new(Unit::from_index(limits.max_texture_units));
+    for (&stage, &max_units) in &limits.max_stage_texture_units {
+      texture_units.set_stage_limit(stage, max_units);
+    }
+
+    Self {
+      texture_units,
+      uniform_buffer_units: Units::new(Unit::from_index(limits.max_uniform_buffer_units)),
+      ..Self::default()
+    }
+  }
+
+  pub(crate) fn texture_units(&mut self) -> &mut Units<B, B::TextureUnit> {
+    &mut self.texture_units
+  }
+
+  pub(crate) fn uniform_buffer_units(&mut self) -> &mut Units<B, B::UniformBufferUnit> {
+    &mut self.uniform_buffer_units
+  }
+
+  /// Bind resources to units in `cmd_buf`.
+  ///
+  /// Units are shared by every command buffer, but what a command buffer binds is not known by the others, since they
+  /// can be executed in any order: the resources bound to idle units are forgotten when switching to another command
+  /// buffer.
+  pub(crate) fn bind_units_in(&mut self, cmd_buf: &B::ScarceIndex) {
+    if self.units_cmd_buf.as_ref() != Some(cmd_buf) {
+      self.texture_units.forget_all();
+      self.uniform_buffer_units.forget_all();
+      self.units_cmd_buf = Some(cmd_buf.clone());
+    }
+  }
+
+  /// Forget the command buffer resources were bound to units by, if it is `cmd_buf`, e.g. because it was dropped.
+  pub(crate) fn forget_units_cmd_buf(&mut self, cmd_buf: &B::ScarceIndex) {
+    if self.units_cmd_buf.as_ref() == Some(cmd_buf) {
+      self.units_cmd_buf = None;
+    }
+  }
+
+  /// Defaults set on the device, if any.
+  pub fn defaults(&self) -> Option<&PipelineDefaults> {
+    self.defaults.as_ref()
+  }
+
+  pub(crate) fn set_defaults(&mut self, defaults: PipelineDefaults) {
+    self.defaults = Some(defaults);
+  }
+
+  pub fn set_name(&mut self, kind: NamedKind, id: ResourceId<B>, name: String) {
+    self.names.insert((kind, id), name);
+  }
+
+  pub fn name(&self, kind: NamedKind, id: ResourceId<B>) -> Option<&str> {
+    self.names.get(&(kind, id)).map(String::as_str)
+  }
+
+  pub fn forget_name(&mut self, kind: NamedKind, id: ResourceId<B>) {
+    self.names.remove(&(kind, id));
+  }
+
+  /// Describe everything the cache tracks.
+  pub fn debug_dump(&self) -> CacheDump<B> {
+    CacheDump {
+      cmd_bufs: self.dump_tracked(&self.cmd_bufs, Some(NamedKind::CmdBuf), |()| String::new()),
+      render_targets: self.dump_tracked(
+        &self.render_targets,
+        Some(NamedKind::RenderTargets),
+        |(storage, points, layers)| {
+          format!("{storage:?}, color attachments {points:?}, layers {layers:?}")
+        },
+      ),
+      shaders: self.dump_tracked(&self.shaders, Some(NamedKind::Shader), |points| {
+        points.describe()
+      }),
+      storage_buffers: self
+        .dump_tracked(&self.storage_buffers, None, |size| format!("{size} bytes")),
+      swap_chains: self.dump_tracked(&self.swap_chains, None, |info| format!("{info:?}")),
+      textures: self.dump_tracked(
+        &self.textures,
+        Some(NamedKind::Texture),
+        |(storage, pixel)| format!("{storage:?}, {pixel:?}"),
+      ),
+      vertex_arrays: self.dump_tracked(&self.vertex_arrays, None, |(vertices, instances)| {
+        format!("{vertices} vertices, instances {instances:?}")
+      }),
+      texture_units: self.texture_units.bindings(),
+      uniform_buffer_units: self.uniform_buffer_units.bindings(),
+      state_generations: self.state_generations,
+      garbage_len: self.garbage.len(),
+      frame: self.frame,
+    }
+  }
+
+  /// Describe tracked resources, sorted by identifier.
+  fn dump_tracked<R, I>(
+    &self,
+    tracked: &HashMap<B::ScarceIndex, Tracked<R, I>>,
+    kind: Option<NamedKind>,
+    describe: impl Fn(&I) -> String,
+  ) -> Vec<TrackedDump<B>> {
+    let mut dumps: Vec<TrackedDump<B>> = tracked
+      .iter()
+      .map(|(id, tracked)| TrackedDump {
+        id: id.clone(),
+        name: kind
+          .and_then(|kind| self.name(kind, id.clone()))
+          .map(str::to_owned),
+        info: describe(&tracked.info),
+      })
+      .collect();
+    dumps.sort_by(|a, b| a.id.cmp(&b.id));
+    dumps
+  }
+
+  /// Number of dropped resources waiting to be destroyed.
+  pub fn garbage_len(&self) -> usize {
+    self.garbage.len()
+  }
+
+  /// Queue garbage, tagged with the current frame.
+  ///
+  /// If `wait_submission` is `true`, the garbage cannot be popped until the next submission.
+  pub(crate) fn push_garbage(&mut self, garbage: Garbage<B>, wait_submission: bool) {
+    let submission = wait_submission.then_some(self.submissions);
+    self.garbage.push_back((self.frame, submission, garbage));
+  }
+
+  /// Take all the queued garbage, whatever its age.
+  pub(crate) fn drain_garbage(&mut self) -> Vec<Garbage<B>> {
+    self
+      .garbage
+      .drain(..)
+      .map(|(_, _, garbage)| garbage)
+      .collect()
+  }
+
+  /// Untrack every resource, returning them as garbage.
+  ///
+  /// Resources using other resources come first: command buffers, then render targets, shaders and vertex arrays,
+  /// then buffers, textures and swap chains.
+  pub(crate) fn drain_tracked(&mut self) -> Vec<Garbage<B>> {
+    self.shader_variants.clear();
+    self.shader_variant_keys.clear();
+    self.names.clear();
+
+    let cmd_bufs = self.cmd_bufs.drain().map(|(_, t)| Garbage::CmdBuf(t.raw));
+    let render_targets = self
+      .render_targets
+      .drain()
+      .map(|(_, t)| Garbage::RenderTargets(t.raw));
+    let shaders = self.shaders.drain().map(|(_, t)| Garbage::Shader(t.raw));
+    let vertex_arrays = self
+      .vertex_arrays
+      .drain()
+      .map(|(_, t)| Garbage::VertexArray(t.raw));
+    let storage_buffers = self
+      .storage_buffers
+      .drain()
+      .map(|(_, t)| Garbage::StorageBuffer(t.raw));
+    let textures = self.textures.drain().map(|(_, t)| Garbage::Texture(t.raw));
+    let swap_chains = self
+      .swap_chains
+      .drain()
+      .map(|(_, t)| Garbage::SwapChain(t.raw));
+
+    cmd_bufs
+      .chain(render_targets)
+      .chain(shaders)
+      .chain(vertex_arrays)
+      .chain(storage_buffers)
+      .chain(textures)
+      .chain(swap_chains)
+      .collect()
+  }
+
+  pub(crate) fn gc_mode(&self) -> GcMode {
+    self.gc_mode
+  }
+
+  pub(crate) fn set_gc_mode(&mut self, gc_mode: GcMode) {
+    self.gc_mode = gc_mode;
+  }
+
+  /// Keep an error destroying a resource whose last handle was dropped, for the device to report it.
+  pub(crate) fn push_drop_error(&mut self, err: B::Err) {
+    self.drop_errors.0.push(err);
+  }
+
+  pub(crate) fn take_drop_errors(&mut self) -> Vec<B::Err> {
+    std::mem::take(&mut self.drop_errors.0)
+  }
+
+  /// Release a handle on the command buffer `id`; return it as garbage if it was the last handle.
+  pub(crate) fn drop_cmd_buf(
+    &mut self,
+    id: &ResourceId<B>,
+    tag: TrackingTag,
+  ) -> Option<Garbage<B>> {
+    let raw = self.release_cmd_buf(id, tag)?;
+    self.forget_name(NamedKind::CmdBuf, id.clone());
+    self.forget_units_cmd_buf(id);
+    Some(Garbage::CmdBuf(raw))
+  }
+
+  /// Release a handle on the render targets `id`; return them as garbage if it was the last handle.
+  pub(crate) fn drop_render_targets(
+    &mut self,
+    id: &ResourceId<B>,
+    tag: TrackingTag,
+  ) -> Option<Garbage<B>> {
+    let raw = self.release_render_targets(id, tag)?;
+    self.forget_active_attachments(id);
+    self.forget_name(NamedKind::RenderTargets, id.clone());
+    // its scarce index might be reused by other render targets
+    self.invalidate_bound_render_targets();
+    Some(Garbage::RenderTargets(raw))
+  }
+
+  /// Release a handle on the shader `id`; return it as garbage if it was the last handle.
+  pub(crate) fn drop_shader(&mut self, id: &ResourceId<B>, tag: TrackingTag) -> Option<Garbage<B>> {
+    let raw = self.release_shader(id, tag)?;
+    if let Some(key) = self.shader_variant_keys.remove(id) {
+      self.shader_variants.remove(&key);
+    }
+    self.forget_name(NamedKind::Shader, id.clone());
+    // its scarce indices might be reused by other shaders and uniforms
+    self.invalidate_shader_binding();
+    self.invalidate_uniform_values();
+    Some(Garbage::Shader(raw))
+  }
+
+  /// Release a handle on the storage buffer `id`; return it as garbage if it was the last handle.
+  pub(crate) fn drop_storage_buffer(
+    &mut self,
+    id: &ResourceId<B>,
+    tag: TrackingTag,
+  ) -> Option<Garbage<B>> {
+    self
+      .release_storage_buffer(id, tag)
+      .map(Garbage::StorageBuffer)
+  }
+
+  /// Release a handle on the swap chain `id`; return it as garbage if it was the last handle.
+  pub(crate) fn drop_swap_chain(
+    &mut self,
+    id: &ResourceId<B>,
+    tag: TrackingTag,
+  ) -> Option<Garbage<B>> {
+    self.release_swap_chain(id, tag).map(Garbage::SwapChain)
+  }
+
+  /// Release a handle on the texture `id`; return it as garbage if it was the last handle.
+  pub(crate) fn drop_texture(
+    &mut self,
+    id: &ResourceId<B>,
+    tag: TrackingTag,
+  ) -> Option<Garbage<B>> {
+    let raw = self.release_texture(id, tag)?;
+    self.texture_units.forget(id);
+    self.forget_name(NamedKind::Texture, id.clone());
+    Some(Garbage::Texture(raw))
+  }
+
+  /// Release a handle on the vertex array `id`; return it as garbage if it was the last handle.
+  pub(crate) fn drop_vertex_array(
+    &mut self,
+    id: &ResourceId<B>,
+    tag: TrackingTag,
+  ) -> Option<Garbage<B>> {
+    self.release_vertex_array(id, tag).map(Garbage::VertexArray)
+  }
+
+  fn next_tag(&mut self) -> TrackingTag {
+    self.last_tag += 1;
+    TrackingTag(self.last_tag)
+  }
+
+  /// Pop the oldest garbage, if it was dropped at least `min_age` frames ago and doesn’t wait for a submission.
+  pub(crate) fn pop_garbage(&mut self, min_age: u64) -> Option<Garbage<B>> {
+    let (dropped_at, submission, _) = self.garbage.front()?;
+
+    if self.frame.frames_since(*dropped_at) < min_age
+      || submission.is_some_and(|submission| submission >= self.submissions)
+    {
+      return None;
+    }
+
+    self.garbage.pop_front().map(|(_, _, garbage)| garbage)
+  }
+
+  /// Record a submission: a command buffer was finished, or an image was presented.
+  pub(crate) fn submit(&mut self) {
+    self.submissions += 1;
+  }
+
+  /// Current frame; the last frame begun.
+  pub fn frame(&self) -> FrameIndex {
+    self.frame
+  }
+
+  /// Whether a frame has begun and is not ended yet.
+  pub fn is_in_frame(&self) -> bool {
+    self.in_frame
+  }
+
+  pub(crate) fn begin_frame(&mut self) -> Result<FrameIndex, Error> {
+    if self.in_frame {
+      return Err(Error::InvalidFrame {
+        reason: format!("{} has not ended", self.frame),
+      });
+    }
+
+    self.frame = self.frame.next();
+    self.in_frame = true;
+    Ok(self.frame)
+  }
+
+  pub fn state_generations(&self) -> StateGenerations {
+    self.state_generations
+  }
+
+  /// Make command buffers forget the pipeline state they set.
+  pub fn invalidate_pipeline_state(&mut self) {
+    self.state_generations.pipeline += 1;
+  }
+
+  /// Forget which textures are bound to texture units.
+  pub fn invalidate_texture_bindings(&mut self) {
+    self.texture_units.forget_all();
+  }
+
+  /// Forget which uniform buffers are bound to uniform buffer units.
+  pub fn invalidate_uniform_buffer_bindings(&mut self) {
+    self.uniform_buffer_units.forget_all();
+  }
+
+  /// Forget which render targets are bound, and which of their color attachments are active.
+  pub fn invalidate_render_targets_bindings(&mut self) {
+    self.invalidate_bound_render_targets();
+    self.active_attachments.clear();
+  }
+
+  /// Make command buffers forget which render targets they bound.
+  ///
+  /// Needed whenever backends might have bound other render targets behind our back, e.g. to change attachments.
+  pub(crate) fn invalidate_bound_render_targets(&mut self) {
+    self.state_generations.render_targets += 1;
+  }
+
+  /// Make command buffers forget which shader they bound.
+  pub fn invalidate_shader_binding(&mut self) {
+    self.state_generations.shader += 1;
+  }
+
+  /// Make command buffers forget the values they set to uniforms.
+  pub fn invalidate_uniform_values(&mut self) {
+    self.state_generations.uniforms += 1;
+  }
+
+  /// Forget everything cached about the state of the backend.
+  ///
+  /// Tracked resources are not affected.
+  pub fn invalidate(&mut self) {
+    self.invalidate_pipeline_state();
+    self.invalidate_shader_binding();
+    self.invalidate_uniform_values();
+    self.invalidate_texture_bindings();
+    self.invalidate_uniform_buffer_bindings();
+    self.invalidate_render_targets_bindings();
+  }
+
+  /// Cached active color attachments of the render targets `id`.
+  pub(crate) fn active_attachments(&mut self, id: &ResourceId<B>) -> &mut Cached<Vec<usize>> {
+    self.active_attachments.entry(id.clone()).or_default()
+  }
+
+  pub(crate) fn forget_active_attachments(&mut self, id: &ResourceId<B>) {
+    self.active_attachments.remove(id);
+  }
+
+  /// Update the storage tracked for the render targets `id`, after they were resized.
+  pub(crate) fn resize_render_targets(&mut self, id: &ResourceId<B>, storage: Storage) {
+    if let Some(tracked) = self.render_targets.get_mut(id) {
+      tracked.info.0 = storage;
+    }
+  }
+
+  /// Update the storage tracked for the swap chain `id` after it was resized, and return its new generation.
+  ///
+  /// The image acquired before resizing, if any, is gone, and so is the last presented one.
+  pub(crate) fn resize_swap_chain(&mut self, id: &ResourceId<B>, storage: Storage) -> Option<u64> {
+    let info = &mut self.swap_chains.get_mut(id)?.info;
+    info.storage = storage;
+    info.generation += 1;
+    info.acquired = false;
+    info.presented = false;
+    Some(info.generation)
+  }
+
+  pub(crate) fn present_swap_chain(&mut self, id: &ResourceId<B>) {
+    if let Some(tracked) = self.swap_chains.get_mut(id) {
+      tracked.info.presented = true;
+    }
+  }
+
+  pub(crate) fn lose_swap_chain(&mut self, id: &ResourceId<B>) {
+    if let Some(tracked) = self.swap_chains.get_mut(id) {
+      tracked.info.lost = true;
+    }
+  }
+
+  /// Mark whether an image of the swap chain `id` is acquired, and return whether one was.
+  pub(crate) fn set_swap_chain_acquired(&mut self, id: &ResourceId<B>, acquired: bool) -> bool {
+    self
+      .swap_chains
+      .get_mut(id)
+      .is_some_and(|tracked| std::mem::replace(&mut tracked.info.acquired, acquired))
+  }
+
+  /// Get back the shader created for `key`, if still alive, sharing it with one more handle.
+  pub(crate) fn acquire_shader_variant(
+    &mut self,
+    key: &ShaderKey,
+  ) -> Option<(B::Shader, ShaderInfo<B>, TrackingTag)> {
+    let id = self.shader_variants.get(key)?.clone();
+    self.share_shader(&id)
+  }
+
+  pub(crate) fn track_shader_variant(&mut self, key: ShaderKey, id: ResourceId<B>) {
+    self.shader_variants.insert(key.clone(), id.clone());
+    self.shader_variant_keys.insert(id, key);
+  }
+
+  pub(crate) fn end_frame(&mut self) -> Result<FrameIndex, Error> {
+    if !self.in_frame {
+      return Err(Error::InvalidFrame {
+        reason: format!("no frame has begun since {}", self.frame),
+      });
+    }
+
+    self.in_frame = false;
+    Ok(self.frame)
+  }
+
+  #[cfg(feature = "ext-profiler")]
+  pub(crate) fn push_timing(&mut self, timing: PendingTiming<B>) {
+    self.pending_timings.push(timing);
+  }
+
+  /// Take the timings that are known, oldest first, and keep the other ones pending.
+  #[cfg(feature = "ext-profiler")]
+  pub(crate) fn take_timings(&mut self) -> Result<Vec<Timing>, B::Err> {
+    let mut timings = Vec::new();
+    let mut pending = Vec::new();
+
+    for pending_timing in self.pending_timings.drain(..) {
+      match pending_timing.poll()? {
+        Some(timing) => timings.push(timing),
+        None => pending.push(pending_timing),
+      }
+    }
+
+    self.pending_timings = pending;
+    Ok(timings)
+  }
+}
+
+mk_tracking_methods!(cmd_bufs: CmdBuf => track_cmd_buf, share_cmd_buf, release_cmd_buf, cmd_buf);
+mk_tracking_methods!(
+  render_targets: RenderTargets with RenderTargetsInfo =>
+    track_render_targets, share_render_targets, release_render_targets, render_targets
+);
+mk_tracking_methods!(
+  shaders: Shader with ShaderInfo<B> => track_shader, share_shader, release_shader, shader
+);
+mk_tracking_methods!(
+  storage_buffers: StorageBuffer with usize =>
+    track_storage_buffer, share_storage_buffer, release_storage_buffer, storage_buffer
+);
+mk_tracking_methods!(
+  swap_chains: SwapChain with SwapChainInfo => track_swap_chain, share_swap_chain, release_swap_chain, swap_chain
+);
+mk_tracking_methods!(
+  textures: Texture with TextureInfo => track_texture, share_texture, release_texture, texture
+);
+mk_tracking_methods!(
+  vertex_arrays: VertexArray with VertexArrayInfo =>
+    track_vertex_array, share_vertex_array, release_vertex_array, vertex_array
+);
+
+/// Cached pipeline state.
+#[derive(Debug, Default)]
+pub struct CachedPipelineState {
+  pub blending: Cached<BlendingMode>,
+  pub depth_test: Cached<DepthTest>,
+  pub depth_write: Cached<DepthWrite>,
+  pub stencil_test: Cached<StencilTest>,
+  pub face_culling: Cached<FaceCulling>,
+  pub viewport: Cached<Viewport>,
+  pub scissor: Cached<Scissor>,
+  pub clear_color: Cached<RGBA32F>,
+  pub clear_depth: Cached<f32>,
+  pub srgb: Cached<bool>,
+  pub primitive_restart: Cached<bool>,
+}
+
+impl CachedPipelineState {
+  /// Compute the difference between the cached pipeline state and a target pipeline state.
+  ///
+  /// Variables that are not cached are always part of the difference.
+  pub fn diff(&self, state: PipelineState) -> PipelineStateDiff {
+    self.diff_partial(state.into())
+  }
+
+  /// Same as [`CachedPipelineState::diff`], with a partial target pipeline state: the variables set to [`None`] are
+  /// left out of the difference.
+  pub fn diff_partial(&self, state: PipelineStateDiff) -> PipelineStateDiff {
+    fn diff_var<T>(cached: &Cached<T>, value: Option<T>) -> Option<T>
+    where
+      T: Clone + PartialEq,
+    {
+      value.filter(|value| cached.is_invalid(value))
+    }
+
+    PipelineStateDiff {
+      blending: diff_var(&self.blending, state.blending),
+      depth_test: diff_var(&self.depth_test, state.depth_test),
+      depth_write: diff_var(&self.depth_write, state.depth_write),
+      stencil_test: diff_var(&self.stencil_test, state.stencil_test),
+      face_culling: diff_var(&self.face_culling, state.face_culling),
+      viewport: diff_var(&self.viewport, state.viewport),
+      scissor: diff_var(&self.scissor, state.scissor),
+      clear_color: diff_var(&self.clear_color, state.clear_color),
+      clear_depth: diff_var(&self.clear_depth, state.clear_depth),
+      srgb: diff_var(&self.srgb, state.srgb),
+      primitive_restart: diff_var(&self.primitive_restart, state.primitive_restart),
+    }
+  }
+}
@@ -1,90 +1,825 @@
+use std::{
+  cell::{Cell, RefCell},
+  collections::{hash_map::DefaultHasher, HashMap},
+  hash::{Hash, Hasher},
+  sync::{Mutex, Weak},
+};
+
+#[cfg(feature = "ext-profiler")]
+use piksels_backend::extension::profiler::BackendProfiler;
+#[cfg(feature = "ext-readback")]
+use piksels_backend::extension::readback::BackendReadback;
+#[cfg(feature = "ext-trace")]
+use piksels_backend::extension::trace::{Trace, TraceCall};
 use piksels_backend::{
   blending::BlendingMode,
+  cache::Cached,
   color::RGBA32F,
   depth_stencil::{DepthTest, DepthWrite, StencilTest},
+  error::Error,
   face_culling::FaceCulling,
-  scissor::Scissor,
+  primitive::DEFAULT_RESTART_INDEX,
+  render_targets::AttachmentUsage,
+  scissor::{Scissor, ScissorRegion},
+  shader::{UniformType, UniformValue},
   viewport::Viewport,
-  Backend,
+  warn, Backend, Scarce,
 };
 
+#[cfg(feature = "ext-profiler")]
+use crate::profiler::{PendingTiming, TimerQuery};
+#[cfg(feature = "ext-readback")]
+use crate::readback::Readback;
+#[cfg(feature = "ext-trace")]
+use crate::trace::{TraceRecorder, TraceResourceKind};
 use crate::{
-  render_targets::RenderTargets,
+  binding_report::{BindingRecorder, BindingReport},
+  budget::RecordingBudget,
+  cache::{Cache, CachedPipelineState, ResourceId, StateGenerations, TrackingTag},
+  convention::{self, CoordinateConvention},
+  gc,
+  pipeline::{PipelineSnapshot, PipelineState, PipelineStateDiff},
+  render_targets::{ColorAttachment, DepthStencilAttachment, RenderTargets},
   shader::{
     Shader, ShaderTextureBindingPoint, ShaderUniformBufferBindingPoint, Uniform, UniformBuffer,
     UniformBufferBindingPoint,
   },
+  storage_buffer::{ShaderStorageBufferBindingPoint, StorageBuffer, StorageBufferBindingPoint},
   texture::{Texture, TextureBindingPoint},
+  vertex_array::{VertexArray, VertexArrayView},
 };
 
+/// Commands to be executed by the backend.
+///
+/// Command buffers cache the state they set: pipeline state setters (e.g. [`CmdBuf::viewport`]) do nothing when the
+/// variable was already set to the value by the same command buffer, so redundant state changes are neither issued nor
+/// counted against the recording budget. State set by other command buffers is not taken into account, since they can
+/// be executed in any order.
+///
+/// The command buffer is destroyed once every handle on it is dropped.
 #[derive(Debug)]
 pub struct CmdBuf<B>
 where
   B: Backend,
 {
   pub(crate) raw: B::CmdBuf,
+  backend: Weak<B>,
+  cache: Weak<Mutex<Cache<B>>>,
+  coordinate_convention: CoordinateConvention,
+
+  /// Height of the currently bound render targets, used to apply the coordinate convention.
+  target_height: Cell<Option<u32>>,
+
+  /// Bindings recorded for debugging, if enabled.
+  binding_recorder: RefCell<Option<BindingRecorder<B>>>,
+
+  /// Commands captured in a trace, if enabled.
+  #[cfg(feature = "ext-trace")]
+  trace_recorder: RefCell<Option<TraceRecorder<B>>>,
+
+  recording_budget: Cell<RecordingBudget>,
+
+  /// Draws recorded since the last finish.
+  draws: Cell<usize>,
+
+  /// State changes recorded since the last finish.
+  state_changes: Cell<usize>,
+
+  /// Whether the command buffer is deferred.
+  deferred: bool,
+
+  /// Pipeline state [`CmdBuf::clear_state`] resets to.
+  default_state: PipelineState,
+
+  /// State set by the command buffer.
+  state: RefCell<CmdBufState<B>>,
+
+  /// Cache tracking the command buffer, deferred or not.
+  tracker: Weak<Mutex<Cache<B>>>,
+  pub(crate) tag: TrackingTag,
+}
+
+impl<B> Drop for CmdBuf<B>
+where
+  B: Backend,
+{
+  fn drop(&mut self) {
+    let id = self.id();
+    gc::drop_handle(&self.tracker, self.tag, |cache, tag| {
+      cache.drop_cmd_buf(&id, tag)
+    });
+  }
+}
+
+/// State set by a command buffer, and hence known by it.
+#[derive(Debug)]
+struct CmdBufState<B>
+where
+  B: Backend,
+{
+  /// Generations of the cache of the device the state is valid for.
+  generations: StateGenerations,
+  pipeline: CachedPipelineState,
+  shader: Cached<ResourceId<B>>,
+  render_targets: Cached<ResourceId<B>>,
+  /// Hashes of the values last set to uniforms.
+  uniform_values: HashMap<ResourceId<B>, u64>,
+}
+
+impl<B> Default for CmdBufState<B>
+where
+  B: Backend,
+{
+  fn default() -> Self {
+    Self {
+      generations: StateGenerations::default(),
+      pipeline: CachedPipelineState::default(),
+      shader: Cached::default(),
+      render_targets: Cached::default(),
+      uniform_values: HashMap::default(),
+    }
+  }
+}
+
+impl<B> CmdBufState<B>
+where
+  B: Backend,
+{
+  /// Forget the state invalidated in the cache of the device since the last synchronization.
+  fn sync(&mut self, generations: StateGenerations) {
+    if self.generations.pipeline != generations.pipeline {
+      self.pipeline = CachedPipelineState::default();
+    }
+
+    if self.generations.shader != generations.shader {
+      self.shader.invalidate();
+    }
+
+    if self.generations.render_targets != generations.render_targets {
+      self.render_targets.invalidate();
+    }
+
+    if self.generations.uniforms != generations.uniforms {
+      self.uniform_values.clear();
+    }
+
+    self.generations = generations;
+  }
+
+  /// Forget everything, keeping the generations.
+  fn invalidate(&mut self) {
+    *self = Self {
+      generations: self.generations,
+      ..Self::default()
+    };
+  }
+
+  fn snapshot(&self) -> PipelineSnapshot<B> {
+    let pipeline = &self.pipeline;
+
+    PipelineSnapshot {
+      state: PipelineStateDiff {
+        blending: pipeline.blending.get().cloned(),
+        depth_test: pipeline.depth_test.get().cloned(),
+        depth_write: pipeline.depth_write.get().cloned(),
+        stencil_test: pipeline.stencil_test.get().cloned(),
+        face_culling: pipeline.face_culling.get().cloned(),
+        viewport: pipeline.viewport.get().cloned(),
+        scissor: pipeline.scissor.get().cloned(),
+        clear_color: pipeline.clear_color.get().cloned(),
+        clear_depth: pipeline.clear_depth.get().cloned(),
+        srgb: pipeline.srgb.get().cloned(),
+        primitive_restart: pipeline.primitive_restart.get().cloned(),
+      },
+      shader: self.shader.get().cloned(),
+      render_targets: self.render_targets.get().cloned(),
+    }
+  }
 }
 
 impl<B> CmdBuf<B>
 where
   B: Backend,
 {
-  pub(crate) fn from_raw(raw: B::CmdBuf) -> Self {
-    Self { raw }
+  pub(crate) fn from_raw(
+    raw: B::CmdBuf,
+    backend: Weak<B>,
+    cache: Weak<Mutex<Cache<B>>>,
+    coordinate_convention: CoordinateConvention,
+    recording_budget: RecordingBudget,
+    default_state: PipelineState,
+    tag: TrackingTag,
+  ) -> Self {
+    Self {
+      raw,
+      backend,
+      tracker: cache.clone(),
+      cache,
+      coordinate_convention,
+      target_height: Cell::new(None),
+      binding_recorder: RefCell::new(None),
+      #[cfg(feature = "ext-trace")]
+      trace_recorder: RefCell::new(None),
+      recording_budget: Cell::new(recording_budget),
+      draws: Cell::new(0),
+      state_changes: Cell::new(0),
+      deferred: false,
+      default_state,
+      state: RefCell::default(),
+      tag,
+    }
+  }
+
+  /// Deferred command buffers don’t share the cache of the device: they cannot be executed by the device directly.
+  pub(crate) fn from_raw_deferred(
+    raw: B::CmdBuf,
+    backend: Weak<B>,
+    tracker: Weak<Mutex<Cache<B>>>,
+    coordinate_convention: CoordinateConvention,
+    recording_budget: RecordingBudget,
+    default_state: PipelineState,
+    tag: TrackingTag,
+  ) -> Self {
+    let mut cmd_buf = Self::from_raw(
+      raw,
+      backend,
+      tracker,
+      coordinate_convention,
+      recording_budget,
+      default_state,
+      tag,
+    );
+    cmd_buf.cache = Weak::new();
+    cmd_buf.deferred = true;
+    cmd_buf
+  }
+
+  pub fn id(&self) -> ResourceId<B> {
+    self.raw.scarce_index()
+  }
+
+  /// Run a function with the cache, if the device it comes from is still alive.
+  fn with_cache<A>(&self, f: impl FnOnce(&mut Cache<B>) -> A) -> Result<Option<A>, B::Err> {
+    match self.cache.upgrade() {
+      Some(cache) => {
+        let mut cache = cache.lock().map_err(Error::from)?;
+        Ok(Some(f(&mut cache)))
+      }
+
+      None => Ok(None),
+    }
+  }
+
+  /// Run a function with the state set by the command buffer, forgetting what was invalidated in the cache of the
+  /// device since the last call.
+  fn with_state<A>(&self, f: impl FnOnce(&mut CmdBufState<B>) -> A) -> Result<A, B::Err> {
+    let generations = self.with_cache(|cache| cache.state_generations())?;
+    let mut state = self.state.borrow_mut();
+
+    if let Some(generations) = generations {
+      state.sync(generations);
+    }
+
+    Ok(f(&mut state))
+  }
+
+  /// Whether a pipeline state variable is known to be already set to a value, making setting it again redundant.
+  fn is_cached(&self, f: impl FnOnce(&CachedPipelineState) -> bool) -> Result<bool, B::Err> {
+    self.with_state(|state| f(&state.pipeline))
+  }
+
+  /// Snapshot the pipeline state set by the command buffer, and the shader and render targets it bound, to restore
+  /// them later with [`CmdBuf::restore`].
+  pub fn snapshot(&self) -> Result<PipelineSnapshot<B>, B::Err> {
+    self.with_state(|state| state.snapshot())
+  }
+
+  /// Pipeline state [`CmdBuf::clear_state`] resets to.
+  pub(crate) fn default_state(&self) -> &PipelineState {
+    &self.default_state
+  }
+
+  /// Whether the command buffer was created by a [`DeferredContext`](crate::deferred::DeferredContext).
+  pub fn is_deferred(&self) -> bool {
+    self.deferred
+  }
+
+  /// Start recording the bindings of every draw.
+  ///
+  /// Any previously recorded bindings are discarded.
+  pub fn record_bindings(&self) {
+    *self.binding_recorder.borrow_mut() = Some(BindingRecorder::default());
+  }
+
+  /// Stop recording bindings and get the report of the draws recorded so far.
+  ///
+  /// Return [`None`] if bindings were not being recorded.
+  pub fn take_binding_report(&self) -> Option<BindingReport<B>> {
+    self
+      .binding_recorder
+      .borrow_mut()
+      .take()
+      .map(BindingRecorder::into_report)
+  }
+
+  /// Change the recording budget.
+  ///
+  /// The draws and state changes already recorded count against the new budget.
+  pub fn set_recording_budget(&self, recording_budget: RecordingBudget) {
+    self.recording_budget.set(recording_budget);
+  }
+
+  pub fn recording_budget(&self) -> RecordingBudget {
+    self.recording_budget.get()
+  }
+
+  /// Number of draws recorded since the last call to [`CmdBuf::finish`].
+  pub fn draw_count(&self) -> usize {
+    self.draws.get()
+  }
+
+  /// Number of state changes recorded since the last call to [`CmdBuf::finish`].
+  pub fn state_change_count(&self) -> usize {
+    self.state_changes.get()
+  }
+
+  fn spend_draw(&self) -> Result<(), B::Err> {
+    self.spend(
+      &self.draws,
+      self.recording_budget.get().max_draws(),
+      "draws",
+    )
+  }
+
+  fn spend_state_change(&self) -> Result<(), B::Err> {
+    self.spend(
+      &self.state_changes,
+      self.recording_budget.get().max_state_changes(),
+      "state changes",
+    )
+  }
+
+  /// Count a command against the recording budget, warning or failing if it exceeds `max`.
+  fn spend(&self, count: &Cell<usize>, max: Option<usize>, what: &str) -> Result<(), B::Err> {
+    let spent = count.get() + 1;
+
+    if let Some(max) = max.filter(|&max| spent > max) {
+      let reason = format!(
+        "more than {max} {what} recorded in command buffer {:?}",
+        self.id()
+      );
+
+      if self.recording_budget.get().is_strict() {
+        return Err(Error::RecordingBudgetExceeded { reason }.into());
+      }
+
+      // only warn once per recording
+      if spent == max + 1 {
+        if let Some(backend) = self.backend.upgrade() {
+          warn!(backend, "recording budget exceeded: {reason}");
+        }
+      }
+    }
+
+    count.set(spent);
+    Ok(())
+  }
+
+  fn record(&self, f: impl FnOnce(&mut BindingRecorder<B>)) {
+    if let Some(recorder) = self.binding_recorder.borrow_mut().as_mut() {
+      f(recorder);
+    }
+  }
+
+  /// Start capturing the commands issued to the backend into a [`Trace`].
+  ///
+  /// Any previously captured commands are discarded. See the [`trace`](crate::trace) module for replaying traces.
+  #[cfg(feature = "ext-trace")]
+  pub fn start_trace(&self) {
+    *self.trace_recorder.borrow_mut() = Some(TraceRecorder::default());
+  }
+
+  /// Stop capturing commands and get the trace of the commands captured so far.
+  ///
+  /// Return [`None`] if commands were not being captured.
+  #[cfg(feature = "ext-trace")]
+  pub fn take_trace(&self) -> Option<Trace> {
+    self
+      .trace_recorder
+      .borrow_mut()
+      .take()
+      .map(TraceRecorder::into_trace)
+  }
+
+  #[cfg(feature = "ext-trace")]
+  fn trace(&self, f: impl FnOnce(&mut TraceRecorder<B>) -> TraceCall) {
+    if let Some(recorder) = self.trace_recorder.borrow_mut().as_mut() {
+      let call = f(recorder);
+      recorder.push(call);
+    }
+  }
+
+  /// Convert the `y` coordinate of a region of the bound render targets to the backend convention.
+  fn flip_y(&self, y: u32, height: u32) -> Result<u32, B::Err> {
+    if self.coordinate_convention == CoordinateConvention::OriginBottomLeft {
+      return Ok(y);
+    }
+
+    let target_height = self
+      .target_height
+      .get()
+      .ok_or_else(|| Error::CoordinateConvention {
+        reason: "no render targets bound to flip against".to_owned(),
+      })?;
+
+    Ok(self.coordinate_convention.flip_y(y, height, target_height))
+  }
+
+  /// Reset the whole pipeline state to its defaults.
+  ///
+  /// Only the variables that are not known to be already set to their default values are changed, and always in the
+  /// same order. Calling this function at the start of recording makes the command buffer independent of the
+  /// command buffers recorded before it.
+  ///
+  /// The defaults are the ones of the device the command buffer was created with (see
+  /// [`Device::set_defaults`](crate::device::Device::set_defaults)), or [`PipelineState::default`] if none were set.
+  pub fn clear_state(&self) -> Result<&Self, B::Err> {
+    let state = self.default_state.clone();
+    let diff = self.with_state(|cached| cached.pipeline.diff(state))?;
+    self.apply_pipeline_diff(diff)
+  }
+
+  /// Restore a snapshot of the pipeline state, and the shader and render targets bound when it was taken.
+  ///
+  /// Only what differs from the cached state is changed. Shaders and render targets dropped since the snapshot was
+  /// taken are not bound again.
+  pub fn restore(&self, snapshot: &PipelineSnapshot<B>) -> Result<&Self, B::Err> {
+    let diff = self.with_state(|state| state.pipeline.diff_partial(snapshot.state.clone()))?;
+    self.apply_pipeline_diff(diff)?;
+
+    if let Some(id) = &snapshot.shader {
+      let shader = if self.with_state(|state| state.shader.is_invalid(id))? {
+        self.with_cache(|cache| cache.shader(id))?.flatten()
+      } else {
+        None
+      };
+
+      if let Some((raw, binding_points, _)) = shader {
+        self.shader(&Shader::from_raw(
+          raw,
+          binding_points,
+          Weak::new(),
+          TrackingTag::default(),
+        ))?;
+      }
+    }
+
+    if let Some(id) = &snapshot.render_targets {
+      let render_targets = if self.with_state(|state| state.render_targets.is_invalid(id))? {
+        self.with_cache(|cache| cache.render_targets(id))?.flatten()
+      } else {
+        None
+      };
+
+      if let Some((raw, (storage, points, layers), _)) = render_targets {
+        self.render_targets(&RenderTargets::from_raw(
+          raw,
+          storage,
+          points,
+          layers,
+          self.cache.clone(),
+          TrackingTag::default(),
+        ))?;
+      }
+    }
+
+    Ok(self)
+  }
+
+  pub(crate) fn apply_pipeline_diff(&self, diff: PipelineStateDiff) -> Result<&Self, B::Err> {
+    if let Some(value) = diff.blending {
+      self.blending(value)?;
+    }
+
+    if let Some(value) = diff.depth_test {
+      self.depth_test(value)?;
+    }
+
+    if let Some(value) = diff.depth_write {
+      self.depth_write(value)?;
+    }
+
+    if let Some(value) = diff.stencil_test {
+      self.stencil_test(value)?;
+    }
+
+    if let Some(value) = diff.face_culling {
+      self.face_culling(value)?;
+    }
+
+    if let Some(value) = diff.viewport {
+      self.viewport(value)?;
+    }
+
+    if let Some(value) = diff.scissor {
+      self.scissor(value)?;
+    }
+
+    if let Some(value) = diff.clear_color {
+      self.clear_color(value)?;
+    }
+
+    if let Some(value) = diff.clear_depth {
+      self.clear_depth(value)?;
+    }
+
+    if let Some(value) = diff.srgb {
+      self.srgb(value)?;
+    }
+
+    if let Some(value) = diff.primitive_restart {
+      self.primitive_restart(value)?;
+    }
+
+    Ok(self)
   }
 
   pub fn blending(&self, value: BlendingMode) -> Result<&Self, B::Err> {
+    if self.is_cached(|pipeline| !pipeline.blending.is_invalid(&value))? {
+      return Ok(self);
+    }
+
+    self.spend_state_change()?;
     B::cmd_buf_blending(&self.raw, value)?;
+    #[cfg(feature = "ext-trace")]
+    self.trace(|_| TraceCall::Blending(value));
+    self.with_state(|state| {
+      state.pipeline.blending.set(value);
+    })?;
     Ok(self)
   }
 
   pub fn depth_test(&self, value: DepthTest) -> Result<&Self, B::Err> {
+    if self.is_cached(|pipeline| !pipeline.depth_test.is_invalid(&value))? {
+      return Ok(self);
+    }
+
+    self.spend_state_change()?;
     B::cmd_buf_depth_test(&self.raw, value)?;
+    #[cfg(feature = "ext-trace")]
+    self.trace(|_| TraceCall::DepthTest(value));
+    self.with_state(|state| {
+      state.pipeline.depth_test.set(value);
+    })?;
     Ok(self)
   }
 
   pub fn depth_write(&self, value: DepthWrite) -> Result<&Self, B::Err> {
+    if self.is_cached(|pipeline| !pipeline.depth_write.is_invalid(&value))? {
+      return Ok(self);
+    }
+
+    self.spend_state_change()?;
     B::cmd_buf_depth_write(&self.raw, value)?;
+    #[cfg(feature = "ext-trace")]
+    self.trace(|_| TraceCall::DepthWrite(value));
+    self.with_state(|state| {
+      state.pipeline.depth_write.set(value);
+    })?;
     Ok(self)
   }
 
   pub fn stencil_test(&self, value: StencilTest) -> Result<&Self, B::Err> {
+    if self.is_cached(|pipeline| !pipeline.stencil_test.is_invalid(&value))? {
+      return Ok(self);
+    }
+
+    self.spend_state_change()?;
     B::cmd_buf_stencil_test(&self.raw, value)?;
+    #[cfg(feature = "ext-trace")]
+    self.trace(|_| TraceCall::StencilTest(value));
+    self.with_state(|state| {
+      state.pipeline.stencil_test.set(value);
+    })?;
     Ok(self)
   }
 
   pub fn face_culling(&self, value: FaceCulling) -> Result<&Self, B::Err> {
+    if self.is_cached(|pipeline| !pipeline.face_culling.is_invalid(&value))? {
+      return Ok(self);
+    }
+
+    self.spend_state_change()?;
     B::cmd_buf_face_culling(&self.raw, value)?;
+    #[cfg(feature = "ext-trace")]
+    self.trace(|_| TraceCall::FaceCulling(value));
+    self.with_state(|state| {
+      state.pipeline.face_culling.set(value);
+    })?;
     Ok(self)
   }
 
   pub fn viewport(&self, value: Viewport) -> Result<&Self, B::Err> {
-    B::cmd_buf_viewport(&self.raw, value)?;
+    if self.is_cached(|pipeline| !pipeline.viewport.is_invalid(&value))? {
+      return Ok(self);
+    }
+
+    self.spend_state_change()?;
+    let flipped = match value {
+      Viewport::Whole => value,
+      Viewport::Specific {
+        x,
+        y,
+        width,
+        height,
+      } => Viewport::Specific {
+        x,
+        y: self.flip_y(y, height)?,
+        width,
+        height,
+      },
+    };
+    B::cmd_buf_viewport(&self.raw, flipped)?;
+    #[cfg(feature = "ext-trace")]
+    self.trace(|_| TraceCall::Viewport(flipped));
+    self.with_state(|state| {
+      state.pipeline.viewport.set(value);
+    })?;
     Ok(self)
   }
 
   pub fn scissor(&self, value: Scissor) -> Result<&Self, B::Err> {
-    B::cmd_buf_scissor(&self.raw, value)?;
+    if self.is_cached(|pipeline| !pipeline.scissor.is_invalid(&value))? {
+      return Ok(self);
+    }
+
+    self.spend_state_change()?;
+    let flipped = match value {
+      Scissor::Off => value,
+      Scissor::On(region) => Scissor::On(ScissorRegion::new(
+        region.x(),
+        self.flip_y(region.y(), region.height())?,
+        region.width(),
+        region.height(),
+      )),
+    };
+    B::cmd_buf_scissor(&self.raw, flipped)?;
+    #[cfg(feature = "ext-trace")]
+    self.trace(|_| TraceCall::Scissor(flipped));
+    self.with_state(|state| {
+      state.pipeline.scissor.set(value);
+    })?;
     Ok(self)
   }
 
   pub fn clear_color(&self, value: RGBA32F) -> Result<&Self, B::Err> {
-    B::cmd_buf_clear_color(&self.raw, value)?;
+    if self.is_cached(|pipeline| !pipeline.clear_color.is_invalid(&value))? {
+      return Ok(self);
+    }
+
+    self.spend_state_change()?;
+    B::cmd_buf_clear_color(&self.raw, value.clone())?;
+    #[cfg(feature = "ext-trace")]
+    self.trace(|_| TraceCall::ClearColor(value.clone()));
+    self.with_state(|state| {
+      state.pipeline.clear_color.set(value);
+    })?;
     Ok(self)
   }
 
   pub fn clear_depth(&self, value: f32) -> Result<&Self, B::Err> {
+    if self.is_cached(|pipeline| !pipeline.clear_depth.is_invalid(&value))? {
+      return Ok(self);
+    }
+
+    self.spend_state_change()?;
     B::cmd_buf_clear_depth(&self.raw, value)?;
+    #[cfg(feature = "ext-trace")]
+    self.trace(|_| TraceCall::ClearDepth(value));
+    self.with_state(|state| {
+      state.pipeline.clear_depth.set(value);
+    })?;
     Ok(self)
   }
 
   pub fn srgb(&self, value: bool) -> Result<&Self, B::Err> {
+    if self.is_cached(|pipeline| !pipeline.srgb.is_invalid(&value))? {
+      return Ok(self);
+    }
+
+    self.spend_state_change()?;
     B::cmd_buf_srgb(&self.raw, value)?;
+    #[cfg(feature = "ext-trace")]
+    self.trace(|_| TraceCall::Srgb(value));
+    self.with_state(|state| {
+      state.pipeline.srgb.set(value);
+    })?;
+    Ok(self)
+  }
+
+  pub fn primitive_restart(&self, value: bool) -> Result<&Self, B::Err> {
+    if self.is_cached(|pipeline| !pipeline.primitive_restart.is_invalid(&value))? {
+      return Ok(self);
+    }
+
+    self.spend_state_change()?;
+    B::cmd_buf_primitive_restart(&self.raw, value)?;
+    #[cfg(feature = "ext-trace")]
+    self.trace(|_| TraceCall::PrimitiveRestart(value));
+    self.with_state(|state| {
+      state.pipeline.primitive_restart.set(value);
+    })?;
     Ok(self)
   }
 
-  pub fn uniform(&self, uniform: &Uniform<B>, value: *const u8) -> Result<&Self, B::Err> {
-    B::cmd_buf_set_uniform(&self.raw, &uniform.raw, value)?;
+  /// Set the value of a uniform.
+  ///
+  /// `value` is converted to the type of the uniform according to the rules of
+  /// [`UniformType::coerce`](piksels_backend::shader::UniformType::coerce); values that
+  /// cannot be converted are rejected with [`Error::UniformTypeMismatch`].
+  pub fn uniform<T>(&self, uniform: &Uniform<B>, value: &T) -> Result<&Self, B::Err>
+  where
+    T: ?Sized + UniformValue,
+  {
+    let bytes = uniform.ty().coerce(value)?;
+    self.uniform_bytes(uniform, &bytes)
+  }
+
+  /// Set a uniform to already coerced bytes.
+  pub(crate) fn uniform_bytes(&self, uniform: &Uniform<B>, bytes: &[u8]) -> Result<&Self, B::Err> {
+    if !uniform.is_cached() {
+      B::cmd_buf_set_uniform(&self.raw, &uniform.raw, bytes)?;
+      #[cfg(feature = "ext-trace")]
+      self.trace_uniform(uniform, bytes);
+      return Ok(self);
+    }
+
+    let id = uniform.raw.scarce_index();
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    let hash = hasher.finish();
+
+    if self.with_state(|state| state.uniform_values.get(&id) == Some(&hash))? {
+      return Ok(self);
+    }
+
+    B::cmd_buf_set_uniform(&self.raw, &uniform.raw, bytes)?;
+    #[cfg(feature = "ext-trace")]
+    self.trace_uniform(uniform, bytes);
+    self.with_state(|state| state.uniform_values.insert(id, hash))?;
+    Ok(self)
+  }
+
+  #[cfg(feature = "ext-trace")]
+  fn trace_uniform(&self, uniform: &Uniform<B>, bytes: &[u8]) {
+    self.trace(|recorder| TraceCall::Uniform {
+      uniform: recorder.id(TraceResourceKind::Uniform, uniform.raw.scarce_index()),
+      bytes: bytes.to_vec(),
+    });
+  }
+
+  /// Set the elements of a uniform array starting at element `first_element`, leaving the other ones untouched.
+  ///
+  /// The elements must fit in the array, and are converted to its element type the same way as with
+  /// [`CmdBuf::uniform`].
+  pub fn uniform_array<T>(
+    &self,
+    uniform: &Uniform<B>,
+    first_element: usize,
+    values: &[T],
+  ) -> Result<&Self, B::Err>
+  where
+    T: UniformValue,
+  {
+    let ty = uniform.ty();
+    let Some(len) = ty.array_len() else {
+      return Err(
+        Error::InvalidUniformArrayRange {
+          reason: format!("{ty:?} is not an array"),
+        }
+        .into(),
+      );
+    };
+
+    let end = first_element + values.len();
+    if end > len {
+      return Err(
+        Error::InvalidUniformArrayRange {
+          reason: format!("elements {first_element}..{end} out of bounds of an array of {len}"),
+        }
+        .into(),
+      );
+    }
+
+    let bytes = UniformType::new(ty.base(), len - first_element).coerce(values)?;
+    B::cmd_buf_set_uniform_array(&self.raw, &uniform.raw, first_element, &bytes)?;
+    #[cfg(feature = "ext-trace")]
+    self.trace(|recorder| TraceCall::UniformArray {
+      uniform: recorder.id(TraceResourceKind::Uniform, uniform.raw.scarce_index()),
+      first_element,
+      bytes: bytes.clone(),
+    });
+    self.with_state(|state| state.uniform_values.remove(&uniform.raw.scarce_index()))?;
     Ok(self)
   }
 
@@ -94,7 +829,103 @@ where
     texture: &Texture<B>,
     binding_point: &TextureBindingPoint<B>,
   ) -> Result<&Self, B::Err> {
-    B::cmd_buf_bind_texture(&self.raw, &texture.raw, &binding_point.raw)?;
+    self.use_raw_texture(&texture.raw, binding_point)
+  }
+
+  pub(crate) fn use_raw_texture(
+    &self,
+    texture: &B::Texture,
+    binding_point: &TextureBindingPoint<B>,
+  ) -> Result<&Self, B::Err> {
+    self.spend_state_change()?;
+    B::cmd_buf_bind_texture(&self.raw, texture, &binding_point.raw)?;
+    self.record(|recorder| {
+      recorder.bind_texture(binding_point.raw.scarce_index(), texture.scarce_index())
+    });
+    #[cfg(feature = "ext-trace")]
+    self.trace(|recorder| TraceCall::Texture {
+      texture: recorder.id(TraceResourceKind::Texture, texture.scarce_index()),
+      binding_point: recorder.id(
+        TraceResourceKind::TextureBindingPoint,
+        binding_point.raw.scarce_index(),
+      ),
+    });
+    Ok(self)
+  }
+
+  /// Mark the color attachment of some render targets as being active, as if it was a texture.
+  ///
+  /// This is how the output of a pass is sampled by the next ones, e.g. in post-processing chains. Render-only
+  /// attachments cannot be used.
+  pub fn use_color_attachment(
+    &self,
+    color_attachment: &ColorAttachment<B>,
+    binding_point: &TextureBindingPoint<B>,
+  ) -> Result<&Self, B::Err> {
+    if color_attachment.usage() == AttachmentUsage::RenderOnly {
+      return Err(
+        Error::NonSampleableAttachment {
+          reason: "render-only color attachments cannot be sampled".to_owned(),
+        }
+        .into(),
+      );
+    }
+
+    self.spend_state_change()?;
+    B::cmd_buf_bind_color_attachment(&self.raw, &color_attachment.raw, &binding_point.raw)?;
+    self.record(|recorder| {
+      recorder.bind_texture(
+        binding_point.raw.scarce_index(),
+        color_attachment.raw.scarce_index(),
+      )
+    });
+    #[cfg(feature = "ext-trace")]
+    self.trace(|recorder| TraceCall::ColorAttachment {
+      color_attachment: recorder.id(
+        TraceResourceKind::ColorAttachment,
+        color_attachment.raw.scarce_index(),
+      ),
+      binding_point: recorder.id(
+        TraceResourceKind::TextureBindingPoint,
+        binding_point.raw.scarce_index(),
+      ),
+    });
+    Ok(self)
+  }
+
+  /// Mark the depth/stencil attachment of some render targets as being active, as if it was a texture.
+  ///
+  /// This is how shadow maps are sampled; see
+  /// [`Device::new_depth_only_render_targets`](crate::device::Device::new_depth_only_render_targets). Render-only
+  /// attachments cannot be used; backends report an error if they are.
+  pub fn use_depth_stencil_attachment(
+    &self,
+    depth_stencil_attachment: &DepthStencilAttachment<B>,
+    binding_point: &TextureBindingPoint<B>,
+  ) -> Result<&Self, B::Err> {
+    self.spend_state_change()?;
+    B::cmd_buf_bind_depth_stencil_attachment(
+      &self.raw,
+      &depth_stencil_attachment.raw,
+      &binding_point.raw,
+    )?;
+    self.record(|recorder| {
+      recorder.bind_texture(
+        binding_point.raw.scarce_index(),
+        depth_stencil_attachment.raw.scarce_index(),
+      )
+    });
+    #[cfg(feature = "ext-trace")]
+    self.trace(|recorder| TraceCall::DepthStencilAttachment {
+      depth_stencil_attachment: recorder.id(
+        TraceResourceKind::DepthStencilAttachment,
+        depth_stencil_attachment.raw.scarce_index(),
+      ),
+      binding_point: recorder.id(
+        TraceResourceKind::TextureBindingPoint,
+        binding_point.raw.scarce_index(),
+      ),
+    });
     Ok(self)
   }
 
@@ -109,6 +940,17 @@ where
       &texture_binding_point.raw,
       &shader_texture_binding_point.raw,
     )?;
+    #[cfg(feature = "ext-trace")]
+    self.trace(|recorder| TraceCall::AssociateTexture {
+      binding_point: recorder.id(
+        TraceResourceKind::TextureBindingPoint,
+        texture_binding_point.raw.scarce_index(),
+      ),
+      shader_binding_point: recorder.id(
+        TraceResourceKind::ShaderTextureBindingPoint,
+        shader_texture_binding_point.raw.scarce_index(),
+      ),
+    });
     Ok(self)
   }
 
@@ -118,7 +960,33 @@ where
     uniform_buffer: &UniformBuffer<B>,
     binding_point: &UniformBufferBindingPoint<B>,
   ) -> Result<&Self, B::Err> {
-    B::cmd_buf_bind_uniform_buffer(&self.raw, &uniform_buffer.raw, &binding_point.raw)?;
+    self.use_raw_uniform_buffer(&uniform_buffer.raw, binding_point)
+  }
+
+  pub(crate) fn use_raw_uniform_buffer(
+    &self,
+    uniform_buffer: &B::UniformBuffer,
+    binding_point: &UniformBufferBindingPoint<B>,
+  ) -> Result<&Self, B::Err> {
+    self.spend_state_change()?;
+    B::cmd_buf_bind_uniform_buffer(&self.raw, uniform_buffer, &binding_point.raw)?;
+    self.record(|recorder| {
+      recorder.bind_uniform_buffer(
+        binding_point.raw.scarce_index(),
+        uniform_buffer.scarce_index(),
+      )
+    });
+    #[cfg(feature = "ext-trace")]
+    self.trace(|recorder| TraceCall::UniformBuffer {
+      uniform_buffer: recorder.id(
+        TraceResourceKind::UniformBuffer,
+        uniform_buffer.scarce_index(),
+      ),
+      binding_point: recorder.id(
+        TraceResourceKind::UniformBufferBindingPoint,
+        binding_point.raw.scarce_index(),
+      ),
+    });
     Ok(self)
   }
 
@@ -133,20 +1001,369 @@ where
       &uniform_buffer_binding_point.raw,
       &shader_uniform_buffer_binding_point.raw,
     )?;
+    #[cfg(feature = "ext-trace")]
+    self.trace(|recorder| TraceCall::AssociateUniformBuffer {
+      binding_point: recorder.id(
+        TraceResourceKind::UniformBufferBindingPoint,
+        uniform_buffer_binding_point.raw.scarce_index(),
+      ),
+      shader_binding_point: recorder.id(
+        TraceResourceKind::ShaderUniformBufferBindingPoint,
+        shader_uniform_buffer_binding_point.raw.scarce_index(),
+      ),
+    });
+    Ok(self)
+  }
+
+  /// Mark a storage buffer as being active.
+  pub fn use_storage_buffer(
+    &self,
+    storage_buffer: &StorageBuffer<B>,
+    binding_point: &StorageBufferBindingPoint<B>,
+  ) -> Result<&Self, B::Err> {
+    self.spend_state_change()?;
+    B::cmd_buf_bind_storage_buffer(&self.raw, &storage_buffer.raw, &binding_point.raw)?;
+    self.record(|recorder| {
+      recorder.bind_storage_buffer(binding_point.raw.scarce_index(), storage_buffer.id())
+    });
+    #[cfg(feature = "ext-trace")]
+    self.trace(|recorder| TraceCall::StorageBuffer {
+      storage_buffer: recorder.id(TraceResourceKind::StorageBuffer, storage_buffer.id()),
+      binding_point: recorder.id(
+        TraceResourceKind::StorageBufferBindingPoint,
+        binding_point.raw.scarce_index(),
+      ),
+    });
+    Ok(self)
+  }
+
+  /// Associate a storage buffer binding point with a shader storage buffer binding point.
+  pub fn associate_storage_buffer(
+    &self,
+    storage_buffer_binding_point: &StorageBufferBindingPoint<B>,
+    shader_storage_buffer_binding_point: &ShaderStorageBufferBindingPoint<B>,
+  ) -> Result<&Self, B::Err> {
+    B::cmd_buf_associate_storage_buffer_binding_point(
+      &self.raw,
+      &storage_buffer_binding_point.raw,
+      &shader_storage_buffer_binding_point.raw,
+    )?;
+    #[cfg(feature = "ext-trace")]
+    self.trace(|recorder| TraceCall::AssociateStorageBuffer {
+      binding_point: recorder.id(
+        TraceResourceKind::StorageBufferBindingPoint,
+        storage_buffer_binding_point.raw.scarce_index(),
+      ),
+      shader_binding_point: recorder.id(
+        TraceResourceKind::ShaderStorageBufferBindingPoint,
+        shader_storage_buffer_binding_point.raw.scarce_index(),
+      ),
+    });
+    Ok(self)
+  }
+
+  /// Time the commands recorded by `f` on the device, e.g. the draws of a pass.
+  ///
+  /// The timing is reported as `name` by [`Device::timings`](crate::device::Device::timings) once the device has
+  /// executed the commands.
+  #[cfg(feature = "ext-profiler")]
+  pub fn timed<F>(&self, name: impl Into<String>, f: F) -> Result<&Self, B::Err>
+  where
+    B: BackendProfiler + 'static,
+    F: FnOnce(&Self) -> Result<&Self, B::Err>,
+  {
+    let query = self.begin_timer_query()?;
+    f(self)?;
+    self.end_timer_query(name.into(), query)?;
+    Ok(self)
+  }
+
+  /// Start timing the commands recorded next.
+  #[cfg(feature = "ext-profiler")]
+  pub(crate) fn begin_timer_query(&self) -> Result<TimerQuery<B>, B::Err>
+  where
+    B: BackendProfiler,
+  {
+    let raw = B::cmd_buf_begin_timer_query(&self.raw)?;
+    Ok(TimerQuery { raw })
+  }
+
+  /// Stop timing commands, and report the timing as `name` in [`Device::timings`](crate::device::Device::timings).
+  ///
+  /// The timing is not reported if the device is gone.
+  #[cfg(feature = "ext-profiler")]
+  pub(crate) fn end_timer_query(&self, name: String, query: TimerQuery<B>) -> Result<(), B::Err>
+  where
+    B: BackendProfiler + 'static,
+  {
+    B::cmd_buf_end_timer_query(&self.raw, &query.raw)?;
+    self.with_cache(|cache| cache.push_timing(PendingTiming::new(name, query)))?;
+    Ok(())
+  }
+
+  /// Read back the texels of a color attachment of some render targets.
+  #[cfg(feature = "ext-readback")]
+  pub fn read_color_attachment(
+    &self,
+    render_targets: &RenderTargets<B>,
+    index: usize,
+  ) -> Result<Readback<B>, B::Err>
+  where
+    B: BackendReadback,
+  {
+    let color_attachment = render_targets.color_attachment(index)?;
+    let raw = B::cmd_buf_read_color_attachment(&self.raw, &color_attachment.raw)?;
+    #[cfg(feature = "ext-trace")]
+    self.trace(|recorder| {
+      TraceCall::ReadColorAttachment(recorder.id(
+        TraceResourceKind::ColorAttachment,
+        color_attachment.raw.scarce_index(),
+      ))
+    });
+    Ok(Readback::from_raw(raw, render_targets.storage()))
+  }
+
+  /// Resolve a multisample texture into a single-sample texture.
+  ///
+  /// `dst` must have the single-sample equivalent storage of `src` (see
+  /// [`Storage::resolved`](piksels_backend::texture::Storage::resolved)).
+  pub fn resolve_texture(&self, src: &Texture<B>, dst: &Texture<B>) -> Result<&Self, B::Err> {
+    if src.storage().resolved() != Some(dst.storage()) {
+      return Err(
+        Error::IncompatibleResolve {
+          reason: format!(
+            "cannot resolve {:?} into {:?}",
+            src.storage(),
+            dst.storage()
+          ),
+        }
+        .into(),
+      );
+    }
+
+    B::cmd_buf_resolve_texture(&self.raw, &src.raw, &dst.raw)?;
+    #[cfg(feature = "ext-trace")]
+    self.trace(|recorder| TraceCall::ResolveTexture {
+      src: recorder.id(TraceResourceKind::Texture, src.id()),
+      dst: recorder.id(TraceResourceKind::Texture, dst.id()),
+    });
+    Ok(self)
+  }
+
+  /// Resolve multisample render targets into single-sample render targets, e.g. to post-process the result of a
+  /// multisample pass.
+  ///
+  /// `dst` must have the single-sample equivalent storage of `src` (see
+  /// [`Storage::resolved`](piksels_backend::texture::Storage::resolved)), and color attachments with the same indices
+  /// and types. Depth/stencil attachments are resolved too.
+  pub fn resolve_render_targets(
+    &self,
+    src: &RenderTargets<B>,
+    dst: &RenderTargets<B>,
+  ) -> Result<&Self, B::Err> {
+    let incompatible = |reason| Err(Error::IncompatibleResolve { reason }.into());
+
+    if src.storage().resolved() != Some(dst.storage()) {
+      return incompatible(format!(
+        "cannot resolve {:?} into {:?}",
+        src.storage(),
+        dst.storage()
+      ));
+    }
+
+    let attachments = |render_targets: &RenderTargets<B>| {
+      render_targets
+        .color_attachments()
+        .map(|(index, _, ty)| (index, ty))
+        .collect::<Vec<_>>()
+    };
+    if attachments(src) != attachments(dst) {
+      return incompatible(format!(
+        "cannot resolve color attachments {:?} into {:?}",
+        attachments(src),
+        attachments(dst)
+      ));
+    }
+
+    B::cmd_buf_resolve_render_targets(&self.raw, &src.raw, &dst.raw)?;
+    #[cfg(feature = "ext-trace")]
+    self.trace(|recorder| TraceCall::ResolveRenderTargets {
+      src: recorder.id(TraceResourceKind::RenderTargets, src.id()),
+      dst: recorder.id(TraceResourceKind::RenderTargets, dst.id()),
+    });
     Ok(self)
   }
 
+  /// Bind render targets.
+  ///
+  /// If the render targets have sRGB color attachments, sRGB encoding is switched to their
+  /// [`RenderTargets::srgb_encoding`], so that linear and sRGB render targets can be rendered to in turn. Otherwise, as
+  /// for swap chain render targets, it is left as set with [`CmdBuf::srgb`].
+  ///
+  /// Swap chain render targets that became stale after resizing the swap chain cannot be bound. Render targets that
+  /// are already bound are not bound again.
   pub fn render_targets(&self, render_targets: &RenderTargets<B>) -> Result<&Self, B::Err> {
-    B::cmd_buf_bind_render_targets(&self.raw, &render_targets.raw)?;
+    render_targets.check_not_stale()?;
+
+    let id = render_targets.id();
+    if self.with_state(|state| state.render_targets.is_invalid(&id))? {
+      self.spend_state_change()?;
+      B::cmd_buf_bind_render_targets(&self.raw, &render_targets.raw)?;
+      #[cfg(feature = "ext-trace")]
+      self.trace(|recorder| {
+        TraceCall::RenderTargets(recorder.id(TraceResourceKind::RenderTargets, id.clone()))
+      });
+      self.with_state(|state| {
+        state.render_targets.set(id);
+      })?;
+    }
+
+    if let Some(srgb) = render_targets.srgb_encoding() {
+      self.srgb(srgb)?;
+    }
+
+    // flipped viewports and scissors depend on the height of the render targets
+    let target_height = convention::storage_height(render_targets.storage());
+    let previous_height = self.target_height.replace(target_height);
+    if self.coordinate_convention != CoordinateConvention::OriginBottomLeft
+      && previous_height != target_height
+    {
+      self.with_state(|state| {
+        state.pipeline.viewport.invalidate();
+        state.pipeline.scissor.invalidate();
+      })?;
+    }
+
     Ok(self)
   }
 
+  /// Bind a shader, unless it is already bound.
   pub fn shader(&self, shader: &Shader<B>) -> Result<&Self, B::Err> {
-    B::cmd_buf_bind_shader(&self.raw, &shader.raw)?;
+    let id = shader.id();
+    if self.with_state(|state| state.shader.is_invalid(&id))? {
+      self.spend_state_change()?;
+      B::cmd_buf_bind_shader(&self.raw, &shader.raw)?;
+      #[cfg(feature = "ext-trace")]
+      self.trace(|recorder| TraceCall::Shader(recorder.id(TraceResourceKind::Shader, id.clone())));
+      self.with_state(|state| {
+        state.shader.set(id);
+      })?;
+    }
+
+    self.record(|recorder| recorder.bind_shader(shader.id()));
     Ok(self)
   }
 
+  /// Draw a vertex array.
+  ///
+  /// If primitive restart is enabled, primitives restart at [`DEFAULT_RESTART_INDEX`].
+  pub fn draw(&self, vertex_array: &VertexArray<B>) -> Result<&Self, B::Err> {
+    self.draw_with_restart_index(vertex_array, DEFAULT_RESTART_INDEX)
+  }
+
+  /// Draw a vertex array, restarting primitives at `restart_index` if primitive restart is enabled.
+  pub fn draw_with_restart_index(
+    &self,
+    vertex_array: &VertexArray<B>,
+    restart_index: u32,
+  ) -> Result<&Self, B::Err> {
+    self.spend_draw()?;
+    B::cmd_buf_draw_vertex_array(&self.raw, &vertex_array.raw, restart_index)?;
+    self.record(|recorder| recorder.draw(vertex_array.id()));
+    #[cfg(feature = "ext-trace")]
+    self.trace(|recorder| TraceCall::Draw {
+      vertex_array: recorder.id(TraceResourceKind::VertexArray, vertex_array.id()),
+      restart_index,
+    });
+    Ok(self)
+  }
+
+  /// Draw a view on a vertex array.
+  ///
+  /// If primitive restart is enabled, primitives restart at [`DEFAULT_RESTART_INDEX`].
+  pub fn draw_view(&self, view: &VertexArrayView<B>) -> Result<&Self, B::Err> {
+    self.draw_view_with_restart_index(view, DEFAULT_RESTART_INDEX)
+  }
+
+  /// Draw a view on a vertex array, restarting primitives at `restart_index` if primitive restart is enabled.
+  pub fn draw_view_with_restart_index(
+    &self,
+    view: &VertexArrayView<B>,
+    restart_index: u32,
+  ) -> Result<&Self, B::Err> {
+    self.spend_draw()?;
+    B::cmd_buf_draw_vertex_array_range(
+      &self.raw,
+      view.vertex_array(),
+      view.start_vertex(),
+      view.vertex_count(),
+      view.start_instance(),
+      view.instance_count(),
+      restart_index,
+    )?;
+    self.record(|recorder| recorder.draw(view.vertex_array().scarce_index()));
+    #[cfg(feature = "ext-trace")]
+    self.trace(|recorder| TraceCall::DrawRange {
+      vertex_array: recorder.id(
+        TraceResourceKind::VertexArray,
+        view.vertex_array().scarce_index(),
+      ),
+      start_vertex: view.start_vertex(),
+      vertex_count: view.vertex_count(),
+      start_instance: view.start_instance(),
+      instance_count: view.instance_count(),
+      restart_index,
+    });
+    Ok(self)
+  }
+
+  /// Execute the commands recorded in a deferred command buffer.
+  ///
+  /// `deferred` must be finished and must come from a [`DeferredContext`](crate::deferred::DeferredContext); deferred
+  /// command buffers cannot execute other ones. The state set by the command buffer is unknown afterwards, so the next
+  /// state changes are never skipped as redundant.
+  pub fn execute(&self, deferred: &CmdBuf<B>) -> Result<&Self, B::Err> {
+    let invalid = |reason: &str| {
+      Err(
+        Error::InvalidExecute {
+          reason: reason.to_owned(),
+        }
+        .into(),
+      )
+    };
+
+    if self.deferred {
+      return invalid("deferred command buffers cannot execute command buffers");
+    }
+
+    if !deferred.deferred {
+      return invalid("only deferred command buffers can be executed");
+    }
+
+    B::cmd_buf_execute(&self.raw, &deferred.raw)?;
+    #[cfg(feature = "ext-trace")]
+    self
+      .trace(|recorder| TraceCall::Execute(recorder.id(TraceResourceKind::CmdBuf, deferred.id())));
+    self.with_state(CmdBufState::invalidate)?;
+    self.target_height.set(None);
+    Ok(self)
+  }
+
+  /// Finish recording.
+  ///
+  /// The draws and state changes counted against the recording budget are reset. Resources dropped with
+  /// [`GcMode::Deferred`](crate::gc::GcMode::Deferred) before finishing can be collected.
   pub fn finish(&self) -> Result<(), B::Err> {
-    B::cmd_buf_finish(&self.raw)
+    B::cmd_buf_finish(&self.raw)?;
+    #[cfg(feature = "ext-trace")]
+    self.trace(|_| TraceCall::Finish);
+
+    if let Some(cache) = self.cache.upgrade() {
+      cache.lock().map_err(Error::from)?.submit();
+    }
+
+    self.draws.set(0);
+    self.state_changes.set(0);
+    Ok(())
   }
 }
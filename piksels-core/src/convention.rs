@@ -0,0 +1,43 @@
+//! Coordinate conventions.
+
+use piksels_backend::texture::Storage;
+
+/// Origin of the coordinate system used for viewports, scissors and texture uploads.
+///
+/// Backends use [`CoordinateConvention::OriginBottomLeft`] natively. With [`CoordinateConvention::OriginTopLeft`],
+/// rectangles and texel rows are flipped vertically before being handed to the backend, which eases porting code
+/// written against window systems or top-left APIs.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum CoordinateConvention {
+  /// The origin is the bottom-left corner and `y` goes up.
+  #[default]
+  OriginBottomLeft,
+
+  /// The origin is the top-left corner and `y` goes down.
+  OriginTopLeft,
+}
+
+impl CoordinateConvention {
+  /// Convert the `y` coordinate of a region of height `height` inside a surface of height `surface_height` from this
+  /// convention to the backend one.
+  pub fn flip_y(self, y: u32, height: u32, surface_height: u32) -> u32 {
+    match self {
+      CoordinateConvention::OriginBottomLeft => y,
+      CoordinateConvention::OriginTopLeft => {
+        surface_height.saturating_sub(y.saturating_add(height))
+      }
+    }
+  }
+}
+
+/// Height of a storage, if it has rows that can be flipped.
+pub(crate) fn storage_height(storage: Storage) -> Option<u32> {
+  match storage {
+    Storage::Flat2D { height, .. }
+    | Storage::Flat2DMultiSample { height, .. }
+    | Storage::Flat3D { height, .. }
+    | Storage::Layered2D { height, .. }
+    | Storage::Layered2DMultiSample { height, .. } => Some(height),
+    _ => None,
+  }
+}
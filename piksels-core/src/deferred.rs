@@ -0,0 +1,110 @@
+//! Deferred contexts.
+//!
+//! Recording command buffers is usually bound to the thread the graphics context lives on. A [`DeferredContext`]
+//! creates deferred command buffers instead, that only record commands, without executing them: they can be recorded
+//! on worker threads — e.g. to traverse a scene in parallel — and are executed later with
+//! [`CmdBuf::execute`](crate::cmd_buf::CmdBuf::execute), on the thread of the context. Backends without native
+//! support for secondary command buffers replay the recorded commands.
+//!
+//! ```ignore
+//! let context = device.create_deferred_context();
+//! let cmd_bufs = thread::scope(|scope| {
+//!   let workers = chunks.map(|chunk| scope.spawn(|| record_chunk(&context, chunk)));
+//!   workers.map(|worker| worker.join().unwrap()).collect::<Vec<_>>()
+//! });
+//!
+//! for deferred in &cmd_bufs {
+//!   cmd_buf.execute(deferred)?;
+//! }
+//! ```
+
+use std::sync::{Arc, Mutex, Weak};
+
+use piksels_backend::{error::Error, Backend};
+
+use crate::{
+  budget::RecordingBudget,
+  cache::{Cache, TrackingTag},
+  cmd_buf::CmdBuf,
+  convention::CoordinateConvention,
+};
+
+/// Context creating deferred command buffers, from any thread.
+///
+/// Create one with [`Device::create_deferred_context`](crate::device::Device::create_deferred_context).
+#[derive(Debug)]
+pub struct DeferredContext<B>
+where
+  B: Backend,
+{
+  backend: Arc<B>,
+  cache: Weak<Mutex<Cache<B>>>,
+  coordinate_convention: CoordinateConvention,
+  recording_budget: RecordingBudget,
+}
+
+impl<B> Clone for DeferredContext<B>
+where
+  B: Backend,
+{
+  fn clone(&self) -> Self {
+    Self {
+      backend: self.backend.clone(),
+      cache: self.cache.clone(),
+      coordinate_convention: self.coordinate_convention,
+      recording_budget: self.recording_budget,
+    }
+  }
+}
+
+impl<B> DeferredContext<B>
+where
+  B: Backend,
+{
+  pub(crate) fn new(
+    backend: Arc<B>,
+    cache: Weak<Mutex<Cache<B>>>,
+    coordinate_convention: CoordinateConvention,
+    recording_budget: RecordingBudget,
+  ) -> Self {
+    Self {
+      backend,
+      cache,
+      coordinate_convention,
+      recording_budget,
+    }
+  }
+
+  /// Create a new deferred [`CmdBuf`].
+  ///
+  /// As for any command buffer, the pipeline state a deferred command buffer is executed on is not known while
+  /// recording it. Call [`CmdBuf::clear_state`] first to make it independent of that state; this is
+  /// done automatically if defaults were set with [`Device::set_defaults`](crate::device::Device::set_defaults).
+  pub fn new_cmd_buf(&self) -> Result<CmdBuf<B>, B::Err> {
+    let raw = self.backend.new_deferred_cmd_buf()?;
+    let mut defaults = None;
+    let mut tag = TrackingTag::default();
+
+    if let Some(cache) = self.cache.upgrade() {
+      let mut cache = cache.lock().map_err(Error::from)?;
+      tag = cache.track_cmd_buf(&raw);
+      defaults = cache.defaults().map(|defaults| defaults.state.clone());
+    }
+
+    let cmd_buf = CmdBuf::from_raw_deferred(
+      raw,
+      Arc::downgrade(&self.backend),
+      self.cache.clone(),
+      self.coordinate_convention,
+      self.recording_budget,
+      defaults.clone().unwrap_or_default(),
+      tag,
+    );
+
+    if defaults.is_some() {
+      cmd_buf.clear_state()?;
+    }
+
+    Ok(cmd_buf)
+  }
+}
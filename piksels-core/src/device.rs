@@ -1,29 +1,148 @@
-use std::collections::HashSet;
+use std::{
+  collections::HashSet,
+  sync::{Arc, Mutex, MutexGuard},
+  time::Instant,
+};
 
+#[cfg(feature = "ext-profiler")]
+use piksels_backend::extension::profiler::BackendProfiler;
 use piksels_backend::{
-  render_targets::{ColorAttachmentPoint, DepthStencilAttachmentPoint},
+  depth_stencil::Comparison,
+  error,
+  error::Error,
+  extension::{
+    logger::{Logger, LoggerExt},
+    ExtensionKind, Extensions, NegotiatedExtensions,
+  },
+  pixel::Pixel,
+  render_targets::{
+    AttachmentUsage, ChannelBits, ColorAttachmentPoint, DepthStencilAttachmentPoint,
+    DepthStencilType, RenderTargetsError, RenderTargetsFormat,
+  },
   shader::ShaderSources,
-  swap_chain::SwapChainMode,
-  texture::{Sampling, Storage},
-  vertex_array::VertexArrayData,
-  Backend, BackendInfo,
+  swap_chain::{SwapChainCapabilities, SwapChainFormat, SwapChainMode},
+  texture::{MagFilter, MinFilter, Sampling, Storage, Wrap},
+  vertex::VertexAttr,
+  vertex_array::{StepRate, VertexArrayData, VertexStream},
+  Backend, BackendInfo, DeviceFeatures, DeviceLimits, Scarce,
 };
+#[cfg(feature = "ext-sparse-texture")]
+use piksels_backend::{extension::sparse_texture::BackendSparseTexture, texture::Size};
 
+#[cfg(feature = "ext-profiler")]
+use crate::profiler::Timing;
 use crate::{
+  budget::RecordingBudget,
+  cache::{Cache, CacheDump, ResourceId, SwapChainInfo},
   cmd_buf::CmdBuf,
-  render_targets::RenderTargets,
-  shader::{Shader, UniformBufferBindingPoint},
+  convention::CoordinateConvention,
+  deferred::DeferredContext,
+  diagnostic::DiagnosticReport,
+  error_policy::ErrorPolicy,
+  frame::FrameIndex,
+  gc::{Garbage, GcBudget, GcMode},
+  layers::Layers,
+  name::Named,
+  pipeline::PipelineDefaults,
+  preprocessor::PreprocessedSources,
+  render_targets::{DepthStencilAttachment, RenderTargets},
+  shader::{Shader, ShaderBindingPoints, ShaderKey, UniformBufferBindingPoint},
+  storage_buffer::{StorageBuffer, StorageBufferBindingPoint},
   swap_chain::SwapChain,
   texture::{Texture, TextureBindingPoint},
+  units::{SpillStrategy, UnitsTelemetry},
   vertex_array::VertexArray,
 };
 
+/// Builder of [`Device`]s, negotiating extensions with the backend.
+///
+/// ```ignore
+/// let (device, extensions) = DeviceBuilder::new()
+///   .logger(LoggerExt::new(LogLevel::Warn, MyLogger))
+///   .extension(ExtensionKind::Readback)
+///   .build::<MyBackend>()?;
+///
+/// if !extensions.is_accepted(ExtensionKind::Readback) {
+///   // disable screenshots
+/// }
+/// ```
+#[derive(Default)]
+pub struct DeviceBuilder {
+  extensions: Extensions,
+}
+
+impl DeviceBuilder {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Request an extension.
+  pub fn extension(mut self, kind: ExtensionKind) -> Self {
+    self.extensions = self.extensions.with(kind);
+    self
+  }
+
+  /// Request the logger extension, logging with `logger`.
+  pub fn logger(mut self, logger: LoggerExt<impl 'static + Logger + Send + Sync>) -> Self {
+    self.extensions = self.extensions.logger(logger);
+    self
+  }
+
+  /// Build the backend with the requested extensions, and create a device from it.
+  ///
+  /// Extensions the backend doesn’t support are not an error; check the returned [`NegotiatedExtensions`] to know
+  /// which ones were accepted.
+  pub fn build<B>(mut self) -> Result<(Device<B>, NegotiatedExtensions), B::Err>
+  where
+    B: Backend,
+  {
+    let backend = B::build(&mut self.extensions)?;
+    let negotiated = self.extensions.negotiated();
+    let mut device = Device::new(backend)?;
+    device.extensions = negotiated.clone();
+
+    Ok((device, negotiated))
+  }
+}
+
+/// Entry point to create resources and command buffers.
+///
+/// # Threading
+///
+/// A device is [`Send`] and [`Sync`] as soon as its backend is, so it can be shared across threads behind an
+/// [`Arc`]. The same goes for resources (textures, vertex arrays, shaders, render targets, swap chains, etc.).
+/// [`CmdBuf`]s are only [`Send`]: a command buffer can be recorded on another thread, but by a single thread at a
+/// time. Mapped bytes borrow their resource and cannot outlive it.
+///
+/// Backends with a notion of current context (e.g. OpenGL) can only be used from the thread their context is current
+/// on. Use [`Device::make_current`] before using a device — or anything created from it — on another thread.
+///
+/// # Resource lifetime
+///
+/// Resources are handles: several of them can refer to the same resource, e.g. when getting it back with
+/// [`Device::texture_by_id`], or when shader variants are shared. A resource is destroyed once its last handle is
+/// dropped, and handed to the garbage collector (see [`GcMode`]); errors destroying it are reported on the next
+/// [`Device::collect_garbage`] or [`Device::drain_errors`]. The `drop_*` functions of the device (e.g.
+/// [`Device::drop_texture`]) drop a handle the same way, but report errors right away. The cache is the single owner of
+/// resources: a resource is never destroyed twice, even if its [`ResourceId`] was given to another resource since then.
+///
+/// Dropping the device destroys the garbage not collected yet, and then every resource still alive. Handles outliving
+/// the device must not be used anymore, besides being dropped, which then does nothing.
 #[derive(Debug)]
 pub struct Device<B>
 where
   B: Backend,
 {
-  backend: B,
+  backend: Arc<B>,
+  cache: Arc<Mutex<Cache<B>>>,
+  limits: DeviceLimits,
+  features: DeviceFeatures,
+  extensions: NegotiatedExtensions,
+  coordinate_convention: CoordinateConvention,
+  gc_mode: GcMode,
+  error_policy: ErrorPolicy,
+  errors: Mutex<Vec<B::Err>>,
+  recording_budget: RecordingBudget,
 }
 
 impl<B> Device<B>
@@ -31,7 +150,332 @@ where
   B: Backend,
 {
   pub fn new(backend: B) -> Result<Self, B::Err> {
-    Ok(Self { backend })
+    let limits = backend.limits()?;
+    let features = backend.features()?;
+    let cache = Cache::with_units(&limits);
+
+    Ok(Self {
+      backend: Arc::new(backend),
+      cache: Arc::new(Mutex::new(cache)),
+      limits,
+      features,
+      extensions: NegotiatedExtensions::default(),
+      coordinate_convention: CoordinateConvention::default(),
+      gc_mode: GcMode::default(),
+      error_policy: ErrorPolicy::default(),
+      errors: Mutex::new(Vec::new()),
+      recording_budget: RecordingBudget::default(),
+    })
+  }
+
+  /// Use a given coordinate convention for viewports, scissors and texture uploads.
+  ///
+  /// Only the command buffers and textures created after the call are affected.
+  pub fn with_coordinate_convention(mut self, coordinate_convention: CoordinateConvention) -> Self {
+    self.coordinate_convention = coordinate_convention;
+    self
+  }
+
+  pub fn coordinate_convention(&self) -> CoordinateConvention {
+    self.coordinate_convention
+  }
+
+  /// Use a given garbage collection mode for dropped resources.
+  pub fn with_gc_mode(mut self, gc_mode: GcMode) -> Self {
+    self.gc_mode = gc_mode;
+
+    if let Ok(mut cache) = self.cache.lock() {
+      cache.set_gc_mode(gc_mode);
+    }
+
+    self
+  }
+
+  pub fn gc_mode(&self) -> GcMode {
+    self.gc_mode
+  }
+
+  /// Use a given policy for non-fatal backend errors.
+  pub fn with_error_policy(mut self, error_policy: ErrorPolicy) -> Self {
+    self.error_policy = error_policy;
+    self
+  }
+
+  pub fn error_policy(&self) -> ErrorPolicy {
+    self.error_policy
+  }
+
+  /// Use a given recording budget for command buffers.
+  ///
+  /// Only the command buffers created after the call are affected. The budget of a command buffer can also be changed
+  /// with [`CmdBuf::set_recording_budget`].
+  pub fn with_recording_budget(mut self, recording_budget: RecordingBudget) -> Self {
+    self.recording_budget = recording_budget;
+    self
+  }
+
+  pub fn recording_budget(&self) -> RecordingBudget {
+    self.recording_budget
+  }
+
+  /// Set the defaults of the device.
+  ///
+  /// Once defaults are set, every new [`CmdBuf`] starts with their pipeline state (see [`CmdBuf::clear_state`]), and
+  /// textures created with [`Device::new_texture_with_defaults`] use their sampling. Command buffers created before
+  /// the call are not affected.
+  pub fn set_defaults(&self, defaults: PipelineDefaults) -> Result<(), B::Err> {
+    self.cache()?.set_defaults(defaults);
+    Ok(())
+  }
+
+  /// Defaults of the device; [`PipelineDefaults::default`] if none were set.
+  pub fn defaults(&self) -> Result<PipelineDefaults, B::Err> {
+    Ok(self.cache()?.defaults().cloned().unwrap_or_default())
+  }
+
+  /// Forget everything cached about the state of the backend.
+  ///
+  /// The cache assumes it is the only one changing the state of the backend. Call this after the state was changed
+  /// behind its back — e.g. by raw graphics API calls or another library sharing the context — so that the next
+  /// commands are issued even if they look redundant. The category-specific functions forget less, e.g.
+  /// [`Device::invalidate_pipeline_state`].
+  pub fn invalidate_cache(&self) -> Result<(), B::Err> {
+    self.cache()?.invalidate();
+    Ok(())
+  }
+
+  /// Forget which shader is bound.
+  ///
+  /// See [`Device::invalidate_cache`].
+  pub fn invalidate_shader_binding(&self) -> Result<(), B::Err> {
+    self.cache()?.invalidate_shader_binding();
+    Ok(())
+  }
+
+  /// Forget the values set to uniforms.
+  ///
+  /// See [`Device::invalidate_cache`].
+  pub fn invalidate_uniform_values(&self) -> Result<(), B::Err> {
+    self.cache()?.invalidate_uniform_values();
+    Ok(())
+  }
+
+  /// Forget the cached pipeline state (blending, depth test, viewport, etc.).
+  ///
+  /// See [`Device::invalidate_cache`].
+  pub fn invalidate_pipeline_state(&self) -> Result<(), B::Err> {
+    self.cache()?.invalidate_pipeline_state();
+    Ok(())
+  }
+
+  /// Forget which textures are bound to texture units.
+  ///
+  /// See [`Device::invalidate_cache`].
+  pub fn invalidate_texture_bindings(&self) -> Result<(), B::Err> {
+    self.cache()?.invalidate_texture_bindings();
+    Ok(())
+  }
+
+  /// Forget which uniform buffers are bound to uniform buffer units.
+  ///
+  /// See [`Device::invalidate_cache`].
+  pub fn invalidate_uniform_buffer_bindings(&self) -> Result<(), B::Err> {
+    self.cache()?.invalidate_uniform_buffer_bindings();
+    Ok(())
+  }
+
+  /// Forget which render targets are bound.
+  ///
+  /// See [`Device::invalidate_cache`].
+  pub fn invalidate_render_targets_bindings(&self) -> Result<(), B::Err> {
+    self.cache()?.invalidate_render_targets_bindings();
+    Ok(())
+  }
+
+  /// Take the errors accumulated so far with [`ErrorPolicy::Accumulate`].
+  ///
+  /// This is typically called once per frame.
+  pub fn drain_errors(&self) -> Result<Vec<B::Err>, B::Err> {
+    self.report_drop_errors()?;
+
+    let mut errors = self.errors.lock().map_err(Error::from)?;
+    Ok(errors.drain(..).collect())
+  }
+
+  /// Handle a non-fatal backend error according to the error policy.
+  fn handle_error(&self, err: B::Err) -> Result<(), B::Err> {
+    match self.error_policy {
+      ErrorPolicy::Panic => panic!("non-fatal backend error: {err}"),
+      ErrorPolicy::Accumulate => self.errors.lock().map_err(Error::from)?.push(err),
+      ErrorPolicy::LogAndContinue => error!(self.backend, "non-fatal backend error: {err}"),
+    }
+
+    Ok(())
+  }
+
+  /// Handle the errors destroying resources when dropping their handles.
+  fn report_drop_errors(&self) -> Result<(), B::Err> {
+    let errors = self.cache()?.take_drop_errors();
+
+    for err in errors {
+      self.handle_error(err)?;
+    }
+
+    Ok(())
+  }
+
+  /// Destroy a dropped resource, or queue it as garbage, depending on the garbage collection mode.
+  fn dispose(&self, garbage: Garbage<B>) -> Result<(), B::Err> {
+    match self.gc_mode {
+      GcMode::Immediate => {
+        if let Err(err) = garbage.destroy() {
+          self.handle_error(err)?;
+        }
+      }
+
+      GcMode::TimeSliced => self.cache()?.push_garbage(garbage, false),
+      GcMode::Deferred => self.cache()?.push_garbage(garbage, true),
+    }
+
+    Ok(())
+  }
+
+  /// Destroy queued garbage, within a budget.
+  ///
+  /// Return the number of resources destroyed. This is typically called once per frame with
+  /// [`GcMode::TimeSliced`], and after finishing command buffers or presenting with [`GcMode::Deferred`]. Resources
+  /// failing to be destroyed are reported according to the [`ErrorPolicy`].
+  pub fn collect_garbage(&self, budget: GcBudget) -> Result<usize, B::Err> {
+    self.report_drop_errors()?;
+
+    let start = Instant::now();
+    let mut count = 0;
+
+    while !budget.is_exhausted(count, start) {
+      // the lock is not held while destroying, as it might take a while
+      let Some(garbage) = self.cache()?.pop_garbage(budget.min_age_frames()) else {
+        break;
+      };
+
+      if let Err(err) = garbage.destroy() {
+        self.handle_error(err)?;
+      }
+
+      count += 1;
+    }
+
+    Ok(count)
+  }
+
+  /// Begin a new frame.
+  ///
+  /// Return the index of the frame, which is greater than the index of all the frames begun before. Frames cannot be
+  /// nested: the previous frame must have been ended with [`Device::end_frame`].
+  pub fn begin_frame(&self) -> Result<FrameIndex, B::Err> {
+    Ok(self.cache()?.begin_frame()?)
+  }
+
+  /// End the current frame.
+  ///
+  /// Return the index of the frame ended.
+  pub fn end_frame(&self) -> Result<FrameIndex, B::Err> {
+    Ok(self.cache()?.end_frame()?)
+  }
+
+  /// Index of the current frame — the last frame begun.
+  pub fn frame(&self) -> Result<FrameIndex, B::Err> {
+    Ok(self.cache()?.frame())
+  }
+
+  /// Number of dropped resources waiting to be destroyed.
+  pub fn garbage_len(&self) -> Result<usize, B::Err> {
+    Ok(self.cache()?.garbage_len())
+  }
+
+  /// Collect the timings of the timed sections executed by the device since the last call, oldest first.
+  ///
+  /// Timings of sections the device has not executed yet are kept for later calls.
+  #[cfg(feature = "ext-profiler")]
+  pub fn timings(&self) -> Result<Vec<Timing>, B::Err>
+  where
+    B: BackendProfiler,
+  {
+    self.cache()?.take_timings()
+  }
+
+  /// Name a resource.
+  ///
+  /// The name is forwarded to the backend, which can use it as a debug label, and is kept until the resource is
+  /// dropped. Naming a resource again replaces its name.
+  pub fn set_name<R>(&self, resource: &R, name: impl Into<String>) -> Result<(), B::Err>
+  where
+    R: Named<B>,
+  {
+    let name = name.into();
+    resource.label(&name)?;
+    self
+      .cache()?
+      .set_name(R::KIND, resource.resource_id(), name);
+    Ok(())
+  }
+
+  /// Name given to a resource with [`Device::set_name`], if any.
+  pub fn resource_name<R>(&self, resource: &R) -> Result<Option<String>, B::Err>
+  where
+    R: Named<B>,
+  {
+    Ok(
+      self
+        .cache()?
+        .name(R::KIND, resource.resource_id())
+        .map(str::to_owned),
+    )
+  }
+
+  pub fn drop_vertex_array(&self, mut vertex_array: VertexArray<B>) -> Result<(), B::Err> {
+    let tag = std::mem::take(&mut vertex_array.tag);
+    let garbage = self.cache()?.drop_vertex_array(&vertex_array.id(), tag);
+    garbage.map_or(Ok(()), |garbage| self.dispose(garbage))
+  }
+
+  pub fn drop_render_targets(&self, mut render_targets: RenderTargets<B>) -> Result<(), B::Err> {
+    let tag = std::mem::take(&mut render_targets.tag);
+    let garbage = self.cache()?.drop_render_targets(&render_targets.id(), tag);
+    garbage.map_or(Ok(()), |garbage| self.dispose(garbage))
+  }
+
+  pub fn drop_shader(&self, mut shader: Shader<B>) -> Result<(), B::Err> {
+    let tag = std::mem::take(&mut shader.tag);
+    let garbage = self.cache()?.drop_shader(&shader.id(), tag);
+    garbage.map_or(Ok(()), |garbage| self.dispose(garbage))
+  }
+
+  pub fn drop_texture(&self, mut texture: Texture<B>) -> Result<(), B::Err> {
+    let tag = std::mem::take(&mut texture.tag);
+    let garbage = self.cache()?.drop_texture(&texture.id(), tag);
+    garbage.map_or(Ok(()), |garbage| self.dispose(garbage))
+  }
+
+  pub fn drop_storage_buffer(&self, mut storage_buffer: StorageBuffer<B>) -> Result<(), B::Err> {
+    let tag = std::mem::take(&mut storage_buffer.tag);
+    let garbage = self.cache()?.drop_storage_buffer(&storage_buffer.id(), tag);
+    garbage.map_or(Ok(()), |garbage| self.dispose(garbage))
+  }
+
+  pub fn drop_cmd_buf(&self, mut cmd_buf: CmdBuf<B>) -> Result<(), B::Err> {
+    let tag = std::mem::take(&mut cmd_buf.tag);
+    let garbage = self.cache()?.drop_cmd_buf(&cmd_buf.id(), tag);
+    garbage.map_or(Ok(()), |garbage| self.dispose(garbage))
+  }
+
+  pub fn drop_swap_chain(&self, mut swap_chain: SwapChain<B>) -> Result<(), B::Err> {
+    let tag = std::mem::take(&mut swap_chain.tag);
+    let garbage = self.cache()?.drop_swap_chain(&swap_chain.id(), tag);
+    garbage.map_or(Ok(()), |garbage| self.dispose(garbage))
+  }
+
+  fn cache(&self) -> Result<MutexGuard<'_, Cache<B>>, B::Err> {
+    Ok(self.cache.lock().map_err(Error::from)?)
   }
 
   pub fn author(&self) -> Result<String, B::Err> {
@@ -54,23 +498,187 @@ where
     self.backend.info()
   }
 
+  /// Make the graphics context of the backend current on the calling thread, until the returned guard is dropped.
+  ///
+  /// Only one thread should hold a guard at a time. Backends without a notion of current context do nothing.
+  pub fn make_current(&self) -> Result<CurrentContext<'_, B>, B::Err> {
+    self.backend.make_current()?;
+    Ok(CurrentContext { device: self })
+  }
+
+  /// Limits of the graphics device, queried once when the device is created.
+  pub fn limits(&self) -> &DeviceLimits {
+    &self.limits
+  }
+
+  /// Optional features of the graphics device, queried once when the device is created.
+  ///
+  /// Check them to select code paths, rather than trying to use a feature and falling back on failure.
+  pub fn features(&self) -> DeviceFeatures {
+    self.features
+  }
+
+  /// Extensions negotiated with the backend by [`DeviceBuilder::build`].
+  ///
+  /// Devices created with [`Device::new`] have no negotiated extensions.
+  pub fn extensions(&self) -> &NegotiatedExtensions {
+    &self.extensions
+  }
+
+  /// Usage statistics of the texture units shared by [`Layers`].
+  pub fn texture_units_telemetry(&self) -> Result<UnitsTelemetry, B::Err> {
+    Ok(self.cache()?.texture_units().telemetry())
+  }
+
+  /// Usage statistics of the uniform buffer units shared by [`Layers`].
+  pub fn uniform_buffer_units_telemetry(&self) -> Result<UnitsTelemetry, B::Err> {
+    Ok(self.cache()?.uniform_buffer_units().telemetry())
+  }
+
+  /// Reset the statistics of the texture and uniform buffer units, e.g. at the beginning of a scene.
+  pub fn reset_units_telemetry(&self) -> Result<(), B::Err> {
+    let mut cache = self.cache()?;
+    cache.texture_units().reset_telemetry();
+    cache.uniform_buffer_units().reset_telemetry();
+    Ok(())
+  }
+
+  /// Set the strategy used when all the texture or uniform buffer units shared by [`Layers`] are in use.
+  ///
+  /// With [`SpillStrategy::Steal`], resources whose unit was stolen are rebound by their layer before the next draw.
+  pub fn set_units_spill_strategy(&self, spill_strategy: SpillStrategy) -> Result<(), B::Err> {
+    let mut cache = self.cache()?;
+    cache.texture_units().set_spill_strategy(spill_strategy);
+    cache
+      .uniform_buffer_units()
+      .set_spill_strategy(spill_strategy);
+    Ok(())
+  }
+
+  /// Describe every resource tracked by the device, and which ones are bound to units.
+  ///
+  /// See [`CacheDump`].
+  pub fn debug_dump(&self) -> Result<CacheDump<B>, B::Err> {
+    Ok(self.cache()?.debug_dump())
+  }
+
+  /// Gather information about the backend and the graphics device, e.g. to attach to bug reports.
+  pub fn diagnostic_report(&self) -> Result<DiagnosticReport, B::Err> {
+    Ok(DiagnosticReport {
+      name: self.backend.name()?,
+      author: self.backend.author()?,
+      version: self.backend.version()?,
+      shading_lang_version: self.backend.shading_lang_version()?,
+      info: self.backend.info()?,
+      limits: self.limits.clone(),
+      features: self.features,
+      extensions: self.extensions.clone(),
+    })
+  }
+
+  /// Create a vertex array with a per-vertex stream and a per-instance stream.
+  ///
+  /// `instances` is ignored if empty. See [`Device::new_vertex_array_with_streams`] for more streams or other step
+  /// rates.
   pub fn new_vertex_array(
     &self,
     vertices: VertexArrayData,
     instances: VertexArrayData,
     indices: impl Into<Vec<u32>>,
   ) -> Result<VertexArray<B>, B::Err> {
+    let mut streams = vec![VertexStream::per_vertex(vertices)];
+
+    if !instances.is_empty() {
+      streams.push(VertexStream::per_instance(instances));
+    }
+
+    self.new_vertex_array_with_streams(streams, indices)
+  }
+
+  /// Create a vertex array from several vertex streams.
+  ///
+  /// All the per-vertex streams must have the same number of vertices, and per-instance streams must have a non-zero
+  /// divisor. The number of instances that can be drawn is limited by the shortest per-instance stream.
+  pub fn new_vertex_array_with_streams(
+    &self,
+    streams: impl Into<Vec<VertexStream>>,
+    indices: impl Into<Vec<u32>>,
+  ) -> Result<VertexArray<B>, B::Err> {
+    let streams = streams.into();
     let indices = indices.into();
+    let mut stream_vertex_count = None;
+    let mut instance_count: Option<usize> = None;
+
+    for (i, stream) in streams.iter().enumerate() {
+      match stream.rate() {
+        StepRate::PerVertex => {
+          let len = stream.data().len();
+
+          match stream_vertex_count {
+            Some(count) if count != len => {
+              return Err(
+                Error::InvalidVertexData {
+                  reason: format!("stream {i} has {len} vertices, expected {count}"),
+                }
+                .into(),
+              );
+            }
+
+            _ => stream_vertex_count = Some(len),
+          }
+        }
+
+        StepRate::PerInstance { divisor: 0 } => {
+          return Err(
+            Error::InvalidVertexData {
+              reason: format!("stream {i} has a zero instance divisor"),
+            }
+            .into(),
+          );
+        }
+
+        StepRate::PerInstance { divisor } => {
+          let count = stream.data().len() * divisor as usize;
+          instance_count = Some(instance_count.map_or(count, |instances| instances.min(count)));
+        }
+      }
+    }
+
     let vertex_count = if indices.is_empty() {
-      vertices.len()
+      stream_vertex_count.unwrap_or(0)
     } else {
       indices.len()
     };
 
-    self
-      .backend
-      .new_vertex_array(&vertices, &instances, &indices)
-      .map(|raw| VertexArray::from_raw(raw, vertex_count))
+    let raw = self.backend.new_vertex_array(&streams, &indices)?;
+    let tag = self
+      .cache()?
+      .track_vertex_array(&raw, (vertex_count, instance_count));
+
+    Ok(VertexArray::from_raw(
+      raw,
+      vertex_count,
+      instance_count,
+      Arc::downgrade(&self.cache),
+      tag,
+    ))
+  }
+
+  /// Create a vertex array without any vertex data, for attribute-less draws.
+  ///
+  /// Drawing it runs the vertex stage `vertex_count` times, which is useful for fullscreen triangles or vertex
+  /// pulling.
+  pub fn new_empty_vertex_array(&self, vertex_count: usize) -> Result<VertexArray<B>, B::Err> {
+    let raw = self.backend.new_empty_vertex_array(vertex_count)?;
+    let tag = self.cache()?.track_vertex_array(&raw, (vertex_count, None));
+
+    Ok(VertexArray::from_raw(
+      raw,
+      vertex_count,
+      None,
+      Arc::downgrade(&self.cache),
+      tag,
+    ))
   }
 
   pub fn new_render_targets(
@@ -79,41 +687,406 @@ where
     depth_stencil_attachment_point: Option<DepthStencilAttachmentPoint>,
     storage: Storage,
   ) -> Result<RenderTargets<B>, B::Err> {
-    self
+    let layers = check_attachment_points(
+      &color_attachment_points,
+      depth_stencil_attachment_point,
+      storage,
+      self.limits.max_color_attachments,
+    )?;
+
+    let points = sorted_color_attachment_points(&color_attachment_points);
+    let raw = self.backend.new_render_targets(
+      color_attachment_points,
+      depth_stencil_attachment_point,
+      storage,
+    )?;
+    let tag = self
+      .cache()?
+      .track_render_targets(&raw, (storage, points.clone(), layers));
+
+    Ok(RenderTargets::from_raw(
+      raw,
+      storage,
+      points,
+      layers,
+      Arc::downgrade(&self.cache),
+      tag,
+    ))
+  }
+
+  /// Create new flat 2D render targets with a single depth attachment, sampled with `comparison` — i.e. a shadow map.
+  ///
+  /// The depth attachment, at index `0`, can be used right away as a texture with
+  /// [`CmdBuf::use_depth_stencil_attachment`](crate::cmd_buf::CmdBuf::use_depth_stencil_attachment). It is sampled with
+  /// linear filtering, so that backends supporting it filter the comparison results, and clamped to its edges.
+  pub fn new_depth_only_render_targets(
+    &self,
+    width: u32,
+    height: u32,
+    depth_bits: ChannelBits,
+    comparison: Comparison,
+  ) -> Result<RenderTargets<B>, B::Err> {
+    let render_targets = self.new_render_targets(
+      HashSet::default(),
+      Some(DepthStencilAttachmentPoint::new(
+        0,
+        "depth",
+        DepthStencilType::Depth { depth_bits },
+      )),
+      Storage::Flat2D { width, height },
+    )?;
+
+    render_targets
+      .depth_stencil_attachment(0)?
+      .set_sampling(Sampling {
+        wrap_r: Wrap::ClampToEdge,
+        wrap_s: Wrap::ClampToEdge,
+        wrap_t: Wrap::ClampToEdge,
+        min_filter: MinFilter::Linear,
+        mag_filter: MagFilter::Linear,
+        depth_comparison: Some(comparison),
+      })?;
+
+    Ok(render_targets)
+  }
+
+  /// Create a new [`RenderTargets`] sharing the depth/stencil attachment of another [`RenderTargets`].
+  ///
+  /// `storage` must be the same as the storage of the render targets the depth/stencil attachment comes from.
+  pub fn new_render_targets_with_depth_stencil_attachment(
+    &self,
+    color_attachment_points: HashSet<ColorAttachmentPoint>,
+    depth_stencil_attachment: &DepthStencilAttachment<B>,
+    storage: Storage,
+  ) -> Result<RenderTargets<B>, B::Err> {
+    if depth_stencil_attachment.storage() != storage {
+      return Err(
+        Error::IncompatibleDepthStencilAttachment {
+          reason: format!(
+            "attachment storage is {:?}, but render targets storage is {:?}",
+            depth_stencil_attachment.storage(),
+            storage
+          ),
+        }
+        .into(),
+      );
+    }
+
+    let layers = if color_attachment_points.is_empty() {
+      depth_stencil_attachment.layers()
+    } else {
+      check_attachment_points(
+        &color_attachment_points,
+        None,
+        storage,
+        self.limits.max_color_attachments,
+      )?
+    };
+
+    if layers != depth_stencil_attachment.layers() {
+      return Err(
+        Error::IncompatibleDepthStencilAttachment {
+          reason: format!(
+            "attachment renders to {:?} layers, but color attachments to {:?} layers",
+            depth_stencil_attachment.layers(),
+            layers
+          ),
+        }
+        .into(),
+      );
+    }
+
+    let points = sorted_color_attachment_points(&color_attachment_points);
+    let raw = self
       .backend
-      .new_render_targets(
+      .new_render_targets_with_depth_stencil_attachment(
         color_attachment_points,
-        depth_stencil_attachment_point,
+        &depth_stencil_attachment.raw,
         storage,
-      )
-      .map(RenderTargets::from_raw)
+      )?;
+    let tag = self
+      .cache()?
+      .track_render_targets(&raw, (storage, points.clone(), layers));
+
+    Ok(RenderTargets::from_raw(
+      raw,
+      storage,
+      points,
+      layers,
+      Arc::downgrade(&self.cache),
+      tag,
+    ))
   }
 
+  /// Create a shader program.
+  ///
+  /// The compute stage, if any, must be the only stage of `sources`. Shaders are cached by variant — the sources of
+  /// their stages and their set of defines: while a shader is alive, asking for the same variant returns a handle
+  /// sharing it instead of compiling a new one. The shader is only destroyed once every handle sharing it is dropped.
   pub fn new_shader(&self, sources: ShaderSources) -> Result<Shader<B>, B::Err> {
-    self.backend.new_shader(sources).map(Shader::from_raw)
+    sources.validate()?;
+
+    let key = ShaderKey::new(&sources);
+    if let Some((raw, binding_points, tag)) = self.cache()?.acquire_shader_variant(&key) {
+      return Ok(Shader::from_raw(
+        raw,
+        binding_points,
+        Arc::downgrade(&self.cache),
+        tag,
+      ));
+    }
+
+    let specialized;
+    let sources = if sources.defines().is_empty() {
+      sources
+    } else {
+      specialized = PreprocessedSources::specialize(&sources);
+      specialized.sources()
+    };
+
+    let raw = self.backend.new_shader(sources)?;
+    let binding_points = Arc::new(ShaderBindingPoints::<B>::resolve(&raw)?);
+    let mut cache = self.cache()?;
+
+    // another thread might have compiled the same variant while the cache was unlocked; share it instead
+    if let Some((shared, shared_binding_points, tag)) = cache.acquire_shader_variant(&key) {
+      drop(cache);
+      self.dispose(Garbage::Shader(raw))?;
+      return Ok(Shader::from_raw(
+        shared,
+        shared_binding_points,
+        Arc::downgrade(&self.cache),
+        tag,
+      ));
+    }
+
+    let tag = cache.track_shader(&raw, binding_points.clone());
+    cache.track_shader_variant(key, raw.scarce_index());
+
+    Ok(Shader::from_raw(
+      raw,
+      binding_points,
+      Arc::downgrade(&self.cache),
+      tag,
+    ))
   }
 
-  pub fn new_texture(&self, storage: Storage, sampling: Sampling) -> Result<Texture<B>, B::Err> {
-    self
-      .backend
-      .new_texture(storage, sampling)
-      .map(Texture::from_raw)
+  /// Create a shader program from canonical GLSL sources, translated to the dialect of the backend.
+  ///
+  /// See [`translate`](crate::translate) for what canonical GLSL is.
+  #[cfg(feature = "glsl-translate")]
+  pub fn new_translated_shader(&self, sources: ShaderSources) -> Result<Shader<B>, B::Err> {
+    sources.validate()?;
+
+    let translated = crate::translate::translate_sources(&sources, self.backend.shader_dialect())?;
+    self.new_shader(translated.sources())
+  }
+
+  /// Create a storage buffer of `size` bytes, initialized to zero.
+  pub fn new_storage_buffer(&self, size: usize) -> Result<StorageBuffer<B>, B::Err> {
+    let raw = self.backend.new_storage_buffer(size)?;
+    let tag = self.cache()?.track_storage_buffer(&raw, size);
+
+    Ok(StorageBuffer::from_raw(
+      raw,
+      size,
+      Arc::downgrade(&self.cache),
+      tag,
+    ))
+  }
+
+  pub fn new_texture(
+    &self,
+    storage: Storage,
+    pixel: Pixel,
+    sampling: Sampling,
+  ) -> Result<Texture<B>, B::Err> {
+    let raw = self.backend.new_texture(storage, pixel, sampling)?;
+    let tag = self.cache()?.track_texture(&raw, (storage, pixel));
+
+    Ok(Texture::from_raw(
+      raw,
+      storage,
+      pixel,
+      self.coordinate_convention,
+      Arc::downgrade(&self.cache),
+      tag,
+    ))
+  }
+
+  /// Warm up every combination of `shaders`, `vertex_layouts` and `render_targets_formats`, so that drawing with them
+  /// for the first time doesn’t cause hitches.
+  ///
+  /// This is typically called at load time. Use an empty vertex layout for attributeless draws. Backends without
+  /// lazily compiled pipelines do nothing.
+  pub fn warm_up(
+    &self,
+    shaders: &[&Shader<B>],
+    vertex_layouts: &[&[VertexAttr]],
+    render_targets_formats: &[RenderTargetsFormat],
+  ) -> Result<(), B::Err> {
+    for shader in shaders {
+      for vertex_attrs in vertex_layouts {
+        for format in render_targets_formats {
+          self.backend.warm_up(&shader.raw, vertex_attrs, format)?;
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Create a new [`Texture`] sampled with the default sampling of the device.
+  ///
+  /// See [`Device::set_defaults`].
+  pub fn new_texture_with_defaults(
+    &self,
+    storage: Storage,
+    pixel: Pixel,
+  ) -> Result<Texture<B>, B::Err> {
+    let sampling = self.defaults()?.sampling;
+    self.new_texture(storage, pixel, sampling)
+  }
+
+  #[cfg(feature = "ext-sparse-texture")]
+  pub fn sparse_page_size(&self, storage: Storage) -> Result<Option<Size>, B::Err>
+  where
+    B: BackendSparseTexture,
+  {
+    self.backend.sparse_page_size(storage)
+  }
+
+  #[cfg(feature = "ext-sparse-texture")]
+  pub fn new_sparse_texture(
+    &self,
+    storage: Storage,
+    pixel: Pixel,
+    sampling: Sampling,
+  ) -> Result<Texture<B>, B::Err>
+  where
+    B: BackendSparseTexture,
+  {
+    let raw = self.backend.new_sparse_texture(storage, pixel, sampling)?;
+    let tag = self.cache()?.track_texture(&raw, (storage, pixel));
+
+    Ok(Texture::from_raw(
+      raw,
+      storage,
+      pixel,
+      self.coordinate_convention,
+      Arc::downgrade(&self.cache),
+      tag,
+    ))
   }
 
   pub fn new_cmd_buf(&self) -> Result<CmdBuf<B>, B::Err> {
-    self.backend.new_cmd_buf().map(CmdBuf::from_raw)
+    let raw = self.backend.new_cmd_buf()?;
+    let (tag, defaults) = {
+      let mut cache = self.cache()?;
+      let tag = cache.track_cmd_buf(&raw);
+      (tag, cache.defaults().map(|defaults| defaults.state.clone()))
+    };
+
+    let cmd_buf = CmdBuf::from_raw(
+      raw,
+      Arc::downgrade(&self.backend),
+      Arc::downgrade(&self.cache),
+      self.coordinate_convention,
+      self.recording_budget,
+      defaults.clone().unwrap_or_default(),
+      tag,
+    );
+
+    // seed the command buffer with the defaults of the device
+    if defaults.is_some() {
+      cmd_buf.clear_state()?;
+    }
+
+    Ok(cmd_buf)
+  }
+
+  /// Create a new [`CmdBuf`] recorded with [`Layers`], managing texture and uniform buffer units automatically.
+  ///
+  /// Units are taken from pools shared by every layer of the device, sized by [`DeviceLimits::max_texture_units`] and
+  /// [`DeviceLimits::max_uniform_buffer_units`]. Texture units used by a stage are also limited by
+  /// [`DeviceLimits::max_stage_texture_units`].
+  pub fn new_layers(&self) -> Result<Layers<B>, B::Err> {
+    Ok(Layers::new(
+      self.new_cmd_buf()?,
+      self.backend.clone(),
+      self.cache.clone(),
+    ))
+  }
+
+  /// Create a context recording deferred [`CmdBuf`]s, from any thread.
+  ///
+  /// See [`DeferredContext`] for further details.
+  pub fn create_deferred_context(&self) -> DeferredContext<B> {
+    DeferredContext::new(
+      self.backend.clone(),
+      Arc::downgrade(&self.cache),
+      self.coordinate_convention,
+      self.recording_budget,
+    )
   }
 
+  /// Create a new [`CmdBuf`] starting with a reset of the pipeline state.
+  ///
+  /// See [`CmdBuf::clear_state`] for further details.
+  pub fn new_cmd_buf_with_clear_state(&self) -> Result<CmdBuf<B>, B::Err> {
+    let cmd_buf = self.new_cmd_buf()?;
+    cmd_buf.clear_state()?;
+    Ok(cmd_buf)
+  }
+
+  /// Parameters swap chains can be created with — e.g. the formats allowing HDR output, if any.
+  pub fn swap_chain_capabilities(&self) -> Result<SwapChainCapabilities, B::Err> {
+    self.backend.swap_chain_capabilities()
+  }
+
+  /// Create a new swap chain of `image_count` images in `format`.
+  ///
+  /// `image_count` is the maximum number of frames in flight: more images let the CPU record frames further ahead of
+  /// the display, which improves throughput at the cost of latency. Use `2` for double buffering and `3` for triple
+  /// buffering.
+  ///
+  /// Parameters are checked against [`Device::swap_chain_capabilities`] before creating the swap chain.
   pub fn new_swap_chain(
     &self,
     width: u32,
     height: u32,
     mode: SwapChainMode,
+    image_count: usize,
+    format: SwapChainFormat,
   ) -> Result<SwapChain<B>, B::Err> {
-    self
+    check_swap_chain_parameters(
+      &self.swap_chain_capabilities()?,
+      (width, height),
+      mode,
+      image_count,
+      format,
+    )?;
+
+    let raw = self
       .backend
-      .new_swap_chain(width, height, mode)
-      .map(SwapChain::from_raw)
+      .new_swap_chain(width, height, mode, image_count, format)?;
+    let info = SwapChainInfo {
+      storage: Storage::Flat2D { width, height },
+      image_count,
+      format,
+      generation: 0,
+      acquired: false,
+      presented: false,
+      lost: false,
+    };
+    let tag = self.cache()?.track_swap_chain(&raw, info);
+
+    Ok(SwapChain::from_raw(
+      raw,
+      info,
+      Arc::downgrade(&self.cache),
+      tag,
+    ))
   }
 
   pub fn get_texture_binding_point(&self, index: usize) -> Result<TextureBindingPoint<B>, B::Err> {
@@ -132,4 +1105,355 @@ where
       .get_uniform_buffer_binding_point(index)
       .map(UniformBufferBindingPoint::from_raw)
   }
+
+  pub fn get_storage_buffer_binding_point(
+    &self,
+    index: usize,
+  ) -> Result<StorageBufferBindingPoint<B>, B::Err> {
+    self
+      .backend
+      .get_storage_buffer_binding_point(index)
+      .map(StorageBufferBindingPoint::from_raw)
+  }
+
+  pub fn vertex_array_by_id(&self, id: &ResourceId<B>) -> Result<Option<VertexArray<B>>, B::Err> {
+    Ok(
+      self
+        .cache()?
+        .share_vertex_array(id)
+        .map(|(raw, (vertex_count, instance_count), tag)| {
+          VertexArray::from_raw(
+            raw,
+            vertex_count,
+            instance_count,
+            Arc::downgrade(&self.cache),
+            tag,
+          )
+        }),
+    )
+  }
+
+  pub fn render_targets_by_id(
+    &self,
+    id: &ResourceId<B>,
+  ) -> Result<Option<RenderTargets<B>>, B::Err> {
+    Ok(
+      self
+        .cache()?
+        .share_render_targets(id)
+        .map(|(raw, (storage, points, layers), tag)| {
+          RenderTargets::from_raw(
+            raw,
+            storage,
+            points,
+            layers,
+            Arc::downgrade(&self.cache),
+            tag,
+          )
+        }),
+    )
+  }
+
+  pub fn shader_by_id(&self, id: &ResourceId<B>) -> Result<Option<Shader<B>>, B::Err> {
+    Ok(
+      self
+        .cache()?
+        .share_shader(id)
+        .map(|(raw, binding_points, tag)| {
+          Shader::from_raw(raw, binding_points, Arc::downgrade(&self.cache), tag)
+        }),
+    )
+  }
+
+  pub fn texture_by_id(&self, id: &ResourceId<B>) -> Result<Option<Texture<B>>, B::Err> {
+    Ok(
+      self
+        .cache()?
+        .share_texture(id)
+        .map(|(raw, (storage, pixel), tag)| {
+          Texture::from_raw(
+            raw,
+            storage,
+            pixel,
+            self.coordinate_convention,
+            Arc::downgrade(&self.cache),
+            tag,
+          )
+        }),
+    )
+  }
+
+  pub fn cmd_buf_by_id(&self, id: &ResourceId<B>) -> Result<Option<CmdBuf<B>>, B::Err> {
+    let mut cache = self.cache()?;
+    let default_state = cache
+      .defaults()
+      .map(|defaults| defaults.state.clone())
+      .unwrap_or_default();
+
+    Ok(cache.share_cmd_buf(id).map(|(raw, tag)| {
+      CmdBuf::from_raw(
+        raw,
+        Arc::downgrade(&self.backend),
+        Arc::downgrade(&self.cache),
+        self.coordinate_convention,
+        self.recording_budget,
+        default_state,
+        tag,
+      )
+    }))
+  }
+
+  pub fn storage_buffer_by_id(
+    &self,
+    id: &ResourceId<B>,
+  ) -> Result<Option<StorageBuffer<B>>, B::Err> {
+    Ok(
+      self
+        .cache()?
+        .share_storage_buffer(id)
+        .map(|(raw, size, tag)| {
+          StorageBuffer::from_raw(raw, size, Arc::downgrade(&self.cache), tag)
+        }),
+    )
+  }
+
+  pub fn swap_chain_by_id(&self, id: &ResourceId<B>) -> Result<Option<SwapChain<B>>, B::Err> {
+    Ok(
+      self
+        .cache()?
+        .share_swap_chain(id)
+        .map(|(raw, info, tag)| SwapChain::from_raw(raw, info, Arc::downgrade(&self.cache), tag)),
+    )
+  }
+}
+
+/// Check swap chain creation parameters against the `capabilities` of the surface.
+fn check_swap_chain_parameters(
+  capabilities: &SwapChainCapabilities,
+  (width, height): (u32, u32),
+  mode: SwapChainMode,
+  image_count: usize,
+  format: SwapChainFormat,
+) -> Result<(), Error> {
+  let invalid = |reason| Err(Error::InvalidSwapChain { reason });
+  let (min_width, min_height) = capabilities.min_extent;
+  let (max_width, max_height) = capabilities.max_extent;
+
+  if image_count == 0 {
+    return invalid("a swap chain needs at least one image".to_owned());
+  }
+
+  if !(min_width..=max_width).contains(&width) || !(min_height..=max_height).contains(&height) {
+    return invalid(format!(
+      "{width}×{height} is out of the supported extents, from {min_width}×{min_height} to {max_width}×{max_height}"
+    ));
+  }
+
+  if !(capabilities.min_image_count..=capabilities.max_image_count).contains(&image_count) {
+    return invalid(format!(
+      "{image_count} images is out of the supported image counts, from {} to {}",
+      capabilities.min_image_count, capabilities.max_image_count
+    ));
+  }
+
+  if !capabilities.modes.contains(&mode) {
+    return invalid(format!("{mode:?} is not supported"));
+  }
+
+  if !capabilities.formats.contains(&format) {
+    return invalid(format!("{format:?} is not supported"));
+  }
+
+  Ok(())
+}
+
+/// Garbage not collected yet is destroyed with the device, whatever the [`GcMode`], and then every resource still
+/// alive.
+impl<B> Drop for Device<B>
+where
+  B: Backend,
+{
+  fn drop(&mut self) {
+    let (drop_errors, garbage, tracked) = match self.cache.lock() {
+      Ok(mut cache) => (
+        cache.take_drop_errors(),
+        cache.drain_garbage(),
+        cache.drain_tracked(),
+      ),
+      Err(_) => return,
+    };
+
+    for err in drop_errors {
+      error!(self.backend, "cannot destroy dropped resource: {err}");
+    }
+
+    for garbage in garbage {
+      if let Err(err) = garbage.destroy() {
+        error!(self.backend, "cannot destroy garbage: {err}");
+      }
+    }
+
+    for resource in tracked {
+      if let Err(err) = resource.destroy() {
+        error!(self.backend, "cannot destroy resource: {err}");
+      }
+    }
+  }
+}
+
+/// Guard keeping the graphics context of a [`Device`] current on a thread.
+///
+/// The context is released when the guard is dropped.
+pub struct CurrentContext<'a, B>
+where
+  B: Backend,
+{
+  device: &'a Device<B>,
+}
+
+impl<'a, B> Drop for CurrentContext<'a, B>
+where
+  B: Backend,
+{
+  fn drop(&mut self) {
+    let _ = self.device.backend.release_current();
+  }
+}
+
+/// Check that there are at most `max_color_attachments` color attachment points, with distinct indices, and that the
+/// mipmap level and layer selected by every attachment point exist in `storage`.
+///
+/// Attachments must either all select a layer, or all be layered; return the number of layers in the latter case.
+fn check_attachment_points(
+  color_attachment_points: &HashSet<ColorAttachmentPoint>,
+  depth_stencil_attachment_point: Option<DepthStencilAttachmentPoint>,
+  storage: Storage,
+  max_color_attachments: usize,
+) -> Result<Option<usize>, Error> {
+  if color_attachment_points.len() > max_color_attachments {
+    return Err(Error::IncompleteRenderTargets(
+      RenderTargetsError::TooManyAttachments {
+        count: color_attachment_points.len(),
+        max: max_color_attachments,
+      },
+    ));
+  }
+
+  let sorted = sorted_color_attachment_points(color_attachment_points);
+  if let Some([a, b]) = sorted
+    .windows(2)
+    .find(|points| points[0].index() == points[1].index())
+    .map(|points| [points[0], points[1]])
+  {
+    let mut attachments = [a.name(), b.name()];
+    attachments.sort_unstable();
+
+    return Err(Error::IncompleteRenderTargets(
+      RenderTargetsError::DuplicateIndex {
+        index: a.index(),
+        attachments,
+      },
+    ));
+  }
+
+  if let Some(point) = color_attachment_points
+    .iter()
+    .find(|point| point.srgb_encoding() && !point.ty().is_srgb())
+  {
+    return Err(Error::InvalidAttachmentPoint {
+      reason: format!(
+        "{:?} encodes to sRGB, but {:?} is not sRGB",
+        point.name(),
+        point.ty()
+      ),
+    });
+  }
+
+  let mut srgb = sorted.iter().filter(|point| point.ty().is_srgb());
+  if let Some(first) = srgb.next() {
+    if let Some(point) = srgb.find(|point| point.srgb_encoding() != first.srgb_encoding()) {
+      let mut attachments = [first.name(), point.name()];
+      attachments.sort_unstable();
+
+      return Err(Error::InvalidAttachmentPoint {
+        reason: format!("sRGB attachments {attachments:?} must all encode to sRGB, or none"),
+      });
+    }
+  }
+
+  let points = color_attachment_points
+    .iter()
+    .map(|point| (point.name(), point.level(), point.layer(), point.usage()))
+    .chain(
+      depth_stencil_attachment_point
+        .map(|point| (point.name(), point.level(), point.layer(), point.usage())),
+    );
+
+  for (name, level, layer, usage) in points.clone() {
+    if usage == AttachmentUsage::RenderOnly {
+      if level > 0 {
+        return Err(Error::InvalidAttachmentPoint {
+          reason: format!("{name:?} is render-only, but renders to level {level}"),
+        });
+      }
+
+      if storage.layers().is_some() {
+        return Err(Error::InvalidAttachmentPoint {
+          reason: format!("{name:?} is render-only, but {storage:?} has layers"),
+        });
+      }
+    }
+
+    if level >= storage.levels() {
+      return Err(Error::InvalidAttachmentPoint {
+        reason: format!(
+          "{name:?} renders to level {level}, but {storage:?} has {} levels",
+          storage.levels()
+        ),
+      });
+    }
+
+    match (layer, storage.layers()) {
+      (Some(layer), None) => {
+        return Err(Error::InvalidAttachmentPoint {
+          reason: format!("{name:?} renders to layer {layer}, but {storage:?} has no layers"),
+        });
+      }
+
+      (Some(layer), Some(layers)) if layer >= layers => {
+        return Err(Error::InvalidAttachmentPoint {
+          reason: format!("{name:?} renders to layer {layer}, but {storage:?} has {layers} layers"),
+        });
+      }
+
+      _ => (),
+    }
+  }
+
+  let Some(layers) = storage.layers() else {
+    return Ok(None);
+  };
+
+  let selected = points
+    .clone()
+    .filter(|(_, _, layer, _)| layer.is_some())
+    .count();
+  if selected == 0 {
+    Ok(Some(layers))
+  } else if selected == points.count() {
+    Ok(None)
+  } else {
+    Err(Error::InvalidAttachmentPoint {
+      reason: "attachments must either all select a layer, or all be layered".to_owned(),
+    })
+  }
+}
+
+/// Color attachment points, sorted by index.
+fn sorted_color_attachment_points(
+  points: &HashSet<ColorAttachmentPoint>,
+) -> Arc<[ColorAttachmentPoint]> {
+  let mut points = points.iter().copied().collect::<Vec<_>>();
+  points.sort_by_key(ColorAttachmentPoint::index);
+  points.into()
 }
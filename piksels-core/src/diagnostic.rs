@@ -0,0 +1,22 @@
+//! Diagnostic reports.
+//!
+//! A [`DiagnosticReport`] gathers everything known about the backend and the graphics device it runs on, so that it
+//! can be attached to bug reports. With the `serde` feature, reports can be serialized, e.g. to JSON.
+//!
+//! Use [`Device::diagnostic_report`](crate::device::Device::diagnostic_report) to get a report.
+
+use piksels_backend::{extension::NegotiatedExtensions, BackendInfo, DeviceFeatures, DeviceLimits};
+
+/// Report about a backend and the graphics device it runs on.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DiagnosticReport {
+  pub name: String,
+  pub author: String,
+  pub version: String,
+  pub shading_lang_version: String,
+  pub info: BackendInfo,
+  pub limits: DeviceLimits,
+  pub features: DeviceFeatures,
+  pub extensions: NegotiatedExtensions,
+}
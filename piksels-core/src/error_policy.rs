@@ -0,0 +1,20 @@
+//! Handling of non-fatal backend errors.
+//!
+//! Some backend errors happen when there is nobody to report them to, or when reporting them would be of no use to
+//! the caller — e.g. when a resource fails to be destroyed while collecting garbage. The [`ErrorPolicy`] of a
+//! [`Device`](crate::device::Device) decides what happens to them. With [`ErrorPolicy::Accumulate`], they are kept
+//! until drained with [`Device::drain_errors`](crate::device::Device::drain_errors), typically once per frame.
+
+/// What to do with non-fatal backend errors.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum ErrorPolicy {
+  /// Panic on the first error.
+  #[default]
+  Panic,
+
+  /// Keep errors around, so that they can be drained later.
+  Accumulate,
+
+  /// Log errors through the backend logger, and carry on.
+  LogAndContinue,
+}
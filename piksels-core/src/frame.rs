@@ -0,0 +1,37 @@
+//! Frame counter.
+//!
+//! A [`Device`](crate::device::Device) counts frames with
+//! [`Device::begin_frame`](crate::device::Device::begin_frame) and
+//! [`Device::end_frame`](crate::device::Device::end_frame). The resulting [`FrameIndex`] is what per-frame
+//! subsystems key off — e.g. deferred destruction only destroys resources once the frames that might still use them
+//! are old enough (see [`GcBudget::min_age`](crate::gc::GcBudget::min_age)).
+
+use std::fmt::{self, Display};
+
+/// Index of a frame.
+///
+/// Frame indices increase monotonically. Frame `0` is the time before the first frame begins, so the first frame is
+/// frame `1`.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct FrameIndex(u64);
+
+impl FrameIndex {
+  pub fn get(self) -> u64 {
+    self.0
+  }
+
+  pub(crate) fn next(self) -> Self {
+    Self(self.0 + 1)
+  }
+
+  /// Number of frames between an earlier frame and this one.
+  pub fn frames_since(self, earlier: Self) -> u64 {
+    self.0.saturating_sub(earlier.0)
+  }
+}
+
+impl Display for FrameIndex {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "frame {}", self.0)
+  }
+}
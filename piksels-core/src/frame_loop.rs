@@ -0,0 +1,168 @@
+//! Frame lifecycle.
+//!
+//! A frame goes through the same steps whether it ends up on a window or not: render targets are acquired, commands
+//! are recorded and submitted, and the frame is presented. [`FrameLoop`] captures this lifecycle, so that code
+//! rendering frames can be written once and driven either by a [`SwapChain`] or, without any window, by a
+//! [`HeadlessLoop`] — e.g. in automated tests or server-side renderers.
+
+#[cfg(feature = "ext-readback")]
+use std::collections::VecDeque;
+
+use piksels_backend::Backend;
+#[cfg(feature = "ext-readback")]
+use piksels_backend::{error::Error, extension::readback::BackendReadback};
+
+#[cfg(feature = "ext-readback")]
+use crate::readback::Readback;
+use crate::{cmd_buf::CmdBuf, render_targets::RenderTargets, swap_chain::SwapChain};
+
+/// Frame-structured rendering.
+pub trait FrameLoop<B>
+where
+  B: Backend,
+{
+  /// Render a frame.
+  ///
+  /// The render targets of the frame are acquired and bound to `cmd_buf`, then passed to `record`, which records the
+  /// frame. `cmd_buf` is then finished and the frame is presented.
+  fn frame(
+    &mut self,
+    cmd_buf: &CmdBuf<B>,
+    record: impl FnOnce(&CmdBuf<B>, &RenderTargets<B>) -> Result<(), B::Err>,
+  ) -> Result<(), B::Err>;
+}
+
+impl<B> FrameLoop<B> for SwapChain<B>
+where
+  B: Backend,
+{
+  fn frame(
+    &mut self,
+    cmd_buf: &CmdBuf<B>,
+    record: impl FnOnce(&CmdBuf<B>, &RenderTargets<B>) -> Result<(), B::Err>,
+  ) -> Result<(), B::Err> {
+    let render_targets = self.acquire()?;
+
+    cmd_buf.render_targets(&render_targets)?;
+    record(cmd_buf, &render_targets)?;
+    cmd_buf.finish()?;
+
+    self.present(&render_targets)
+  }
+}
+
+/// A frame loop without swap chain.
+///
+/// Frames are rendered into a pool of render targets, used in turn. Presenting a frame records a readback of one of
+/// its color attachments, which plays the role of the fence marking the end of the frame; presented frames are then
+/// taken with [`HeadlessLoop::take_frame`]. The size of the pool is the maximum number of frames in flight: if all
+/// the render targets are in flight when a new frame starts, the oldest presented frame is discarded, the same way a
+/// window doesn’t keep the frames nobody looked at.
+#[cfg(feature = "ext-readback")]
+#[derive(Debug)]
+pub struct HeadlessLoop<B>
+where
+  B: BackendReadback,
+{
+  pool: Vec<RenderTargets<B>>,
+  color_attachment: usize,
+  next_frame: u64,
+  presented: VecDeque<(u64, Readback<B>)>,
+  discarded_frames: u64,
+}
+
+#[cfg(feature = "ext-readback")]
+impl<B> HeadlessLoop<B>
+where
+  B: BackendReadback,
+{
+  /// Create a headless loop rendering into `pool`.
+  ///
+  /// Frames are read back from the color attachment at index `color_attachment` of the render targets.
+  pub fn new(pool: Vec<RenderTargets<B>>, color_attachment: usize) -> Result<Self, B::Err> {
+    if pool.is_empty() {
+      return Err(
+        Error::HeadlessLoop {
+          reason: "empty render targets pool".to_owned(),
+        }
+        .into(),
+      );
+    }
+
+    Ok(Self {
+      pool,
+      color_attachment,
+      next_frame: 0,
+      presented: VecDeque::new(),
+      discarded_frames: 0,
+    })
+  }
+
+  /// Index of the next frame to render, starting at `0`.
+  pub fn next_frame(&self) -> u64 {
+    self.next_frame
+  }
+
+  /// Number of frames presented but not taken yet.
+  pub fn frames_in_flight(&self) -> usize {
+    self.presented.len()
+  }
+
+  /// Number of presented frames discarded because they were not taken in time.
+  pub fn discarded_frames(&self) -> u64 {
+    self.discarded_frames
+  }
+
+  /// Wait for the oldest presented frame and copy its texels into `texels`.
+  ///
+  /// Return the index of the frame, or [`None`] if no frame is in flight. Rows are in the backend order (bottom row
+  /// first).
+  pub fn take_frame(&mut self, texels: &mut [u8]) -> Result<Option<u64>, B::Err> {
+    let Some((index, readback)) = self.presented.pop_front() else {
+      return Ok(None);
+    };
+
+    readback.read(texels)?;
+    Ok(Some(index))
+  }
+
+  /// Same as [`HeadlessLoop::take_frame`], without blocking.
+  ///
+  /// Return [`None`] if no frame is in flight or if the oldest one is not complete yet.
+  pub fn try_take_frame(&mut self, texels: &mut [u8]) -> Result<Option<u64>, B::Err> {
+    match self.presented.front() {
+      Some((_, readback)) if readback.is_ready()? => self.take_frame(texels),
+      _ => Ok(None),
+    }
+  }
+}
+
+#[cfg(feature = "ext-readback")]
+impl<B> FrameLoop<B> for HeadlessLoop<B>
+where
+  B: BackendReadback,
+{
+  fn frame(
+    &mut self,
+    cmd_buf: &CmdBuf<B>,
+    record: impl FnOnce(&CmdBuf<B>, &RenderTargets<B>) -> Result<(), B::Err>,
+  ) -> Result<(), B::Err> {
+    if self.presented.len() == self.pool.len() {
+      self.presented.pop_front();
+      self.discarded_frames += 1;
+    }
+
+    let index = self.next_frame;
+    let render_targets = &self.pool[(index % self.pool.len() as u64) as usize];
+
+    cmd_buf.render_targets(render_targets)?;
+    record(cmd_buf, render_targets)?;
+    let readback = cmd_buf.read_color_attachment(render_targets, self.color_attachment)?;
+    cmd_buf.finish()?;
+
+    self.presented.push_back((index, readback));
+    self.next_frame += 1;
+
+    Ok(())
+  }
+}
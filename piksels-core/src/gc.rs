@@ -0,0 +1,187 @@
+//! Resource garbage collection.
+//!
+//! Dropping a resource through a [`Device`](crate::device::Device) untracks it from the cache. With
+//! [`GcMode::Immediate`], the resource is destroyed right away. With [`GcMode::TimeSliced`], it is queued as garbage
+//! instead, and destroyed later by [`Device::collect_garbage`](crate::device::Device::collect_garbage) with a
+//! per-call [`GcBudget`]. This avoids frame hitches when many resources — e.g. all the textures of a level — are
+//! dropped at once.
+//!
+//! With [`GcMode::Deferred`], dropped resources are queued as well, but are only destroyed once a command buffer was
+//! finished or an image presented after they were dropped. Resources can then be dropped while command buffers using
+//! them are still recording, regardless of whether these were submitted yet.
+//!
+//! Resource handles dropped without going through the device are garbage collected the same way once the last handle
+//! on their resource is dropped. Since a handle cannot report errors when dropped, errors destroying its resource right
+//! away are reported by the device later, on [`Device::collect_garbage`](crate::device::Device::collect_garbage) or
+//! [`Device::drain_errors`](crate::device::Device::drain_errors).
+
+use std::{
+  fmt::{self, Debug},
+  sync::{Mutex, Weak},
+  time::{Duration, Instant},
+};
+
+use piksels_backend::Backend;
+
+use crate::cache::{Cache, TrackingTag};
+
+/// How resources are destroyed once dropped.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum GcMode {
+  /// Destroy resources as soon as they are dropped.
+  #[default]
+  Immediate,
+
+  /// Queue dropped resources and destroy them when collecting garbage.
+  TimeSliced,
+
+  /// Queue dropped resources, and destroy them when collecting garbage once a command buffer was finished or an image
+  /// presented.
+  ///
+  /// When recording several command buffers at once, collect garbage after the last one is finished.
+  Deferred,
+}
+
+/// Budget of a garbage collection.
+///
+/// Collection stops as soon as one of the limits is reached.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct GcBudget {
+  max_count: Option<usize>,
+  max_time: Option<Duration>,
+  min_age: u64,
+}
+
+impl GcBudget {
+  /// Budget without limits; all the garbage is collected.
+  pub fn unlimited() -> Self {
+    Self::default()
+  }
+
+  /// Destroy at most `max_count` resources.
+  pub fn count(mut self, max_count: usize) -> Self {
+    self.max_count = Some(max_count);
+    self
+  }
+
+  /// Stop destroying resources once `max_time` has elapsed.
+  pub fn time(mut self, max_time: Duration) -> Self {
+    self.max_time = Some(max_time);
+    self
+  }
+
+  /// Only destroy resources dropped at least `frames` frames ago.
+  ///
+  /// Frames are counted with [`Device::begin_frame`](crate::device::Device::begin_frame). This leaves time for the
+  /// frames in flight, which might still use the resources, to complete.
+  pub fn min_age(mut self, frames: u64) -> Self {
+    self.min_age = frames;
+    self
+  }
+
+  pub(crate) fn min_age_frames(&self) -> u64 {
+    self.min_age
+  }
+
+  /// Check whether the budget is exhausted after having destroyed `count` resources since `start`.
+  pub(crate) fn is_exhausted(&self, count: usize, start: Instant) -> bool {
+    self.max_count.is_some_and(|max_count| count >= max_count)
+      || self
+        .max_time
+        .is_some_and(|max_time| start.elapsed() >= max_time)
+  }
+}
+
+/// A dropped resource waiting to be destroyed.
+#[derive(Debug)]
+pub(crate) enum Garbage<B>
+where
+  B: Backend,
+{
+  CmdBuf(B::CmdBuf),
+  RenderTargets(B::RenderTargets),
+  Shader(B::Shader),
+  StorageBuffer(B::StorageBuffer),
+  SwapChain(B::SwapChain),
+  Texture(B::Texture),
+  VertexArray(B::VertexArray),
+}
+
+impl<B> Garbage<B>
+where
+  B: Backend,
+{
+  /// Destroy the resource.
+  pub(crate) fn destroy(self) -> Result<(), B::Err> {
+    match self {
+      Garbage::CmdBuf(raw) => B::drop_cmd_buf(&raw),
+      Garbage::RenderTargets(raw) => B::drop_render_targets(&raw),
+      Garbage::Shader(raw) => B::drop_shader(&raw),
+      Garbage::StorageBuffer(raw) => B::drop_storage_buffer(&raw),
+      Garbage::SwapChain(raw) => B::drop_swap_chain(&raw),
+      Garbage::Texture(raw) => B::drop_texture(&raw),
+      Garbage::VertexArray(raw) => B::drop_vertex_array(&raw),
+    }
+  }
+}
+
+/// Errors destroying resources whose last handle was dropped, not reported by the device yet.
+pub(crate) struct DropErrors<B>(pub(crate) Vec<B::Err>)
+where
+  B: Backend;
+
+impl<B> Default for DropErrors<B>
+where
+  B: Backend,
+{
+  fn default() -> Self {
+    Self(Vec::new())
+  }
+}
+
+impl<B> Debug for DropErrors<B>
+where
+  B: Backend,
+{
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_list()
+      .entries(self.0.iter().map(ToString::to_string))
+      .finish()
+  }
+}
+
+/// Release a handle being dropped, with `release`, and dispose of its resource if it was the last handle.
+///
+/// Handles with the default tag don’t count as handles on their resource, and release nothing.
+pub(crate) fn drop_handle<B>(
+  cache: &Weak<Mutex<Cache<B>>>,
+  tag: TrackingTag,
+  release: impl FnOnce(&mut Cache<B>, TrackingTag) -> Option<Garbage<B>>,
+) where
+  B: Backend,
+{
+  if tag == TrackingTag::default() {
+    return;
+  }
+
+  let Some(cache) = cache.upgrade() else {
+    return;
+  };
+  let Ok(mut cache) = cache.lock() else {
+    return;
+  };
+  let Some(garbage) = release(&mut cache, tag) else {
+    return;
+  };
+
+  match cache.gc_mode() {
+    GcMode::Immediate => {
+      if let Err(err) = garbage.destroy() {
+        cache.push_drop_error(err);
+      }
+    }
+
+    GcMode::TimeSliced => cache.push_garbage(garbage, false),
+    GcMode::Deferred => cache.push_garbage(garbage, true),
+  }
+}
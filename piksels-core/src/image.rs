@@ -0,0 +1,81 @@
+//! [image](https://crates.io/crates/image) integration.
+//!
+//! This module allows to create textures directly from images, picking the right storage and pixel format for them.
+
+use ::image::DynamicImage;
+use piksels_backend::{
+  pixel::{ChannelBits, Format, Pixel, Type},
+  texture::{MinFilter, Offset, Rect, Sampling, Size, Storage},
+  Backend,
+};
+
+use crate::{device::Device, texture::Texture};
+
+/// Pixel format to use to represent an image.
+///
+/// 8-bit color images are considered to be encoded in sRGB; all the other images are considered linear.
+pub fn image_pixel(image: &DynamicImage) -> Option<Pixel> {
+  use ChannelBits::{Eight, Sixteen, ThirtyTwo};
+
+  let (encoding, format) = match image {
+    DynamicImage::ImageLuma8(_) => (Type::NormUnsigned, Format::R(Eight)),
+    DynamicImage::ImageLumaA8(_) => (Type::NormUnsigned, Format::RG(Eight, Eight)),
+    DynamicImage::ImageRgb8(_) => (Type::NormUnsigned, Format::SRGB(Eight, Eight, Eight)),
+    DynamicImage::ImageRgba8(_) => (
+      Type::NormUnsigned,
+      Format::SRGBA(Eight, Eight, Eight, Eight),
+    ),
+    DynamicImage::ImageLuma16(_) => (Type::NormUnsigned, Format::R(Sixteen)),
+    DynamicImage::ImageLumaA16(_) => (Type::NormUnsigned, Format::RG(Sixteen, Sixteen)),
+    DynamicImage::ImageRgb16(_) => (Type::NormUnsigned, Format::RGB(Sixteen, Sixteen, Sixteen)),
+    DynamicImage::ImageRgba16(_) => (
+      Type::NormUnsigned,
+      Format::RGBA(Sixteen, Sixteen, Sixteen, Sixteen),
+    ),
+    DynamicImage::ImageRgb32F(_) => (Type::Floating, Format::RGB(ThirtyTwo, ThirtyTwo, ThirtyTwo)),
+    DynamicImage::ImageRgba32F(_) => (
+      Type::Floating,
+      Format::RGBA(ThirtyTwo, ThirtyTwo, ThirtyTwo, ThirtyTwo),
+    ),
+    _ => return None,
+  };
+
+  Some(Pixel { encoding, format })
+}
+
+impl<B> Device<B>
+where
+  B: Backend,
+{
+  /// Create a 2D texture from an image and upload its texels.
+  ///
+  /// The pixel format is chosen with [`image_pixel`]. Images using a pixel format not supported by piksels are
+  /// converted to 8-bit sRGBA first.
+  ///
+  /// Mipmaps are generated if the minification filter of `sampling` uses them.
+  pub fn new_texture_from_image(
+    &self,
+    image: &DynamicImage,
+    sampling: Sampling,
+  ) -> Result<Texture<B>, B::Err> {
+    let converted;
+    let (image, pixel) = match image_pixel(image) {
+      Some(pixel) => (image, pixel),
+      None => {
+        converted = DynamicImage::ImageRgba8(image.to_rgba8());
+        let pixel = image_pixel(&converted).expect("8-bit RGBA pixel format");
+        (&converted, pixel)
+      }
+    };
+
+    let width = image.width();
+    let height = image.height();
+    let texture = self.new_texture(Storage::Flat2D { width, height }, pixel, sampling)?;
+
+    let rect = Rect::new(Offset::Dim2 { x: 0, y: 0 }, Size::Dim2 { width, height });
+    let mipmaps = !matches!(sampling.min_filter, MinFilter::Nearest | MinFilter::Linear);
+    texture.set(rect, mipmaps, 0, image.as_bytes())?;
+
+    Ok(texture)
+  }
+}
@@ -0,0 +1,194 @@
+//! Shader interface validation.
+//!
+//! Drivers are lenient with mismatched interfaces: a vertex attribute the shader expects but the vertex array doesn’t
+//! provide reads as zero, and a fragment input no previous stage writes is undefined, which usually shows up as black
+//! geometry rather than as an error. [`InterfaceReport`] compares the interface of a shader, as found by reflection,
+//! with the vertex attributes it will be fed, and lists every mismatch.
+//!
+//! Use [`Shader::validate`](crate::shader::Shader::validate) to validate a shader.
+
+use std::fmt::{self, Display};
+
+use piksels_backend::{
+  shader::{InterfaceVariable, ShaderInterface},
+  vertex::{Normalized, Type, VertexAttr},
+};
+
+/// Mismatch between the interface of a shader and the vertex attributes it is fed with.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum InterfaceMismatch {
+  /// The vertex stage expects an attribute that is not provided.
+  MissingAttribute { name: String },
+
+  /// An attribute is provided at a different location than the one set in the vertex stage.
+  AttributeLocation {
+    name: String,
+    shader: usize,
+    vertex: usize,
+  },
+
+  /// An attribute is provided with a different type than the one declared in the vertex stage.
+  ///
+  /// Normalized integral attributes are seen as floating-point by shaders.
+  AttributeType {
+    name: String,
+    shader: (Type, Option<usize>),
+    vertex: (Type, Option<usize>),
+  },
+
+  /// The fragment stage expects an input that the previous stage doesn’t output.
+  MissingVarying { name: String },
+
+  /// The fragment stage expects an input with a different type than the one output by the previous stage.
+  VaryingType {
+    name: String,
+    output: (Type, Option<usize>),
+    input: (Type, Option<usize>),
+  },
+}
+
+impl Display for InterfaceMismatch {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::MissingAttribute { name } => write!(f, "missing vertex attribute {name:?}"),
+
+      Self::AttributeLocation {
+        name,
+        shader,
+        vertex,
+      } => write!(
+        f,
+        "vertex attribute {name:?} is at location {shader} in the shader, but at index {vertex} in the vertex"
+      ),
+
+      Self::AttributeType {
+        name,
+        shader,
+        vertex,
+      } => write!(
+        f,
+        "vertex attribute {name:?} is {shader:?} in the shader, but {vertex:?} in the vertex"
+      ),
+
+      Self::MissingVarying { name } => {
+        write!(f, "fragment input {name:?} is not output by the previous stage")
+      }
+
+      Self::VaryingType {
+        name,
+        output,
+        input,
+      } => write!(
+        f,
+        "fragment input {name:?} is {input:?}, but output as {output:?} by the previous stage"
+      ),
+    }
+  }
+}
+
+/// Mismatches found when validating the interface of a shader.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct InterfaceReport {
+  mismatches: Vec<InterfaceMismatch>,
+}
+
+impl InterfaceReport {
+  /// Compare `interface` with the vertex attributes `attrs` it is fed with.
+  ///
+  /// Attributes are matched by name. Fragment inputs are matched with outputs of the previous stage by location if
+  /// both have one set, and by name otherwise. Provided attributes and outputs that are not used are not mismatches.
+  pub fn new(interface: &ShaderInterface, attrs: &[VertexAttr]) -> Self {
+    let mut mismatches = Vec::new();
+
+    for attribute in &interface.attributes {
+      let Some(attr) = attrs.iter().find(|attr| attr.name == attribute.name) else {
+        mismatches.push(InterfaceMismatch::MissingAttribute {
+          name: attribute.name.clone(),
+        });
+        continue;
+      };
+
+      if let Some(location) = attribute
+        .location
+        .filter(|&location| location != attr.index)
+      {
+        mismatches.push(InterfaceMismatch::AttributeLocation {
+          name: attribute.name.clone(),
+          shader: location,
+          vertex: attr.index,
+        });
+      }
+
+      let shader = (attribute.ty, attribute.array);
+      let vertex = (attr.ty, attr.array);
+      if shader != (shader_type(attr.ty), attr.array) {
+        mismatches.push(InterfaceMismatch::AttributeType {
+          name: attribute.name.clone(),
+          shader,
+          vertex,
+        });
+      }
+    }
+
+    for input in &interface.varying_inputs {
+      let Some(output) = interface
+        .varying_outputs
+        .iter()
+        .find(|output| matches(output, input))
+      else {
+        mismatches.push(InterfaceMismatch::MissingVarying {
+          name: input.name.clone(),
+        });
+        continue;
+      };
+
+      if (output.ty, output.array) != (input.ty, input.array) {
+        mismatches.push(InterfaceMismatch::VaryingType {
+          name: input.name.clone(),
+          output: (output.ty, output.array),
+          input: (input.ty, input.array),
+        });
+      }
+    }
+
+    Self { mismatches }
+  }
+
+  /// Whether no mismatch was found.
+  pub fn is_valid(&self) -> bool {
+    self.mismatches.is_empty()
+  }
+
+  pub fn mismatches(&self) -> &[InterfaceMismatch] {
+    &self.mismatches
+  }
+}
+
+impl Display for InterfaceReport {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for mismatch in &self.mismatches {
+      writeln!(f, "{mismatch}")?;
+    }
+
+    Ok(())
+  }
+}
+
+/// Whether a fragment input is fed by an output of the previous stage.
+fn matches(output: &InterfaceVariable, input: &InterfaceVariable) -> bool {
+  match (output.location, input.location) {
+    (Some(output), Some(input)) => output == input,
+    _ => output.name == input.name,
+  }
+}
+
+/// Type of a vertex attribute, as seen by shaders.
+fn shader_type(ty: Type) -> Type {
+  match ty {
+    Type::Int(Normalized::Yes) | Type::Uint(Normalized::Yes) => Type::Float,
+    Type::Int2(Normalized::Yes) | Type::Uint2(Normalized::Yes) => Type::Float2,
+    Type::Int3(Normalized::Yes) | Type::Uint3(Normalized::Yes) => Type::Float3,
+    Type::Int4(Normalized::Yes) | Type::Uint4(Normalized::Yes) => Type::Float4,
+    _ => ty,
+  }
+}
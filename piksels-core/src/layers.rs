@@ -0,0 +1,1460 @@
+//! Layers.
+//!
+//! Layers are a structured way to record a [`CmdBuf`]: render targets are bound first, then shaders, then the
+//! resources and draws of each shader, each layer being closed with `done`. The structure allows to manage texture and
+//! uniform buffer units automatically: resources are bound to units taken from pools shared by the whole
+//! [`Device`](crate::device::Device), and the units are given back to the pools when the shader layer using them is
+//! done. Units given back keep their resource bound, so that binding the same resource again in a later layer of the
+//! same command buffer doesn’t issue any command. Command buffers can be executed in any order, so what is bound by a
+//! command buffer is forgotten when layers of another one bind resources.
+//!
+//! With [`SpillStrategy::Steal`](crate::units::SpillStrategy::Steal) (see
+//! [`Device::set_units_spill_strategy`](crate::device::Device::set_units_spill_strategy)), the unit of a resource can
+//! be stolen by another one while its layer is still open. Shader layers keep track of the resources they bound, and
+//! rebind the ones whose unit was stolen before the next draw.
+//!
+//! ```ignore
+//! let cmd_buf = device
+//!   .new_layers()?
+//!   .render_targets(&render_targets)?
+//!     .shader(&shader)?
+//!       .texture(&albedo, &albedo_binding_point)?
+//!       .draw(&mesh)?
+//!       .done()?
+//!     .done()
+//!   .done()?;
+//! ```
+//!
+//! Binding resources with [`CmdBuf::use_texture`] or [`CmdBuf::use_uniform_buffer`] while recording layers bypasses
+//! the unit pools, which then don’t know what is bound anymore; stick to the layers API.
+//!
+//! # Sorting draws
+//!
+//! Scenes are often submitted in an order that is convenient for the application, but not for the device: switching
+//! render targets, shaders or textures between every draw. [`Layers::sort_draws`] defers the draws until the layers
+//! are done instead, sorts the draws of each render targets layer by shader and textures, and only then records them,
+//! so that each of those is bound once per group of draws sharing it. Render targets layers are recorded in order, so
+//! that a pass can use what the previous ones rendered.
+//!
+//! # Pipeline state and groups
+//!
+//! Every layer implements [`LayerCommons`], changing the pipeline state (blending, depth test, viewport, etc.) from
+//! within the chain of layers. When sorting draws, the state is not changed right away, but along with the draws
+//! recorded after it.
+//!
+//! [`LayerCommons::group`] opens a [`GroupLayer`] inside a layer. Resources bound in a group of a shader layer (see
+//! [`LayerShader`]) have their units given back to the pools when the group is done, instead of when the shader layer
+//! is, so that a shader layer drawing many objects doesn’t run out of units:
+//!
+//! ```ignore
+//! let layer = layer
+//!   .group()
+//!     .texture(&albedo, &albedo_binding_point)?
+//!     .draw(&mesh)?
+//!     .done()?
+//!   .blending(BlendingMode::Off)?
+//!   .draw(&overlay)?;
+//! ```
+//!
+//! # Conditional layers
+//!
+//! Every layer has `when` and `when_with`, recording a part of the layer only if a condition holds, so that debug
+//! overlays or optional passes can be toggled without breaking the chain of layers:
+//!
+//! ```ignore
+//! let layers = layers
+//!   .render_targets(&render_targets)?
+//!     .shader(&shader)?
+//!       .draw(&mesh)?
+//!       .when(show_wireframe, |layer| layer.draw(&wireframe))?
+//!       .done()?
+//!     .done();
+//! ```
+//!
+//! Conditions are evaluated once, while recording. Draws frozen with [`Layers::freeze`] keep the parts recorded at
+//! that time: submitting them doesn’t evaluate the conditions again, so freeze one [`RecordedLayers`] per combination
+//! of conditions to toggle parts of frozen layers.
+//!
+//! # Timing
+//!
+//! With the profiler extension, `ShaderLayer::timed` wraps the draws of a part of a shader layer in timer queries,
+//! giving pass-level GPU timings collected with `Device::timings`. Sorted draws cannot be timed.
+
+use std::{
+  cmp::Ordering,
+  marker::PhantomData,
+  mem,
+  sync::{Arc, Mutex, MutexGuard},
+};
+
+use piksels_backend::{
+  blending::BlendingMode,
+  color::RGBA32F,
+  depth_stencil::{DepthTest, DepthWrite, StencilTest},
+  error::Error,
+  face_culling::FaceCulling,
+  scissor::Scissor,
+  shader::{Stage, UniformValue},
+  unit::Unit,
+  viewport::Viewport,
+  Backend, Scarce,
+};
+#[cfg(feature = "ext-profiler")]
+use piksels_backend::{extension::profiler::BackendProfiler, warn};
+
+use crate::{
+  cache::{Cache, ResourceId},
+  cmd_buf::CmdBuf,
+  pipeline::PipelineStateDiff,
+  render_targets::RenderTargets,
+  shader::{
+    Shader, ShaderTextureBindingPoint, ShaderUniformBufferBindingPoint, Uniform, UniformBuffer,
+    UniformBufferBindingPoint,
+  },
+  texture::{Texture, TextureBindingPoint},
+  vertex_array::{OwnedVertexArrayView, VertexArray, VertexArrayView},
+};
+
+/// State shared by all the layers of a recording.
+#[derive(Debug)]
+struct LayersState<B>
+where
+  B: Backend,
+{
+  cmd_buf: CmdBuf<B>,
+  backend: Arc<B>,
+  cache: Arc<Mutex<Cache<B>>>,
+  /// Draws deferred until the layers are done, if sorting draws.
+  sorted_draws: Option<Vec<DeferredDraw<B>>>,
+  /// Pipeline state set by the layers, applied before the deferred draws, if sorting draws.
+  sorted_pipeline: PipelineStateDiff,
+  /// Index of the render targets layer draws are deferred in, if sorting draws.
+  sorted_pass: usize,
+}
+
+impl<B> LayersState<B>
+where
+  B: Backend,
+{
+  fn cache(&self) -> Result<MutexGuard<'_, Cache<B>>, B::Err> {
+    Ok(self.cache.lock().map_err(Error::from)?)
+  }
+
+  /// Lock the cache to bind resources to units in the command buffer of the layers.
+  fn units_cache(&self) -> Result<MutexGuard<'_, Cache<B>>, B::Err> {
+    let mut cache = self.cache()?;
+    cache.bind_units_in(&self.cmd_buf.id());
+    Ok(cache)
+  }
+
+  /// Change the pipeline state, or defer the change along with the next draws, if sorting draws.
+  fn set_pipeline(&mut self, diff: PipelineStateDiff) -> Result<(), B::Err> {
+    if self.sorted_draws.is_some() {
+      self.sorted_pipeline.merge(diff);
+    } else {
+      self.cmd_buf.apply_pipeline_diff(diff)?;
+    }
+
+    Ok(())
+  }
+
+  fn bind_texture(&self, binding: &TextureLayerBinding<B>, bind: bool) -> Result<(), B::Err> {
+    let unit = TextureBindingPoint::from_raw(
+      self
+        .backend
+        .get_texture_binding_point(binding.unit.index())?,
+    );
+    if bind {
+      self.cmd_buf.use_raw_texture(&binding.raw, &unit)?;
+    }
+    self
+      .cmd_buf
+      .associate_texture(&unit, &binding.binding_point)?;
+
+    Ok(())
+  }
+
+  fn bind_uniform_buffer(
+    &self,
+    binding: &UniformBufferLayerBinding<B>,
+    bind: bool,
+  ) -> Result<(), B::Err> {
+    let unit = UniformBufferBindingPoint::from_raw(
+      self
+        .backend
+        .get_uniform_buffer_binding_point(binding.unit.index())?,
+    );
+    if bind {
+      self.cmd_buf.use_raw_uniform_buffer(&binding.raw, &unit)?;
+    }
+    self
+      .cmd_buf
+      .associate_uniform_buffer(&unit, &binding.binding_point)?;
+
+    Ok(())
+  }
+
+  /// Record deferred draws, sorted with [`sort_deferred`].
+  fn record_sorted(&self, draws: &[DeferredDraw<B>]) -> Result<(), B::Err> {
+    let mut bindings = LayerBindings::default();
+    let mut previous: Option<&DrawKey<B>> = None;
+
+    for draw in draws {
+      if previous != Some(&draw.key) {
+        mem::take(&mut bindings).release(self)?;
+
+        let same_render_targets = previous.is_some_and(|key| key.pass == draw.key.pass);
+        if !same_render_targets {
+          self.cmd_buf.render_targets(&draw.render_targets)?;
+        }
+
+        if !same_render_targets || previous.map_or(true, |key| key.shader != draw.key.shader) {
+          self.cmd_buf.shader(&draw.shader)?;
+        }
+
+        for (texture, binding_point, stage) in &draw.bindings.textures {
+          bindings.bind_texture(
+            self,
+            texture.scarce_clone(),
+            binding_point.scarce_clone(),
+            *stage,
+          )?;
+        }
+
+        for (uniform_buffer, binding_point) in &draw.bindings.uniform_buffers {
+          bindings.bind_uniform_buffer(
+            self,
+            uniform_buffer.scarce_clone(),
+            binding_point.scarce_clone(),
+          )?;
+        }
+      }
+
+      for (uniform, bytes) in &draw.bindings.uniforms {
+        self.cmd_buf.uniform_bytes(uniform, bytes)?;
+      }
+
+      if !draw.pipeline.is_empty() {
+        self.cmd_buf.apply_pipeline_diff(draw.pipeline.clone())?;
+      }
+
+      bindings.rebind_stolen(self)?;
+
+      match &draw.call {
+        DeferredCall::VertexArray(vertex_array) => {
+          self.cmd_buf.draw(vertex_array)?;
+        }
+
+        DeferredCall::View(view) => {
+          self.cmd_buf.draw_view(&view.view())?;
+        }
+      }
+
+      previous = Some(&draw.key);
+    }
+
+    bindings.release(self)
+  }
+}
+
+/// Sort the deferred draws of each render targets layer by shader and bound resources; draws sharing all of them keep
+/// their order.
+fn sort_deferred<B>(draws: &mut [DeferredDraw<B>])
+where
+  B: Backend,
+{
+  draws.sort_by(|a, b| a.key.cmp(&b.key));
+}
+
+/// A resource bound to a unit by a [`ShaderLayer`].
+#[derive(Debug)]
+struct LayerBinding<R, P, U> {
+  raw: R,
+  /// Shader binding point the unit is associated with.
+  binding_point: P,
+  stage: Stage,
+  unit: U,
+}
+
+type TextureLayerBinding<B> =
+  LayerBinding<<B as Backend>::Texture, ShaderTextureBindingPoint<B>, <B as Backend>::TextureUnit>;
+
+type UniformBufferLayerBinding<B> = LayerBinding<
+  <B as Backend>::UniformBuffer,
+  ShaderUniformBufferBindingPoint<B>,
+  <B as Backend>::UniformBufferUnit,
+>;
+
+/// Resources bound to units by a [`ShaderLayer`].
+#[derive(Debug)]
+struct LayerBindings<B>
+where
+  B: Backend,
+{
+  textures: Vec<TextureLayerBinding<B>>,
+  uniform_buffers: Vec<UniformBufferLayerBinding<B>>,
+}
+
+impl<B> Default for LayerBindings<B>
+where
+  B: Backend,
+{
+  fn default() -> Self {
+    Self {
+      textures: Vec::new(),
+      uniform_buffers: Vec::new(),
+    }
+  }
+}
+
+impl<B> LayerBindings<B>
+where
+  B: Backend,
+{
+  /// Bind a texture to a unit, unless it is still bound to an idle one, and associate the unit with `binding_point`.
+  fn bind_texture(
+    &mut self,
+    state: &LayersState<B>,
+    texture: B::Texture,
+    binding_point: B::ShaderTextureBindingPoint,
+    stage: Stage,
+  ) -> Result<(), B::Err> {
+    let id = texture.scarce_index();
+    let (unit, bound) = {
+      let mut cache = state.units_cache()?;
+      let units = cache.texture_units();
+
+      match units.get_bound_unit(&id, stage) {
+        Some(unit) => (unit, true),
+        None => (units.get_unit(id, stage)?.unit, false),
+      }
+    };
+    let binding = LayerBinding {
+      raw: texture,
+      binding_point: ShaderTextureBindingPoint { raw: binding_point },
+      stage,
+      unit,
+    };
+
+    state.bind_texture(&binding, !bound)?;
+    self.textures.push(binding);
+
+    Ok(())
+  }
+
+  /// Bind a uniform buffer the same way as with [`LayerBindings::bind_texture`].
+  fn bind_uniform_buffer(
+    &mut self,
+    state: &LayersState<B>,
+    uniform_buffer: B::UniformBuffer,
+    binding_point: B::ShaderUniformBufferBindingPoint,
+  ) -> Result<(), B::Err> {
+    let id = uniform_buffer.scarce_index();
+    let (unit, bound) = {
+      let mut cache = state.units_cache()?;
+      let units = cache.uniform_buffer_units();
+
+      match units.get_bound_unit(&id, Stage::Fragment) {
+        Some(unit) => (unit, true),
+        None => (units.get_unit(id, Stage::Fragment)?.unit, false),
+      }
+    };
+    let binding = LayerBinding {
+      raw: uniform_buffer,
+      binding_point: ShaderUniformBufferBindingPoint { raw: binding_point },
+      stage: Stage::Fragment,
+      unit,
+    };
+
+    state.bind_uniform_buffer(&binding, !bound)?;
+    self.uniform_buffers.push(binding);
+
+    Ok(())
+  }
+
+  /// Rebind the resources whose unit was stolen by another resource since they were bound.
+  ///
+  /// Rebinding a resource can steal the unit of another resource of the layer; if that happens, the layer uses more
+  /// resources than there are units, and [`Error::NoMoreUnits`] is returned.
+  fn rebind_stolen(&mut self, state: &LayersState<B>) -> Result<(), B::Err> {
+    for binding in &mut self.textures {
+      let id = binding.raw.scarce_index();
+      let unit = {
+        let mut cache = state.cache()?;
+        let units = cache.texture_units();
+
+        if units.owner(&binding.unit) == Some(&id) {
+          continue;
+        }
+
+        units.get_unit(id, binding.stage)?.unit
+      };
+
+      binding.unit = unit;
+      state.bind_texture(binding, true)?;
+    }
+
+    for binding in &mut self.uniform_buffers {
+      let id = binding.raw.scarce_index();
+      let unit = {
+        let mut cache = state.cache()?;
+        let units = cache.uniform_buffer_units();
+
+        if units.owner(&binding.unit) == Some(&id) {
+          continue;
+        }
+
+        units.get_unit(id, binding.stage)?.unit
+      };
+
+      binding.unit = unit;
+      state.bind_uniform_buffer(binding, true)?;
+    }
+
+    let mut cache = state.cache()?;
+    let textures_bound = self.textures.iter().all(|binding| {
+      cache.texture_units().owner(&binding.unit) == Some(&binding.raw.scarce_index())
+    });
+    let uniform_buffers_bound = self.uniform_buffers.iter().all(|binding| {
+      cache.uniform_buffer_units().owner(&binding.unit) == Some(&binding.raw.scarce_index())
+    });
+
+    if textures_bound && uniform_buffers_bound {
+      Ok(())
+    } else {
+      Err(Error::NoMoreUnits.into())
+    }
+  }
+
+  /// Give the units back to the pools of the device.
+  ///
+  /// Units stolen by other resources are not given back, since they are not ours anymore.
+  fn release(self, state: &LayersState<B>) -> Result<(), B::Err> {
+    let mut cache = state.cache()?;
+
+    for binding in self.textures {
+      let units = cache.texture_units();
+      if units.owner(&binding.unit) == Some(&binding.raw.scarce_index()) {
+        units.idle(binding.unit);
+      }
+    }
+
+    for binding in self.uniform_buffers {
+      let units = cache.uniform_buffer_units();
+      if units.owner(&binding.unit) == Some(&binding.raw.scarce_index()) {
+        units.idle(binding.unit);
+      }
+    }
+
+    Ok(())
+  }
+}
+
+/// Resources and uniform values set in a [`ShaderLayer`] whose draws are deferred.
+#[derive(Debug)]
+struct DeferredBindings<B>
+where
+  B: Backend,
+{
+  uniforms: Vec<(Uniform<B>, Vec<u8>)>,
+  textures: Vec<(B::Texture, B::ShaderTextureBindingPoint, Stage)>,
+  uniform_buffers: Vec<(B::UniformBuffer, B::ShaderUniformBufferBindingPoint)>,
+}
+
+impl<B> Default for DeferredBindings<B>
+where
+  B: Backend,
+{
+  fn default() -> Self {
+    Self {
+      uniforms: Vec::new(),
+      textures: Vec::new(),
+      uniform_buffers: Vec::new(),
+    }
+  }
+}
+
+impl<B> DeferredBindings<B>
+where
+  B: Backend,
+{
+  fn scarce_clone(&self) -> Self {
+    Self {
+      uniforms: self
+        .uniforms
+        .iter()
+        .map(|(uniform, bytes)| (uniform.scarce_clone(), bytes.clone()))
+        .collect(),
+      textures: self
+        .textures
+        .iter()
+        .map(|(texture, binding_point, stage)| {
+          (texture.scarce_clone(), binding_point.scarce_clone(), *stage)
+        })
+        .collect(),
+      uniform_buffers: self
+        .uniform_buffers
+        .iter()
+        .map(|(uniform_buffer, binding_point)| {
+          (uniform_buffer.scarce_clone(), binding_point.scarce_clone())
+        })
+        .collect(),
+    }
+  }
+}
+
+/// Key deferred draws are sorted by.
+#[derive(Debug)]
+struct DrawKey<B>
+where
+  B: Backend,
+{
+  /// Render targets layer of the draw, so that draws are not sorted across layers.
+  pass: usize,
+  shader: ResourceId<B>,
+  /// Bound textures, along with their binding point and stage, sorted.
+  textures: Vec<(ResourceId<B>, ResourceId<B>, Stage)>,
+  /// Bound uniform buffers, along with their binding point, sorted.
+  uniform_buffers: Vec<(ResourceId<B>, ResourceId<B>)>,
+}
+
+impl<B> PartialEq for DrawKey<B>
+where
+  B: Backend,
+{
+  fn eq(&self, other: &Self) -> bool {
+    self.pass == other.pass
+      && self.shader == other.shader
+      && self.textures == other.textures
+      && self.uniform_buffers == other.uniform_buffers
+  }
+}
+
+impl<B> Eq for DrawKey<B> where B: Backend {}
+
+impl<B> PartialOrd for DrawKey<B>
+where
+  B: Backend,
+{
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl<B> Ord for DrawKey<B>
+where
+  B: Backend,
+{
+  fn cmp(&self, other: &Self) -> Ordering {
+    self
+      .pass
+      .cmp(&other.pass)
+      .then_with(|| self.shader.cmp(&other.shader))
+      .then_with(|| self.textures.cmp(&other.textures))
+      .then_with(|| self.uniform_buffers.cmp(&other.uniform_buffers))
+  }
+}
+
+/// Draw call of a [`DeferredDraw`].
+#[derive(Debug)]
+enum DeferredCall<B>
+where
+  B: Backend,
+{
+  VertexArray(VertexArray<B>),
+  View(OwnedVertexArrayView<B>),
+}
+
+/// A draw deferred by [`Layers::sort_draws`].
+#[derive(Debug)]
+struct DeferredDraw<B>
+where
+  B: Backend,
+{
+  key: DrawKey<B>,
+  render_targets: Arc<RenderTargets<B>>,
+  shader: Arc<Shader<B>>,
+  bindings: DeferredBindings<B>,
+  /// Pipeline state set by the layers before the draw.
+  pipeline: PipelineStateDiff,
+  call: DeferredCall<B>,
+}
+
+/// Top layer, binding render targets.
+///
+/// Create one with [`Device::new_layers`](crate::device::Device::new_layers).
+#[derive(Debug)]
+pub struct Layers<B>
+where
+  B: Backend,
+{
+  state: LayersState<B>,
+}
+
+impl<B> Layers<B>
+where
+  B: Backend,
+{
+  pub(crate) fn new(cmd_buf: CmdBuf<B>, backend: Arc<B>, cache: Arc<Mutex<Cache<B>>>) -> Self {
+    Self {
+      state: LayersState {
+        cmd_buf,
+        backend,
+        cache,
+        sorted_draws: None,
+        sorted_pipeline: PipelineStateDiff::default(),
+        sorted_pass: 0,
+      },
+    }
+  }
+
+  /// Command buffer the layers are recorded in, e.g. to change the pipeline state.
+  pub fn cmd_buf(&self) -> &CmdBuf<B> {
+    &self.state.cmd_buf
+  }
+
+  /// Defer the draws until the layers are done, and record them sorted by shader and textures.
+  ///
+  /// Nothing is recorded until [`Layers::done`]: draws only keep the uniform values set and resources bound in their
+  /// shader layer. Pipeline state changed with [`Layers::cmd_buf`] applies to all the draws. Draws are only reordered
+  /// within their render targets layer: render targets layers are recorded in the order they were opened, and so are
+  /// the parts of a layer before and after a [`RenderTargetsLayer::deeper`] pass.
+  pub fn sort_draws(mut self) -> Self {
+    self.state.sorted_draws.get_or_insert_with(Vec::new);
+    self
+  }
+
+  /// Freeze the draws deferred so far by [`Layers::sort_draws`], to record them again every frame.
+  ///
+  /// The frozen draws are taken out of the layers; submit them with [`RecordedLayers::submit`], in these layers or in
+  /// the layers of later frames. Layers not sorting draws have nothing to freeze.
+  pub fn freeze(&mut self) -> RecordedLayers<B> {
+    let mut draws = self
+      .state
+      .sorted_draws
+      .as_mut()
+      .map(mem::take)
+      .unwrap_or_default();
+    sort_deferred(&mut draws);
+
+    RecordedLayers { draws }
+  }
+
+  /// Bind render targets, and open a layer to render to them.
+  pub fn render_targets(
+    self,
+    render_targets: &RenderTargets<B>,
+  ) -> Result<RenderTargetsLayer<B>, B::Err> {
+    if self.state.sorted_draws.is_some() {
+      render_targets.check_not_stale()?;
+
+      let mut state = self.state;
+      state.sorted_pass += 1;
+      return Ok(RenderTargetsLayer {
+        state,
+        sorted_render_targets: Some(Arc::new(render_targets.scarce_clone())),
+      });
+    }
+
+    self.state.cmd_buf.render_targets(render_targets)?;
+    Ok(RenderTargetsLayer {
+      state: self.state,
+      sorted_render_targets: None,
+    })
+  }
+
+  /// Finish recording, and get back the recorded command buffer.
+  ///
+  /// Deferred draws are recorded first, if sorting draws.
+  pub fn done(mut self) -> Result<CmdBuf<B>, B::Err> {
+    if let Some(mut draws) = self.state.sorted_draws.take() {
+      sort_deferred(&mut draws);
+      self.state.record_sorted(&draws)?;
+    }
+
+    self.state.cmd_buf.finish()?;
+    Ok(self.state.cmd_buf)
+  }
+}
+
+/// Draws recorded once with layers, and submitted as many times as needed.
+///
+/// Static parts of a scene don’t change from a frame to another; recording them once saves traversing the scene and
+/// sorting its draws every frame. Create one with [`Layers::freeze`].
+///
+/// Recorded layers keep handles on the resources they use, which must not be dropped while the recorded layers are
+/// still submitted.
+#[derive(Debug)]
+pub struct RecordedLayers<B>
+where
+  B: Backend,
+{
+  draws: Vec<DeferredDraw<B>>,
+}
+
+impl<B> RecordedLayers<B>
+where
+  B: Backend,
+{
+  /// Number of recorded draws.
+  pub fn draw_count(&self) -> usize {
+    self.draws.len()
+  }
+
+  /// Change the value of a uniform for the draws that set it when they were recorded.
+  ///
+  /// Draws that didn’t set the uniform are left untouched.
+  pub fn uniform<T>(&mut self, uniform: &Uniform<B>, value: &T) -> Result<(), B::Err>
+  where
+    T: ?Sized + UniformValue,
+  {
+    let id = uniform.raw.scarce_index();
+    let bytes = uniform.ty().coerce(value)?;
+
+    for draw in &mut self.draws {
+      for (recorded, recorded_bytes) in &mut draw.bindings.uniforms {
+        if recorded.raw.scarce_index() == id {
+          recorded_bytes.clone_from(&bytes);
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Record the draws in `layers`.
+  ///
+  /// The draws are recorded right away, even if `layers` sorts its draws.
+  pub fn submit(&self, layers: Layers<B>) -> Result<Layers<B>, B::Err> {
+    layers.state.record_sorted(&self.draws)?;
+    Ok(layers)
+  }
+}
+
+/// Layer rendering to render targets.
+#[derive(Debug)]
+pub struct RenderTargetsLayer<B>
+where
+  B: Backend,
+{
+  state: LayersState<B>,
+  /// Render targets of the layer, if sorting draws.
+  sorted_render_targets: Option<Arc<RenderTargets<B>>>,
+}
+
+impl<B> RenderTargetsLayer<B>
+where
+  B: Backend,
+{
+  /// Command buffer the layers are recorded in, e.g. to change the pipeline state.
+  pub fn cmd_buf(&self) -> &CmdBuf<B> {
+    &self.state.cmd_buf
+  }
+
+  /// Bind a shader, and open a layer to draw with it.
+  pub fn shader(self, shader: &Shader<B>) -> Result<ShaderLayer<B>, B::Err> {
+    if self.sorted_render_targets.is_none() {
+      self.state.cmd_buf.shader(shader)?;
+    }
+
+    Ok(ShaderLayer {
+      state: self.state,
+      shader: Arc::new(shader.scarce_clone()),
+      sorted_render_targets: self.sorted_render_targets,
+      bindings: LayerBindings::default(),
+      deferred: DeferredBindings::default(),
+      groups: Vec::new(),
+    })
+  }
+
+  /// Render to other render targets in the middle of the layer, e.g. a shadow map while recording the main pass.
+  ///
+  /// `f` records the nested layer, and gives it back once done. The pipeline state set by the command buffer (e.g.
+  /// viewport or scissor), along with the bound shader and render targets, is then restored as it was before the
+  /// nested layer was opened (see [`CmdBuf::restore`]), so that state changes of the nested pass don’t leak.
+  /// Variables set by the nested layer only are reset to the defaults of the command buffer (see
+  /// [`CmdBuf::clear_state`]).
+  ///
+  /// When sorting draws, the restored state is deferred as well, and applies to the draws recorded after the nested
+  /// layer.
+  pub fn deeper<F>(self, render_targets: &RenderTargets<B>, f: F) -> Result<Self, B::Err>
+  where
+    F: FnOnce(RenderTargetsLayer<B>) -> Result<RenderTargetsLayer<B>, B::Err>,
+  {
+    let sorted_render_targets = self.sorted_render_targets;
+
+    if sorted_render_targets.is_some() {
+      let mut pipeline = self.state.sorted_pipeline.clone();
+      let mut state = f(Layers { state: self.state }.render_targets(render_targets)?)?.state;
+      pipeline.reset_unset(&state.sorted_pipeline, state.cmd_buf.default_state());
+      state.sorted_pipeline = pipeline;
+      // draws after the nested pass might use what it rendered
+      state.sorted_pass += 1;
+
+      return Ok(RenderTargetsLayer {
+        state,
+        sorted_render_targets,
+      });
+    }
+
+    let mut snapshot = self.state.cmd_buf.snapshot()?;
+    let nested = f(Layers { state: self.state }.render_targets(render_targets)?)?;
+    let cmd_buf = &nested.state.cmd_buf;
+    snapshot
+      .state
+      .reset_unset(&cmd_buf.snapshot()?.state, cmd_buf.default_state());
+    cmd_buf.restore(&snapshot)?;
+
+    Ok(RenderTargetsLayer {
+      state: nested.state,
+      sorted_render_targets,
+    })
+  }
+
+  /// Close the layer.
+  pub fn done(self) -> Layers<B> {
+    Layers { state: self.state }
+  }
+}
+
+/// Layer drawing with a shader.
+#[derive(Debug)]
+pub struct ShaderLayer<B>
+where
+  B: Backend,
+{
+  state: LayersState<B>,
+  shader: Arc<Shader<B>>,
+  /// Render targets of the parent layer, if sorting draws.
+  sorted_render_targets: Option<Arc<RenderTargets<B>>>,
+  /// Resources bound by the layer.
+  bindings: LayerBindings<B>,
+  /// Resources and uniform values the deferred draws of the layer use, if sorting draws.
+  deferred: DeferredBindings<B>,
+  /// Number of textures and uniform buffers bound (or deferred) when each open group was opened.
+  groups: Vec<(usize, usize)>,
+}
+
+impl<B> ShaderLayer<B>
+where
+  B: Backend,
+{
+  /// Command buffer the layers are recorded in, e.g. to change the pipeline state.
+  pub fn cmd_buf(&self) -> &CmdBuf<B> {
+    &self.state.cmd_buf
+  }
+
+  /// Set a uniform of the shader.
+  ///
+  /// The value is converted to the type of the uniform and cached the same way as with [`CmdBuf::uniform`]: setting
+  /// the value a cached uniform already has doesn’t reach the backend.
+  pub fn uniform<T>(mut self, uniform: &Uniform<B>, value: &T) -> Result<Self, B::Err>
+  where
+    T: ?Sized + UniformValue,
+  {
+    if self.sorted_render_targets.is_some() {
+      let bytes = uniform.ty().coerce(value)?;
+      self.deferred.uniforms.push((uniform.scarce_clone(), bytes));
+      return Ok(self);
+    }
+
+    self.state.cmd_buf.uniform(uniform, value)?;
+    Ok(self)
+  }
+
+  /// Bind a texture to a unit, and associate the unit with `binding_point`.
+  ///
+  /// The unit is used until the layer is done. If the texture is still bound to a unit given back by a previous layer,
+  /// that unit is used and the texture is not bound again.
+  ///
+  /// The texture is expected to be sampled by the fragment stage; use [`ShaderLayer::stage_texture`] otherwise.
+  pub fn texture(
+    self,
+    texture: &Texture<B>,
+    binding_point: &ShaderTextureBindingPoint<B>,
+  ) -> Result<Self, B::Err> {
+    self.stage_texture(texture, binding_point, Stage::Fragment)
+  }
+
+  /// Bind a texture to the binding point of the shader named `name`.
+  ///
+  /// This is a shortcut for [`Shader::texture_binding_point`] followed by [`ShaderLayer::texture`].
+  pub fn texture_named(self, name: &str, texture: &Texture<B>) -> Result<Self, B::Err> {
+    let binding_point = self.shader.texture_binding_point(name)?;
+    self.texture(texture, &binding_point)
+  }
+
+  /// Bind a texture sampled by `stage` to a unit, and associate the unit with `binding_point`.
+  ///
+  /// Units are managed the same way as with [`ShaderLayer::texture`], respecting the limit of texture units of `stage`
+  /// (see [`DeviceLimits::max_stage_texture_units`](piksels_backend::DeviceLimits::max_stage_texture_units)).
+  pub fn stage_texture(
+    mut self,
+    texture: &Texture<B>,
+    binding_point: &ShaderTextureBindingPoint<B>,
+    stage: Stage,
+  ) -> Result<Self, B::Err> {
+    let texture = texture.raw.scarce_clone();
+    let binding_point = binding_point.raw.scarce_clone();
+
+    if self.sorted_render_targets.is_some() {
+      self.deferred.textures.push((texture, binding_point, stage));
+    } else {
+      self
+        .bindings
+        .bind_texture(&self.state, texture, binding_point, stage)?;
+    }
+
+    Ok(self)
+  }
+
+  /// Bind a uniform buffer to a unit, and associate the unit with `binding_point`.
+  ///
+  /// Units are managed the same way as with [`ShaderLayer::texture`]. Uniform buffer units are not limited per stage.
+  pub fn uniform_buffer(
+    mut self,
+    uniform_buffer: &UniformBuffer<B>,
+    binding_point: &ShaderUniformBufferBindingPoint<B>,
+  ) -> Result<Self, B::Err> {
+    let uniform_buffer = uniform_buffer.raw.scarce_clone();
+    let binding_point = binding_point.raw.scarce_clone();
+
+    if self.sorted_render_targets.is_some() {
+      self
+        .deferred
+        .uniform_buffers
+        .push((uniform_buffer, binding_point));
+    } else {
+      self
+        .bindings
+        .bind_uniform_buffer(&self.state, uniform_buffer, binding_point)?;
+    }
+
+    Ok(self)
+  }
+
+  /// Draw, after rebinding the resources of the layer whose unit was stolen.
+  pub fn draw(mut self, vertex_array: &VertexArray<B>) -> Result<Self, B::Err> {
+    if self.defer(DeferredCall::VertexArray(vertex_array.scarce_clone())) {
+      return Ok(self);
+    }
+
+    self.bindings.rebind_stolen(&self.state)?;
+    self.state.cmd_buf.draw(vertex_array)?;
+    Ok(self)
+  }
+
+  /// Draw a view on a vertex array, e.g. a range of its vertices or several instances.
+  ///
+  /// See [`CmdBuf::draw_view`] and [`ShaderLayer::draw`].
+  pub fn draw_view(mut self, view: &VertexArrayView<B>) -> Result<Self, B::Err> {
+    if self.defer(DeferredCall::View(view.to_owned_view())) {
+      return Ok(self);
+    }
+
+    self.bindings.rebind_stolen(&self.state)?;
+    self.state.cmd_buf.draw_view(view)?;
+    Ok(self)
+  }
+
+  /// Time the commands recorded by `f` on the device, e.g. the draws of a pass.
+  ///
+  /// The timing is reported as `name` by [`Device::timings`](crate::device::Device::timings) once the device has
+  /// executed the commands. Sorted draws are only recorded when the layers are done, in another order, so they cannot
+  /// be timed: when sorting draws, `f` is recorded without timing it, and a warning is logged.
+  #[cfg(feature = "ext-profiler")]
+  pub fn timed<F>(self, name: impl Into<String>, f: F) -> Result<Self, B::Err>
+  where
+    B: BackendProfiler + 'static,
+    F: FnOnce(Self) -> Result<Self, B::Err>,
+  {
+    let name = name.into();
+
+    if self.sorted_render_targets.is_some() {
+      warn!(
+        self.state.backend,
+        "sorted draws cannot be timed; {name} is recorded without timing it"
+      );
+      return f(self);
+    }
+
+    let query = self.state.cmd_buf.begin_timer_query()?;
+    let layer = f(self)?;
+    layer.state.cmd_buf.end_timer_query(name, query)?;
+    Ok(layer)
+  }
+
+  /// Defer a draw, if sorting draws; return whether the draw was deferred.
+  fn defer(&mut self, call: DeferredCall<B>) -> bool {
+    let (Some(render_targets), Some(draws)) =
+      (&self.sorted_render_targets, &mut self.state.sorted_draws)
+    else {
+      return false;
+    };
+
+    let mut textures: Vec<_> = self
+      .deferred
+      .textures
+      .iter()
+      .map(|(texture, binding_point, stage)| {
+        (texture.scarce_index(), binding_point.scarce_index(), *stage)
+      })
+      .collect();
+    textures.sort();
+
+    let mut uniform_buffers: Vec<_> = self
+      .deferred
+      .uniform_buffers
+      .iter()
+      .map(|(uniform_buffer, binding_point)| {
+        (uniform_buffer.scarce_index(), binding_point.scarce_index())
+      })
+      .collect();
+    uniform_buffers.sort();
+
+    draws.push(DeferredDraw {
+      key: DrawKey {
+        pass: self.state.sorted_pass,
+        shader: self.shader.id(),
+        textures,
+        uniform_buffers,
+      },
+      render_targets: render_targets.clone(),
+      shader: self.shader.clone(),
+      bindings: self.deferred.scarce_clone(),
+      pipeline: self.state.sorted_pipeline.clone(),
+      call,
+    });
+
+    true
+  }
+
+  /// Close the layer, giving its units back to the pools of the device.
+  ///
+  /// Units stolen by other resources are not given back, since they are not the layer’s anymore.
+  pub fn done(self) -> Result<RenderTargetsLayer<B>, B::Err> {
+    self.bindings.release(&self.state)?;
+
+    Ok(RenderTargetsLayer {
+      state: self.state,
+      sorted_render_targets: self.sorted_render_targets,
+    })
+  }
+}
+
+macro_rules! impl_conditional_layers {
+  ($($ty:ident),*) => {
+    $(
+      impl<B> $ty<B>
+      where
+        B: Backend,
+      {
+        /// Record a part of the layer only if `condition` holds, e.g. a debug overlay or an optional pass.
+        ///
+        /// `f` records the part and gives the layer back; it is not called at all if `condition` doesn’t hold.
+        pub fn when<F>(self, condition: bool, f: F) -> Result<Self, B::Err>
+        where
+          F: FnOnce(Self) -> Result<Self, B::Err>,
+        {
+          if condition {
+            f(self)
+          } else {
+            Ok(self)
+          }
+        }
+
+        /// Same as [`when`](Self::when), with the condition queried from the layer itself by `predicate`, e.g. from
+        /// its command buffer.
+        ///
+        /// `predicate` is called right away, once: if the draws of `f` are frozen with [`Layers::freeze`],
+        /// [`RecordedLayers::submit`] records them without calling `predicate` again.
+        pub fn when_with<P, F>(self, predicate: P, f: F) -> Result<Self, B::Err>
+        where
+          P: FnOnce(&Self) -> bool,
+          F: FnOnce(Self) -> Result<Self, B::Err>,
+        {
+          let condition = predicate(&self);
+          self.when(condition, f)
+        }
+      }
+    )*
+  };
+}
+
+impl_conditional_layers!(Layers, RenderTargetsLayer, ShaderLayer);
+
+mod private {
+  use piksels_backend::Backend;
+
+  use crate::pipeline::PipelineStateDiff;
+
+  /// Plumbing of [`LayerCommons`](super::LayerCommons), not to be implemented outside of this module.
+  pub trait Layer<B>: Sized
+  where
+    B: Backend,
+  {
+    /// Change the pipeline state, or defer the change if sorting draws.
+    fn set_pipeline(&mut self, diff: PipelineStateDiff) -> Result<(), B::Err>;
+
+    /// Start a scope for the resources bound by a group.
+    fn open_group(&mut self);
+
+    /// End the scope of the last opened group, giving the units of its resources back to the pools.
+    fn close_group(&mut self) -> Result<(), B::Err>;
+  }
+}
+
+/// Operations common to all layers.
+pub trait LayerCommons<B>: private::Layer<B>
+where
+  B: Backend,
+{
+  fn blending(mut self, value: BlendingMode) -> Result<Self, B::Err> {
+    self.set_pipeline(PipelineStateDiff {
+      blending: Some(value),
+      ..PipelineStateDiff::default()
+    })?;
+    Ok(self)
+  }
+
+  fn depth_test(mut self, value: DepthTest) -> Result<Self, B::Err> {
+    self.set_pipeline(PipelineStateDiff {
+      depth_test: Some(value),
+      ..PipelineStateDiff::default()
+    })?;
+    Ok(self)
+  }
+
+  fn depth_write(mut self, value: DepthWrite) -> Result<Self, B::Err> {
+    self.set_pipeline(PipelineStateDiff {
+      depth_write: Some(value),
+      ..PipelineStateDiff::default()
+    })?;
+    Ok(self)
+  }
+
+  fn stencil_test(mut self, value: StencilTest) -> Result<Self, B::Err> {
+    self.set_pipeline(PipelineStateDiff {
+      stencil_test: Some(value),
+      ..PipelineStateDiff::default()
+    })?;
+    Ok(self)
+  }
+
+  fn face_culling(mut self, value: FaceCulling) -> Result<Self, B::Err> {
+    self.set_pipeline(PipelineStateDiff {
+      face_culling: Some(value),
+      ..PipelineStateDiff::default()
+    })?;
+    Ok(self)
+  }
+
+  fn viewport(mut self, value: Viewport) -> Result<Self, B::Err> {
+    self.set_pipeline(PipelineStateDiff {
+      viewport: Some(value),
+      ..PipelineStateDiff::default()
+    })?;
+    Ok(self)
+  }
+
+  fn scissor(mut self, value: Scissor) -> Result<Self, B::Err> {
+    self.set_pipeline(PipelineStateDiff {
+      scissor: Some(value),
+      ..PipelineStateDiff::default()
+    })?;
+    Ok(self)
+  }
+
+  fn clear_color(mut self, value: RGBA32F) -> Result<Self, B::Err> {
+    self.set_pipeline(PipelineStateDiff {
+      clear_color: Some(value),
+      ..PipelineStateDiff::default()
+    })?;
+    Ok(self)
+  }
+
+  fn clear_depth(mut self, value: f32) -> Result<Self, B::Err> {
+    self.set_pipeline(PipelineStateDiff {
+      clear_depth: Some(value),
+      ..PipelineStateDiff::default()
+    })?;
+    Ok(self)
+  }
+
+  fn srgb(mut self, value: bool) -> Result<Self, B::Err> {
+    self.set_pipeline(PipelineStateDiff {
+      srgb: Some(value),
+      ..PipelineStateDiff::default()
+    })?;
+    Ok(self)
+  }
+
+  fn primitive_restart(mut self, value: bool) -> Result<Self, B::Err> {
+    self.set_pipeline(PipelineStateDiff {
+      primitive_restart: Some(value),
+      ..PipelineStateDiff::default()
+    })?;
+    Ok(self)
+  }
+
+  /// Open a group, scoping the resources bound in it; close it with [`GroupLayer::done`].
+  fn group(mut self) -> GroupLayer<B, Self> {
+    self.open_group();
+
+    GroupLayer {
+      parent: self,
+      _phantom: PhantomData,
+    }
+  }
+}
+
+impl<B> private::Layer<B> for Layers<B>
+where
+  B: Backend,
+{
+  fn set_pipeline(&mut self, diff: PipelineStateDiff) -> Result<(), B::Err> {
+    self.state.set_pipeline(diff)
+  }
+
+  fn open_group(&mut self) {}
+
+  fn close_group(&mut self) -> Result<(), B::Err> {
+    Ok(())
+  }
+}
+
+impl<B> private::Layer<B> for RenderTargetsLayer<B>
+where
+  B: Backend,
+{
+  fn set_pipeline(&mut self, diff: PipelineStateDiff) -> Result<(), B::Err> {
+    self.state.set_pipeline(diff)
+  }
+
+  fn open_group(&mut self) {}
+
+  fn close_group(&mut self) -> Result<(), B::Err> {
+    Ok(())
+  }
+}
+
+impl<B> private::Layer<B> for ShaderLayer<B>
+where
+  B: Backend,
+{
+  fn set_pipeline(&mut self, diff: PipelineStateDiff) -> Result<(), B::Err> {
+    self.state.set_pipeline(diff)
+  }
+
+  fn open_group(&mut self) {
+    let mark = if self.sorted_render_targets.is_some() {
+      (
+        self.deferred.textures.len(),
+        self.deferred.uniform_buffers.len(),
+      )
+    } else {
+      (
+        self.bindings.textures.len(),
+        self.bindings.uniform_buffers.len(),
+      )
+    };
+
+    self.groups.push(mark);
+  }
+
+  fn close_group(&mut self) -> Result<(), B::Err> {
+    let Some((textures, uniform_buffers)) = self.groups.pop() else {
+      return Ok(());
+    };
+
+    if self.sorted_render_targets.is_some() {
+      self.deferred.textures.truncate(textures);
+      self.deferred.uniform_buffers.truncate(uniform_buffers);
+      return Ok(());
+    }
+
+    let group = LayerBindings {
+      textures: self.bindings.textures.split_off(textures),
+      uniform_buffers: self.bindings.uniform_buffers.split_off(uniform_buffers),
+    };
+    group.release(&self.state)
+  }
+}
+
+impl<B, Parent> private::Layer<B> for GroupLayer<B, Parent>
+where
+  B: Backend,
+  Parent: LayerCommons<B>,
+{
+  fn set_pipeline(&mut self, diff: PipelineStateDiff) -> Result<(), B::Err> {
+    self.parent.set_pipeline(diff)
+  }
+
+  fn open_group(&mut self) {
+    self.parent.open_group();
+  }
+
+  fn close_group(&mut self) -> Result<(), B::Err> {
+    self.parent.close_group()
+  }
+}
+
+impl<B> LayerCommons<B> for Layers<B> where B: Backend {}
+
+impl<B> LayerCommons<B> for RenderTargetsLayer<B> where B: Backend {}
+
+impl<B> LayerCommons<B> for ShaderLayer<B> where B: Backend {}
+
+impl<B, Parent> LayerCommons<B> for GroupLayer<B, Parent>
+where
+  B: Backend,
+  Parent: LayerCommons<B>,
+{
+}
+
+/// Group of a layer, opened with [`LayerCommons::group`].
+///
+/// Groups of shader layers bind resources and draw the same way as their shader layer (see [`LayerShader`]); the
+/// units of the resources bound in the group are given back to the pools when the group is done. The pipeline state
+/// changed in a group is not restored when the group is done.
+#[derive(Debug)]
+pub struct GroupLayer<B, Parent>
+where
+  B: Backend,
+{
+  parent: Parent,
+  _phantom: PhantomData<B>,
+}
+
+impl<B, Parent> GroupLayer<B, Parent>
+where
+  B: Backend,
+  Parent: LayerCommons<B>,
+{
+  /// Close the group, giving the units of the resources bound in it back to the pools of the device.
+  pub fn done(mut self) -> Result<Parent, B::Err> {
+    self.parent.close_group()?;
+    Ok(self.parent)
+  }
+}
+
+/// Operations of layers drawing with a shader: shader layers and their groups.
+pub trait LayerShader<B>: LayerCommons<B>
+where
+  B: Backend,
+{
+  /// See [`ShaderLayer::uniform`].
+  fn uniform<T>(self, uniform: &Uniform<B>, value: &T) -> Result<Self, B::Err>
+  where
+    T: ?Sized + UniformValue;
+
+  /// See [`ShaderLayer::texture`].
+  fn texture(
+    self,
+    texture: &Texture<B>,
+    binding_point: &ShaderTextureBindingPoint<B>,
+  ) -> Result<Self, B::Err> {
+    self.stage_texture(texture, binding_point, Stage::Fragment)
+  }
+
+  /// See [`ShaderLayer::stage_texture`].
+  fn stage_texture(
+    self,
+    texture: &Texture<B>,
+    binding_point: &ShaderTextureBindingPoint<B>,
+    stage: Stage,
+  ) -> Result<Self, B::Err>;
+
+  /// See [`ShaderLayer::uniform_buffer`].
+  fn uniform_buffer(
+    self,
+    uniform_buffer: &UniformBuffer<B>,
+    binding_point: &ShaderUniformBufferBindingPoint<B>,
+  ) -> Result<Self, B::Err>;
+
+  /// See [`ShaderLayer::draw`].
+  fn draw(self, vertex_array: &VertexArray<B>) -> Result<Self, B::Err>;
+
+  /// See [`ShaderLayer::draw_view`].
+  fn draw_view(self, view: &VertexArrayView<B>) -> Result<Self, B::Err>;
+}
+
+impl<B> LayerShader<B> for ShaderLayer<B>
+where
+  B: Backend,
+{
+  fn uniform<T>(self, uniform: &Uniform<B>, value: &T) -> Result<Self, B::Err>
+  where
+    T: ?Sized + UniformValue,
+  {
+    ShaderLayer::uniform(self, uniform, value)
+  }
+
+  fn stage_texture(
+    self,
+    texture: &Texture<B>,
+    binding_point: &ShaderTextureBindingPoint<B>,
+    stage: Stage,
+  ) -> Result<Self, B::Err> {
+    ShaderLayer::stage_texture(self, texture, binding_point, stage)
+  }
+
+  fn uniform_buffer(
+    self,
+    uniform_buffer: &UniformBuffer<B>,
+    binding_point: &ShaderUniformBufferBindingPoint<B>,
+  ) -> Result<Self, B::Err> {
+    ShaderLayer::uniform_buffer(self, uniform_buffer, binding_point)
+  }
+
+  fn draw(self, vertex_array: &VertexArray<B>) -> Result<Self, B::Err> {
+    ShaderLayer::draw(self, vertex_array)
+  }
+
+  fn draw_view(self, view: &VertexArrayView<B>) -> Result<Self, B::Err> {
+    ShaderLayer::draw_view(self, view)
+  }
+}
+
+impl<B, Parent> LayerShader<B> for GroupLayer<B, Parent>
+where
+  B: Backend,
+  Parent: LayerShader<B>,
+{
+  fn uniform<T>(mut self, uniform: &Uniform<B>, value: &T) -> Result<Self, B::Err>
+  where
+    T: ?Sized + UniformValue,
+  {
+    self.parent = self.parent.uniform(uniform, value)?;
+    Ok(self)
+  }
+
+  fn stage_texture(
+    mut self,
+    texture: &Texture<B>,
+    binding_point: &ShaderTextureBindingPoint<B>,
+    stage: Stage,
+  ) -> Result<Self, B::Err> {
+    self.parent = self.parent.stage_texture(texture, binding_point, stage)?;
+    Ok(self)
+  }
+
+  fn uniform_buffer(
+    mut self,
+    uniform_buffer: &UniformBuffer<B>,
+    binding_point: &ShaderUniformBufferBindingPoint<B>,
+  ) -> Result<Self, B::Err> {
+    self.parent = self.parent.uniform_buffer(uniform_buffer, binding_point)?;
+    Ok(self)
+  }
+
+  fn draw(mut self, vertex_array: &VertexArray<B>) -> Result<Self, B::Err> {
+    self.parent = self.parent.draw(vertex_array)?;
+    Ok(self)
+  }
+
+  fn draw_view(mut self, view: &VertexArrayView<B>) -> Result<Self, B::Err> {
+    self.parent = self.parent.draw_view(view)?;
+    Ok(self)
+  }
+}
@@ -1,7 +1,39 @@
+pub mod binding_report;
+pub mod budget;
+pub mod cache;
 pub mod cmd_buf;
+pub mod convention;
+pub mod deferred;
 pub mod device;
+pub mod diagnostic;
+pub mod error_policy;
+pub mod frame;
+pub mod frame_loop;
+pub mod gc;
+#[cfg(feature = "image")]
+pub mod image;
+pub mod interface;
+pub mod layers;
+pub mod name;
+pub mod pipeline;
+pub mod preprocessor;
+#[cfg(feature = "ext-profiler")]
+pub mod profiler;
+#[cfg(feature = "ext-readback")]
+pub mod readback;
 pub mod render_targets;
 pub mod shader;
+pub mod shapes;
+pub mod storage_buffer;
+pub mod streaming;
 pub mod swap_chain;
 pub mod texture;
+#[cfg(feature = "ext-trace")]
+pub mod trace;
+pub mod transient;
+#[cfg(feature = "glsl-translate")]
+pub mod translate;
+pub mod units;
 pub mod vertex_array;
+#[cfg(feature = "ext-video")]
+pub mod video;
@@ -0,0 +1,95 @@
+//! Resource names.
+//!
+//! Resources can be named with [`Device::set_name`](crate::device::Device::set_name). Names are forwarded to the
+//! backend, which can use them as debug labels — e.g. in graphics debuggers — and are kept by the device until the
+//! resource is dropped, so that debugging output and error messages can refer to resources by name rather than by
+//! [`ResourceId`].
+
+use piksels_backend::Backend;
+
+use crate::{
+  cache::ResourceId, cmd_buf::CmdBuf, render_targets::RenderTargets, shader::Shader,
+  texture::Texture,
+};
+
+/// Kind of resource that can be named.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum NamedKind {
+  CmdBuf,
+  RenderTargets,
+  Shader,
+  Texture,
+}
+
+/// Resources that can be named.
+pub trait Named<B>
+where
+  B: Backend,
+{
+  const KIND: NamedKind;
+
+  fn resource_id(&self) -> ResourceId<B>;
+
+  /// Forward the name to the backend.
+  fn label(&self, name: &str) -> Result<(), B::Err>;
+}
+
+impl<B> Named<B> for CmdBuf<B>
+where
+  B: Backend,
+{
+  const KIND: NamedKind = NamedKind::CmdBuf;
+
+  fn resource_id(&self) -> ResourceId<B> {
+    self.id()
+  }
+
+  fn label(&self, name: &str) -> Result<(), B::Err> {
+    B::set_cmd_buf_name(&self.raw, name)
+  }
+}
+
+impl<B> Named<B> for RenderTargets<B>
+where
+  B: Backend,
+{
+  const KIND: NamedKind = NamedKind::RenderTargets;
+
+  fn resource_id(&self) -> ResourceId<B> {
+    self.id()
+  }
+
+  fn label(&self, name: &str) -> Result<(), B::Err> {
+    B::set_render_targets_name(&self.raw, name)
+  }
+}
+
+impl<B> Named<B> for Shader<B>
+where
+  B: Backend,
+{
+  const KIND: NamedKind = NamedKind::Shader;
+
+  fn resource_id(&self) -> ResourceId<B> {
+    self.id()
+  }
+
+  fn label(&self, name: &str) -> Result<(), B::Err> {
+    B::set_shader_name(&self.raw, name)
+  }
+}
+
+impl<B> Named<B> for Texture<B>
+where
+  B: Backend,
+{
+  const KIND: NamedKind = NamedKind::Texture;
+
+  fn resource_id(&self) -> ResourceId<B> {
+    self.id()
+  }
+
+  fn label(&self, name: &str) -> Result<(), B::Err> {
+    B::set_texture_name(&self.raw, name)
+  }
+}
@@ -0,0 +1,205 @@
+//! Pipeline state.
+//!
+//! The pipeline state gathers all the variables a command buffer can set and that affect how draws are performed
+//! (blending, depth test, viewport, etc.).
+
+use piksels_backend::{
+  blending::BlendingMode,
+  color::RGBA32F,
+  depth_stencil::{Comparison, DepthTest, DepthWrite, StencilTest},
+  face_culling::FaceCulling,
+  scissor::Scissor,
+  texture::Sampling,
+  viewport::Viewport,
+  Backend,
+};
+
+use crate::cache::ResourceId;
+
+/// Full pipeline state.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PipelineState {
+  pub blending: BlendingMode,
+  pub depth_test: DepthTest,
+  pub depth_write: DepthWrite,
+  pub stencil_test: StencilTest,
+  pub face_culling: FaceCulling,
+  pub viewport: Viewport,
+  pub scissor: Scissor,
+  pub clear_color: RGBA32F,
+  pub clear_depth: f32,
+  pub srgb: bool,
+  pub primitive_restart: bool,
+}
+
+impl Default for PipelineState {
+  /// Default pipeline state.
+  ///
+  /// - No blending.
+  /// - Depth test enabled with [`Comparison::Less`], and depth write enabled.
+  /// - No stencil test.
+  /// - No face culling.
+  /// - Whole viewport, no scissor.
+  /// - Clear color set to transparent black and clear depth set to `1.`.
+  /// - No sRGB conversion.
+  /// - No primitive restart.
+  fn default() -> Self {
+    Self {
+      blending: BlendingMode::Off,
+      depth_test: DepthTest::On(Comparison::Less),
+      depth_write: DepthWrite::On,
+      stencil_test: StencilTest::Off,
+      face_culling: FaceCulling::Off,
+      viewport: Viewport::Whole,
+      scissor: Scissor::Off,
+      clear_color: RGBA32F::new(0., 0., 0., 0.),
+      clear_depth: 1.,
+      srgb: false,
+      primitive_restart: false,
+    }
+  }
+}
+
+/// Defaults of a device, set with [`Device::set_defaults`](crate::device::Device::set_defaults).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PipelineDefaults {
+  /// Pipeline state new command buffers start with, and [`CmdBuf::clear_state`](crate::cmd_buf::CmdBuf::clear_state)
+  /// resets to.
+  pub state: PipelineState,
+
+  /// Sampling of textures created with
+  /// [`Device::new_texture_with_defaults`](crate::device::Device::new_texture_with_defaults).
+  pub sampling: Sampling,
+}
+
+/// Difference between two pipeline states.
+///
+/// Every variable set to [`Some`] has to be changed to reach the target pipeline state.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PipelineStateDiff {
+  pub blending: Option<BlendingMode>,
+  pub depth_test: Option<DepthTest>,
+  pub depth_write: Option<DepthWrite>,
+  pub stencil_test: Option<StencilTest>,
+  pub face_culling: Option<FaceCulling>,
+  pub viewport: Option<Viewport>,
+  pub scissor: Option<Scissor>,
+  pub clear_color: Option<RGBA32F>,
+  pub clear_depth: Option<f32>,
+  pub srgb: Option<bool>,
+  pub primitive_restart: Option<bool>,
+}
+
+impl From<PipelineState> for PipelineStateDiff {
+  fn from(state: PipelineState) -> Self {
+    Self {
+      blending: Some(state.blending),
+      depth_test: Some(state.depth_test),
+      depth_write: Some(state.depth_write),
+      stencil_test: Some(state.stencil_test),
+      face_culling: Some(state.face_culling),
+      viewport: Some(state.viewport),
+      scissor: Some(state.scissor),
+      clear_color: Some(state.clear_color),
+      clear_depth: Some(state.clear_depth),
+      srgb: Some(state.srgb),
+      primitive_restart: Some(state.primitive_restart),
+    }
+  }
+}
+
+/// Snapshot of the pipeline state and of the bound shader and render targets, as set by a command buffer.
+///
+/// Take one with [`CmdBuf::snapshot`](crate::cmd_buf::CmdBuf::snapshot), and restore it with
+/// [`CmdBuf::restore`](crate::cmd_buf::CmdBuf::restore) — e.g. to render an overlay without clobbering the state of
+/// the host application. What the command buffer didn’t set is left as [`None`], and is not restored.
+#[derive(Debug)]
+pub struct PipelineSnapshot<B>
+where
+  B: Backend,
+{
+  pub state: PipelineStateDiff,
+  pub shader: Option<ResourceId<B>>,
+  pub render_targets: Option<ResourceId<B>>,
+}
+
+impl<B> Clone for PipelineSnapshot<B>
+where
+  B: Backend,
+{
+  fn clone(&self) -> Self {
+    Self {
+      state: self.state.clone(),
+      shader: self.shader.clone(),
+      render_targets: self.render_targets.clone(),
+    }
+  }
+}
+
+impl PipelineStateDiff {
+  /// Check whether the diff is empty, i.e. nothing has to be changed.
+  pub fn is_empty(&self) -> bool {
+    *self == Self::default()
+  }
+
+  /// Set the variables set in `other`, leaving the other ones as they are.
+  pub(crate) fn merge(&mut self, other: Self) {
+    self.blending = other.blending.or(self.blending);
+    self.depth_test = other.depth_test.or(self.depth_test);
+    self.depth_write = other.depth_write.or(self.depth_write);
+    self.stencil_test = other.stencil_test.or(self.stencil_test);
+    self.face_culling = other.face_culling.or(self.face_culling);
+    self.viewport = other.viewport.or(self.viewport);
+    self.scissor = other.scissor.or(self.scissor);
+    self.clear_color = other.clear_color.or(self.clear_color.take());
+    self.clear_depth = other.clear_depth.or(self.clear_depth);
+    self.srgb = other.srgb.or(self.srgb);
+    self.primitive_restart = other.primitive_restart.or(self.primitive_restart);
+  }
+
+  /// Set the variables unset here but set in `set` to their `defaults`.
+  pub(crate) fn reset_unset(&mut self, set: &Self, defaults: &PipelineState) {
+    fn reset<T: Clone>(value: &mut Option<T>, set: &Option<T>, default: &T) {
+      if value.is_none() && set.is_some() {
+        *value = Some(default.clone());
+      }
+    }
+
+    reset(&mut self.blending, &set.blending, &defaults.blending);
+    reset(&mut self.depth_test, &set.depth_test, &defaults.depth_test);
+    reset(
+      &mut self.depth_write,
+      &set.depth_write,
+      &defaults.depth_write,
+    );
+    reset(
+      &mut self.stencil_test,
+      &set.stencil_test,
+      &defaults.stencil_test,
+    );
+    reset(
+      &mut self.face_culling,
+      &set.face_culling,
+      &defaults.face_culling,
+    );
+    reset(&mut self.viewport, &set.viewport, &defaults.viewport);
+    reset(&mut self.scissor, &set.scissor, &defaults.scissor);
+    reset(
+      &mut self.clear_color,
+      &set.clear_color,
+      &defaults.clear_color,
+    );
+    reset(
+      &mut self.clear_depth,
+      &set.clear_depth,
+      &defaults.clear_depth,
+    );
+    reset(&mut self.srgb, &set.srgb, &defaults.srgb);
+    reset(
+      &mut self.primitive_restart,
+      &set.primitive_restart,
+      &defaults.primitive_restart,
+    );
+  }
+}
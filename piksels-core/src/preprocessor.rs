@@ -0,0 +1,199 @@
+//! Shader preprocessing.
+//!
+//! GLSL has no module system: shared code must be pasted into every stage using it. A [`ShaderLibrary`] is a virtual
+//! filesystem of shader snippets, resolving `#include "path"` directives against the snippets registered in it, along
+//! with `#define`s injected at the top of every stage (right after `#version`, if present).
+//!
+//! ```ignore
+//! let library = ShaderLibrary::new()
+//!   .with_file("lighting.glsl", LIGHTING_SRC)
+//!   .with_define("MAX_LIGHTS", "16");
+//! let sources = library.preprocess_sources(
+//!   &ShaderSources::default()
+//!     .vertex(VS_SRC)
+//!     .fragment(FS_SRC),
+//! )?;
+//! let shader = device.new_shader(sources.sources())?;
+//! ```
+
+use std::collections::HashMap;
+
+use piksels_backend::{
+  error::Error,
+  shader::{inject_defines, ShaderSources},
+};
+
+/// Virtual filesystem of shader snippets, and `#define`s to inject.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ShaderLibrary {
+  files: HashMap<String, String>,
+  defines: Vec<(String, String)>,
+}
+
+impl ShaderLibrary {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Register `src` as the file at `path`, replacing any previous file at that path.
+  pub fn with_file(mut self, path: impl Into<String>, src: impl Into<String>) -> Self {
+    self.files.insert(path.into(), src.into());
+    self
+  }
+
+  /// Inject `#define name value` in every preprocessed stage.
+  ///
+  /// Defines are injected in the order they were added.
+  pub fn with_define(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+    self.defines.push((name.into(), value.into()));
+    self
+  }
+
+  pub fn file(&self, path: &str) -> Option<&str> {
+    self.files.get(path).map(String::as_str)
+  }
+
+  pub fn defines(&self) -> impl Iterator<Item = (&str, &str)> {
+    self
+      .defines
+      .iter()
+      .map(|(name, value)| (name.as_str(), value.as_str()))
+  }
+
+  /// Preprocess the source of a single stage.
+  ///
+  /// Includes are resolved recursively; including a file from itself, directly or not, is an error.
+  pub fn preprocess(&self, src: &str) -> Result<String, Error> {
+    self.preprocess_with_defines(src, &[])
+  }
+
+  /// Preprocess every stage of `sources`; absent stages stay absent.
+  ///
+  /// The defines of `sources` are injected after the ones of the library.
+  pub fn preprocess_sources(&self, sources: &ShaderSources) -> Result<PreprocessedSources, Error> {
+    let preprocess = |src: &str| {
+      if src.is_empty() {
+        Ok(String::new())
+      } else {
+        self.preprocess_with_defines(src, sources.defines())
+      }
+    };
+
+    Ok(PreprocessedSources {
+      tess_ctrl_stage: preprocess(sources.tess_ctrl_stage())?,
+      tess_eval_stage: preprocess(sources.tess_eval_stage())?,
+      vertex_stage: preprocess(sources.vertex_stage())?,
+      geometry_stage: preprocess(sources.geometry_stage())?,
+      fragment_stage: preprocess(sources.fragment_stage())?,
+      compute_stage: preprocess(sources.compute_stage())?,
+    })
+  }
+
+  fn preprocess_with_defines(&self, src: &str, defines: &[(&str, &str)]) -> Result<String, Error> {
+    let mut output = String::with_capacity(src.len());
+
+    for line in src.lines() {
+      self.preprocess_line(line, &mut Vec::new(), &mut output)?;
+    }
+
+    Ok(inject_defines(
+      &output,
+      self.defines().chain(defines.iter().copied()),
+    ))
+  }
+
+  /// Preprocess a line, `includes` being the stack of files currently being included.
+  fn preprocess_line<'a>(
+    &'a self,
+    line: &str,
+    includes: &mut Vec<&'a str>,
+    output: &mut String,
+  ) -> Result<(), Error> {
+    let Some(directive) = line.trim_start().strip_prefix("#include") else {
+      output.push_str(line);
+      output.push('\n');
+      return Ok(());
+    };
+
+    let path = directive
+      .trim()
+      .strip_prefix('"')
+      .and_then(|path| path.strip_suffix('"'))
+      .ok_or_else(|| Error::ShaderPreprocessing {
+        reason: format!("malformed include directive: {}", line.trim()),
+      })?;
+
+    let (path, src) = self
+      .files
+      .get_key_value(path)
+      .ok_or_else(|| Error::ShaderPreprocessing {
+        reason: format!("unknown include file {path:?}"),
+      })?;
+
+    if includes.contains(&path.as_str()) {
+      let cycle = includes
+        .iter()
+        .copied()
+        .chain(Some(path.as_str()))
+        .collect::<Vec<_>>()
+        .join(" -> ");
+
+      return Err(Error::ShaderPreprocessing {
+        reason: format!("include cycle: {cycle}"),
+      });
+    }
+
+    includes.push(path);
+    for line in src.lines() {
+      self.preprocess_line(line, includes, output)?;
+    }
+    includes.pop();
+
+    Ok(())
+  }
+}
+
+/// Preprocessed sources of a shader program.
+///
+/// Defines are already injected in the stages. Use [`PreprocessedSources::sources`] to create the shader.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PreprocessedSources {
+  pub(crate) tess_ctrl_stage: String,
+  pub(crate) tess_eval_stage: String,
+  pub(crate) vertex_stage: String,
+  pub(crate) geometry_stage: String,
+  pub(crate) fragment_stage: String,
+  pub(crate) compute_stage: String,
+}
+
+impl PreprocessedSources {
+  /// Inject the defines of `sources` in its stages, without resolving includes.
+  pub(crate) fn specialize(sources: &ShaderSources) -> Self {
+    let specialize = |src: &str| {
+      if src.is_empty() {
+        String::new()
+      } else {
+        inject_defines(src, sources.defines().iter().copied())
+      }
+    };
+
+    Self {
+      tess_ctrl_stage: specialize(sources.tess_ctrl_stage()),
+      tess_eval_stage: specialize(sources.tess_eval_stage()),
+      vertex_stage: specialize(sources.vertex_stage()),
+      geometry_stage: specialize(sources.geometry_stage()),
+      fragment_stage: specialize(sources.fragment_stage()),
+      compute_stage: specialize(sources.compute_stage()),
+    }
+  }
+
+  pub fn sources(&self) -> ShaderSources<'_> {
+    ShaderSources::default()
+      .tess_ctrl(&self.tess_ctrl_stage)
+      .tess_eval(&self.tess_eval_stage)
+      .vertex(&self.vertex_stage)
+      .geometry(&self.geometry_stage)
+      .fragment(&self.fragment_stage)
+      .compute(&self.compute_stage)
+  }
+}
@@ -0,0 +1,99 @@
+//! GPU timings.
+//!
+//! Sections of command buffers timed with [`CmdBuf::timed`](crate::cmd_buf::CmdBuf::timed), or of layers with
+//! [`ShaderLayer::timed`](crate::layers::ShaderLayer::timed), are wrapped in timer queries. Their results become
+//! available once the device has executed the timed commands, and are collected with
+//! [`Device::timings`](crate::device::Device::timings).
+
+use std::{
+  fmt::{self, Debug},
+  time::Duration,
+};
+
+use piksels_backend::{extension::profiler::BackendProfiler, Backend};
+
+/// Time the device spent executing a named section of recorded commands.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Timing {
+  pub name: String,
+  pub duration: Duration,
+}
+
+/// A timer query, dropped with the backend once done.
+#[derive(Debug)]
+pub(crate) struct TimerQuery<B>
+where
+  B: BackendProfiler,
+{
+  pub(crate) raw: B::TimerQuery,
+}
+
+impl<B> Drop for TimerQuery<B>
+where
+  B: BackendProfiler,
+{
+  fn drop(&mut self) {
+    B::drop_timer_query(&self.raw);
+  }
+}
+
+/// Timer queries whose backend is only known when they are created.
+trait ElapsedTime<B>
+where
+  B: Backend,
+{
+  fn elapsed(&self) -> Result<Option<Duration>, B::Err>;
+}
+
+impl<B> ElapsedTime<B> for TimerQuery<B>
+where
+  B: BackendProfiler,
+{
+  fn elapsed(&self) -> Result<Option<Duration>, B::Err> {
+    B::timer_query_elapsed(&self.raw)
+  }
+}
+
+/// An ended timer query whose result might not be known yet.
+pub(crate) struct PendingTiming<B>
+where
+  B: Backend,
+{
+  name: String,
+  query: Box<dyn ElapsedTime<B> + Send>,
+}
+
+impl<B> Debug for PendingTiming<B>
+where
+  B: Backend,
+{
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("PendingTiming")
+      .field("name", &self.name)
+      .finish_non_exhaustive()
+  }
+}
+
+impl<B> PendingTiming<B>
+where
+  B: Backend,
+{
+  pub(crate) fn new(name: String, query: TimerQuery<B>) -> Self
+  where
+    B: BackendProfiler + 'static,
+  {
+    Self {
+      name,
+      query: Box::new(query),
+    }
+  }
+
+  /// Get the timing, if known.
+  pub(crate) fn poll(&self) -> Result<Option<Timing>, B::Err> {
+    let timing = self.query.elapsed()?.map(|duration| Timing {
+      name: self.name.clone(),
+      duration,
+    });
+    Ok(timing)
+  }
+}
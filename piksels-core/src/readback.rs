@@ -0,0 +1,64 @@
+//! Readbacks of render targets.
+
+use piksels_backend::{
+  extension::readback::BackendReadback,
+  pixel::{ChannelBits, Format},
+  texture::Storage,
+};
+
+/// A pending readback of a color attachment.
+///
+/// Use [`CmdBuf::read_color_attachment`](crate::cmd_buf::CmdBuf::read_color_attachment) to create one.
+#[derive(Debug)]
+pub struct Readback<B>
+where
+  B: BackendReadback,
+{
+  raw: B::Readback,
+  storage: Storage,
+}
+
+impl<B> Readback<B>
+where
+  B: BackendReadback,
+{
+  pub(crate) fn from_raw(raw: B::Readback, storage: Storage) -> Self {
+    Self { raw, storage }
+  }
+
+  /// Storage of the render targets the texels are read from.
+  pub fn storage(&self) -> Storage {
+    self.storage
+  }
+
+  /// Format of the texels read back.
+  pub fn format(&self) -> Format {
+    Format::RGBA(
+      ChannelBits::Eight,
+      ChannelBits::Eight,
+      ChannelBits::Eight,
+      ChannelBits::Eight,
+    )
+  }
+
+  /// Check whether the readback is complete, without blocking.
+  pub fn is_ready(&self) -> Result<bool, B::Err> {
+    B::is_readback_ready(&self.raw)
+  }
+
+  /// Wait for the readback to complete and copy its texels into `texels`.
+  ///
+  /// Rows are in the backend order (bottom row first).
+  pub fn read(&self, texels: &mut [u8]) -> Result<(), B::Err> {
+    B::readback_texels(&self.raw, texels)
+  }
+}
+
+impl<B> Drop for Readback<B>
+where
+  B: BackendReadback,
+{
+  fn drop(&mut self) {
+    B::drop_readback(&self.raw);
+  }
+}
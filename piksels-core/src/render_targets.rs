@@ -1,30 +1,379 @@
-use piksels_backend::Backend;
+use std::sync::{Arc, Mutex, Weak};
 
+#[cfg(feature = "ext-readback")]
+use piksels_backend::extension::readback::BackendReadback;
+use piksels_backend::{
+  error::Error,
+  render_targets::{AttachmentUsage, BlitFilter, BlitMask, ColorAttachmentPoint, ColorType},
+  texture::{Offset, Rect, Sampling, Size, Storage},
+  Backend, Scarce,
+};
+
+use crate::{
+  cache::{Cache, ResourceId, TrackingTag},
+  gc,
+  texture::Texture,
+};
+
+/// Handle on render targets.
+///
+/// Render targets keep a weak reference to the cache of their device, to keep it in sync when their attachments change.
+/// As other resources, they are destroyed once every handle on them is dropped.
 #[derive(Debug)]
 pub struct RenderTargets<B>
 where
   B: Backend,
 {
   pub(crate) raw: B::RenderTargets,
+  storage: Storage,
+  color_attachment_points: Arc<[ColorAttachmentPoint]>,
+  layers: Option<usize>,
+  cache: Weak<Mutex<Cache<B>>>,
+  /// Swap chain the render targets come from, along with its generation at that time.
+  swap_chain: Option<(ResourceId<B>, u64)>,
+  pub(crate) tag: TrackingTag,
+}
+
+impl<B> Drop for RenderTargets<B>
+where
+  B: Backend,
+{
+  fn drop(&mut self) {
+    let id = self.id();
+    gc::drop_handle(&self.cache, self.tag, |cache, tag| {
+      cache.drop_render_targets(&id, tag)
+    });
+  }
 }
 
 impl<B> RenderTargets<B>
 where
   B: Backend,
 {
-  pub(crate) fn from_raw(raw: B::RenderTargets) -> Self {
-    Self { raw }
+  pub(crate) fn from_raw(
+    raw: B::RenderTargets,
+    storage: Storage,
+    color_attachment_points: Arc<[ColorAttachmentPoint]>,
+    layers: Option<usize>,
+    cache: Weak<Mutex<Cache<B>>>,
+    tag: TrackingTag,
+  ) -> Self {
+    Self {
+      raw,
+      storage,
+      color_attachment_points,
+      layers,
+      cache,
+      swap_chain: None,
+      tag,
+    }
+  }
+
+  /// Another handle on the same render targets, e.g. to defer their use.
+  ///
+  /// The handle doesn’t count as a handle on the render targets: it doesn’t keep them alive.
+  pub(crate) fn scarce_clone(&self) -> Self {
+    Self {
+      raw: self.raw.scarce_clone(),
+      storage: self.storage,
+      color_attachment_points: self.color_attachment_points.clone(),
+      layers: self.layers,
+      cache: self.cache.clone(),
+      swap_chain: self.swap_chain.clone(),
+      tag: TrackingTag::default(),
+    }
+  }
+
+  pub(crate) fn with_swap_chain(mut self, id: ResourceId<B>, generation: u64) -> Self {
+    self.swap_chain = Some((id, generation));
+    self
+  }
+
+  /// Swap chain the render targets were acquired from, if any.
+  pub(crate) fn swap_chain_id(&self) -> Option<&ResourceId<B>> {
+    self.swap_chain.as_ref().map(|(id, _)| id)
+  }
+
+  pub fn id(&self) -> ResourceId<B> {
+    self.raw.scarce_index()
+  }
+
+  pub fn storage(&self) -> Storage {
+    self.storage
+  }
+
+  /// Reallocate all the attachments with a width and height of `width` and `height`, e.g. when the window is resized.
+  ///
+  /// The render targets keep their identity: anything referring to them or their attachments, such as command buffers
+  /// or depth/stencil attachments shared with other render targets, remains valid. Render targets sharing a
+  /// depth/stencil attachment must be resized to the same size. The previous content of the attachments is lost.
+  ///
+  /// See [`Storage::resized`] for the sizes each storage accepts.
+  pub fn resize(&mut self, width: u32, height: u32) -> Result<(), B::Err> {
+    let storage =
+      self
+        .storage
+        .resized(width, height)
+        .ok_or_else(|| Error::InvalidRenderTargetsResize {
+          reason: format!("{:?} cannot be resized to {width}×{height}", self.storage),
+        })?;
+
+    if let Some(point) = self
+      .color_attachment_points
+      .iter()
+      .find(|point| point.level() >= storage.levels())
+    {
+      return Err(
+        Error::InvalidRenderTargetsResize {
+          reason: format!(
+            "{:?} renders to level {}, but {storage:?} has {} levels",
+            point.name(),
+            point.level(),
+            storage.levels()
+          ),
+        }
+        .into(),
+      );
+    }
+
+    B::resize_render_targets(&self.raw, storage)?;
+    self.storage = storage;
+
+    if let Some(cache) = self.cache.upgrade() {
+      cache
+        .lock()
+        .map_err(Error::from)?
+        .resize_render_targets(&self.id(), storage);
+    }
+
+    Ok(())
+  }
+
+  /// Restrict the color attachments written to by draws to the ones at `indices`, e.g. to write only a subset of a
+  /// G-buffer during some passes. Pass all the indices to write to every attachment again.
+  ///
+  /// The active attachments are cached: setting them to the ones already active doesn’t reach the backend.
+  pub fn set_active_attachments(&self, indices: &[usize]) -> Result<(), B::Err> {
+    let mut indices = indices.to_vec();
+    indices.sort_unstable();
+    indices.dedup();
+
+    for &index in &indices {
+      self.check_color_attachment_index(index)?;
+    }
+
+    match self.cache.upgrade() {
+      Some(cache) => cache
+        .lock()
+        .map_err(Error::from)?
+        .active_attachments(&self.id())
+        .set_if_invalid(&indices, || {
+          B::set_render_targets_active_attachments(&self.raw, &indices)
+        })
+        .map(|_| ()),
+
+      None => B::set_render_targets_active_attachments(&self.raw, &indices),
+    }
+  }
+
+  /// Copy the `src_rect` region of the attachments selected by `mask` to the `dst_rect` region of `dst`, scaling with
+  /// `filter` if the regions have different sizes — e.g. to downsample a bloom chain, or to copy a depth buffer.
+  ///
+  /// Both render targets must have flat 2D storage, and rects must be 2D and fit in their storage. The storage is looked
+  /// up in the cache, so that render targets resized through another handle are checked against their current size.
+  /// Multisample render targets can be blitted only to single-sample regions of the same size, which resolves them;
+  /// depth and stencil are only copied with [`BlitFilter::Nearest`]. Empty masks are ignored.
+  pub fn blit(
+    &self,
+    dst: &RenderTargets<B>,
+    src_rect: Rect,
+    dst_rect: Rect,
+    filter: BlitFilter,
+    mask: BlitMask,
+  ) -> Result<(), B::Err> {
+    if mask.is_empty() {
+      return Ok(());
+    }
+
+    let invalid = |reason| Err(Error::InvalidBlit { reason }.into());
+
+    if filter == BlitFilter::Linear && (mask.depth || mask.stencil) {
+      return invalid(format!("{mask:?} cannot be linearly filtered"));
+    }
+
+    let src_storage = self.current_storage()?;
+    let dst_storage = dst.current_storage()?;
+    let src_size = blit_size(src_storage, src_rect)?;
+    let dst_size = blit_size(dst_storage, dst_rect)?;
+
+    if dst_storage.samples().is_some() {
+      return invalid(format!("cannot blit to multisample {dst_storage:?}"));
+    }
+
+    if src_storage.samples().is_some() && src_size != dst_size {
+      return invalid(format!(
+        "multisample {src_rect:?} must be resolved to a region of the same size, not {dst_rect:?}"
+      ));
+    }
+
+    B::blit_render_targets(&self.raw, &dst.raw, src_rect, dst_rect, filter, mask)
+  }
+
+  /// Storage of the render targets, as known by the cache.
+  fn current_storage(&self) -> Result<Storage, B::Err> {
+    let Some(cache) = self.cache.upgrade() else {
+      return Ok(self.storage);
+    };
+
+    let storage = cache
+      .lock()
+      .map_err(Error::from)?
+      .render_targets(&self.id())
+      .map_or(self.storage, |(_, (storage, _, _), _)| storage);
+    Ok(storage)
+  }
+
+  /// Render the color attachment at `index` to `texture` instead of the attachment it was created with, e.g. to
+  /// ping-pong between two textures without recreating the render targets.
+  ///
+  /// `texture` must have the same storage as the render targets; the level and layer of the attachment point are kept.
+  /// Use [`RenderTargets::detach_texture`] to render to the original attachment again.
+  pub fn attach_texture(&self, index: usize, texture: &Texture<B>) -> Result<(), B::Err> {
+    self.check_color_attachment_index(index)?;
+
+    let storage = self.current_storage()?;
+    if texture.storage() != storage {
+      return Err(
+        Error::InvalidAttachmentPoint {
+          reason: format!(
+            "texture storage is {:?}, but render targets storage is {storage:?}",
+            texture.storage()
+          ),
+        }
+        .into(),
+      );
+    }
+
+    B::attach_render_targets_texture(&self.raw, index, &texture.raw)?;
+    self.invalidate_bound_render_targets()
+  }
+
+  /// Render the color attachment at `index` to the attachment the render targets were created with again.
+  pub fn detach_texture(&self, index: usize) -> Result<(), B::Err> {
+    self.check_color_attachment_index(index)?;
+    B::detach_render_targets_texture(&self.raw, index)?;
+    self.invalidate_bound_render_targets()
+  }
+
+  fn check_color_attachment_index(&self, index: usize) -> Result<(), Error> {
+    if self
+      .color_attachment_points
+      .iter()
+      .all(|point| point.index() != index)
+    {
+      return Err(Error::InvalidAttachmentPoint {
+        reason: format!("no color attachment at index {index}"),
+      });
+    }
+
+    Ok(())
+  }
+
+  /// Changing attachments might bind the render targets in the backend.
+  fn invalidate_bound_render_targets(&self) -> Result<(), B::Err> {
+    if let Some(cache) = self.cache.upgrade() {
+      cache
+        .lock()
+        .map_err(Error::from)?
+        .invalidate_bound_render_targets();
+    }
+
+    Ok(())
+  }
+
+  /// Whether the render targets come from a swap chain that was resized since they were obtained.
+  ///
+  /// Stale render targets cannot be bound nor presented anymore.
+  pub fn is_stale(&self) -> Result<bool, B::Err> {
+    let (Some((id, generation)), Some(cache)) = (&self.swap_chain, self.cache.upgrade()) else {
+      return Ok(false);
+    };
+
+    let stale = cache
+      .lock()
+      .map_err(Error::from)?
+      .swap_chain(id)
+      .is_some_and(|(_, info, _)| info.generation != *generation);
+    Ok(stale)
+  }
+
+  pub(crate) fn check_not_stale(&self) -> Result<(), B::Err> {
+    if self.is_stale()? {
+      return Err(
+        Error::StaleRenderTargets {
+          reason: "the swap chain was resized since the render targets were obtained".to_owned(),
+        }
+        .into(),
+      );
+    }
+
+    Ok(())
+  }
+
+  /// Number of layers of layered render targets.
+  ///
+  /// Render targets are layered when their storage has layers (see [`Storage::layers`]) and none of their attachments
+  /// select a single layer. A geometry stage then selects the layer each primitive is rendered to by writing
+  /// `gl_Layer`, which allows rendering all faces of a cubemap in a single pass. Return [`None`] if not layered.
+  pub fn layers(&self) -> Option<usize> {
+    self.layers
+  }
+
+  /// Whether colors written to the sRGB color attachments are encoded to sRGB.
+  ///
+  /// Return [`None`] if no color attachment is sRGB — as for swap chain render targets, whose attachments are owned by
+  /// the backend. See [`ColorAttachmentPoint::with_srgb_encoding`].
+  pub fn srgb_encoding(&self) -> Option<bool> {
+    self
+      .color_attachment_points
+      .iter()
+      .find(|point| point.ty().is_srgb())
+      .map(ColorAttachmentPoint::srgb_encoding)
+  }
+
+  /// Color attachments the render targets were created with, as `(index, name, type)`, ordered by index.
+  pub fn color_attachments(
+    &self,
+  ) -> impl '_ + ExactSizeIterator<Item = (usize, &'static str, ColorType)> {
+    self
+      .color_attachment_points
+      .iter()
+      .map(|point| (point.index(), point.name(), point.ty()))
   }
 
   pub fn color_attachment(&self, index: usize) -> Result<ColorAttachment<B>, B::Err> {
-    B::get_color_attachment(&self.raw, index).map(|raw| ColorAttachment { raw })
+    let usage = self
+      .color_attachment_points
+      .iter()
+      .find(|point| point.index() == index)
+      .map_or(AttachmentUsage::Sampled, ColorAttachmentPoint::usage);
+
+    B::get_color_attachment(&self.raw, index).map(|raw| ColorAttachment {
+      raw,
+      storage: self.storage,
+      usage,
+    })
   }
 
   pub fn depth_stencil_attachment(
     &self,
     index: usize,
   ) -> Result<DepthStencilAttachment<B>, B::Err> {
-    B::get_depth_stencil_attachment(&self.raw, index).map(|raw| DepthStencilAttachment { raw })
+    B::get_depth_stencil_attachment(&self.raw, index).map(|raw| DepthStencilAttachment {
+      raw,
+      storage: self.storage,
+      layers: self.layers,
+    })
   }
 }
 
@@ -34,6 +383,59 @@ where
   B: Backend,
 {
   pub(crate) raw: B::ColorAttachment,
+  storage: Storage,
+  usage: AttachmentUsage,
+}
+
+impl<B> ColorAttachment<B>
+where
+  B: Backend,
+{
+  /// Storage of the render targets the attachment comes from.
+  pub fn storage(&self) -> Storage {
+    self.storage
+  }
+
+  pub fn usage(&self) -> AttachmentUsage {
+    self.usage
+  }
+
+  /// Read back the texels of `rect`, blocking until they are available.
+  ///
+  /// Only flat 2D single-sample attachments can be read; resolve multisample ones first. Texels are read back as 8-bit
+  /// RGBA, with rows in the backend order (bottom row first). This stalls the pipeline, so it is better suited to tests
+  /// and tools; see [`CmdBuf::read_color_attachment`](crate::cmd_buf::CmdBuf::read_color_attachment) for asynchronous
+  /// readbacks.
+  #[cfg(feature = "ext-readback")]
+  pub fn read(&self, rect: Rect) -> Result<Vec<u8>, B::Err>
+  where
+    B: BackendReadback,
+  {
+    let invalid = |reason| Err(Error::InvalidReadback { reason }.into());
+
+    let Storage::Flat2D { width, height } = self.storage else {
+      return invalid(format!("cannot read attachments of {:?}", self.storage));
+    };
+
+    let (
+      Offset::Dim2 { x, y },
+      Size::Dim2 {
+        width: w,
+        height: h,
+      },
+    ) = (rect.offset(), rect.size())
+    else {
+      return invalid(format!("{rect:?} is not a 2D rect"));
+    };
+
+    if u64::from(x) + u64::from(w) > u64::from(width)
+      || u64::from(y) + u64::from(h) > u64::from(height)
+    {
+      return invalid(format!("{rect:?} out of bounds of {:?}", self.storage));
+    }
+
+    B::read_color_attachment_texels(&self.raw, rect)
+  }
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -42,4 +444,58 @@ where
   B: Backend,
 {
   pub(crate) raw: B::DepthStencilAttachment,
+  storage: Storage,
+  layers: Option<usize>,
+}
+
+impl<B> DepthStencilAttachment<B>
+where
+  B: Backend,
+{
+  /// Storage of the render targets the attachment comes from.
+  pub fn storage(&self) -> Storage {
+    self.storage
+  }
+
+  /// Number of layers of the render targets the attachment comes from, if layered.
+  pub fn layers(&self) -> Option<usize> {
+    self.layers
+  }
+
+  /// Set how the attachment is sampled when used as a texture.
+  ///
+  /// Set [`Sampling::depth_comparison`] to sample it with depth comparison, e.g. for shadow mapping.
+  pub fn set_sampling(&self, sampling: Sampling) -> Result<(), B::Err> {
+    B::set_depth_stencil_attachment_sampling(&self.raw, sampling)
+  }
+}
+
+/// Size of the `rect` region blitted from or to render targets with `storage`.
+fn blit_size(storage: Storage, rect: Rect) -> Result<(u32, u32), Error> {
+  let invalid = |reason| Err(Error::InvalidBlit { reason });
+
+  let (Storage::Flat2D { width, height } | Storage::Flat2DMultiSample { width, height, .. }) =
+    storage
+  else {
+    return invalid(format!("cannot blit {storage:?}"));
+  };
+
+  let (
+    Offset::Dim2 { x, y },
+    Size::Dim2 {
+      width: w,
+      height: h,
+    },
+  ) = (rect.offset(), rect.size())
+  else {
+    return invalid(format!("{rect:?} is not a 2D rect"));
+  };
+
+  if u64::from(x) + u64::from(w) > u64::from(width)
+    || u64::from(y) + u64::from(h) > u64::from(height)
+  {
+    return invalid(format!("{rect:?} out of bounds of {storage:?}"));
+  }
+
+  Ok((w, h))
 }
@@ -1,19 +1,88 @@
-use piksels_backend::{shader::UniformType, Backend};
+use std::{
+  collections::HashMap,
+  sync::{Arc, Mutex, Weak},
+};
 
+use piksels_backend::{
+  shader::{ShaderSources, UniformType},
+  uniform_block::{UniformBlock, UniformBlockLayout},
+  vertex::VertexAttr,
+  Backend, Scarce,
+};
+
+use crate::{
+  cache::{Cache, ResourceId, TrackingTag},
+  gc,
+  interface::InterfaceReport,
+  storage_buffer::ShaderStorageBufferBindingPoint,
+};
+
+/// Handle on a shader program, shared with the other handles on the same variant.
+///
+/// The shader is destroyed once every handle sharing it is dropped, or with the device.
 #[derive(Debug)]
 pub struct Shader<B>
 where
   B: Backend,
 {
   pub(crate) raw: B::Shader,
+  binding_points: Arc<ShaderBindingPoints<B>>,
+  cache: Weak<Mutex<Cache<B>>>,
+  pub(crate) tag: TrackingTag,
+}
+
+impl<B> Drop for Shader<B>
+where
+  B: Backend,
+{
+  fn drop(&mut self) {
+    let id = self.id();
+    gc::drop_handle(&self.cache, self.tag, |cache, tag| {
+      cache.drop_shader(&id, tag)
+    });
+  }
 }
 
 impl<B> Shader<B>
 where
   B: Backend,
 {
-  pub(crate) fn from_raw(raw: B::Shader) -> Self {
-    Self { raw }
+  pub(crate) fn from_raw(
+    raw: B::Shader,
+    binding_points: Arc<ShaderBindingPoints<B>>,
+    cache: Weak<Mutex<Cache<B>>>,
+    tag: TrackingTag,
+  ) -> Self {
+    Self {
+      raw,
+      binding_points,
+      cache,
+      tag,
+    }
+  }
+
+  /// Another handle on the same shader, e.g. to defer its use.
+  ///
+  /// The handle doesn’t count as a handle on the shader: it doesn’t keep it alive.
+  pub(crate) fn scarce_clone(&self) -> Self {
+    Self {
+      raw: self.raw.scarce_clone(),
+      binding_points: self.binding_points.clone(),
+      cache: Weak::new(),
+      tag: TrackingTag::default(),
+    }
+  }
+
+  pub fn id(&self) -> ResourceId<B> {
+    self.raw.scarce_index()
+  }
+
+  /// Validate the interface of the shader: its vertex attributes against `attrs`, and the inputs of its fragment stage
+  /// against the outputs of the previous stage.
+  ///
+  /// The interface is reflected by the backend on every call, so this is better done once, at load time.
+  pub fn validate(&self, attrs: &[VertexAttr]) -> Result<InterfaceReport, B::Err> {
+    B::reflect_shader_interface(&self.raw).map(|interface| InterfaceReport::new(&interface, attrs))
   }
 
   pub fn uniform(
@@ -21,27 +90,167 @@ where
     name: impl AsRef<str>,
     ty: impl Into<UniformType>,
   ) -> Result<Uniform<B>, B::Err> {
-    B::get_uniform(&self.raw, name.as_ref(), ty.into()).map(|raw| Uniform { raw })
+    let ty = ty.into();
+    B::get_uniform(&self.raw, name.as_ref(), ty).map(|raw| Uniform {
+      raw,
+      ty,
+      cached: true,
+    })
   }
 
   pub fn uniform_buffer(&self, name: impl AsRef<str>) -> Result<UniformBuffer<B>, B::Err> {
     B::get_uniform_buffer(&self.raw, name.as_ref()).map(|raw| UniformBuffer { raw })
   }
 
+  /// Get a texture binding point by name.
+  ///
+  /// Binding points found by reflection at creation are resolved without calling the backend.
   pub fn texture_binding_point(
     &self,
     name: impl AsRef<str>,
   ) -> Result<ShaderTextureBindingPoint<B>, B::Err> {
-    B::get_shader_texture_binding_point(&self.raw, name.as_ref())
-      .map(|raw| ShaderTextureBindingPoint { raw })
+    let name = name.as_ref();
+
+    match self.binding_points.textures.get(name) {
+      Some(raw) => Ok(raw.scarce_clone()),
+      None => B::get_shader_texture_binding_point(&self.raw, name),
+    }
+    .map(|raw| ShaderTextureBindingPoint { raw })
   }
 
+  /// Get a uniform buffer binding point by name.
+  ///
+  /// Binding points found by reflection at creation are resolved without calling the backend.
   pub fn uniform_buffer_binding_point(
     &self,
     name: impl AsRef<str>,
   ) -> Result<ShaderUniformBufferBindingPoint<B>, B::Err> {
-    B::get_shader_uniform_buffer_binding_point(&self.raw, name.as_ref())
-      .map(|raw| ShaderUniformBufferBindingPoint { raw })
+    let name = name.as_ref();
+
+    match self.binding_points.uniform_buffers.get(name) {
+      Some(raw) => Ok(raw.scarce_clone()),
+      None => B::get_shader_uniform_buffer_binding_point(&self.raw, name),
+    }
+    .map(|raw| ShaderUniformBufferBindingPoint { raw })
+  }
+
+  /// Get a storage buffer binding point by name.
+  ///
+  /// Binding points found by reflection at creation are resolved without calling the backend.
+  pub fn storage_buffer_binding_point(
+    &self,
+    name: impl AsRef<str>,
+  ) -> Result<ShaderStorageBufferBindingPoint<B>, B::Err> {
+    let name = name.as_ref();
+
+    match self.binding_points.storage_buffers.get(name) {
+      Some(raw) => Ok(raw.scarce_clone()),
+      None => B::get_shader_storage_buffer_binding_point(&self.raw, name),
+    }
+    .map(|raw| ShaderStorageBufferBindingPoint { raw })
+  }
+}
+
+/// Key identifying a shader variant: the sources of its stages and the set of its defines.
+///
+/// Defines are sorted by name, so that specializing the same sources with the same defines in a different order yields
+/// the same variant.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub(crate) struct ShaderKey {
+  stages: [String; 6],
+  defines: Vec<(String, String)>,
+}
+
+impl ShaderKey {
+  pub(crate) fn new(sources: &ShaderSources) -> Self {
+    let stages = [
+      sources.tess_ctrl_stage(),
+      sources.tess_eval_stage(),
+      sources.vertex_stage(),
+      sources.geometry_stage(),
+      sources.fragment_stage(),
+      sources.compute_stage(),
+    ]
+    .map(str::to_owned);
+
+    let mut defines = sources
+      .defines()
+      .iter()
+      .map(|&(name, value)| (name.to_owned(), value.to_owned()))
+      .collect::<Vec<_>>();
+    defines.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    Self { stages, defines }
+  }
+}
+
+/// Binding points of a shader, resolved once at creation.
+#[derive(Debug)]
+pub struct ShaderBindingPoints<B>
+where
+  B: Backend,
+{
+  textures: HashMap<String, B::ShaderTextureBindingPoint>,
+  uniform_buffers: HashMap<String, B::ShaderUniformBufferBindingPoint>,
+  storage_buffers: HashMap<String, B::ShaderStorageBufferBindingPoint>,
+}
+
+impl<B> ShaderBindingPoints<B>
+where
+  B: Backend,
+{
+  /// Describe the binding points by their sorted names.
+  pub(crate) fn describe(&self) -> String {
+    fn names<P>(points: &HashMap<String, P>) -> Vec<&str> {
+      let mut names: Vec<_> = points.keys().map(String::as_str).collect();
+      names.sort_unstable();
+      names
+    }
+
+    format!(
+      "textures {:?}, uniform buffers {:?}, storage buffers {:?}",
+      names(&self.textures),
+      names(&self.uniform_buffers),
+      names(&self.storage_buffers)
+    )
+  }
+
+  /// Reflect the bindings of a shader and resolve all of their binding points.
+  pub(crate) fn resolve(shader: &B::Shader) -> Result<Self, B::Err> {
+    let bindings = B::reflect_shader_bindings(shader)?;
+
+    let textures = bindings
+      .textures
+      .into_iter()
+      .map(|name| {
+        let raw = B::get_shader_texture_binding_point(shader, &name)?;
+        Ok((name, raw))
+      })
+      .collect::<Result<_, B::Err>>()?;
+
+    let uniform_buffers = bindings
+      .uniform_buffers
+      .into_iter()
+      .map(|name| {
+        let raw = B::get_shader_uniform_buffer_binding_point(shader, &name)?;
+        Ok((name, raw))
+      })
+      .collect::<Result<_, B::Err>>()?;
+
+    let storage_buffers = bindings
+      .storage_buffers
+      .into_iter()
+      .map(|name| {
+        let raw = B::get_shader_storage_buffer_binding_point(shader, &name)?;
+        Ok((name, raw))
+      })
+      .collect::<Result<_, B::Err>>()?;
+
+    Ok(Self {
+      textures,
+      uniform_buffers,
+      storage_buffers,
+    })
   }
 }
 
@@ -51,6 +260,41 @@ where
   B: Backend,
 {
   pub(crate) raw: B::Uniform,
+  ty: UniformType,
+  cached: bool,
+}
+
+impl<B> Uniform<B>
+where
+  B: Backend,
+{
+  /// Another handle on the same uniform, e.g. to defer setting it.
+  pub(crate) fn scarce_clone(&self) -> Self {
+    Self {
+      raw: self.raw.scarce_clone(),
+      ty: self.ty,
+      cached: self.cached,
+    }
+  }
+
+  pub fn ty(&self) -> UniformType {
+    self.ty
+  }
+
+  /// Don’t cache the values set to the uniform.
+  ///
+  /// Values set with [`CmdBuf::uniform`](crate::cmd_buf::CmdBuf::uniform) are cached, so that setting the same value
+  /// again doesn’t reach the backend. Hashing the value is wasted work for uniforms known to change every draw (e.g.
+  /// the model matrix of each object); opt them out.
+  pub fn uncached(mut self) -> Self {
+    self.cached = false;
+    self
+  }
+
+  /// Whether the values set to the uniform are cached.
+  pub fn is_cached(&self) -> bool {
+    self.cached
+  }
 }
 
 #[derive(Debug)]
@@ -61,6 +305,26 @@ where
   pub(crate) raw: B::UniformBuffer,
 }
 
+impl<B> UniformBuffer<B>
+where
+  B: Backend,
+{
+  /// Upload `block` to the uniform buffer, laid out according to the std140 rules.
+  pub fn upload<T>(&self, block: &T) -> Result<(), B::Err>
+  where
+    T: UniformBlock,
+  {
+    B::update_uniform_buffer(&self.raw, &block.to_std140_bytes())
+  }
+
+  /// Query the layout of the block, as laid out by the driver.
+  ///
+  /// Use [`UniformBlockLayout::check`] to validate CPU-side structs against it.
+  pub fn layout(&self) -> Result<UniformBlockLayout, B::Err> {
+    B::uniform_buffer_layout(&self.raw)
+  }
+}
+
 #[derive(Debug)]
 pub struct UniformBufferBindingPoint<B>
 where
@@ -0,0 +1,258 @@
+//! Procedural meshes.
+//!
+//! Common shapes, ready to be turned into vertex arrays. All the shapes are indexed triangle lists with
+//! counter-clockwise front faces, made of [`ShapeVertex`] vertices, and — except for the full-screen triangle — fit in
+//! the unit cube centered at the origin.
+//!
+//! ```ignore
+//! let sphere = shapes::uv_sphere(32, 16);
+//! let vertex_array = device.new_vertex_array_with_streams(
+//!   vec![VertexStream::per_vertex(sphere.vertices().clone())],
+//!   sphere.indices(),
+//! )?;
+//! ```
+
+use std::f32::consts::{PI, TAU};
+
+use piksels_backend::{
+  assert_vertex_layout,
+  vertex::{Type, Vertex, VertexAttr},
+  vertex_array::VertexArrayData,
+};
+
+/// Vertex of the procedural shapes.
+///
+/// Attributes are `position` (index `0`), `normal` (index `1`) and `uv` (index `2`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(C)]
+pub struct ShapeVertex {
+  pub position: [f32; 3],
+  pub normal: [f32; 3],
+  pub uv: [f32; 2],
+}
+
+assert_vertex_layout!(ShapeVertex {
+  position: Type::Float3,
+  normal: Type::Float3,
+  uv: Type::Float2,
+});
+
+// SAFETY: the layout of ShapeVertex is checked above
+unsafe impl Vertex for ShapeVertex {
+  fn vertex_attrs() -> Vec<VertexAttr> {
+    vec![
+      VertexAttr {
+        index: 0,
+        name: "position",
+        ty: Type::Float3,
+        array: None,
+      },
+      VertexAttr {
+        index: 1,
+        name: "normal",
+        ty: Type::Float3,
+        array: None,
+      },
+      VertexAttr {
+        index: 2,
+        name: "uv",
+        ty: Type::Float2,
+        array: None,
+      },
+    ]
+  }
+}
+
+/// A procedural mesh: interleaved vertex data and triangle indices.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Shape {
+  vertices: VertexArrayData,
+  indices: Vec<u32>,
+}
+
+impl Shape {
+  fn new(vertices: &[ShapeVertex], indices: Vec<u32>) -> Self {
+    Self {
+      vertices: VertexArrayData::from_vertices(vertices),
+      indices,
+    }
+  }
+
+  pub fn vertices(&self) -> &VertexArrayData {
+    &self.vertices
+  }
+
+  pub fn indices(&self) -> &[u32] {
+    &self.indices
+  }
+
+  pub fn into_parts(self) -> (VertexArrayData, Vec<u32>) {
+    (self.vertices, self.indices)
+  }
+}
+
+/// Quad in the XY plane, facing `+Z`.
+pub fn quad() -> Shape {
+  let vertex = |x: f32, y: f32| ShapeVertex {
+    position: [x - 0.5, y - 0.5, 0.],
+    normal: [0., 0., 1.],
+    uv: [x, y],
+  };
+
+  Shape::new(
+    &[
+      vertex(0., 0.),
+      vertex(1., 0.),
+      vertex(1., 1.),
+      vertex(0., 1.),
+    ],
+    vec![0, 1, 2, 0, 2, 3],
+  )
+}
+
+/// Cube, with a separate set of vertices per face so that normals are flat.
+pub fn cube() -> Shape {
+  // normal, then the two axes spanning the face, so that u × v = normal
+  const FACES: [([f32; 3], [f32; 3], [f32; 3]); 6] = [
+    ([1., 0., 0.], [0., 0., -1.], [0., 1., 0.]),
+    ([-1., 0., 0.], [0., 0., 1.], [0., 1., 0.]),
+    ([0., 1., 0.], [1., 0., 0.], [0., 0., -1.]),
+    ([0., -1., 0.], [1., 0., 0.], [0., 0., 1.]),
+    ([0., 0., 1.], [1., 0., 0.], [0., 1., 0.]),
+    ([0., 0., -1.], [-1., 0., 0.], [0., 1., 0.]),
+  ];
+
+  let mut vertices = Vec::with_capacity(4 * FACES.len());
+  let mut indices = Vec::with_capacity(6 * FACES.len());
+
+  for (normal, u, v) in FACES {
+    let base = vertices.len() as u32;
+
+    for [s, t] in [[0., 0.], [1., 0.], [1., 1.], [0., 1.]] {
+      let position = [0, 1, 2].map(|i| 0.5 * normal[i] + (s - 0.5) * u[i] + (t - 0.5) * v[i]);
+
+      vertices.push(ShapeVertex {
+        position,
+        normal,
+        uv: [s, t],
+      });
+    }
+
+    indices.extend([0, 1, 2, 0, 2, 3].map(|i| base + i));
+  }
+
+  Shape::new(&vertices, indices)
+}
+
+/// UV sphere, with `slices` subdivisions around the `Y` axis and `stacks` subdivisions from pole to pole.
+///
+/// `slices` is clamped to at least `3` and `stacks` to at least `2`.
+pub fn uv_sphere(slices: u32, stacks: u32) -> Shape {
+  let (slices, stacks) = (slices.max(3), stacks.max(2));
+  let mut vertices = Vec::with_capacity(((slices + 1) * (stacks + 1)) as usize);
+
+  for stack in 0..=stacks {
+    let v = stack as f32 / stacks as f32;
+    let (sin_phi, cos_phi) = (v * PI).sin_cos();
+
+    for slice in 0..=slices {
+      let u = slice as f32 / slices as f32;
+      let (sin_theta, cos_theta) = (u * TAU).sin_cos();
+      let normal = [sin_phi * sin_theta, cos_phi, sin_phi * cos_theta];
+
+      vertices.push(ShapeVertex {
+        position: normal.map(|x| 0.5 * x),
+        normal,
+        uv: [u, 1. - v],
+      });
+    }
+  }
+
+  let mut indices = Vec::with_capacity((6 * slices * stacks) as usize);
+  let row = slices + 1;
+  for stack in 0..stacks {
+    for slice in 0..slices {
+      let (a, b) = (stack * row + slice, (stack + 1) * row + slice);
+      indices.extend([a, b, b + 1, a, b + 1, a + 1]);
+    }
+  }
+
+  Shape::new(&vertices, indices)
+}
+
+/// Cylinder along the `Y` axis, with `slices` subdivisions around it, closed by two caps.
+///
+/// `slices` is clamped to at least `3`.
+pub fn cylinder(slices: u32) -> Shape {
+  let slices = slices.max(3);
+  let mut vertices = Vec::new();
+  let mut indices = Vec::new();
+
+  // side
+  for y in [-0.5, 0.5] {
+    for slice in 0..=slices {
+      let u = slice as f32 / slices as f32;
+      let (sin_theta, cos_theta) = (u * TAU).sin_cos();
+
+      vertices.push(ShapeVertex {
+        position: [0.5 * sin_theta, y, 0.5 * cos_theta],
+        normal: [sin_theta, 0., cos_theta],
+        uv: [u, y + 0.5],
+      });
+    }
+  }
+
+  let row = slices + 1;
+  for slice in 0..slices {
+    let (a, b) = (slice, row + slice);
+    indices.extend([a, a + 1, b + 1, a, b + 1, b]);
+  }
+
+  // caps, as fans around their center
+  for (y, normal_y) in [(-0.5, -1.), (0.5, 1.)] {
+    let center = vertices.len() as u32;
+    vertices.push(ShapeVertex {
+      position: [0., y, 0.],
+      normal: [0., normal_y, 0.],
+      uv: [0.5, 0.5],
+    });
+
+    for slice in 0..=slices {
+      let (sin_theta, cos_theta) = (slice as f32 / slices as f32 * TAU).sin_cos();
+
+      vertices.push(ShapeVertex {
+        position: [0.5 * sin_theta, y, 0.5 * cos_theta],
+        normal: [0., normal_y, 0.],
+        uv: [0.5 + 0.5 * sin_theta, 0.5 + 0.5 * cos_theta],
+      });
+    }
+
+    for slice in 0..slices {
+      let a = center + 1 + slice;
+
+      if normal_y > 0. {
+        indices.extend([center, a, a + 1]);
+      } else {
+        indices.extend([center, a + 1, a]);
+      }
+    }
+  }
+
+  Shape::new(&vertices, indices)
+}
+
+/// Triangle covering the whole clip space, for full-screen passes.
+///
+/// Positions are in clip space, at `z = 0`; UVs are `[0; 1]` on the visible part of the triangle.
+pub fn fullscreen_triangle() -> Shape {
+  let vertex = |x: f32, y: f32| ShapeVertex {
+    position: [x, y, 0.],
+    normal: [0., 0., 1.],
+    uv: [(x + 1.) * 0.5, (y + 1.) * 0.5],
+  };
+
+  Shape::new(
+    &[vertex(-1., -1.), vertex(3., -1.), vertex(-1., 3.)],
+    vec![0, 1, 2],
+  )
+}
@@ -0,0 +1,193 @@
+//! Shader storage buffers.
+//!
+//! A [`StorageBuffer`] is a GPU buffer shaders can both read and write — e.g. the output of a compute shader, or
+//! vertex data pulled manually by a vertex shader. Storage buffers are bound to [`StorageBufferBindingPoint`]s, which
+//! are associated to the [`ShaderStorageBufferBindingPoint`]s of shaders, the same way uniform buffers are.
+
+use std::{
+  marker::PhantomData,
+  ops::{Deref, DerefMut, Range},
+  sync::{Mutex, Weak},
+};
+
+use piksels_backend::{error::Error, vertex_array::MapAccess, Backend, Scarce};
+
+use crate::{
+  cache::{Cache, ResourceId, TrackingTag},
+  gc,
+};
+
+#[derive(Debug)]
+pub struct StorageBuffer<B>
+where
+  B: Backend,
+{
+  pub(crate) raw: B::StorageBuffer,
+  size: usize,
+  cache: Weak<Mutex<Cache<B>>>,
+  pub(crate) tag: TrackingTag,
+}
+
+impl<B> Drop for StorageBuffer<B>
+where
+  B: Backend,
+{
+  fn drop(&mut self) {
+    let id = self.id();
+    gc::drop_handle(&self.cache, self.tag, |cache, tag| {
+      cache.drop_storage_buffer(&id, tag)
+    });
+  }
+}
+
+impl<B> StorageBuffer<B>
+where
+  B: Backend,
+{
+  pub(crate) fn from_raw(
+    raw: B::StorageBuffer,
+    size: usize,
+    cache: Weak<Mutex<Cache<B>>>,
+    tag: TrackingTag,
+  ) -> Self {
+    Self {
+      raw,
+      size,
+      cache,
+      tag,
+    }
+  }
+
+  pub fn id(&self) -> ResourceId<B> {
+    self.raw.scarce_index()
+  }
+
+  /// Size of the storage buffer, in bytes.
+  pub fn size(&self) -> usize {
+    self.size
+  }
+
+  /// Write `bytes` to the storage buffer, starting at byte `offset`.
+  ///
+  /// Updates with no bytes are ignored.
+  pub fn update(&self, offset: usize, bytes: &[u8]) -> Result<(), B::Err> {
+    if bytes.is_empty() {
+      return Ok(());
+    }
+
+    self.check_range(&(offset..offset + bytes.len()))?;
+    B::update_storage_buffer(&self.raw, offset, bytes)
+  }
+
+  /// Map a range of bytes of the storage buffer.
+  ///
+  /// Only `byte_range` is mapped, and flushed when the mapped bytes are dropped.
+  pub fn map(
+    &self,
+    byte_range: Range<usize>,
+    access: MapAccess,
+  ) -> Result<StorageBufferMappedBytes<'_, B>, B::Err> {
+    if byte_range.is_empty() {
+      return Err(
+        Error::InvalidMapRange {
+          reason: format!("empty byte range {byte_range:?}"),
+        }
+        .into(),
+      );
+    }
+
+    self.check_range(&byte_range)?;
+    B::map_storage_buffer_bytes(&self.raw, byte_range, access)
+      .map(StorageBufferMappedBytes::from_raw)
+  }
+
+  fn check_range(&self, byte_range: &Range<usize>) -> Result<(), Error> {
+    if byte_range.end > self.size {
+      return Err(Error::InvalidStorageBufferRange {
+        reason: format!(
+          "bytes {byte_range:?} out of bounds of a storage buffer of {} bytes",
+          self.size
+        ),
+      });
+    }
+
+    Ok(())
+  }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StorageBufferMappedBytes<'a, B>
+where
+  B: Backend,
+{
+  raw: B::StorageBufferMappedBytes,
+  _phantom: PhantomData<&'a mut ()>,
+}
+
+impl<'a, B> Drop for StorageBufferMappedBytes<'a, B>
+where
+  B: Backend,
+{
+  fn drop(&mut self) {
+    let _ = B::unmap_storage_buffer_bytes(&self.raw);
+  }
+}
+
+impl<'a, B> Deref for StorageBufferMappedBytes<'a, B>
+where
+  B: Backend,
+{
+  type Target = [u8];
+
+  fn deref(&self) -> &Self::Target {
+    let (data, len) = B::storage_buffer_bytes_data(&self.raw);
+    unsafe { std::slice::from_raw_parts(data, len) }
+  }
+}
+
+impl<'a, B> DerefMut for StorageBufferMappedBytes<'a, B>
+where
+  B: Backend,
+{
+  fn deref_mut(&mut self) -> &mut Self::Target {
+    let (data, len) = B::storage_buffer_bytes_data_mut(&mut self.raw);
+    unsafe { std::slice::from_raw_parts_mut(data, len) }
+  }
+}
+
+impl<'a, B> StorageBufferMappedBytes<'a, B>
+where
+  B: Backend,
+{
+  fn from_raw(raw: B::StorageBufferMappedBytes) -> Self {
+    Self {
+      raw,
+      _phantom: PhantomData,
+    }
+  }
+}
+
+#[derive(Debug)]
+pub struct StorageBufferBindingPoint<B>
+where
+  B: Backend,
+{
+  pub(crate) raw: B::StorageBufferBindingPoint,
+}
+
+impl<B> StorageBufferBindingPoint<B>
+where
+  B: Backend,
+{
+  pub(crate) fn from_raw(raw: B::StorageBufferBindingPoint) -> Self {
+    Self { raw }
+  }
+}
+
+#[derive(Debug)]
+pub struct ShaderStorageBufferBindingPoint<B>
+where
+  B: Backend,
+{
+  pub(crate) raw: B::ShaderStorageBufferBindingPoint,
+}
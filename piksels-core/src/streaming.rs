@@ -0,0 +1,128 @@
+//! Streaming vertex arrays.
+//!
+//! A [`StreamingVertexArray`] is meant for geometry rebuilt every frame, such as UI or debug lines. It allocates a
+//! single vertex array split into a ring of per-frame segments. Vertices are written to the segment of the current
+//! frame through mapped ranges, so that only the written bytes are flushed, and the GPU can still read the segments of
+//! the previous frames while the current one is being filled.
+
+use std::{cell::Cell, marker::PhantomData, mem};
+
+use piksels_backend::{
+  error::Error,
+  vertex::Vertex,
+  vertex_array::{DataSelector, MapAccess, MemoryLayout, VertexArrayData},
+  Backend,
+};
+
+use crate::{
+  device::Device,
+  vertex_array::{VertexArray, VertexArrayView, View},
+};
+
+/// Ring of per-frame vertex segments for dynamic geometry.
+#[derive(Debug)]
+pub struct StreamingVertexArray<B, V>
+where
+  B: Backend,
+{
+  vertex_array: VertexArray<B>,
+  capacity: usize,
+  frames: usize,
+  frame: usize,
+  cursor: Cell<usize>,
+  _vertex: PhantomData<V>,
+}
+
+impl<B, V> StreamingVertexArray<B, V>
+where
+  B: Backend,
+  V: Vertex,
+{
+  /// Create a streaming vertex array holding up to `capacity` vertices per frame, for `frames` frames.
+  ///
+  /// `frames` should be at least the number of frames the GPU can have in flight, so that a segment is never written
+  /// while it is still being read.
+  pub fn new(device: &Device<B>, capacity: usize, frames: usize) -> Result<Self, B::Err> {
+    let frames = frames.max(1);
+    let vertices = VertexArrayData::new(
+      V::vertex_attrs(),
+      MemoryLayout::Interleaved {
+        data: vec![0; capacity * frames * mem::size_of::<V>()],
+      },
+    );
+    let instances =
+      VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() });
+    let vertex_array = device.new_vertex_array(vertices, instances, Vec::new())?;
+
+    Ok(Self {
+      vertex_array,
+      capacity,
+      frames,
+      frame: 0,
+      cursor: Cell::new(0),
+      _vertex: PhantomData,
+    })
+  }
+
+  /// Maximum number of vertices per frame.
+  pub fn capacity(&self) -> usize {
+    self.capacity
+  }
+
+  /// Number of vertices already pushed for the current frame.
+  pub fn len(&self) -> usize {
+    self.cursor.get()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Move to the segment of the next frame, which is emptied.
+  pub fn next_frame(&mut self) {
+    self.frame = (self.frame + 1) % self.frames;
+    self.cursor.set(0);
+  }
+
+  /// Write vertices to the current frame, and get a view to draw them.
+  pub fn push(&self, vertices: &[V]) -> Result<VertexArrayView<'_, B>, B::Err> {
+    let cursor = self.cursor.get();
+
+    if cursor + vertices.len() > self.capacity {
+      return Err(
+        Error::StreamingOverflow {
+          reason: format!(
+            "cannot push {} vertices; {} out of {} already pushed this frame",
+            vertices.len(),
+            cursor,
+            self.capacity
+          ),
+        }
+        .into(),
+      );
+    }
+
+    let start_vertex = self.frame * self.capacity + cursor;
+
+    if !vertices.is_empty() {
+      let bytes = V::slice_as_bytes(vertices);
+      let offset = start_vertex * mem::size_of::<V>();
+      let mut mapped = self.vertex_array.map_range(
+        DataSelector::InterleavedVertices,
+        offset..offset + bytes.len(),
+        MapAccess::Write,
+      )?;
+      mapped.copy_from_slice(bytes);
+    }
+
+    self.cursor.set(cursor + vertices.len());
+
+    self
+      .vertex_array
+      .view(start_vertex..start_vertex + vertices.len())
+  }
+
+  pub fn vertex_array(&self) -> &VertexArray<B> {
+    &self.vertex_array
+  }
+}
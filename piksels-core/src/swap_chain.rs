@@ -1,6 +1,21 @@
-use piksels_backend::Backend;
+use std::sync::{Mutex, Weak};
 
-use crate::render_targets::RenderTargets;
+#[cfg(feature = "ext-frame-stats")]
+use piksels_backend::extension::frame_stats::{BackendFrameStats, FrameStats};
+#[cfg(feature = "ext-readback")]
+use piksels_backend::extension::readback::BackendReadback;
+use piksels_backend::{
+  error::Error,
+  swap_chain::{PresentStatus, SwapChainFormat},
+  texture::Storage,
+  Backend, Scarce,
+};
+
+use crate::{
+  cache::{Cache, ResourceId, SwapChainInfo, TrackingTag},
+  gc,
+  render_targets::RenderTargets,
+};
 
 #[derive(Debug)]
 pub struct SwapChain<B>
@@ -8,21 +23,290 @@ where
   B: Backend,
 {
   pub(crate) raw: B::SwapChain,
+  storage: Storage,
+  image_count: usize,
+  format: SwapChainFormat,
+  generation: u64,
+  cache: Weak<Mutex<Cache<B>>>,
+  pub(crate) tag: TrackingTag,
+}
+
+impl<B> Drop for SwapChain<B>
+where
+  B: Backend,
+{
+  fn drop(&mut self) {
+    let id = self.id();
+    gc::drop_handle(&self.cache, self.tag, |cache, tag| {
+      cache.drop_swap_chain(&id, tag)
+    });
+  }
 }
 
 impl<B> SwapChain<B>
 where
   B: Backend,
 {
-  pub(crate) fn from_raw(raw: B::SwapChain) -> Self {
-    Self { raw }
+  pub(crate) fn from_raw(
+    raw: B::SwapChain,
+    info: SwapChainInfo,
+    cache: Weak<Mutex<Cache<B>>>,
+    tag: TrackingTag,
+  ) -> Self {
+    Self {
+      raw,
+      storage: info.storage,
+      image_count: info.image_count,
+      format: info.format,
+      generation: info.generation,
+      cache,
+      tag,
+    }
+  }
+
+  pub fn id(&self) -> ResourceId<B> {
+    self.raw.scarce_index()
+  }
+
+  pub fn storage(&self) -> Storage {
+    self.storage
+  }
+
+  /// Number of images of the swap chain, i.e. maximum number of frames in flight.
+  pub fn image_count(&self) -> usize {
+    self.image_count
   }
 
-  pub fn render_targets(&self) -> Result<RenderTargets<B>, B::Err> {
-    B::swap_chain_render_targets(&self.raw).map(RenderTargets::from_raw)
+  pub fn format(&self) -> SwapChainFormat {
+    self.format
   }
 
+  /// Presentation statistics, to pace frames.
+  ///
+  /// If the backend doesn’t know the refresh rate of the display, it is estimated from the present timestamps: the
+  /// median interval between two displayed images is taken as the refresh period, which is robust to missed vertical
+  /// blanks as long as most frames are on time. The refresh rate stays unknown with less than two timestamps.
+  #[cfg(feature = "ext-frame-stats")]
+  pub fn frame_stats(&self) -> Result<FrameStats, B::Err>
+  where
+    B: BackendFrameStats,
+  {
+    let mut stats = B::swap_chain_frame_stats(&self.raw)?;
+
+    if stats.refresh_rate.is_none() {
+      let mut intervals = stats
+        .present_timestamps
+        .windows(2)
+        .map(|timestamps| timestamps[1].saturating_sub(timestamps[0]))
+        .filter(|interval| !interval.is_zero())
+        .collect::<Vec<_>>();
+      intervals.sort_unstable();
+
+      stats.refresh_rate = intervals
+        .get(intervals.len() / 2)
+        .map(|period| 1. / period.as_secs_f64());
+    }
+
+    Ok(stats)
+  }
+
+  /// Read back the texels of the last presented image, e.g. to take a screenshot.
+  ///
+  /// Texels are read back as 8-bit RGBA, with rows in the backend order (bottom row first). An image must have been
+  /// presented since the swap chain was created or resized. This stalls the pipeline, so it is better suited to
+  /// occasional captures than to recording every frame; see [`FrameLoop`](crate::frame_loop::FrameLoop) for that.
+  #[cfg(feature = "ext-readback")]
+  pub fn capture(&self) -> Result<Vec<u8>, B::Err>
+  where
+    B: BackendReadback,
+  {
+    self.check_not_lost()?;
+
+    if let Some(cache) = self.cache.upgrade() {
+      let presented = cache
+        .lock()
+        .map_err(Error::from)?
+        .swap_chain(&self.id())
+        .is_some_and(|(_, info, _)| info.presented);
+      if !presented {
+        return Err(
+          Error::InvalidReadback {
+            reason: "no image was presented since the swap chain was created or resized".to_owned(),
+          }
+          .into(),
+        );
+      }
+    }
+
+    B::read_swap_chain_texels(&self.raw)
+  }
+
+  /// Block until every presented image is displayed.
+  ///
+  /// This drains the frames in flight, e.g. to measure latency, or to reach a known state before tearing resources
+  /// down.
+  pub fn wait_idle(&self) -> Result<(), B::Err> {
+    B::swap_chain_wait_idle(&self.raw)
+  }
+
+  /// Recreate the surfaces of the swap chain with a width and height of `width` and `height`, e.g. when the window is
+  /// resized.
+  ///
+  /// Render targets previously returned by [`SwapChain::acquire`], from any handle on the swap chain, become
+  /// stale: binding or presenting them fails. Get new ones after resizing.
+  pub fn resize(&mut self, width: u32, height: u32) -> Result<(), B::Err> {
+    self.check_not_lost()?;
+    B::resize_swap_chain(&self.raw, width, height)?;
+    self.storage = Storage::Flat2D { width, height };
+
+    let generation = match self.cache.upgrade() {
+      Some(cache) => cache
+        .lock()
+        .map_err(Error::from)?
+        .resize_swap_chain(&self.id(), self.storage),
+      None => None,
+    };
+    self.generation = generation.unwrap_or(self.generation + 1);
+
+    Ok(())
+  }
+
+  /// Acquire the render targets of the next image of the swap chain.
+  ///
+  /// Their color attachments are owned by the backend, so [`RenderTargets::color_attachments`] is empty. An image must
+  /// be presented with [`SwapChain::present`] before acquiring the next one. Swap chains are independent, so rendering
+  /// to several windows from the same [`Device`](crate::device::Device) is a matter of acquiring an image of each of
+  /// their swap chains, and presenting them in any order.
+  pub fn acquire(&self) -> Result<RenderTargets<B>, B::Err> {
+    self.check_not_lost()?;
+
+    if self.set_acquired(true)? {
+      return Err(
+        Error::InvalidAcquire {
+          reason: "the previously acquired image was not presented".to_owned(),
+        }
+        .into(),
+      );
+    }
+
+    let (storage, generation) = self.current()?;
+    let raw = B::swap_chain_render_targets(&self.raw).map_err(|err| {
+      let _ = self.set_acquired(false);
+      err
+    })?;
+
+    Ok(
+      RenderTargets::from_raw(
+        raw,
+        storage,
+        Vec::new().into(),
+        None,
+        self.cache.clone(),
+        TrackingTag::default(),
+      )
+      .with_swap_chain(self.id(), generation),
+    )
+  }
+
+  /// Present render targets acquired from this swap chain with [`SwapChain::acquire`].
+  ///
+  /// The image is given back to the swap chain even if it cannot be presented, so that presenting failures are
+  /// recoverable:
+  ///
+  /// - [`Error::SwapChainOutOfDate`] means the swap chain doesn’t match its surface anymore, e.g. because the window
+  ///   was resized. Resize the swap chain with [`SwapChain::resize`], then acquire again. While the window is
+  ///   minimized, frames can simply be skipped until it is restored.
+  /// - [`Error::SurfaceLost`] means the surface is gone, e.g. because the display was disconnected. Acquiring from or
+  ///   resizing the swap chain fails from then on; drop it and create a new one.
   pub fn present(&self, render_targets: &RenderTargets<B>) -> Result<(), B::Err> {
-    B::present_render_targets(&self.raw, &render_targets.raw)
+    let invalid = |reason: &str| {
+      Err(
+        Error::InvalidPresent {
+          reason: reason.to_owned(),
+        }
+        .into(),
+      )
+    };
+
+    if render_targets.swap_chain_id() != Some(&self.id()) {
+      return invalid("render targets were not acquired from this swap chain");
+    }
+
+    render_targets.check_not_stale()?;
+
+    if self.cache.upgrade().is_some() && !self.set_acquired(false)? {
+      return invalid("no image is acquired");
+    }
+
+    match B::present_render_targets(&self.raw, &render_targets.raw)? {
+      PresentStatus::Presented => {
+        if let Some(cache) = self.cache.upgrade() {
+          let mut cache = cache.lock().map_err(Error::from)?;
+          cache.present_swap_chain(&self.id());
+          cache.submit();
+        }
+
+        Ok(())
+      }
+      PresentStatus::OutOfDate => Err(Error::SwapChainOutOfDate.into()),
+      PresentStatus::SurfaceLost => {
+        if let Some(cache) = self.cache.upgrade() {
+          cache
+            .lock()
+            .map_err(Error::from)?
+            .lose_swap_chain(&self.id());
+        }
+
+        Err(Error::SurfaceLost.into())
+      }
+    }
+  }
+
+  fn check_not_lost(&self) -> Result<(), B::Err> {
+    let Some(cache) = self.cache.upgrade() else {
+      return Ok(());
+    };
+
+    let lost = cache
+      .lock()
+      .map_err(Error::from)?
+      .swap_chain(&self.id())
+      .is_some_and(|(_, info, _)| info.lost);
+    if lost {
+      return Err(Error::SurfaceLost.into());
+    }
+
+    Ok(())
+  }
+
+  /// Mark whether an image is acquired, and return whether one was.
+  ///
+  /// Without cache, acquiring is not tracked and no image is ever seen as acquired.
+  fn set_acquired(&self, acquired: bool) -> Result<bool, B::Err> {
+    let Some(cache) = self.cache.upgrade() else {
+      return Ok(false);
+    };
+
+    let was_acquired = cache
+      .lock()
+      .map_err(Error::from)?
+      .set_swap_chain_acquired(&self.id(), acquired);
+    Ok(was_acquired)
+  }
+
+  /// Storage and generation of the swap chain, as known by the cache, in case it was resized through another handle.
+  fn current(&self) -> Result<(Storage, u64), B::Err> {
+    let Some(cache) = self.cache.upgrade() else {
+      return Ok((self.storage, self.generation));
+    };
+
+    let current = cache
+      .lock()
+      .map_err(Error::from)?
+      .swap_chain(&self.id())
+      .map_or((self.storage, self.generation), |(_, info, _)| {
+        (info.storage, info.generation)
+      });
+    Ok(current)
   }
 }
@@ -1,40 +1,349 @@
+use std::{
+  borrow::Cow,
+  sync::{Mutex, Weak},
+};
+
+#[cfg(feature = "ext-sparse-texture")]
+use piksels_backend::extension::sparse_texture::BackendSparseTexture;
 use piksels_backend::{
-  texture::{Rect, Size},
-  Backend,
+  error::Error,
+  pixel::Pixel,
+  texture::{Offset, Rect, Size, Storage},
+  Backend, Scarce,
 };
 
+use crate::{
+  cache::{Cache, ResourceId, TrackingTag},
+  convention::{self, CoordinateConvention},
+  gc,
+};
+
+/// Handle on a texture.
+///
+/// The texture is destroyed once every handle on it is dropped.
 #[derive(Debug)]
 pub struct Texture<B>
 where
   B: Backend,
 {
   pub(crate) raw: B::Texture,
+  storage: Storage,
+  pixel: Pixel,
+  coordinate_convention: CoordinateConvention,
+  cache: Weak<Mutex<Cache<B>>>,
+  pub(crate) tag: TrackingTag,
+}
+
+impl<B> Drop for Texture<B>
+where
+  B: Backend,
+{
+  fn drop(&mut self) {
+    let id = self.id();
+    gc::drop_handle(&self.cache, self.tag, |cache, tag| {
+      cache.drop_texture(&id, tag)
+    });
+  }
 }
 
 impl<B> Texture<B>
 where
   B: Backend,
 {
-  pub(crate) fn from_raw(raw: B::Texture) -> Self {
-    Self { raw }
+  pub(crate) fn from_raw(
+    raw: B::Texture,
+    storage: Storage,
+    pixel: Pixel,
+    coordinate_convention: CoordinateConvention,
+    cache: Weak<Mutex<Cache<B>>>,
+    tag: TrackingTag,
+  ) -> Self {
+    Self {
+      raw,
+      storage,
+      pixel,
+      coordinate_convention,
+      cache,
+      tag,
+    }
+  }
+
+  pub fn id(&self) -> ResourceId<B> {
+    self.raw.scarce_index()
+  }
+
+  pub fn storage(&self) -> Storage {
+    self.storage
+  }
+
+  pub fn pixel(&self) -> Pixel {
+    self.pixel
   }
 
   pub fn resize(&self, size: Size) -> Result<(), B::Err> {
     B::resize_texture(&self.raw, size)
   }
 
-  pub fn set(
+  /// Restrict the mipmap levels the texture can be sampled from to `base_level..=max_level`.
+  ///
+  /// This allows to progressively reveal higher levels as their texels are uploaded, without recreating the texture.
+  pub fn set_levels(&self, base_level: usize, max_level: usize) -> Result<(), B::Err> {
+    let levels = self.storage.levels();
+
+    if base_level > max_level || max_level >= levels {
+      return Err(
+        Error::InvalidTextureLevels {
+          reason: format!("{base_level}..={max_level} (texture has {levels} levels)"),
+        }
+        .into(),
+      );
+    }
+
+    B::set_texture_levels(&self.raw, base_level, max_level)
+  }
+
+  /// Upload texels to a rect of a mipmap level.
+  ///
+  /// `texels` must contain at least the texels of the whole rect, tightly packed.
+  pub fn set(&self, rect: Rect, mipmaps: bool, level: usize, texels: &[u8]) -> Result<(), B::Err> {
+    let len = rect_texels(rect.size()) * self.pixel.format.bytes();
+    if texels.len() < len {
+      return Err(invalid_texels_upload(format!(
+        "expected at least {len} bytes for {rect:?}, got {}",
+        texels.len()
+      )));
+    }
+
+    let Some((flipped_rect, [width, height])) = self.flip_rect(rect, level) else {
+      return B::set_texels(&self.raw, rect, mipmaps, level, texels.as_ptr());
+    };
+
+    let row_len = width as usize * self.pixel.format.bytes();
+    let slice_len = row_len * height as usize;
+    let flipped_texels: Vec<u8> = texels[..len]
+      .chunks_exact(slice_len.max(1))
+      .flat_map(|slice| slice.chunks_exact(row_len.max(1)).rev().flatten())
+      .copied()
+      .collect();
+
+    B::set_texels(
+      &self.raw,
+      flipped_rect,
+      mipmaps,
+      level,
+      flipped_texels.as_ptr(),
+    )
+  }
+
+  pub fn clear(&self, rect: Rect, mipmaps: bool, value: *const u8) -> Result<(), B::Err> {
+    let rect = self.flip_rect(rect, 0).map_or(rect, |(rect, _)| rect);
+    B::clear_texels(&self.raw, rect, mipmaps, value)
+  }
+
+  /// Convert a rect of a mipmap level to the backend coordinate convention.
+  ///
+  /// Return [`None`] if the rect doesn’t need flipping; otherwise, return the flipped rect along with its width and height.
+  fn flip_rect(&self, rect: Rect, level: usize) -> Option<(Rect, [u32; 2])> {
+    if self.coordinate_convention == CoordinateConvention::OriginBottomLeft {
+      return None;
+    }
+
+    let level_height = level_dim(convention::storage_height(self.storage)?, level);
+    let flip_y = |y, height| self.coordinate_convention.flip_y(y, height, level_height);
+
+    match (rect.offset(), rect.size()) {
+      (Offset::Dim2 { x, y }, size @ Size::Dim2 { width, height }) => Some((
+        Rect::new(
+          Offset::Dim2 {
+            x,
+            y: flip_y(y, height),
+          },
+          size,
+        ),
+        [width, height],
+      )),
+
+      (Offset::Dim3 { x, y, z }, size @ Size::Dim3 { width, height, .. }) => Some((
+        Rect::new(
+          Offset::Dim3 {
+            x,
+            y: flip_y(y, height),
+            z,
+          },
+          size,
+        ),
+        [width, height],
+      )),
+
+      _ => None,
+    }
+  }
+
+  /// Size of a mipmap level of a 3D texture.
+  fn level_size_3d(&self, level: usize) -> Result<[u32; 3], B::Err> {
+    match self.storage {
+      Storage::Flat3D {
+        width,
+        height,
+        depth,
+      } => Ok([
+        level_dim(width, level),
+        level_dim(height, level),
+        level_dim(depth, level),
+      ]),
+
+      storage => Err(invalid_texels_upload(format!(
+        "{storage:?} is not a 3D storage"
+      ))),
+    }
+  }
+
+  /// Upload a single z-slice of a 3D texture.
+  ///
+  /// `texels` must contain exactly the texels of the slice, tightly packed.
+  pub fn set_slice(&self, level: usize, z: u32, texels: &[u8]) -> Result<(), B::Err> {
+    let [width, height, depth] = self.level_size_3d(level)?;
+
+    if z >= depth {
+      return Err(invalid_texels_upload(format!(
+        "slice {z} out of bounds (level {level} has {depth} slices)"
+      )));
+    }
+
+    let slice_len = width as usize * height as usize * self.pixel.format.bytes();
+    if texels.len() != slice_len {
+      return Err(invalid_texels_upload(format!(
+        "expected {slice_len} bytes for a slice, got {}",
+        texels.len()
+      )));
+    }
+
+    let rect = Rect::new(
+      Offset::Dim3 { x: 0, y: 0, z },
+      Size::Dim3 {
+        width,
+        height,
+        depth: 1,
+      },
+    );
+    self.set(rect, false, level, texels)
+  }
+
+  /// Upload a 3D region of a 3D texture.
+  ///
+  /// `texels` holds the region slice by slice, and each slice row by row. `row_pitch` is the number of bytes between
+  /// two consecutive rows, and `slice_pitch` the number of bytes between two consecutive slices; they allow to upload
+  /// texels from a padded buffer.
+  pub fn set_region_3d(
     &self,
-    rect: Rect,
-    mipmaps: bool,
     level: usize,
-    texels: *const u8,
+    rect: Rect,
+    texels: &[u8],
+    row_pitch: usize,
+    slice_pitch: usize,
   ) -> Result<(), B::Err> {
-    B::set_texels(&self.raw, rect, mipmaps, level, texels)
+    let [level_width, level_height, level_depth] = self.level_size_3d(level)?;
+
+    let (
+      Offset::Dim3 { x, y, z },
+      Size::Dim3 {
+        width,
+        height,
+        depth,
+      },
+    ) = (rect.offset(), rect.size())
+    else {
+      return Err(invalid_texels_upload(format!(
+        "{rect:?} is not a 3D region"
+      )));
+    };
+
+    if x + width > level_width || y + height > level_height || z + depth > level_depth {
+      return Err(invalid_texels_upload(format!(
+        "{rect:?} out of bounds (level {level} is {level_width}x{level_height}x{level_depth})"
+      )));
+    }
+
+    let row_len = width as usize * self.pixel.format.bytes();
+    let slice_len = row_len * height as usize;
+
+    if row_pitch < row_len || slice_pitch < row_pitch * height as usize {
+      return Err(invalid_texels_upload(format!(
+        "pitches too small (row pitch: {row_pitch}, slice pitch: {slice_pitch})"
+      )));
+    }
+
+    let len = if width == 0 || height == 0 || depth == 0 {
+      0
+    } else {
+      slice_pitch * (depth as usize - 1) + row_pitch * (height as usize - 1) + row_len
+    };
+    if texels.len() < len {
+      return Err(invalid_texels_upload(format!(
+        "expected at least {len} bytes, got {}",
+        texels.len()
+      )));
+    }
+
+    // repack the texels if they are not tightly packed
+    let texels = if row_pitch == row_len && slice_pitch == slice_len {
+      Cow::Borrowed(texels)
+    } else {
+      let mut packed = Vec::with_capacity(slice_len * depth as usize);
+
+      for slice in 0..depth as usize {
+        for row in 0..height as usize {
+          let start = slice * slice_pitch + row * row_pitch;
+          packed.extend_from_slice(&texels[start..start + row_len]);
+        }
+      }
+
+      Cow::Owned(packed)
+    };
+
+    self.set(rect, false, level, &texels)
   }
+}
 
-  pub fn clear(&self, rect: Rect, mipmaps: bool, value: *const u8) -> Result<(), B::Err> {
-    B::clear_texels(&self.raw, rect, mipmaps, value)
+/// Number of texels in a rect of a given size.
+fn rect_texels(size: Size) -> usize {
+  match size {
+    Size::Dim1 { width } => width as usize,
+    Size::Dim2 { width, height } => width as usize * height as usize,
+    Size::Dim3 {
+      width,
+      height,
+      depth,
+    } => width as usize * height as usize * depth as usize,
+    Size::Cubemap { size } => size as usize * size as usize,
+  }
+}
+
+/// Size of a dimension at a given mipmap level.
+fn level_dim(dim: u32, level: usize) -> u32 {
+  dim.checked_shr(level as u32).unwrap_or(0).max(1)
+}
+
+fn invalid_texels_upload<E>(reason: String) -> E
+where
+  E: From<Error>,
+{
+  Error::InvalidTexelsUpload { reason }.into()
+}
+
+#[cfg(feature = "ext-sparse-texture")]
+impl<B> Texture<B>
+where
+  B: BackendSparseTexture,
+{
+  /// Make a region of a sparse texture resident.
+  pub fn commit(&self, rect: Rect, level: usize) -> Result<(), B::Err> {
+    B::commit_texture_region(&self.raw, rect, level)
+  }
+
+  /// Release the physical memory of a region of a sparse texture.
+  pub fn decommit(&self, rect: Rect, level: usize) -> Result<(), B::Err> {
+    B::decommit_texture_region(&self.raw, rect, level)
   }
 }
 
@@ -0,0 +1,543 @@
+//! Trace capture and replay.
+//!
+//! Use [`CmdBuf::start_trace`] to capture the commands recorded in a command buffer into a [`Trace`], and a
+//! [`TraceReplay`] to replay it against another command buffer — possibly of another backend — measuring how long each
+//! call and each frame takes. The resulting [`ReplayReport`] allows to benchmark backend implementations against
+//! identical workloads.
+//!
+//! Resources are identified in traces by [`TraceId`]s, given in the order resources are first used while capturing.
+//! Replaying requires registering a resource of the same kind for each of them, e.g. by creating the resources of the
+//! captured workload in the same order.
+//!
+//! Only commands issued to the backend are captured: state changes skipped as redundant are not. Deferred command
+//! buffers are captured as executed, not their commands: replaying executes the command buffers registered for them.
+//! Readbacks are only replayed with [`TraceReplay::run_with_readbacks`].
+
+use std::{
+  collections::{BTreeMap, HashMap},
+  fmt::{self, Display},
+  time::{Duration, Instant},
+};
+
+#[cfg(feature = "ext-readback")]
+use piksels_backend::extension::readback::BackendReadback;
+use piksels_backend::{
+  error::Error,
+  extension::trace::{BackendTrace, Trace, TraceCall, TraceCallKind, TraceId},
+  Backend,
+};
+
+use crate::{
+  cache::ResourceId,
+  cmd_buf::CmdBuf,
+  render_targets::{ColorAttachment, DepthStencilAttachment, RenderTargets},
+  shader::{
+    Shader, ShaderTextureBindingPoint, ShaderUniformBufferBindingPoint, Uniform, UniformBuffer,
+    UniformBufferBindingPoint,
+  },
+  storage_buffer::{ShaderStorageBufferBindingPoint, StorageBuffer, StorageBufferBindingPoint},
+  texture::{Texture, TextureBindingPoint},
+  vertex_array::VertexArray,
+};
+
+/// Kind of resources captured in traces.
+///
+/// Scarce indices are only unique among resources of the same kind.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub(crate) enum TraceResourceKind {
+  CmdBuf,
+  RenderTargets,
+  ColorAttachment,
+  DepthStencilAttachment,
+  Shader,
+  Uniform,
+  Texture,
+  TextureBindingPoint,
+  ShaderTextureBindingPoint,
+  UniformBuffer,
+  UniformBufferBindingPoint,
+  ShaderUniformBufferBindingPoint,
+  StorageBuffer,
+  StorageBufferBindingPoint,
+  ShaderStorageBufferBindingPoint,
+  VertexArray,
+}
+
+/// Capture of the commands of a command buffer.
+#[derive(Debug)]
+pub(crate) struct TraceRecorder<B>
+where
+  B: Backend,
+{
+  ids: HashMap<(TraceResourceKind, ResourceId<B>), TraceId>,
+  calls: Vec<TraceCall>,
+}
+
+impl<B> Default for TraceRecorder<B>
+where
+  B: Backend,
+{
+  fn default() -> Self {
+    Self {
+      ids: HashMap::new(),
+      calls: Vec::new(),
+    }
+  }
+}
+
+impl<B> TraceRecorder<B>
+where
+  B: Backend,
+{
+  /// Trace identifier of a resource, given on first use.
+  pub(crate) fn id(&mut self, kind: TraceResourceKind, id: ResourceId<B>) -> TraceId {
+    let next = self.ids.len() as TraceId;
+    *self.ids.entry((kind, id)).or_insert(next)
+  }
+
+  pub(crate) fn push(&mut self, call: TraceCall) {
+    self.calls.push(call);
+  }
+
+  pub(crate) fn into_trace(self) -> Trace {
+    Trace { calls: self.calls }
+  }
+}
+
+macro_rules! mk_trace_resources {
+  ($($variant:ident, $register:ident, $get:ident, $ty:ident, $what:literal);* $(;)?) => {
+    /// A resource registered for replay.
+    #[derive(Debug)]
+    enum TraceResource<'a, B>
+    where
+      B: Backend,
+    {
+      $($variant(&'a $ty<B>)),*
+    }
+
+    impl<'a, B> TraceReplay<'a, B>
+    where
+      B: Backend,
+    {
+      $(
+        #[doc = concat!("Register ", $what, " for a trace identifier.")]
+        pub fn $register(mut self, id: TraceId, resource: &'a $ty<B>) -> Self {
+          self.resources.insert(id, TraceResource::$variant(resource));
+          self
+        }
+
+        fn $get(&self, id: TraceId) -> Result<&'a $ty<B>, B::Err> {
+          match self.resources.get(&id) {
+            Some(TraceResource::$variant(resource)) => Ok(resource),
+            _ => Err(
+              Error::InvalidTrace {
+                reason: format!(concat!("no ", $what, " registered for trace identifier {}"), id),
+              }
+              .into(),
+            ),
+          }
+        }
+      )*
+    }
+  };
+}
+
+/// Replay of [`Trace`]s, measuring call and frame timings.
+///
+/// Resources are registered for the [`TraceId`]s of the trace before replaying it.
+#[derive(Debug)]
+pub struct TraceReplay<'a, B>
+where
+  B: Backend,
+{
+  resources: HashMap<TraceId, TraceResource<'a, B>>,
+}
+
+mk_trace_resources! {
+  CmdBuf, cmd_buf, get_cmd_buf, CmdBuf, "a deferred command buffer";
+  RenderTargets, render_targets, get_render_targets, RenderTargets, "render targets";
+  ColorAttachment, color_attachment, get_color_attachment, ColorAttachment, "a color attachment";
+  DepthStencilAttachment, depth_stencil_attachment, get_depth_stencil_attachment,
+    DepthStencilAttachment, "a depth/stencil attachment";
+  Shader, shader, get_shader, Shader, "a shader";
+  Uniform, uniform, get_uniform, Uniform, "a uniform";
+  Texture, texture, get_texture, Texture, "a texture";
+  TextureBindingPoint, texture_binding_point, get_texture_binding_point, TextureBindingPoint,
+    "a texture binding point";
+  ShaderTextureBindingPoint, shader_texture_binding_point, get_shader_texture_binding_point,
+    ShaderTextureBindingPoint, "a shader texture binding point";
+  UniformBuffer, uniform_buffer, get_uniform_buffer, UniformBuffer, "a uniform buffer";
+  UniformBufferBindingPoint, uniform_buffer_binding_point, get_uniform_buffer_binding_point,
+    UniformBufferBindingPoint, "a uniform buffer binding point";
+  ShaderUniformBufferBindingPoint, shader_uniform_buffer_binding_point,
+    get_shader_uniform_buffer_binding_point, ShaderUniformBufferBindingPoint,
+    "a shader uniform buffer binding point";
+  StorageBuffer, storage_buffer, get_storage_buffer, StorageBuffer, "a storage buffer";
+  StorageBufferBindingPoint, storage_buffer_binding_point, get_storage_buffer_binding_point,
+    StorageBufferBindingPoint, "a storage buffer binding point";
+  ShaderStorageBufferBindingPoint, shader_storage_buffer_binding_point,
+    get_shader_storage_buffer_binding_point, ShaderStorageBufferBindingPoint,
+    "a shader storage buffer binding point";
+  VertexArray, vertex_array, get_vertex_array, VertexArray, "a vertex array";
+}
+
+impl<'a, B> Default for TraceReplay<'a, B>
+where
+  B: Backend,
+{
+  fn default() -> Self {
+    Self {
+      resources: HashMap::new(),
+    }
+  }
+}
+
+impl<'a, B> TraceReplay<'a, B>
+where
+  B: Backend,
+{
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Replay a trace with a command buffer.
+  ///
+  /// Calls are issued to the backend as captured, without going through the command buffer, so that none of them is
+  /// skipped or altered. Every [`TraceCall::Finish`] finishes the command buffer and waits for the device to execute
+  /// its commands, so that frame timings include the time spent executing them, and not only recording them.
+  ///
+  /// Traces with readbacks are rejected; see [`TraceReplay::run_with_readbacks`].
+  pub fn run(&self, cmd_buf: &CmdBuf<B>, trace: &Trace) -> Result<ReplayReport, B::Err>
+  where
+    B: BackendTrace,
+  {
+    self.run_with(cmd_buf, trace, |_, _| {
+      Err(
+        Error::InvalidTrace {
+          reason: "replaying readbacks requires a backend supporting them".to_owned(),
+        }
+        .into(),
+      )
+    })
+  }
+
+  /// Replay a trace with a command buffer, reading back color attachments as captured.
+  ///
+  /// See [`TraceReplay::run`].
+  #[cfg(feature = "ext-readback")]
+  pub fn run_with_readbacks(
+    &self,
+    cmd_buf: &CmdBuf<B>,
+    trace: &Trace,
+  ) -> Result<ReplayReport, B::Err>
+  where
+    B: BackendTrace + BackendReadback,
+  {
+    self.run_with(cmd_buf, trace, |cmd_buf, color_attachment| {
+      let readback = B::cmd_buf_read_color_attachment(cmd_buf, color_attachment)?;
+      B::drop_readback(&readback);
+      Ok(())
+    })
+  }
+
+  fn run_with(
+    &self,
+    cmd_buf: &CmdBuf<B>,
+    trace: &Trace,
+    read_color_attachment: impl Fn(&B::CmdBuf, &B::ColorAttachment) -> Result<(), B::Err>,
+  ) -> Result<ReplayReport, B::Err>
+  where
+    B: BackendTrace,
+  {
+    let mut report = ReplayReport::default();
+    let mut frame_start = Instant::now();
+
+    for call in &trace.calls {
+      let start = Instant::now();
+      self.replay(&cmd_buf.raw, call, &read_color_attachment)?;
+
+      if *call == TraceCall::Finish {
+        B::cmd_buf_wait_idle(&cmd_buf.raw)?;
+      }
+
+      let end = Instant::now();
+      report.record_call(call.kind(), end - start);
+
+      if *call == TraceCall::Finish {
+        report.frames.push(end - frame_start);
+        frame_start = end;
+      }
+    }
+
+    Ok(report)
+  }
+
+  fn replay(
+    &self,
+    cmd_buf: &B::CmdBuf,
+    call: &TraceCall,
+    read_color_attachment: impl Fn(&B::CmdBuf, &B::ColorAttachment) -> Result<(), B::Err>,
+  ) -> Result<(), B::Err> {
+    match call {
+      TraceCall::Blending(value) => B::cmd_buf_blending(cmd_buf, *value),
+      TraceCall::DepthTest(value) => B::cmd_buf_depth_test(cmd_buf, *value),
+      TraceCall::DepthWrite(value) => B::cmd_buf_depth_write(cmd_buf, *value),
+      TraceCall::StencilTest(value) => B::cmd_buf_stencil_test(cmd_buf, *value),
+      TraceCall::FaceCulling(value) => B::cmd_buf_face_culling(cmd_buf, *value),
+      TraceCall::Viewport(value) => B::cmd_buf_viewport(cmd_buf, *value),
+      TraceCall::Scissor(value) => B::cmd_buf_scissor(cmd_buf, *value),
+      TraceCall::ClearColor(value) => B::cmd_buf_clear_color(cmd_buf, value.clone()),
+      TraceCall::ClearDepth(value) => B::cmd_buf_clear_depth(cmd_buf, *value),
+      TraceCall::Srgb(value) => B::cmd_buf_srgb(cmd_buf, *value),
+      TraceCall::PrimitiveRestart(value) => B::cmd_buf_primitive_restart(cmd_buf, *value),
+
+      TraceCall::RenderTargets(id) => {
+        B::cmd_buf_bind_render_targets(cmd_buf, &self.get_render_targets(*id)?.raw)
+      }
+
+      TraceCall::Shader(id) => B::cmd_buf_bind_shader(cmd_buf, &self.get_shader(*id)?.raw),
+
+      TraceCall::Uniform { uniform, bytes } => {
+        B::cmd_buf_set_uniform(cmd_buf, &self.get_uniform(*uniform)?.raw, bytes)
+      }
+
+      TraceCall::UniformArray {
+        uniform,
+        first_element,
+        bytes,
+      } => B::cmd_buf_set_uniform_array(
+        cmd_buf,
+        &self.get_uniform(*uniform)?.raw,
+        *first_element,
+        bytes,
+      ),
+
+      TraceCall::Texture {
+        texture,
+        binding_point,
+      } => B::cmd_buf_bind_texture(
+        cmd_buf,
+        &self.get_texture(*texture)?.raw,
+        &self.get_texture_binding_point(*binding_point)?.raw,
+      ),
+
+      TraceCall::ColorAttachment {
+        color_attachment,
+        binding_point,
+      } => B::cmd_buf_bind_color_attachment(
+        cmd_buf,
+        &self.get_color_attachment(*color_attachment)?.raw,
+        &self.get_texture_binding_point(*binding_point)?.raw,
+      ),
+
+      TraceCall::DepthStencilAttachment {
+        depth_stencil_attachment,
+        binding_point,
+      } => B::cmd_buf_bind_depth_stencil_attachment(
+        cmd_buf,
+        &self
+          .get_depth_stencil_attachment(*depth_stencil_attachment)?
+          .raw,
+        &self.get_texture_binding_point(*binding_point)?.raw,
+      ),
+
+      TraceCall::AssociateTexture {
+        binding_point,
+        shader_binding_point,
+      } => B::cmd_buf_associate_texture_binding_point(
+        cmd_buf,
+        &self.get_texture_binding_point(*binding_point)?.raw,
+        &self
+          .get_shader_texture_binding_point(*shader_binding_point)?
+          .raw,
+      ),
+
+      TraceCall::UniformBuffer {
+        uniform_buffer,
+        binding_point,
+      } => B::cmd_buf_bind_uniform_buffer(
+        cmd_buf,
+        &self.get_uniform_buffer(*uniform_buffer)?.raw,
+        &self.get_uniform_buffer_binding_point(*binding_point)?.raw,
+      ),
+
+      TraceCall::AssociateUniformBuffer {
+        binding_point,
+        shader_binding_point,
+      } => B::cmd_buf_associate_uniform_buffer_binding_point(
+        cmd_buf,
+        &self.get_uniform_buffer_binding_point(*binding_point)?.raw,
+        &self
+          .get_shader_uniform_buffer_binding_point(*shader_binding_point)?
+          .raw,
+      ),
+
+      TraceCall::StorageBuffer {
+        storage_buffer,
+        binding_point,
+      } => B::cmd_buf_bind_storage_buffer(
+        cmd_buf,
+        &self.get_storage_buffer(*storage_buffer)?.raw,
+        &self.get_storage_buffer_binding_point(*binding_point)?.raw,
+      ),
+
+      TraceCall::AssociateStorageBuffer {
+        binding_point,
+        shader_binding_point,
+      } => B::cmd_buf_associate_storage_buffer_binding_point(
+        cmd_buf,
+        &self.get_storage_buffer_binding_point(*binding_point)?.raw,
+        &self
+          .get_shader_storage_buffer_binding_point(*shader_binding_point)?
+          .raw,
+      ),
+
+      TraceCall::ReadColorAttachment(id) => {
+        read_color_attachment(cmd_buf, &self.get_color_attachment(*id)?.raw)
+      }
+
+      TraceCall::ResolveTexture { src, dst } => B::cmd_buf_resolve_texture(
+        cmd_buf,
+        &self.get_texture(*src)?.raw,
+        &self.get_texture(*dst)?.raw,
+      ),
+
+      TraceCall::ResolveRenderTargets { src, dst } => B::cmd_buf_resolve_render_targets(
+        cmd_buf,
+        &self.get_render_targets(*src)?.raw,
+        &self.get_render_targets(*dst)?.raw,
+      ),
+
+      TraceCall::Draw {
+        vertex_array,
+        restart_index,
+      } => B::cmd_buf_draw_vertex_array(
+        cmd_buf,
+        &self.get_vertex_array(*vertex_array)?.raw,
+        *restart_index,
+      ),
+
+      TraceCall::DrawRange {
+        vertex_array,
+        start_vertex,
+        vertex_count,
+        start_instance,
+        instance_count,
+        restart_index,
+      } => B::cmd_buf_draw_vertex_array_range(
+        cmd_buf,
+        &self.get_vertex_array(*vertex_array)?.raw,
+        *start_vertex,
+        *vertex_count,
+        *start_instance,
+        *instance_count,
+        *restart_index,
+      ),
+
+      TraceCall::Execute(id) => B::cmd_buf_execute(cmd_buf, &self.get_cmd_buf(*id)?.raw),
+
+      TraceCall::Finish => B::cmd_buf_finish(cmd_buf),
+    }
+  }
+}
+
+/// Timings of the calls of a kind.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CallTimings {
+  pub count: usize,
+  pub total: Duration,
+  pub min: Duration,
+  pub max: Duration,
+}
+
+impl CallTimings {
+  fn new(duration: Duration) -> Self {
+    Self {
+      count: 1,
+      total: duration,
+      min: duration,
+      max: duration,
+    }
+  }
+
+  fn record(&mut self, duration: Duration) {
+    self.count += 1;
+    self.total += duration;
+    self.min = self.min.min(duration);
+    self.max = self.max.max(duration);
+  }
+
+  pub fn mean(&self) -> Duration {
+    self.total / self.count as u32
+  }
+}
+
+/// Timings measured while replaying a [`Trace`].
+///
+/// Displaying it prints a table of the timings of each kind of call, followed by a summary of the frame timings.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ReplayReport {
+  calls: BTreeMap<TraceCallKind, CallTimings>,
+  frames: Vec<Duration>,
+}
+
+impl ReplayReport {
+  fn record_call(&mut self, kind: TraceCallKind, duration: Duration) {
+    self
+      .calls
+      .entry(kind)
+      .and_modify(|timings| timings.record(duration))
+      .or_insert_with(|| CallTimings::new(duration));
+  }
+
+  /// Timings of the calls, by kind of call.
+  pub fn calls(&self) -> &BTreeMap<TraceCallKind, CallTimings> {
+    &self.calls
+  }
+
+  /// Duration of each frame, including waiting for the device to execute it.
+  pub fn frames(&self) -> &[Duration] {
+    &self.frames
+  }
+
+  /// Total duration of the replay.
+  pub fn total(&self) -> Duration {
+    self.frames.iter().sum()
+  }
+}
+
+impl Display for ReplayReport {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    writeln!(
+      f,
+      "{:<24} {:>8} {:>12} {:>12} {:>12} {:>12}",
+      "call", "count", "total", "mean", "min", "max"
+    )?;
+
+    for (kind, timings) in &self.calls {
+      writeln!(
+        f,
+        "{:<24} {:>8} {:>12} {:>12} {:>12} {:>12}",
+        format!("{kind:?}"),
+        timings.count,
+        format!("{:?}", timings.total),
+        format!("{:?}", timings.mean()),
+        format!("{:?}", timings.min),
+        format!("{:?}", timings.max),
+      )?;
+    }
+
+    let frame_count = self.frames.len();
+    write!(f, "{frame_count} frames in {:?}", self.total())?;
+
+    if let (Some(min), Some(max)) = (self.frames.iter().min(), self.frames.iter().max()) {
+      write!(
+        f,
+        " (mean {:?}, min {min:?}, max {max:?})",
+        self.total() / frame_count as u32
+      )?;
+    }
+
+    Ok(())
+  }
+}
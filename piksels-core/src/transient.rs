@@ -0,0 +1,213 @@
+//! Transient render targets.
+//!
+//! Post-processing chains need lots of intermediate render targets, most of which only live for a couple of passes.
+//! A [`TransientPool`] hands out such render targets by key — storage and attachment points — and takes them back
+//! when they are dropped, so that later passes of the same frame reuse their memory instead of allocating more.
+//!
+//! Render targets which were not used during a frame are destroyed by [`TransientPool::end_frame`], which is meant to
+//! be called once per frame, after presenting.
+
+use std::{
+  cell::RefCell,
+  collections::{HashMap, HashSet},
+  ops::Deref,
+};
+
+use piksels_backend::{
+  render_targets::{ColorAttachmentPoint, DepthStencilAttachmentPoint},
+  texture::Storage,
+  Backend,
+};
+
+use crate::{device::Device, render_targets::RenderTargets};
+
+/// Key identifying interchangeable transient render targets.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct TransientKey {
+  storage: Storage,
+  color_attachment_points: Vec<ColorAttachmentPoint>,
+  depth_stencil_attachment_point: Option<DepthStencilAttachmentPoint>,
+}
+
+impl TransientKey {
+  pub fn new(
+    storage: Storage,
+    color_attachment_points: impl IntoIterator<Item = ColorAttachmentPoint>,
+    depth_stencil_attachment_point: Option<DepthStencilAttachmentPoint>,
+  ) -> Self {
+    let mut color_attachment_points = color_attachment_points.into_iter().collect::<Vec<_>>();
+    color_attachment_points.sort_by_key(ColorAttachmentPoint::index);
+    color_attachment_points.dedup();
+
+    Self {
+      storage,
+      color_attachment_points,
+      depth_stencil_attachment_point,
+    }
+  }
+
+  pub fn storage(&self) -> Storage {
+    self.storage
+  }
+
+  pub fn color_attachment_points(&self) -> &[ColorAttachmentPoint] {
+    &self.color_attachment_points
+  }
+
+  pub fn depth_stencil_attachment_point(&self) -> Option<DepthStencilAttachmentPoint> {
+    self.depth_stencil_attachment_point
+  }
+}
+
+/// Render targets available in a pool, along with whether they were used during the current frame.
+#[derive(Debug)]
+struct Pooled<B>
+where
+  B: Backend,
+{
+  render_targets: RenderTargets<B>,
+  used: bool,
+}
+
+/// Pool of transient render targets.
+#[derive(Debug)]
+pub struct TransientPool<B>
+where
+  B: Backend,
+{
+  free: RefCell<HashMap<TransientKey, Vec<Pooled<B>>>>,
+}
+
+impl<B> Default for TransientPool<B>
+where
+  B: Backend,
+{
+  fn default() -> Self {
+    Self {
+      free: RefCell::new(HashMap::new()),
+    }
+  }
+}
+
+impl<B> TransientPool<B>
+where
+  B: Backend,
+{
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Get render targets matching `key`.
+  ///
+  /// Render targets returned to the pool are reused if any matches `key`; otherwise, new ones are created.
+  pub fn acquire(
+    &self,
+    device: &Device<B>,
+    key: &TransientKey,
+  ) -> Result<TransientRenderTargets<'_, B>, B::Err> {
+    let pooled = self
+      .free
+      .borrow_mut()
+      .get_mut(key)
+      .and_then(|free| free.pop());
+
+    let render_targets = match pooled {
+      Some(pooled) => pooled.render_targets,
+      None => device.new_render_targets(
+        key
+          .color_attachment_points
+          .iter()
+          .copied()
+          .collect::<HashSet<_>>(),
+        key.depth_stencil_attachment_point,
+        key.storage,
+      )?,
+    };
+
+    Ok(TransientRenderTargets {
+      pool: self,
+      key: key.clone(),
+      render_targets: Some(render_targets),
+    })
+  }
+
+  /// Number of render targets available in the pool.
+  pub fn free_len(&self) -> usize {
+    self.free.borrow().values().map(Vec::len).sum()
+  }
+
+  /// Destroy the render targets which were not used since the last call, and start a new frame.
+  pub fn end_frame(&mut self, device: &Device<B>) -> Result<(), B::Err> {
+    let free = self.free.get_mut();
+
+    for pooled in free.values_mut() {
+      let (used, unused): (Vec<_>, Vec<_>) = pooled.drain(..).partition(|pooled| pooled.used);
+      *pooled = used;
+
+      for unused in unused {
+        device.drop_render_targets(unused.render_targets)?;
+      }
+
+      for pooled in pooled.iter_mut() {
+        pooled.used = false;
+      }
+    }
+
+    free.retain(|_, pooled| !pooled.is_empty());
+    Ok(())
+  }
+
+  /// Destroy all the render targets of the pool.
+  pub fn clear(&mut self, device: &Device<B>) -> Result<(), B::Err> {
+    for (_, pooled) in self.free.get_mut().drain() {
+      for pooled in pooled {
+        device.drop_render_targets(pooled.render_targets)?;
+      }
+    }
+
+    Ok(())
+  }
+
+  fn release(&self, key: TransientKey, render_targets: RenderTargets<B>) {
+    self.free.borrow_mut().entry(key).or_default().push(Pooled {
+      render_targets,
+      used: true,
+    });
+  }
+}
+
+/// Render targets borrowed from a [`TransientPool`].
+///
+/// They are given back to the pool when dropped.
+#[derive(Debug)]
+pub struct TransientRenderTargets<'a, B>
+where
+  B: Backend,
+{
+  pool: &'a TransientPool<B>,
+  key: TransientKey,
+  render_targets: Option<RenderTargets<B>>,
+}
+
+impl<'a, B> Deref for TransientRenderTargets<'a, B>
+where
+  B: Backend,
+{
+  type Target = RenderTargets<B>;
+
+  fn deref(&self) -> &Self::Target {
+    // always set until dropped
+    self.render_targets.as_ref().unwrap()
+  }
+}
+
+impl<'a, B> Drop for TransientRenderTargets<'a, B>
+where
+  B: Backend,
+{
+  fn drop(&mut self) {
+    if let Some(render_targets) = self.render_targets.take() {
+      self.pool.release(self.key.clone(), render_targets);
+    }
+  }
+}
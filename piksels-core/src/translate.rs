@@ -0,0 +1,119 @@
+//! Shading language translation.
+//!
+//! Backends don’t all speak the same shading language: desktop OpenGL wants GLSL, OpenGL ES and WebGL want ESSL, and
+//! WebGPU wants WGSL (see [`ShaderDialect`]). This module translates shaders written in a canonical dialect — GLSL
+//! 450 core, with explicit `layout(location = …)` on inputs and outputs — into the dialect of a backend, so that the
+//! same sources run everywhere.
+//!
+//! Only the vertex, fragment and compute stages can be translated. Translation doesn’t adapt coordinate conventions:
+//! a shader written for OpenGL clip space still outputs OpenGL clip space positions once translated to WGSL.
+
+use naga::{
+  back::{glsl, wgsl},
+  front::glsl::{Frontend, Options},
+  proc::BoundsCheckPolicies,
+  valid::{Capabilities, ValidationFlags, Validator},
+  ShaderStage,
+};
+use piksels_backend::{
+  error::Error,
+  shader::{ShaderDialect, ShaderSources},
+};
+
+use crate::preprocessor::PreprocessedSources;
+
+/// Translate the source of a single stage from canonical GLSL to `dialect`.
+pub fn translate(src: &str, stage: ShaderStage, dialect: ShaderDialect) -> Result<String, Error> {
+  let error = |what: &str, details: String| Error::ShaderTranslation {
+    reason: format!("{what} {stage:?} stage: {details}"),
+  };
+
+  let module = Frontend::default()
+    .parse(&Options::from(stage), src)
+    .map_err(|errors| {
+      let details = errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ");
+      error("cannot parse", details)
+    })?;
+
+  let info = Validator::new(ValidationFlags::all(), Capabilities::all())
+    .validate(&module)
+    .map_err(|err| error("invalid", err.into_inner().to_string()))?;
+
+  let version = match dialect {
+    ShaderDialect::Glsl330 => glsl::Version::Desktop(330),
+    ShaderDialect::Essl300 => glsl::Version::Embedded {
+      version: 300,
+      is_webgl: false,
+    },
+    ShaderDialect::Wgsl => {
+      return wgsl::write_string(&module, &info, wgsl::WriterFlags::empty())
+        .map_err(|err| error("cannot write", err.to_string()));
+    }
+  };
+
+  let options = glsl::Options {
+    version,
+    writer_flags: glsl::WriterFlags::empty(),
+    ..glsl::Options::default()
+  };
+  let pipeline_options = glsl::PipelineOptions {
+    shader_stage: stage,
+    entry_point: "main".to_owned(),
+    multiview: None,
+  };
+
+  let mut output = String::new();
+  glsl::Writer::new(
+    &mut output,
+    &module,
+    &info,
+    &options,
+    &pipeline_options,
+    BoundsCheckPolicies::default(),
+  )
+  .and_then(|mut writer| writer.write())
+  .map_err(|err| error("cannot write", err.to_string()))?;
+
+  Ok(output)
+}
+
+/// Translate every stage of `sources` from canonical GLSL to `dialect`; absent stages stay absent.
+///
+/// Defines of `sources` are injected before translation.
+pub fn translate_sources(
+  sources: &ShaderSources,
+  dialect: ShaderDialect,
+) -> Result<PreprocessedSources, Error> {
+  let specialized = PreprocessedSources::specialize(sources);
+
+  for (name, src) in [
+    ("tessellation control", &specialized.tess_ctrl_stage),
+    ("tessellation evaluation", &specialized.tess_eval_stage),
+    ("geometry", &specialized.geometry_stage),
+  ] {
+    if !src.is_empty() {
+      return Err(Error::ShaderTranslation {
+        reason: format!("{name} stages cannot be translated"),
+      });
+    }
+  }
+
+  let translate = |src: &str, stage| {
+    if src.is_empty() {
+      Ok(String::new())
+    } else {
+      translate(src, stage, dialect)
+    }
+  };
+
+  Ok(PreprocessedSources {
+    vertex_stage: translate(&specialized.vertex_stage, ShaderStage::Vertex)?,
+    fragment_stage: translate(&specialized.fragment_stage, ShaderStage::Fragment)?,
+    compute_stage: translate(&specialized.compute_stage, ShaderStage::Compute)?,
+    ..PreprocessedSources::default()
+  })
+}
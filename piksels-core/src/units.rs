@@ -0,0 +1,401 @@
+//! Units for indexed scarce resources, such as textures and uniform buffers.
+//!
+//! Some backends have the concept of « units », and this module exposes the [`Units`] type which helps with units
+//! operations, such as getting the next available unit, etc.
+//!
+//! Units are shared by all the stages of a shader, but some devices limit how many of them a given stage can use; see
+//! [`Units::set_stage_limit`].
+
+use std::collections::{BTreeMap, HashMap};
+
+use piksels_backend::{error::Error, shader::Stage, Backend};
+
+pub use piksels_backend::unit::{U32Unit, Unit};
+
+/// Strategy to use when all the units are in use.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum SpillStrategy {
+  /// Fail with [`Error::NoMoreUnits`].
+  #[default]
+  Fail,
+
+  /// Fail with [`Error::NoMoreUnitsInUse`], listing the resources currently owning the units.
+  Diagnose,
+
+  /// Steal the unit that has been in use for the longest time.
+  ///
+  /// The returned [`UnitEntry`] carries the scarce index of the evicted resource, which must then be rebound before
+  /// being used again; for instance by splitting the draw using it.
+  Steal,
+}
+
+/// A unit in use.
+#[derive(Debug, Eq, PartialEq)]
+struct InUseUnit<B>
+where
+  B: Backend,
+{
+  /// Scarce index of the resource owning the unit.
+  scarce_index: B::ScarceIndex,
+
+  /// Stage the unit is used by.
+  stage: Stage,
+
+  /// Used to find the unit that has been in use for the longest time.
+  acquired_at: u64,
+}
+
+/// An idle unit.
+#[derive(Debug, Eq, PartialEq)]
+struct IdleUnit<B>
+where
+  B: Backend,
+{
+  /// Scarce index of the resource still bound to the unit, if any.
+  scarce_index: Option<B::ScarceIndex>,
+
+  /// Used to find the least recently used unit.
+  idled_at: u64,
+}
+
+/// Usage statistics of [`Units`], to see how close to the limit scenes run.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct UnitsTelemetry {
+  /// Highest number of units in use at the same time.
+  pub high_water_mark: usize,
+
+  /// Number of times an idle unit was reused for another resource.
+  pub reuses: u64,
+
+  /// Number of times all the units were in use when getting one, whatever the [`SpillStrategy`].
+  pub exhaustions: u64,
+}
+
+/// A resource bound to a unit.
+#[derive(Debug, Eq, PartialEq)]
+pub struct UnitBinding<B, U>
+where
+  B: Backend,
+{
+  pub unit: U,
+  /// Scarce index of the bound resource.
+  pub scarce_index: B::ScarceIndex,
+  /// Whether the unit is in use, or idle with the resource still bound.
+  pub in_use: bool,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct Units<B, U>
+where
+  B: Backend,
+  U: Unit,
+{
+  next_unit: U,
+  max_units: U,
+  spill_strategy: SpillStrategy,
+  stage_limits: HashMap<Stage, usize>,
+  acquisitions: u64,
+  idles: u64,
+  in_use_units: BTreeMap<U, InUseUnit<B>>,
+  idle_units: HashMap<U, IdleUnit<B>>,
+  telemetry: UnitsTelemetry,
+}
+
+impl<B, U> Units<B, U>
+where
+  B: Backend,
+  U: Unit,
+{
+  pub fn new(max_unit: U) -> Self {
+    Self {
+      next_unit: Default::default(),
+      max_units: max_unit,
+      spill_strategy: SpillStrategy::default(),
+      stage_limits: HashMap::default(),
+      acquisitions: 0,
+      idles: 0,
+      in_use_units: BTreeMap::default(),
+      idle_units: HashMap::default(),
+      telemetry: UnitsTelemetry::default(),
+    }
+  }
+
+  pub fn spill_strategy(&self) -> SpillStrategy {
+    self.spill_strategy
+  }
+
+  pub fn set_spill_strategy(&mut self, spill_strategy: SpillStrategy) {
+    self.spill_strategy = spill_strategy;
+  }
+
+  /// Maximum number of units `stage` can use at once, if limited.
+  pub fn stage_limit(&self, stage: Stage) -> Option<usize> {
+    self.stage_limits.get(&stage).copied()
+  }
+
+  /// Limit the number of units `stage` can use at once.
+  ///
+  /// Stages without a limit can use all the units.
+  pub fn set_stage_limit(&mut self, stage: Stage, max_units: usize) {
+    self.stage_limits.insert(stage, max_units);
+  }
+
+  /// Whether `stage` uses as many units as it is allowed to.
+  fn is_stage_exhausted(&self, stage: Stage) -> bool {
+    self.stage_limit(stage).is_some_and(|max_units| {
+      self
+        .in_use_units
+        .values()
+        .filter(|in_use| in_use.stage == stage)
+        .count()
+        >= max_units
+    })
+  }
+
+  pub fn telemetry(&self) -> UnitsTelemetry {
+    self.telemetry
+  }
+
+  /// Reset the telemetry, e.g. at the beginning of a scene.
+  ///
+  /// The high-water mark starts again from the number of units currently in use.
+  pub fn reset_telemetry(&mut self) {
+    self.telemetry = UnitsTelemetry {
+      high_water_mark: self.in_use_units.len(),
+      ..UnitsTelemetry::default()
+    };
+  }
+
+  fn update_high_water_mark(&mut self) {
+    self.telemetry.high_water_mark = self.telemetry.high_water_mark.max(self.in_use_units.len());
+  }
+
+  /// Get a unit to bind a resource used by `stage` to.
+  ///
+  /// If `stage` already uses as many units as its limit allows, the spill strategy applies to the units of `stage`.
+  pub fn get_unit(
+    &mut self,
+    scarce_index: B::ScarceIndex,
+    stage: Stage,
+  ) -> Result<UnitEntry<B, U>, B::Err> {
+    let entry = if self.is_stage_exhausted(stage) {
+      self.telemetry.exhaustions += 1;
+      self.spill(Some(stage))?
+    } else if self.next_unit < self.max_units {
+      // we still can use a fresh unit
+      let unit = self.next_unit.clone();
+      self.next_unit = self.next_unit.next_unit();
+
+      UnitEntry {
+        unit,
+        current_scarce_index: None,
+      }
+    } else {
+      // we have exhausted the device units; try to reuse an idle one and if we cannot, spill
+      match self.reuse_unit() {
+        Some(entry) => {
+          self.telemetry.reuses += 1;
+          entry
+        }
+
+        None => {
+          self.telemetry.exhaustions += 1;
+          self.spill(None)?
+        }
+      }
+    };
+
+    self.in_use_units.insert(
+      entry.unit.clone(),
+      InUseUnit {
+        scarce_index,
+        stage,
+        acquired_at: self.acquisitions,
+      },
+    );
+    self.acquisitions += 1;
+    self.update_high_water_mark();
+
+    Ok(entry)
+  }
+
+  /// Get back the idle unit a resource is still bound to, if any, and mark it in use by `stage`.
+  ///
+  /// The resource doesn’t need to be bound again. [`None`] is returned if `stage` already uses as many units as its
+  /// limit allows.
+  pub fn get_bound_unit(&mut self, scarce_index: &B::ScarceIndex, stage: Stage) -> Option<U> {
+    if self.is_stage_exhausted(stage) {
+      return None;
+    }
+
+    let unit = self
+      .idle_units
+      .iter()
+      .find(|(_, idle)| idle.scarce_index.as_ref() == Some(scarce_index))
+      .map(|(unit, _)| unit.clone())?;
+    self.in_use(unit.clone(), stage);
+    Some(unit)
+  }
+
+  /// Resource a unit is in use by, if any.
+  ///
+  /// A resource that got a unit but is not its owner anymore had its unit stolen (see [`SpillStrategy::Steal`]).
+  pub fn owner(&self, unit: &U) -> Option<&B::ScarceIndex> {
+    self
+      .in_use_units
+      .get(unit)
+      .map(|in_use| &in_use.scarce_index)
+  }
+
+  /// Resources bound to units, sorted by unit.
+  pub fn bindings(&self) -> Vec<UnitBinding<B, U>> {
+    let in_use = self.in_use_units.iter().map(|(unit, in_use)| UnitBinding {
+      unit: unit.clone(),
+      scarce_index: in_use.scarce_index.clone(),
+      in_use: true,
+    });
+    let idle = self.idle_units.iter().filter_map(|(unit, idle)| {
+      idle.scarce_index.as_ref().map(|scarce_index| UnitBinding {
+        unit: unit.clone(),
+        scarce_index: scarce_index.clone(),
+        in_use: false,
+      })
+    });
+
+    let mut bindings: Vec<_> = in_use.chain(idle).collect();
+    bindings.sort_by(|a, b| a.unit.cmp(&b.unit));
+    bindings
+  }
+
+  /// Forget about a resource bound to idle units, e.g. because it was dropped.
+  ///
+  /// The units stay idle, but are not seen as bound to the resource anymore.
+  pub fn forget(&mut self, scarce_index: &B::ScarceIndex) {
+    for idle in self.idle_units.values_mut() {
+      if idle.scarce_index.as_ref() == Some(scarce_index) {
+        idle.scarce_index = None;
+      }
+    }
+  }
+
+  /// Forget about every resource bound to idle units, e.g. because other resources were bound behind our back.
+  pub fn forget_all(&mut self) {
+    for idle in self.idle_units.values_mut() {
+      idle.scarce_index = None;
+    }
+  }
+
+  /// Try to reuse a binding. Return [`None`] if no binding is available, or a [`UnitEntry`] mapping a unit with the
+  /// currently bound scarce resource index otherwise.
+  ///
+  /// Idle units without any resource bound are reused first; then, the least recently used unit is evicted, as its
+  /// resource is the least likely to be used again soon.
+  fn reuse_unit(&mut self) -> Option<UnitEntry<B, U>> {
+    let unit = self
+      .idle_units
+      .iter()
+      .min_by_key(|(unit, idle)| (idle.scarce_index.is_some(), idle.idled_at, (*unit).clone()))
+      .map(|(unit, _)| unit.clone())?;
+    let idle = self.idle_units.remove(&unit)?;
+
+    Some(UnitEntry {
+      unit,
+      current_scarce_index: idle.scarce_index,
+    })
+  }
+
+  /// Apply the spill strategy when all units are in use, or all the units `stage` is allowed to use.
+  fn spill(&mut self, stage: Option<Stage>) -> Result<UnitEntry<B, U>, B::Err> {
+    let in_stage = |in_use: &InUseUnit<B>| stage.map_or(true, |stage| in_use.stage == stage);
+
+    match self.spill_strategy {
+      SpillStrategy::Fail => Err(Error::NoMoreUnits.into()),
+
+      SpillStrategy::Diagnose => {
+        let owners = self
+          .in_use_units
+          .iter()
+          .filter(|(_, in_use)| in_stage(in_use))
+          .map(|(unit, in_use)| format!("{unit:?} -> {:?}", in_use.scarce_index))
+          .collect::<Vec<_>>()
+          .join(", ");
+
+        Err(Error::NoMoreUnitsInUse { owners }.into())
+      }
+
+      SpillStrategy::Steal => {
+        let unit = self
+          .in_use_units
+          .iter()
+          .filter(|(_, in_use)| in_stage(in_use))
+          .min_by_key(|(_, in_use)| in_use.acquired_at)
+          .map(|(unit, _)| unit.clone())
+          .ok_or(Error::NoMoreUnits)?;
+        let current_scarce_index = self
+          .in_use_units
+          .remove(&unit)
+          .map(|in_use| in_use.scarce_index);
+
+        Ok(UnitEntry {
+          unit,
+          current_scarce_index,
+        })
+      }
+    }
+  }
+
+  /// Mark a unit as idle.
+  ///
+  /// The resource bound to the unit stays bound, but the unit can be reused for another resource.
+  pub fn idle(&mut self, unit: U) {
+    if let Some(in_use) = self.in_use_units.remove(&unit) {
+      self.idle_units.insert(
+        unit,
+        IdleUnit {
+          scarce_index: Some(in_use.scarce_index),
+          idled_at: self.idles,
+        },
+      );
+      self.idles += 1;
+    }
+  }
+
+  /// Mark a unit as non-idle (in-use) by `stage`.
+  ///
+  /// Idle units not bound to any resource anymore stay idle.
+  pub fn in_use(&mut self, unit: U, stage: Stage) {
+    if let Some(scarce_index) = self
+      .idle_units
+      .get(&unit)
+      .and_then(|idle| idle.scarce_index.clone())
+    {
+      self.idle_units.remove(&unit);
+      self.in_use_units.insert(
+        unit,
+        InUseUnit {
+          scarce_index,
+          stage,
+          acquired_at: self.acquisitions,
+        },
+      );
+      self.acquisitions += 1;
+      self.update_high_water_mark();
+    }
+  }
+}
+
+/// Unit entry.
+///
+/// A unit entry always contains a unit (`U`), along with an optional scarce resource index (`Option<B::ScarceIndex>`).
+#[derive(Debug, Eq, PartialEq)]
+pub struct UnitEntry<B, U>
+where
+  B: Backend,
+  U: Unit,
+{
+  /// Unit the entry refers to.
+  pub unit: U,
+
+  /// Resource that was bound to this unit before; [`None`] if no resource was bound to this unit.
+  pub current_scarce_index: Option<B::ScarceIndex>,
+}
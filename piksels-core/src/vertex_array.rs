@@ -1,10 +1,23 @@
 use std::{
   marker::PhantomData,
-  ops::{Deref, DerefMut, Range, RangeFrom, RangeFull, RangeTo, RangeToInclusive},
+  ops::{Bound, Deref, DerefMut, Range, RangeBounds},
+  sync::{Mutex, Weak},
 };
 
-use piksels_backend::{vertex_array::DataSelector, Backend};
+use piksels_backend::{
+  error::Error,
+  vertex_array::{DataSelector, MapAccess, VertexArrayUpdate},
+  Backend, Scarce,
+};
+
+use crate::{
+  cache::{Cache, ResourceId, TrackingTag},
+  gc,
+};
 
+/// Handle on vertex data.
+///
+/// The vertex array is destroyed once every handle on it is dropped.
 #[derive(Debug)]
 pub struct VertexArray<B>
 where
@@ -12,23 +25,122 @@ where
 {
   pub(crate) raw: B::VertexArray,
   vertex_count: usize,
+  instance_count: Option<usize>,
+  cache: Weak<Mutex<Cache<B>>>,
+  pub(crate) tag: TrackingTag,
+}
+
+impl<B> Drop for VertexArray<B>
+where
+  B: Backend,
+{
+  fn drop(&mut self) {
+    let id = self.id();
+    gc::drop_handle(&self.cache, self.tag, |cache, tag| {
+      cache.drop_vertex_array(&id, tag)
+    });
+  }
 }
 
 impl<B> VertexArray<B>
 where
   B: Backend,
 {
-  pub(crate) fn from_raw(raw: B::VertexArray, vertex_count: usize) -> Self {
-    Self { raw, vertex_count }
+  pub(crate) fn from_raw(
+    raw: B::VertexArray,
+    vertex_count: usize,
+    instance_count: Option<usize>,
+    cache: Weak<Mutex<Cache<B>>>,
+    tag: TrackingTag,
+  ) -> Self {
+    Self {
+      raw,
+      vertex_count,
+      instance_count,
+      cache,
+      tag,
+    }
+  }
+
+  /// Another handle on the same vertex array, e.g. to defer drawing it.
+  ///
+  /// The handle doesn’t count as a handle on the vertex array: it doesn’t keep it alive.
+  pub(crate) fn scarce_clone(&self) -> Self {
+    Self {
+      raw: self.raw.scarce_clone(),
+      vertex_count: self.vertex_count,
+      instance_count: self.instance_count,
+      cache: Weak::new(),
+      tag: TrackingTag::default(),
+    }
+  }
+
+  pub fn id(&self) -> ResourceId<B> {
+    self.raw.scarce_index()
+  }
+
+  /// Partially update the data of the vertex array.
+  ///
+  /// Updates with no bytes are ignored. Index updates must be aligned on indices.
+  pub fn update(&self, update: VertexArrayUpdate) -> Result<(), B::Err> {
+    if update.bytes().is_empty() {
+      return Ok(());
+    }
+
+    let index_len = std::mem::size_of::<u32>();
+    if *update.selector() == DataSelector::Indices
+      && (update.offset() % index_len != 0 || update.bytes().len() % index_len != 0)
+    {
+      return Err(
+        Error::InvalidVertexArrayUpdate {
+          reason: format!(
+            "{} bytes at offset {} are not aligned on indices",
+            update.bytes().len(),
+            update.offset()
+          ),
+        }
+        .into(),
+      );
+    }
+
+    B::update_vertex_array(&self.raw, update)
   }
 
-  pub fn map(&self, data_selector: DataSelector) -> Result<VertexArrayMappedBytes<B>, B::Err> {
+  pub fn map(&self, data_selector: DataSelector) -> Result<VertexArrayMappedBytes<'_, B>, B::Err> {
     B::map_vertex_array_bytes(&self.raw, data_selector).map(VertexArrayMappedBytes::from_raw)
   }
 
+  /// Map a range of bytes of the selected data.
+  ///
+  /// Only `byte_range` is mapped — and flushed when the mapped bytes are dropped — which is much cheaper than
+  /// [`VertexArray::map`] when streaming small updates into a large vertex array.
+  pub fn map_range(
+    &self,
+    data_selector: DataSelector,
+    byte_range: Range<usize>,
+    access: MapAccess,
+  ) -> Result<VertexArrayMappedBytes<'_, B>, B::Err> {
+    if byte_range.is_empty() {
+      return Err(
+        Error::InvalidMapRange {
+          reason: format!("empty byte range {byte_range:?}"),
+        }
+        .into(),
+      );
+    }
+
+    B::map_vertex_array_bytes_range(&self.raw, data_selector, byte_range, access)
+      .map(VertexArrayMappedBytes::from_raw)
+  }
+
   pub fn vertex_count(&self) -> usize {
     self.vertex_count
   }
+
+  /// Maximum number of instances that can be drawn, if limited by per-instance data.
+  pub fn instance_count(&self) -> Option<usize> {
+    self.instance_count
+  }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -84,14 +196,20 @@ where
   }
 }
 
+/// A range of vertices and instances of a [`VertexArray`] to draw.
+///
+/// Views are obtained with [`View::view`], which checks the range of vertices against the vertex array, and draw a
+/// single instance by default; see [`VertexArrayView::with_instances`] to draw more.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct VertexArrayView<'a, B>
 where
   B: Backend,
 {
   vertex_array: &'a B::VertexArray,
+  max_instance_count: Option<usize>,
   start_vertex: usize,
   vertex_count: usize,
+  start_instance: usize,
   instance_count: usize,
 }
 
@@ -107,104 +225,135 @@ where
     self.start_vertex
   }
 
-  pub fn set_start_vertex(mut self, start_vertex: usize) -> Self {
-    self.start_vertex = start_vertex;
-    self
-  }
-
   pub fn vertex_count(&self) -> usize {
     self.vertex_count
   }
 
-  pub fn set_vertex_count(mut self, vertex_count: usize) -> Self {
-    self.vertex_count = vertex_count;
-    self
+  pub fn start_instance(&self) -> usize {
+    self.start_instance
   }
 
   pub fn instance_count(&self) -> usize {
     self.instance_count
   }
 
-  pub fn set_instance_count(mut self, instance_count: usize) -> Self {
-    self.instance_count = instance_count;
-    self
+  /// Draw the instances in `range`.
+  ///
+  /// If the vertex array has per-instance data, the range must fit in it; otherwise, the range must be bounded.
+  pub fn with_instances(mut self, range: impl RangeBounds<usize>) -> Result<Self, B::Err> {
+    let Range { start, end } = resolve_range(&range, self.max_instance_count, "instance")?;
+    self.start_instance = start;
+    self.instance_count = end - start;
+    Ok(self)
+  }
+
+  /// Take a handle on the vertex array, e.g. to defer drawing the view.
+  pub(crate) fn to_owned_view(&self) -> OwnedVertexArrayView<B> {
+    OwnedVertexArrayView {
+      vertex_array: self.vertex_array.scarce_clone(),
+      max_instance_count: self.max_instance_count,
+      start_vertex: self.start_vertex,
+      vertex_count: self.vertex_count,
+      start_instance: self.start_instance,
+      instance_count: self.instance_count,
+    }
   }
 }
 
-/// A helper trait to obtain a [`VertexArrayView`] from a [`VertexArray`].
-pub trait View<B, R>
+/// A [`VertexArrayView`] owning a handle on its vertex array.
+#[derive(Debug)]
+pub(crate) struct OwnedVertexArrayView<B>
 where
   B: Backend,
 {
-  fn view(&self, range: R) -> VertexArrayView<B>;
+  vertex_array: B::VertexArray,
+  max_instance_count: Option<usize>,
+  start_vertex: usize,
+  vertex_count: usize,
+  start_instance: usize,
+  instance_count: usize,
 }
 
-impl<B> View<B, RangeFull> for VertexArray<B>
+impl<B> OwnedVertexArrayView<B>
 where
   B: Backend,
 {
-  fn view(&self, _: RangeFull) -> VertexArrayView<B> {
+  pub(crate) fn view(&self) -> VertexArrayView<'_, B> {
     VertexArrayView {
-      vertex_array: &self.raw,
-      start_vertex: 0,
+      vertex_array: &self.vertex_array,
+      max_instance_count: self.max_instance_count,
+      start_vertex: self.start_vertex,
       vertex_count: self.vertex_count,
-      instance_count: 1,
+      start_instance: self.start_instance,
+      instance_count: self.instance_count,
     }
   }
 }
 
-impl<B> View<B, Range<usize>> for VertexArray<B>
+/// A helper trait to obtain a [`VertexArrayView`] from a [`VertexArray`].
+pub trait View<B, R>
 where
   B: Backend,
 {
-  fn view(&self, range: Range<usize>) -> VertexArrayView<B> {
-    VertexArrayView {
-      vertex_array: &self.raw,
-      start_vertex: range.start,
-      vertex_count: range.end,
-      instance_count: 1,
-    }
-  }
+  /// View the vertices in `range`.
+  ///
+  /// The range must fit in the vertex array.
+  fn view(&self, range: R) -> Result<VertexArrayView<'_, B>, B::Err>;
 }
 
-impl<B> View<B, RangeFrom<usize>> for VertexArray<B>
+impl<B, R> View<B, R> for VertexArray<B>
 where
   B: Backend,
+  R: RangeBounds<usize>,
 {
-  fn view(&self, range: RangeFrom<usize>) -> VertexArrayView<B> {
-    VertexArrayView {
+  fn view(&self, range: R) -> Result<VertexArrayView<'_, B>, B::Err> {
+    let Range { start, end } = resolve_range(&range, Some(self.vertex_count), "vertex")?;
+
+    Ok(VertexArrayView {
       vertex_array: &self.raw,
-      start_vertex: range.start,
-      vertex_count: self.vertex_count - range.start,
+      max_instance_count: self.instance_count,
+      start_vertex: start,
+      vertex_count: end - start,
+      start_instance: 0,
       instance_count: 1,
-    }
+    })
   }
 }
 
-impl<B> View<B, RangeTo<usize>> for VertexArray<B>
-where
-  B: Backend,
-{
-  fn view(&self, range: RangeTo<usize>) -> VertexArrayView<B> {
-    VertexArrayView {
-      vertex_array: &self.raw,
-      start_vertex: 0,
-      vertex_count: range.end - 1,
-      instance_count: 1,
-    }
+/// Resolve `range` against `len`, if any.
+fn resolve_range(
+  range: &impl RangeBounds<usize>,
+  len: Option<usize>,
+  what: &str,
+) -> Result<Range<usize>, Error> {
+  let invalid = |reason: String| Err(Error::InvalidVertexArrayView { reason });
+
+  let start = match range.start_bound() {
+    Bound::Included(&start) => Some(start),
+    Bound::Excluded(&start) => start.checked_add(1),
+    Bound::Unbounded => Some(0),
+  };
+  let end = match range.end_bound() {
+    Bound::Included(&end) => end.checked_add(1),
+    Bound::Excluded(&end) => Some(end),
+    Bound::Unbounded => match len {
+      Some(len) => Some(len),
+      None => return invalid(format!("unbounded {what} range without {what} data")),
+    },
+  };
+
+  let (Some(start), Some(end)) = (start, end) else {
+    return invalid(format!("{what} range overflows"));
+  };
+
+  if start > end {
+    return invalid(format!("{what} range {start}..{end} is reversed"));
   }
-}
 
-impl<B> View<B, RangeToInclusive<usize>> for VertexArray<B>
-where
-  B: Backend,
-{
-  fn view(&self, range: RangeToInclusive<usize>) -> VertexArrayView<B> {
-    VertexArrayView {
-      vertex_array: &self.raw,
-      start_vertex: 0,
-      vertex_count: range.end,
-      instance_count: 1,
-    }
+  match len {
+    Some(len) if end > len => invalid(format!(
+      "{what} range {start}..{end} is out of bounds (count is {len})"
+    )),
+    _ => Ok(start..end),
   }
 }
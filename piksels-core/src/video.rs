@@ -0,0 +1,254 @@
+//! Offscreen video frame export.
+//!
+//! A [`VideoExporter`] renders frames into a pool of render targets and reads them back asynchronously. Frames are
+//! handed to a user callback in the order they were rendered, as soon as their readbacks are complete, which allows
+//! headless batch rendering and video capture without stalling the GPU on every frame.
+
+use std::collections::VecDeque;
+
+use piksels_backend::{error::Error, extension::readback::BackendReadback, texture::Storage};
+
+use crate::{cmd_buf::CmdBuf, readback::Readback, render_targets::RenderTargets};
+
+/// Format of the frames handed to the callback.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum FrameFormat {
+  /// Interleaved 8-bit RGBA.
+  #[default]
+  Rgba8,
+
+  /// Planar YUV 4:2:0 (I420), BT.601 limited range.
+  ///
+  /// The Y plane is followed by the U and V planes, each subsampled by two in both dimensions (rounded up).
+  Yuv420,
+}
+
+/// A frame read back from the GPU.
+#[derive(Debug)]
+pub struct Frame<'a> {
+  /// Index of the frame, starting at `0`.
+  pub index: u64,
+
+  pub width: u32,
+
+  pub height: u32,
+
+  pub format: FrameFormat,
+
+  /// Contiguous texels of the frame, top row first.
+  pub data: &'a [u8],
+}
+
+/// Render frames into a pool of render targets and read them back in order.
+#[derive(Debug)]
+pub struct VideoExporter<B, F>
+where
+  B: BackendReadback,
+{
+  pool: Vec<RenderTargets<B>>,
+  color_attachment: usize,
+  width: u32,
+  height: u32,
+  format: FrameFormat,
+  next_frame: u64,
+  pending: VecDeque<(u64, Readback<B>)>,
+  rgba: Vec<u8>,
+  converted: Vec<u8>,
+  callback: F,
+}
+
+impl<B, F> VideoExporter<B, F>
+where
+  B: BackendReadback,
+  F: FnMut(Frame),
+{
+  /// Create a video exporter rendering into `pool`.
+  ///
+  /// All the render targets of the pool must have the same 2D storage; frames are read back from their color
+  /// attachment at index `color_attachment`. The size of the pool is the maximum number of frames in flight.
+  pub fn new(
+    pool: Vec<RenderTargets<B>>,
+    color_attachment: usize,
+    format: FrameFormat,
+    callback: F,
+  ) -> Result<Self, B::Err> {
+    let storage = pool
+      .first()
+      .map(RenderTargets::storage)
+      .ok_or_else(|| Error::VideoExport {
+        reason: "empty render targets pool".to_owned(),
+      })?;
+
+    let Storage::Flat2D { width, height } = storage else {
+      return Err(
+        Error::VideoExport {
+          reason: format!("{storage:?} is not a 2D storage"),
+        }
+        .into(),
+      );
+    };
+
+    if let Some(render_targets) = pool.iter().find(|rt| rt.storage() != storage) {
+      return Err(
+        Error::VideoExport {
+          reason: format!(
+            "render targets storage mismatch: {:?} and {storage:?}",
+            render_targets.storage()
+          ),
+        }
+        .into(),
+      );
+    }
+
+    Ok(Self {
+      pool,
+      color_attachment,
+      width,
+      height,
+      format,
+      next_frame: 0,
+      pending: VecDeque::new(),
+      rgba: vec![0; width as usize * height as usize * 4],
+      converted: Vec::new(),
+      callback,
+    })
+  }
+
+  /// Number of frames rendered but not handed to the callback yet.
+  pub fn frames_in_flight(&self) -> usize {
+    self.pending.len()
+  }
+
+  /// Render a frame.
+  ///
+  /// The next render targets of the pool are bound to `cmd_buf` and passed to `record`, which records the frame. The
+  /// readback is then recorded and `cmd_buf` is finished. If all the render targets of the pool are in flight, this
+  /// function waits for the oldest frame first.
+  pub fn render_frame(
+    &mut self,
+    cmd_buf: &CmdBuf<B>,
+    record: impl FnOnce(&CmdBuf<B>, &RenderTargets<B>) -> Result<(), B::Err>,
+  ) -> Result<(), B::Err> {
+    if self.pending.len() == self.pool.len() {
+      self.deliver_oldest()?;
+    }
+
+    let index = self.next_frame;
+    let render_targets = &self.pool[(index % self.pool.len() as u64) as usize];
+
+    cmd_buf.render_targets(render_targets)?;
+    record(cmd_buf, render_targets)?;
+    let readback = cmd_buf.read_color_attachment(render_targets, self.color_attachment)?;
+    cmd_buf.finish()?;
+
+    self.pending.push_back((index, readback));
+    self.next_frame += 1;
+
+    self.poll()
+  }
+
+  /// Hand the frames which readbacks are complete to the callback, without blocking.
+  pub fn poll(&mut self) -> Result<(), B::Err> {
+    while let Some((_, readback)) = self.pending.front() {
+      if !readback.is_ready()? {
+        break;
+      }
+
+      self.deliver_oldest()?;
+    }
+
+    Ok(())
+  }
+
+  /// Wait for all the frames in flight and hand them to the callback.
+  pub fn flush(&mut self) -> Result<(), B::Err> {
+    while !self.pending.is_empty() {
+      self.deliver_oldest()?;
+    }
+
+    Ok(())
+  }
+
+  fn deliver_oldest(&mut self) -> Result<(), B::Err> {
+    let Some((index, readback)) = self.pending.pop_front() else {
+      return Ok(());
+    };
+
+    readback.read(&mut self.rgba)?;
+    flip_rows(&mut self.rgba, self.width as usize * 4);
+
+    let data = match self.format {
+      FrameFormat::Rgba8 => &self.rgba,
+      FrameFormat::Yuv420 => {
+        rgba_to_i420(&self.rgba, self.width, self.height, &mut self.converted);
+        &self.converted
+      }
+    };
+
+    (self.callback)(Frame {
+      index,
+      width: self.width,
+      height: self.height,
+      format: self.format,
+      data,
+    });
+
+    Ok(())
+  }
+}
+
+/// Reverse the order of the rows of an image, in place.
+fn flip_rows(texels: &mut [u8], row_len: usize) {
+  if row_len == 0 {
+    return;
+  }
+
+  let rows = texels.len() / row_len;
+  for row in 0..rows / 2 {
+    let (top, bottom) = texels.split_at_mut((rows - 1 - row) * row_len);
+    top[row * row_len..(row + 1) * row_len].swap_with_slice(&mut bottom[..row_len]);
+  }
+}
+
+/// Convert top-down 8-bit RGBA texels to planar YUV 4:2:0, BT.601 limited range.
+fn rgba_to_i420(rgba: &[u8], width: u32, height: u32, yuv: &mut Vec<u8>) {
+  let (width, height) = (width as usize, height as usize);
+  let (chroma_width, chroma_height) = ((width + 1) / 2, (height + 1) / 2);
+  let rgb = |x: usize, y: usize| {
+    let i = (y * width + x) * 4;
+    [rgba[i] as i32, rgba[i + 1] as i32, rgba[i + 2] as i32]
+  };
+
+  yuv.clear();
+  yuv.reserve(width * height + 2 * chroma_width * chroma_height);
+
+  for y in 0..height {
+    for x in 0..width {
+      let [r, g, b] = rgb(x, y);
+      yuv.push((((66 * r + 129 * g + 25 * b + 128) >> 8) + 16) as u8);
+    }
+  }
+
+  // average the 2×2 blocks; blocks on the right and bottom edges might be smaller
+  let mut u_plane = Vec::with_capacity(chroma_width * chroma_height);
+  let mut v_plane = Vec::with_capacity(chroma_width * chroma_height);
+  for cy in 0..chroma_height {
+    for cx in 0..chroma_width {
+      let (mut r, mut g, mut b, mut n) = (0, 0, 0, 0);
+
+      for y in 2 * cy..(2 * cy + 2).min(height) {
+        for x in 2 * cx..(2 * cx + 2).min(width) {
+          let [tr, tg, tb] = rgb(x, y);
+          (r, g, b, n) = (r + tr, g + tg, b + tb, n + 1);
+        }
+      }
+
+      let (r, g, b) = (r / n, g / n, b / n);
+      u_plane.push((((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128) as u8);
+      v_plane.push((((112 * r - 94 * g - 18 * b + 128) >> 8) + 128) as u8);
+    }
+  }
+
+  yuv.extend_from_slice(&u_plane);
+  yuv.extend_from_slice(&v_plane);
+}
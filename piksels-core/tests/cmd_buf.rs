@@ -0,0 +1,588 @@
+use std::{cell::Cell, collections::HashSet, thread};
+
+use piksels_backend::{
+  error::Error,
+  pixel::{ChannelBits, Format, Pixel, Type},
+  render_targets::{self, ColorAttachmentPoint, ColorType, DepthStencilType, RenderTargetsFormat},
+  scissor::{Scissor, ScissorRegion},
+  shader::{ShaderSources, UniformTypeBase},
+  texture::{Offset, Rect, Size, Storage},
+  vertex::{self, VertexAttr},
+  vertex_array::{MemoryLayout, VertexArrayData},
+  viewport::Viewport,
+};
+use piksels_core::{
+  budget::RecordingBudget, cmd_buf::CmdBuf, convention::CoordinateConvention, layers::Layers,
+};
+
+mod common;
+
+use common::*;
+
+#[test]
+fn deferred_cmd_bufs() {
+  let device = dummy_device();
+  let context = device.create_deferred_context();
+
+  let deferred = thread::scope(|scope| {
+    let workers = (0..2)
+      .map(|_| {
+        let context = context.clone();
+        scope.spawn(move || {
+          let cmd_buf = context.new_cmd_buf().unwrap();
+          // deferred command buffers don’t know the state they are executed on, so nothing is skipped
+          cmd_buf.clear_state().unwrap();
+          assert_eq!(cmd_buf.state_change_count(), 11);
+          cmd_buf.finish().unwrap();
+          cmd_buf
+        })
+      })
+      .collect::<Vec<_>>();
+
+    workers
+      .into_iter()
+      .map(|worker| worker.join().unwrap())
+      .collect::<Vec<_>>()
+  });
+
+  assert!(deferred.iter().all(CmdBuf::is_deferred));
+
+  let cmd_buf = device.new_cmd_buf_with_clear_state().unwrap();
+  cmd_buf.clear_state().unwrap();
+  assert_eq!(cmd_buf.state_change_count(), 11);
+
+  for deferred in &deferred {
+    cmd_buf.execute(deferred).unwrap();
+  }
+  assert_eq!(
+    EXECUTED_CMD_BUFS.with(|executed| executed.borrow().clone()),
+    deferred
+      .iter()
+      .map(|deferred| deferred.id())
+      .collect::<Vec<_>>()
+  );
+
+  // the state is unknown after executing deferred command buffers
+  cmd_buf.clear_state().unwrap();
+  assert_eq!(cmd_buf.state_change_count(), 22);
+
+  assert_eq!(
+    deferred[0].execute(&deferred[1]).err(),
+    Some(DummyBackendError::Common(Error::InvalidExecute {
+      reason: "deferred command buffers cannot execute command buffers".to_owned()
+    }))
+  );
+  assert_eq!(
+    cmd_buf.execute(&cmd_buf).err(),
+    Some(DummyBackendError::Common(Error::InvalidExecute {
+      reason: "only deferred command buffers can be executed".to_owned()
+    }))
+  );
+
+  for deferred in deferred {
+    device.drop_cmd_buf(deferred).unwrap();
+  }
+}
+
+#[test]
+fn coordinate_convention_top_left() {
+  let device = dummy_device().with_coordinate_convention(CoordinateConvention::OriginTopLeft);
+  let storage = Storage::Flat2D {
+    width: 800,
+    height: 600,
+  };
+  let render_targets = device
+    .new_render_targets(HashSet::default(), None, storage)
+    .unwrap();
+  let cmd_buf = device.new_cmd_buf().unwrap();
+
+  // flipping requires knowing the height of the render targets
+  assert!(matches!(
+    cmd_buf.viewport(Viewport::Specific {
+      x: 0,
+      y: 0,
+      width: 800,
+      height: 100
+    }),
+    Err(DummyBackendError::Common(
+      Error::CoordinateConvention { .. }
+    ))
+  ));
+
+  cmd_buf.render_targets(&render_targets).unwrap();
+  cmd_buf
+    .viewport(Viewport::Specific {
+      x: 10,
+      y: 0,
+      width: 800,
+      height: 100,
+    })
+    .unwrap()
+    .scissor(Scissor::On(ScissorRegion::new(0, 50, 20, 30)))
+    .unwrap();
+
+  assert_eq!(
+    LAST_VIEWPORT.with(Cell::get),
+    Some(Viewport::Specific {
+      x: 10,
+      y: 500,
+      width: 800,
+      height: 100
+    })
+  );
+  assert_eq!(
+    LAST_SCISSOR.with(Cell::get),
+    Some(Scissor::On(ScissorRegion::new(0, 520, 20, 30)))
+  );
+
+  // texture rows are uploaded bottom-up
+  let pixel = Pixel {
+    encoding: Type::NormUnsigned,
+    format: Format::R(ChannelBits::Eight),
+  };
+  let texture = device
+    .new_texture(
+      Storage::Flat2D {
+        width: 2,
+        height: 4,
+      },
+      pixel,
+      dummy_sampling(),
+    )
+    .unwrap();
+  let rect = Rect::new(
+    Offset::Dim2 { x: 0, y: 0 },
+    Size::Dim2 {
+      width: 2,
+      height: 3,
+    },
+  );
+  texture.set(rect, false, 0, &[1, 1, 2, 2, 3, 3]).unwrap();
+
+  assert_eq!(
+    LAST_TEXELS_UPLOAD.with(Cell::get),
+    Some((
+      Rect::new(
+        Offset::Dim2 { x: 0, y: 1 },
+        Size::Dim2 {
+          width: 2,
+          height: 3
+        }
+      ),
+      3
+    ))
+  );
+  assert!(matches!(
+    texture.set(rect, false, 0, &[1, 1, 2, 2, 3]),
+    Err(DummyBackendError::Common(Error::InvalidTexelsUpload { .. }))
+  ));
+}
+
+#[test]
+fn binding_report() {
+  let device = dummy_device();
+  let pixel = Pixel {
+    encoding: Type::NormUnsigned,
+    format: Format::R(ChannelBits::Eight),
+  };
+  let storage = Storage::Flat2D {
+    width: 4,
+    height: 4,
+  };
+  let albedo = device
+    .new_texture(storage, pixel, dummy_sampling())
+    .unwrap();
+  let normal = device
+    .new_texture(storage, pixel, dummy_sampling())
+    .unwrap();
+  let shader = device.new_shader(ShaderSources::default()).unwrap();
+  let vertex_array = device
+    .new_vertex_array(
+      VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+      VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+      Vec::new(),
+    )
+    .unwrap();
+  let unit0 = device.get_texture_binding_point(0).unwrap();
+  let unit1 = device.get_texture_binding_point(1).unwrap();
+
+  let cmd_buf = device.new_cmd_buf().unwrap();
+  cmd_buf.record_bindings();
+  cmd_buf
+    .shader(&shader)
+    .unwrap()
+    .use_texture(&albedo, &unit0)
+    .unwrap()
+    .draw(&vertex_array)
+    .unwrap()
+    .use_texture(&normal, &unit0)
+    .unwrap()
+    .use_texture(&albedo, &unit1)
+    .unwrap()
+    .draw(&vertex_array)
+    .unwrap();
+
+  let report = cmd_buf.take_binding_report().unwrap();
+  assert!(cmd_buf.take_binding_report().is_none());
+  assert_eq!(report.draws().len(), 2);
+  assert_eq!(report.draws()[1].textures.get(&0), Some(&normal.id()));
+
+  let table = report.to_string();
+  let lines: Vec<_> = table.lines().collect();
+  assert_eq!(lines.len(), 3);
+  assert!(lines[1].contains(&format!("| 0: {} ", albedo.id())));
+  assert!(lines[2].contains(&format!("| 0: {}*, 1: {}* ", normal.id(), albedo.id())));
+}
+
+#[test]
+fn attributeless_draws() {
+  let device = dummy_device();
+  let triangle = device.new_empty_vertex_array(3).unwrap();
+  assert_eq!(triangle.vertex_count(), 3);
+  assert_eq!(
+    device
+      .vertex_array_by_id(&triangle.id())
+      .unwrap()
+      .map(|vertex_array| vertex_array.vertex_count()),
+    Some(3)
+  );
+
+  let cmd_buf = device.new_cmd_buf().unwrap();
+  assert!(cmd_buf.draw(&triangle).is_ok());
+}
+
+#[test]
+fn recording_budget() {
+  let device = dummy_device().with_recording_budget(RecordingBudget::unlimited().draws(2));
+  let triangle = device.new_empty_vertex_array(3).unwrap();
+
+  // exceeding the budget only warns, once per recording
+  let cmd_buf = device.new_cmd_buf().unwrap();
+  let warnings = LOGGED_WARNINGS.with(Cell::get);
+  for _ in 0..4 {
+    cmd_buf.draw(&triangle).unwrap();
+  }
+  assert_eq!(cmd_buf.draw_count(), 4);
+  assert_eq!(LOGGED_WARNINGS.with(Cell::get), warnings + 1);
+
+  cmd_buf.finish().unwrap();
+  assert_eq!(cmd_buf.draw_count(), 0);
+
+  // strict budgets reject the offending commands
+  cmd_buf.set_recording_budget(RecordingBudget::unlimited().state_changes(1).strict());
+  cmd_buf.viewport(Viewport::Whole).unwrap();
+  assert_eq!(
+    cmd_buf.scissor(Scissor::Off).err(),
+    Some(DummyBackendError::Common(Error::RecordingBudgetExceeded {
+      reason: format!(
+        "more than 1 state changes recorded in command buffer {:?}",
+        cmd_buf.id()
+      )
+    }))
+  );
+  assert_eq!(cmd_buf.state_change_count(), 1);
+
+  // draws are not limited by this budget
+  cmd_buf.draw(&triangle).unwrap();
+  cmd_buf.draw(&triangle).unwrap();
+  cmd_buf.draw(&triangle).unwrap();
+  assert_eq!(cmd_buf.draw_count(), 3);
+}
+
+#[test]
+fn pipeline_warm_up() {
+  let device = dummy_device();
+  let shaders = [
+    device.new_shader(ShaderSources::default()).unwrap(),
+    device.new_shader(ShaderSources::default()).unwrap(),
+  ];
+  let position = VertexAttr {
+    index: 0,
+    name: "position",
+    ty: vertex::Type::Float3,
+    array: None,
+  };
+  let rgba = ColorType::IRGBA {
+    red_bits: render_targets::ChannelBits::Eight,
+    green_bits: render_targets::ChannelBits::Eight,
+    blue_bits: render_targets::ChannelBits::Eight,
+    alpha_bits: render_targets::ChannelBits::Eight,
+  };
+  let formats = [
+    RenderTargetsFormat {
+      color_attachments: vec![(0, rgba)],
+      depth_stencil_attachment: None,
+    },
+    RenderTargetsFormat {
+      color_attachments: vec![(0, rgba), (1, rgba)],
+      depth_stencil_attachment: Some(DepthStencilType::Depth {
+        depth_bits: render_targets::ChannelBits::ThirtyTwo,
+      }),
+    },
+  ];
+
+  WARM_UPS.with(|warm_ups| warm_ups.borrow_mut().clear());
+  device
+    .warm_up(&[&shaders[0], &shaders[1]], &[&[position], &[]], &formats)
+    .unwrap();
+
+  let warm_ups = WARM_UPS.with(|warm_ups| warm_ups.take());
+  assert_eq!(warm_ups.len(), 8);
+  assert_eq!(warm_ups[..4], [(1, 1), (1, 2), (0, 1), (0, 2)]);
+}
+
+#[test]
+fn cache_invalidation() {
+  let device = dummy_device();
+
+  let cmd_buf = device.new_cmd_buf().unwrap();
+  cmd_buf.clear_state().unwrap();
+  let state_changes = cmd_buf.state_change_count();
+  cmd_buf.clear_state().unwrap();
+  assert_eq!(cmd_buf.state_change_count(), state_changes);
+
+  // the pipeline state was changed behind the back of the cache
+  device.invalidate_pipeline_state().unwrap();
+  cmd_buf.clear_state().unwrap();
+  assert_eq!(cmd_buf.state_change_count(), 2 * state_changes);
+
+  let pixel = Pixel {
+    encoding: Type::NormUnsigned,
+    format: Format::R(ChannelBits::Eight),
+  };
+  let albedo = device
+    .new_texture(
+      Storage::Flat2D {
+        width: 4,
+        height: 4,
+      },
+      pixel,
+      dummy_sampling(),
+    )
+    .unwrap();
+  let shader = device.new_shader(ShaderSources::default()).unwrap();
+  let albedo_point = shader.texture_binding_point("albedo").unwrap();
+  let render_targets = device
+    .new_render_targets(
+      HashSet::new(),
+      None,
+      Storage::Flat2D {
+        width: 4,
+        height: 4,
+      },
+    )
+    .unwrap();
+  let bind_albedo = |layers: Layers<DummyBackend>| {
+    layers
+      .render_targets(&render_targets)
+      .unwrap()
+      .shader(&shader)
+      .unwrap()
+      .texture(&albedo, &albedo_point)
+      .unwrap()
+      .done()
+      .unwrap()
+      .done()
+  };
+
+  TEXTURE_BINDS.with(|binds| binds.borrow_mut().clear());
+  let layers = bind_albedo(bind_albedo(device.new_layers().unwrap()));
+  assert_eq!(TEXTURE_BINDS.with(|binds| binds.borrow().len()), 1);
+
+  // textures were bound behind the back of the cache
+  device.invalidate_cache().unwrap();
+  let cmd_buf = bind_albedo(layers).done().unwrap();
+  assert_eq!(TEXTURE_BINDS.with(|binds| binds.borrow().len()), 2);
+  device.drop_cmd_buf(cmd_buf).unwrap();
+}
+
+#[test]
+fn pipeline_snapshot() {
+  let device = dummy_device();
+  let host_shader = device
+    .new_shader(ShaderSources::default().fragment("host"))
+    .unwrap();
+  let overlay_shader = device
+    .new_shader(ShaderSources::default().fragment("overlay"))
+    .unwrap();
+  let host_viewport = Viewport::Specific {
+    x: 0,
+    y: 0,
+    width: 800,
+    height: 600,
+  };
+
+  let cmd_buf = device.new_cmd_buf().unwrap();
+  cmd_buf
+    .viewport(host_viewport)
+    .unwrap()
+    .shader(&host_shader)
+    .unwrap();
+
+  let snapshot = cmd_buf.snapshot().unwrap();
+  assert_eq!(snapshot.state.viewport, Some(host_viewport));
+  assert_eq!(snapshot.state.blending, None);
+  assert_eq!(snapshot.shader, Some(host_shader.id()));
+  assert_eq!(snapshot.render_targets, None);
+
+  // render an overlay, then give the state back to the host
+  cmd_buf
+    .viewport(Viewport::Whole)
+    .unwrap()
+    .shader(&overlay_shader)
+    .unwrap();
+  let state_changes = cmd_buf.state_change_count();
+  cmd_buf.restore(&snapshot).unwrap();
+  assert_eq!(LAST_VIEWPORT.with(Cell::get), Some(host_viewport));
+  assert_eq!(cmd_buf.state_change_count(), state_changes + 2);
+
+  // nothing differs anymore
+  cmd_buf.restore(&snapshot).unwrap();
+  assert_eq!(cmd_buf.state_change_count(), state_changes + 2);
+}
+
+#[test]
+fn redundant_state_changes() {
+  let device = dummy_device();
+  let cmd_buf = device.new_cmd_buf().unwrap();
+  let viewport = Viewport::Specific {
+    x: 0,
+    y: 0,
+    width: 800,
+    height: 600,
+  };
+
+  cmd_buf.srgb(true).unwrap().viewport(viewport).unwrap();
+  let state_changes = cmd_buf.state_change_count();
+
+  LAST_VIEWPORT.with(|last| last.set(None));
+  cmd_buf.srgb(true).unwrap().viewport(viewport).unwrap();
+  assert_eq!(cmd_buf.state_change_count(), state_changes);
+  assert_eq!(LAST_VIEWPORT.with(Cell::get), None);
+
+  // state set by other command buffers is not known, since they can be executed in any order
+  let other = device.new_cmd_buf().unwrap();
+  other.viewport(viewport).unwrap();
+  assert_eq!(other.state_change_count(), 1);
+
+  let deferred = device.create_deferred_context().new_cmd_buf().unwrap();
+  deferred
+    .viewport(viewport)
+    .unwrap()
+    .viewport(viewport)
+    .unwrap();
+  assert_eq!(deferred.state_change_count(), 1);
+
+  device.invalidate_pipeline_state().unwrap();
+  cmd_buf.viewport(viewport).unwrap();
+  assert_eq!(cmd_buf.state_change_count(), state_changes + 1);
+  assert_eq!(LAST_VIEWPORT.with(Cell::get), Some(viewport));
+}
+
+#[test]
+fn redundant_binds() {
+  let device = dummy_device();
+  let storage = Storage::Flat2D {
+    width: 800,
+    height: 600,
+  };
+  let srgb = ColorAttachmentPoint::new(
+    0,
+    "color",
+    ColorType::ISRGBA {
+      red_bits: render_targets::ChannelBits::Eight,
+      green_bits: render_targets::ChannelBits::Eight,
+      blue_bits: render_targets::ChannelBits::Eight,
+      alpha_bits: render_targets::ChannelBits::Eight,
+    },
+  );
+  let output = device
+    .new_render_targets(
+      HashSet::from([srgb.with_srgb_encoding(true)]),
+      None,
+      storage,
+    )
+    .unwrap();
+  let shader = device
+    .new_shader(ShaderSources::default().fragment("redundant binds"))
+    .unwrap();
+  let cmd_buf = device.new_cmd_buf().unwrap();
+
+  cmd_buf
+    .render_targets(&output)
+    .unwrap()
+    .shader(&shader)
+    .unwrap();
+  let state_changes = cmd_buf.state_change_count();
+
+  cmd_buf
+    .render_targets(&output)
+    .unwrap()
+    .shader(&shader)
+    .unwrap();
+  assert_eq!(cmd_buf.state_change_count(), state_changes);
+
+  // binding the render targets again still switches sRGB encoding back to theirs
+  SRGB_SWITCHES.with(|switches| switches.take());
+  cmd_buf
+    .srgb(false)
+    .unwrap()
+    .render_targets(&output)
+    .unwrap();
+  assert_eq!(
+    SRGB_SWITCHES.with(|switches| switches.take()),
+    [false, true]
+  );
+  assert_eq!(cmd_buf.state_change_count(), state_changes + 2);
+
+  // forgotten bindings are bound again
+  device.invalidate_shader_binding().unwrap();
+  device.invalidate_render_targets_bindings().unwrap();
+  cmd_buf
+    .render_targets(&output)
+    .unwrap()
+    .shader(&shader)
+    .unwrap();
+  assert_eq!(cmd_buf.state_change_count(), state_changes + 4);
+}
+
+#[test]
+fn uniform_value_caching() {
+  let device = dummy_device();
+  let camera_shader = device
+    .new_shader(ShaderSources::default().vertex("camera"))
+    .unwrap();
+  let model_shader = device
+    .new_shader(ShaderSources::default().vertex("model"))
+    .unwrap();
+  let cmd_buf = device.new_cmd_buf().unwrap();
+  let last_bytes = || LAST_UNIFORM_BYTES.with(|bytes| bytes.take());
+
+  let camera = camera_shader
+    .uniform("camera", UniformTypeBase::Float3)
+    .unwrap();
+  assert!(camera.is_cached());
+  cmd_buf.uniform(&camera, &[1f32, 2., 3.]).unwrap();
+  assert!(!last_bytes().is_empty());
+
+  // setting the same value again doesn’t reach the backend
+  cmd_buf.uniform(&camera, &[1f32, 2., 3.]).unwrap();
+  assert!(last_bytes().is_empty());
+  cmd_buf.uniform(&camera, &[3f32, 2., 1.]).unwrap();
+  assert!(!last_bytes().is_empty());
+
+  // uniforms changed behind the back of the cache
+  device.invalidate_uniform_values().unwrap();
+  cmd_buf.uniform(&camera, &[3f32, 2., 1.]).unwrap();
+  assert!(!last_bytes().is_empty());
+
+  let model = model_shader
+    .uniform("model", UniformTypeBase::Float3)
+    .unwrap()
+    .uncached();
+  assert!(!model.is_cached());
+  cmd_buf.uniform(&model, &[1f32, 2., 3.]).unwrap();
+  assert!(!last_bytes().is_empty());
+  cmd_buf.uniform(&model, &[1f32, 2., 3.]).unwrap();
+  assert!(!last_bytes().is_empty());
+}
@@ -0,0 +1,1245 @@
+//! Dummy backend shared by the integration tests, recording what is asked of it.
+
+#![allow(dead_code)]
+
+use std::{
+  cell::{Cell, RefCell},
+  collections::BTreeMap,
+  fmt::Display,
+  ops::Range,
+};
+
+use piksels_backend::{
+  color::RGBA32F,
+  error::Error,
+  extension::{
+    logger::{BackendLogger, LogEntry, LogLevel, Logger, LoggerExt},
+    Extensions,
+  },
+  info,
+  render_targets::{BlitFilter, BlitMask, ColorType, RenderTargetsError, RenderTargetsFormat},
+  scarce::ScarceIndexAllocator,
+  scissor::Scissor,
+  shader::{ShaderBindings, ShaderDialect, ShaderInterface, ShaderSources},
+  swap_chain::{PresentStatus, SwapChainCapabilities, SwapChainFormat, SwapChainMode},
+  texture::{MagFilter, MinFilter, Rect, Sampling, Storage, Wrap},
+  uniform_block::{UniformBlockLayout, UniformBlockMember},
+  vertex::VertexAttr,
+  vertex_array::{DataSelector, MapAccess, StepRate, VertexArrayUpdate, VertexStream},
+  viewport::Viewport,
+  Backend, BackendInfo, DeviceFeatures, DeviceLimits, Scarce,
+};
+use piksels_core::{
+  device::{Device, DeviceBuilder},
+  units::U32Unit,
+};
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum DummyBackendError {
+  Common(Error),
+  Unimplemented,
+  Injected,
+}
+
+impl From<Error> for DummyBackendError {
+  fn from(e: Error) -> Self {
+    DummyBackendError::Common(e)
+  }
+}
+
+impl Display for DummyBackendError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      DummyBackendError::Common(e) => e.fmt(f),
+      DummyBackendError::Unimplemented => f.write_str("unimplemented"),
+      DummyBackendError::Injected => f.write_str("injected failure"),
+    }
+  }
+}
+
+#[derive(Debug)]
+pub struct DummyResource(pub usize);
+
+impl Scarce<DummyBackend> for DummyResource {
+  fn scarce_index(&self) -> usize {
+    self.0
+  }
+
+  fn scarce_clone(&self) -> Self {
+    DummyResource(self.0)
+  }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct DummyResourceBindingPoint(usize);
+
+impl Scarce<DummyBackend> for DummyResourceBindingPoint {
+  fn scarce_index(&self) -> usize {
+    self.0
+  }
+
+  fn scarce_clone(&self) -> Self {
+    *self
+  }
+}
+
+impl Display for DummyResourceBindingPoint {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str("DummyResourceBindingPoint")
+  }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct DummyShaderBindingPoint;
+
+impl Scarce<DummyBackend> for DummyShaderBindingPoint {
+  fn scarce_index(&self) -> usize {
+    0
+  }
+
+  fn scarce_clone(&self) -> Self {
+    DummyShaderBindingPoint
+  }
+}
+
+impl Display for DummyShaderBindingPoint {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str("DummyShaderBindingPoint")
+  }
+}
+
+/// Vertex stage of shaders whose compilation waits for another thread to compile one too.
+pub const RACING_VERTEX_STAGE: &str = "// racing";
+
+pub static RACING_COMPILATIONS: std::sync::OnceLock<std::sync::Barrier> =
+  std::sync::OnceLock::new();
+
+thread_local! {
+  /// Every set of active attachments set on render targets.
+  pub static ACTIVE_ATTACHMENTS: RefCell<Vec<Vec<usize>>> = RefCell::default();
+
+  /// Last sampling set on a depth/stencil attachment.
+  pub static LAST_DEPTH_STENCIL_SAMPLING: Cell<Option<Sampling>> = const { Cell::new(None) };
+
+  /// Textures attached to (or detached from, if [`None`]) color attachments of render targets.
+  pub static ATTACHED_TEXTURES: RefCell<Vec<(usize, Option<usize>)>> = RefCell::default();
+
+  /// Every sRGB encoding switch.
+  pub static SRGB_SWITCHES: RefCell<Vec<bool>> = RefCell::default();
+
+  /// Number of times a swap chain was waited for.
+  pub static SWAP_CHAIN_WAITS: Cell<usize> = const { Cell::new(0) };
+
+  /// Status the next present returns.
+  pub static NEXT_PRESENT_STATUS: Cell<PresentStatus> = Cell::default();
+
+  /// Last blit, as source rect, destination rect, filter and mask.
+  pub static LAST_BLIT: Cell<Option<(Rect, Rect, BlitFilter, BlitMask)>> = const { Cell::new(None) };
+
+  /// Interface reflected for every shader.
+  pub static SHADER_INTERFACE: RefCell<ShaderInterface> = RefCell::default();
+
+  /// Number of shader binding points resolved by the backend.
+  pub static SHADER_BINDING_LOOKUPS: Cell<usize> = const { Cell::new(0) };
+
+  /// Number of textures destroyed.
+  pub static DROPPED_TEXTURES: Cell<usize> = const { Cell::new(0) };
+
+  /// Number of shaders, vertex arrays and command buffers destroyed.
+  pub static DROPPED_SHADERS: Cell<usize> = const { Cell::new(0) };
+  pub static DROPPED_VERTEX_ARRAYS: Cell<usize> = const { Cell::new(0) };
+  pub static DROPPED_CMD_BUFS: Cell<usize> = const { Cell::new(0) };
+
+  /// Indices of destroyed textures, given to the next textures, if enabled.
+  pub static RECYCLED_TEXTURE_INDICES: RefCell<Option<Vec<usize>>> = const { RefCell::new(None) };
+
+  /// Number of times a command buffer was waited for.
+  pub static CMD_BUF_WAITS: Cell<usize> = const { Cell::new(0) };
+
+  /// Number of timer queries begun and not ended yet.
+  pub static OPEN_TIMER_QUERIES: Cell<usize> = const { Cell::new(0) };
+
+  /// Number of draws recorded while a timer query was open.
+  pub static TIMED_DRAWS: Cell<usize> = const { Cell::new(0) };
+
+  /// Whether the device has executed the commands of ended timer queries.
+  pub static TIMER_QUERIES_READY: Cell<bool> = const { Cell::new(false) };
+
+  /// Number of timer queries destroyed.
+  pub static DROPPED_TIMER_QUERIES: Cell<usize> = const { Cell::new(0) };
+
+  /// Last viewport set on a command buffer.
+  pub static LAST_VIEWPORT: Cell<Option<Viewport>> = const { Cell::new(None) };
+
+  /// Last vertex array update, as selector, offset and bytes.
+  pub static LAST_VERTEX_ARRAY_UPDATE: RefCell<Option<(DataSelector, usize, Vec<u8>)>> = const { RefCell::new(None) };
+
+  /// Vertex stage of the last shader created.
+  pub static LAST_VERTEX_STAGE: RefCell<String> = const { RefCell::new(String::new()) };
+
+  /// Bytes of the last uniform value set.
+  pub static LAST_UNIFORM_BYTES: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+
+  /// Last uniform array update, as first element and bytes.
+  pub static LAST_UNIFORM_ARRAY_UPDATE: RefCell<Option<(usize, Vec<u8>)>> = const { RefCell::new(None) };
+
+  /// Last storage buffer update, as offset and bytes.
+  pub static LAST_STORAGE_BUFFER_UPDATE: RefCell<Option<(usize, Vec<u8>)>> = const { RefCell::new(None) };
+
+  /// Bytes of the last uniform buffer upload.
+  pub static LAST_UNIFORM_BUFFER_BYTES: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+
+  /// Step rates of the streams of the last vertex array created.
+  pub static LAST_VERTEX_STREAM_RATES: RefCell<Vec<StepRate>> = const { RefCell::new(Vec::new()) };
+
+  /// Bytes of the last vertex array mapping, when unmapped.
+  pub static LAST_UNMAPPED_BYTES: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+
+  /// Last vertex array range mapped.
+  pub static LAST_MAP_RANGE: RefCell<Option<(DataSelector, Range<usize>, MapAccess)>> = const { RefCell::new(None) };
+
+  /// Last scissor set on a command buffer.
+  pub static LAST_SCISSOR: Cell<Option<Scissor>> = const { Cell::new(None) };
+
+  /// Rect and first byte of the last texels upload.
+  pub static LAST_TEXELS_UPLOAD: Cell<Option<(Rect, u8)>> = const { Cell::new(None) };
+
+  /// Pipelines warmed up, as (vertex attribute count, color attachment count) pairs, in order.
+  pub static WARM_UPS: RefCell<Vec<(usize, usize)>> = const { RefCell::new(Vec::new()) };
+
+  /// Names forwarded to the backend, as (resource, name) pairs, in order.
+  pub static NAMES: RefCell<Vec<(usize, String)>> = const { RefCell::new(Vec::new()) };
+
+  /// Textures bound, as (unit, texture) pairs, in order.
+  pub static TEXTURE_BINDS: RefCell<Vec<(usize, usize)>> = const { RefCell::new(Vec::new()) };
+
+  /// Deferred command buffers executed, in order.
+  pub static EXECUTED_CMD_BUFS: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
+
+  /// Whether the context of the backend is current on this thread.
+  pub static CURRENT_CONTEXT: Cell<bool> = const { Cell::new(false) };
+
+  /// Number of times limits were queried.
+  pub static LIMITS_QUERIES: Cell<usize> = const { Cell::new(0) };
+
+  /// Extent of the last created or resized swap chain.
+  pub static SWAP_CHAIN_EXTENT: Cell<(u32, u32)> = const { Cell::new((0, 0)) };
+
+  /// Number of frames presented on swap chains.
+  pub static PRESENTED_FRAMES: Cell<usize> = const { Cell::new(0) };
+
+  /// Number of warnings logged.
+  pub static LOGGED_WARNINGS: Cell<usize> = const { Cell::new(0) };
+
+  /// Number of errors logged.
+  pub static LOGGED_ERRORS: Cell<usize> = const { Cell::new(0) };
+
+  /// Whether destroying shaders fails, to inject backend errors.
+  pub static FAILING_SHADER_DROPS: Cell<bool> = const { Cell::new(false) };
+}
+
+#[derive(Debug)]
+pub struct DummyLogger;
+
+impl Logger for DummyLogger {
+  fn log(&self, log_entry: LogEntry) {
+    match log_entry.level {
+      LogLevel::Warn => LOGGED_WARNINGS.with(|warnings| warnings.set(warnings.get() + 1)),
+      LogLevel::Error => LOGGED_ERRORS.with(|errors| errors.set(errors.get() + 1)),
+      _ => (),
+    }
+
+    println!(
+      "{file}:{line}:{column} [{module}] | {level:?} | {msg}",
+      file = log_entry.file,
+      line = log_entry.line,
+      column = log_entry.column,
+      module = log_entry.module,
+      level = log_entry.level,
+      msg = log_entry.msg,
+    );
+  }
+}
+
+pub struct DummyBackend {
+  logger: Option<LoggerExt<Box<dyn 'static + Logger + Send + Sync>>>,
+  scarce_indices: ScarceIndexAllocator,
+}
+
+impl DummyBackend {
+  fn new_resource(&self) -> DummyResource {
+    DummyResource(self.scarce_indices.allocate())
+  }
+}
+
+impl BackendLogger for DummyBackend {
+  fn log(&self, log_entry: LogEntry) {
+    if let Some(logger) = &self.logger {
+      if log_entry.level <= logger.level_filter {
+        logger.logger.log(log_entry)
+      }
+    }
+  }
+}
+
+#[cfg(feature = "ext-readback")]
+impl piksels_backend::extension::readback::BackendReadback for DummyBackend {
+  type Readback = DummyResource;
+
+  fn cmd_buf_read_color_attachment(
+    _cmd_buf: &Self::CmdBuf,
+    _color_attachment: &Self::ColorAttachment,
+  ) -> Result<Self::Readback, Self::Err> {
+    Err(DummyBackendError::Unimplemented)
+  }
+
+  fn read_color_attachment_texels(
+    _color_attachment: &Self::ColorAttachment,
+    rect: Rect,
+  ) -> Result<Vec<u8>, Self::Err> {
+    // opaque white texels
+    match rect.size() {
+      piksels_backend::texture::Size::Dim2 { width, height } => {
+        Ok(vec![255; 4 * width as usize * height as usize])
+      }
+      _ => Err(DummyBackendError::Unimplemented),
+    }
+  }
+
+  fn read_swap_chain_texels(_swap_chain: &Self::SwapChain) -> Result<Vec<u8>, Self::Err> {
+    // opaque white texels
+    let (width, height) = SWAP_CHAIN_EXTENT.with(Cell::get);
+    Ok(vec![255; 4 * width as usize * height as usize])
+  }
+
+  fn drop_readback(_readback: &Self::Readback) {}
+
+  fn is_readback_ready(_readback: &Self::Readback) -> Result<bool, Self::Err> {
+    Err(DummyBackendError::Unimplemented)
+  }
+
+  fn readback_texels(_readback: &Self::Readback, _texels: &mut [u8]) -> Result<(), Self::Err> {
+    Err(DummyBackendError::Unimplemented)
+  }
+}
+
+#[cfg(feature = "ext-frame-stats")]
+impl piksels_backend::extension::frame_stats::BackendFrameStats for DummyBackend {
+  fn swap_chain_frame_stats(
+    _swap_chain: &Self::SwapChain,
+  ) -> Result<piksels_backend::extension::frame_stats::FrameStats, Self::Err> {
+    // a 60 Hz display, the last image of which missed a vertical blank
+    let vblank = std::time::Duration::from_nanos(16_666_667);
+    Ok(piksels_backend::extension::frame_stats::FrameStats {
+      present_timestamps: vec![vblank, 2 * vblank, 3 * vblank, 5 * vblank],
+      missed_vblanks: 1,
+      refresh_rate: None,
+    })
+  }
+}
+
+#[cfg(feature = "ext-profiler")]
+impl piksels_backend::extension::profiler::BackendProfiler for DummyBackend {
+  type TimerQuery = DummyResource;
+
+  fn cmd_buf_begin_timer_query(_cmd_buf: &Self::CmdBuf) -> Result<Self::TimerQuery, Self::Err> {
+    OPEN_TIMER_QUERIES.with(|open| open.set(open.get() + 1));
+    Ok(DummyResource(0))
+  }
+
+  fn cmd_buf_end_timer_query(
+    _cmd_buf: &Self::CmdBuf,
+    _timer_query: &Self::TimerQuery,
+  ) -> Result<(), Self::Err> {
+    OPEN_TIMER_QUERIES.with(|open| open.set(open.get() - 1));
+    Ok(())
+  }
+
+  fn timer_query_elapsed(
+    _timer_query: &Self::TimerQuery,
+  ) -> Result<Option<std::time::Duration>, Self::Err> {
+    // every timed section takes a millisecond
+    Ok(
+      TIMER_QUERIES_READY
+        .with(Cell::get)
+        .then(|| std::time::Duration::from_millis(1)),
+    )
+  }
+
+  fn drop_timer_query(_timer_query: &Self::TimerQuery) {
+    DROPPED_TIMER_QUERIES.with(|dropped| dropped.set(dropped.get() + 1));
+  }
+}
+
+#[cfg(feature = "ext-trace")]
+impl piksels_backend::extension::trace::BackendTrace for DummyBackend {
+  fn cmd_buf_wait_idle(_cmd_buf: &Self::CmdBuf) -> Result<(), Self::Err> {
+    CMD_BUF_WAITS.with(|waits| waits.set(waits.get() + 1));
+    Ok(())
+  }
+}
+
+impl Backend for DummyBackend {
+  type CmdBuf = DummyResource;
+  type ColorAttachment = DummyResource;
+  type DepthStencilAttachment = DummyResource;
+  type Err = DummyBackendError;
+  type RenderTargets = DummyResource;
+  type ScarceIndex = usize;
+  type Shader = DummyResource;
+  type ShaderStorageBufferBindingPoint = DummyShaderBindingPoint;
+  type ShaderTextureBindingPoint = DummyShaderBindingPoint;
+  type ShaderUniformBufferBindingPoint = DummyShaderBindingPoint;
+  type StorageBuffer = DummyResource;
+  type StorageBufferBindingPoint = DummyResourceBindingPoint;
+  type StorageBufferMappedBytes = Vec<u8>;
+  type SwapChain = DummyResource;
+  type Texture = DummyResource;
+  type TextureBindingPoint = DummyResourceBindingPoint;
+  type TextureUnit = usize;
+  type Uniform = DummyResource;
+  type UniformBuffer = DummyResource;
+  type UniformBufferBindingPoint = DummyResourceBindingPoint;
+  type UniformBufferUnit = U32Unit;
+  type VertexArray = DummyResource;
+  type VertexArrayMappedBytes = Vec<u8>;
+
+  fn build(extensions: &mut Extensions) -> Result<Self, Self::Err> {
+    #[cfg(feature = "ext-frame-stats")]
+    extensions.accept(piksels_backend::extension::ExtensionKind::FrameStats);
+    #[cfg(feature = "ext-profiler")]
+    extensions.accept(piksels_backend::extension::ExtensionKind::Profiler);
+    #[cfg(feature = "ext-readback")]
+    extensions.accept(piksels_backend::extension::ExtensionKind::Readback);
+    #[cfg(feature = "ext-trace")]
+    extensions.accept(piksels_backend::extension::ExtensionKind::Trace);
+
+    Ok(DummyBackend {
+      logger: extensions.accept_logger(),
+      scarce_indices: ScarceIndexAllocator::new(),
+    })
+  }
+
+  fn make_current(&self) -> Result<(), Self::Err> {
+    CURRENT_CONTEXT.with(|current| current.set(true));
+    Ok(())
+  }
+
+  fn release_current(&self) -> Result<(), Self::Err> {
+    CURRENT_CONTEXT.with(|current| current.set(false));
+    Ok(())
+  }
+
+  fn author(&self) -> Result<String, Self::Err> {
+    info!(self, "getting author");
+    Ok("Dimitri 'phaazon' Sabadie <dimitri.sabadie@gmail.com>".to_owned())
+  }
+
+  fn name(&self) -> Result<String, Self::Err> {
+    Ok("DummyBackend".to_owned())
+  }
+
+  fn version(&self) -> Result<String, Self::Err> {
+    Ok("v1.0.0-super-dummy".to_owned())
+  }
+
+  fn shading_lang_version(&self) -> Result<String, Self::Err> {
+    Ok("v1.0.0".to_owned())
+  }
+
+  fn info(&self) -> Result<BackendInfo, Self::Err> {
+    Ok(BackendInfo {
+      version: env!("CARGO_PKG_VERSION"),
+      git_commit_hash: "HEAD",
+    })
+  }
+
+  fn limits(&self) -> Result<DeviceLimits, Self::Err> {
+    LIMITS_QUERIES.with(|queries| queries.set(queries.get() + 1));
+    Ok(dummy_limits())
+  }
+
+  fn features(&self) -> Result<DeviceFeatures, Self::Err> {
+    Ok(DeviceFeatures {
+      compute: true,
+      tessellation: true,
+      geometry_shaders: true,
+      anisotropy: true,
+      srgb: true,
+      ..DeviceFeatures::default()
+    })
+  }
+
+  fn shader_dialect(&self) -> ShaderDialect {
+    ShaderDialect::Glsl330
+  }
+
+  fn new_vertex_array(
+    &self,
+    streams: &[VertexStream],
+    _indices: &[u32],
+  ) -> Result<Self::VertexArray, Self::Err> {
+    LAST_VERTEX_STREAM_RATES
+      .with(|rates| *rates.borrow_mut() = streams.iter().map(VertexStream::rate).collect());
+    Ok(self.new_resource())
+  }
+
+  fn new_empty_vertex_array(&self, _vertex_count: usize) -> Result<Self::VertexArray, Self::Err> {
+    Ok(self.new_resource())
+  }
+
+  fn drop_vertex_array(_vertex_array: &Self::VertexArray) -> Result<(), Self::Err> {
+    DROPPED_VERTEX_ARRAYS.with(|dropped| dropped.set(dropped.get() + 1));
+    Ok(())
+  }
+
+  fn update_vertex_array(
+    _vertex_array: &Self::VertexArray,
+    update: VertexArrayUpdate,
+  ) -> Result<(), Self::Err> {
+    LAST_VERTEX_ARRAY_UPDATE.with(|last| {
+      last.replace(Some((
+        update.selector().clone(),
+        update.offset(),
+        update.bytes().to_vec(),
+      )))
+    });
+    Ok(())
+  }
+
+  fn map_vertex_array_bytes(
+    _vertex_array: &Self::VertexArray,
+    _data_selector: DataSelector,
+  ) -> Result<Self::VertexArrayMappedBytes, Self::Err> {
+    Err(DummyBackendError::Unimplemented)
+  }
+
+  fn map_vertex_array_bytes_range(
+    _vertex_array: &Self::VertexArray,
+    data_selector: DataSelector,
+    byte_range: Range<usize>,
+    access: MapAccess,
+  ) -> Result<Self::VertexArrayMappedBytes, Self::Err> {
+    let bytes = vec![0; byte_range.len()];
+    LAST_MAP_RANGE.with(|last| last.replace(Some((data_selector, byte_range, access))));
+    Ok(bytes)
+  }
+
+  fn unmap_vertex_array_bytes(
+    mapped_vertices: &Self::VertexArrayMappedBytes,
+  ) -> Result<(), Self::Err> {
+    LAST_UNMAPPED_BYTES.with(|last| last.replace(mapped_vertices.clone()));
+    Ok(())
+  }
+
+  fn vertex_array_bytes_data(bytes: &Self::VertexArrayMappedBytes) -> (*const u8, usize) {
+    (bytes.as_ptr(), bytes.len())
+  }
+
+  fn vertex_array_bytes_data_mut(bytes: &mut Self::VertexArrayMappedBytes) -> (*mut u8, usize) {
+    (bytes.as_mut_ptr(), bytes.len())
+  }
+
+  fn swap_chain_capabilities(&self) -> Result<SwapChainCapabilities, Self::Err> {
+    Ok(SwapChainCapabilities {
+      min_extent: (1, 1),
+      max_extent: (4096, 4096),
+      min_image_count: 1,
+      max_image_count: 3,
+      modes: vec![SwapChainMode::Fifo, SwapChainMode::Mailbox],
+      formats: vec![SwapChainFormat::SRGBA8, SwapChainFormat::RGBA16F],
+    })
+  }
+
+  fn new_render_targets(
+    &self,
+    color_attachment_points: std::collections::HashSet<
+      piksels_backend::render_targets::ColorAttachmentPoint,
+    >,
+    _depth_stencil_attachment_point: Option<
+      piksels_backend::render_targets::DepthStencilAttachmentPoint,
+    >,
+    _storage: piksels_backend::texture::Storage,
+  ) -> Result<Self::RenderTargets, Self::Err> {
+    // unsigned sRGB attachments are not supported
+    if let Some(point) = color_attachment_points
+      .iter()
+      .find(|point| matches!(point.ty(), ColorType::UintSRGB { .. }))
+    {
+      return Err(
+        Error::IncompleteRenderTargets(RenderTargetsError::UnsupportedFormat {
+          attachment: point.name(),
+        })
+        .into(),
+      );
+    }
+
+    Ok(self.new_resource())
+  }
+
+  fn new_render_targets_with_depth_stencil_attachment(
+    &self,
+    _color_attachment_points: std::collections::HashSet<
+      piksels_backend::render_targets::ColorAttachmentPoint,
+    >,
+    _depth_stencil_attachment: &Self::DepthStencilAttachment,
+    _storage: piksels_backend::texture::Storage,
+  ) -> Result<Self::RenderTargets, Self::Err> {
+    Ok(self.new_resource())
+  }
+
+  fn drop_render_targets(_render_targets: &Self::RenderTargets) -> Result<(), Self::Err> {
+    Ok(())
+  }
+
+  fn set_render_targets_name(
+    render_targets: &Self::RenderTargets,
+    name: &str,
+  ) -> Result<(), Self::Err> {
+    NAMES.with(|names| {
+      names
+        .borrow_mut()
+        .push((render_targets.scarce_index(), name.to_owned()))
+    });
+    Ok(())
+  }
+
+  fn set_render_targets_active_attachments(
+    _render_targets: &Self::RenderTargets,
+    indices: &[usize],
+  ) -> Result<(), Self::Err> {
+    ACTIVE_ATTACHMENTS.with(|active| active.borrow_mut().push(indices.to_owned()));
+    Ok(())
+  }
+
+  fn resize_render_targets(
+    _render_targets: &Self::RenderTargets,
+    _storage: Storage,
+  ) -> Result<(), Self::Err> {
+    Ok(())
+  }
+
+  fn blit_render_targets(
+    _src: &Self::RenderTargets,
+    _dst: &Self::RenderTargets,
+    src_rect: Rect,
+    dst_rect: Rect,
+    filter: BlitFilter,
+    mask: BlitMask,
+  ) -> Result<(), Self::Err> {
+    LAST_BLIT.with(|blit| blit.set(Some((src_rect, dst_rect, filter, mask))));
+    Ok(())
+  }
+
+  fn attach_render_targets_texture(
+    _render_targets: &Self::RenderTargets,
+    index: usize,
+    texture: &Self::Texture,
+  ) -> Result<(), Self::Err> {
+    ATTACHED_TEXTURES.with(|attached| {
+      attached
+        .borrow_mut()
+        .push((index, Some(texture.scarce_index())))
+    });
+    Ok(())
+  }
+
+  fn detach_render_targets_texture(
+    _render_targets: &Self::RenderTargets,
+    index: usize,
+  ) -> Result<(), Self::Err> {
+    ATTACHED_TEXTURES.with(|attached| attached.borrow_mut().push((index, None)));
+    Ok(())
+  }
+
+  fn get_color_attachment(
+    render_targets: &Self::RenderTargets,
+    _index: usize,
+  ) -> Result<Self::ColorAttachment, Self::Err> {
+    Ok(render_targets.scarce_clone())
+  }
+
+  fn set_depth_stencil_attachment_sampling(
+    _depth_stencil_attachment: &Self::DepthStencilAttachment,
+    sampling: Sampling,
+  ) -> Result<(), Self::Err> {
+    LAST_DEPTH_STENCIL_SAMPLING.with(|last| last.set(Some(sampling)));
+    Ok(())
+  }
+
+  fn get_depth_stencil_attachment(
+    render_targets: &Self::RenderTargets,
+    _index: usize,
+  ) -> Result<Self::DepthStencilAttachment, Self::Err> {
+    Ok(render_targets.scarce_clone())
+  }
+
+  fn new_shader(&self, sources: ShaderSources) -> Result<Self::Shader, Self::Err> {
+    LAST_VERTEX_STAGE.with(|stage| *stage.borrow_mut() = sources.vertex_stage().to_owned());
+
+    if sources.vertex_stage() == RACING_VERTEX_STAGE {
+      RACING_COMPILATIONS
+        .get_or_init(|| std::sync::Barrier::new(2))
+        .wait();
+    }
+
+    Ok(self.new_resource())
+  }
+
+  fn drop_shader(_shader: &Self::Shader) -> Result<(), Self::Err> {
+    DROPPED_SHADERS.with(|dropped| dropped.set(dropped.get() + 1));
+
+    if FAILING_SHADER_DROPS.with(Cell::get) {
+      return Err(DummyBackendError::Injected);
+    }
+
+    Ok(())
+  }
+
+  fn set_shader_name(shader: &Self::Shader, name: &str) -> Result<(), Self::Err> {
+    NAMES.with(|names| {
+      names
+        .borrow_mut()
+        .push((shader.scarce_index(), name.to_owned()))
+    });
+    Ok(())
+  }
+
+  fn reflect_shader_bindings(_shader: &Self::Shader) -> Result<ShaderBindings, Self::Err> {
+    Ok(ShaderBindings {
+      textures: vec!["albedo".to_owned(), "normal".to_owned()],
+      uniform_buffers: vec!["camera".to_owned()],
+      storage_buffers: Vec::new(),
+    })
+  }
+
+  fn reflect_shader_interface(_shader: &Self::Shader) -> Result<ShaderInterface, Self::Err> {
+    Ok(SHADER_INTERFACE.with(|interface| interface.borrow().clone()))
+  }
+
+  fn warm_up(
+    &self,
+    _shader: &Self::Shader,
+    vertex_attrs: &[VertexAttr],
+    format: &RenderTargetsFormat,
+  ) -> Result<(), Self::Err> {
+    WARM_UPS.with(|warm_ups| {
+      warm_ups
+        .borrow_mut()
+        .push((vertex_attrs.len(), format.color_attachments.len()))
+    });
+    Ok(())
+  }
+
+  fn get_uniform(
+    shader: &Self::Shader,
+    _name: &str,
+    _ty: piksels_backend::shader::UniformType,
+  ) -> Result<Self::Uniform, Self::Err> {
+    Ok(shader.scarce_clone())
+  }
+
+  fn get_uniform_buffer(
+    shader: &Self::Shader,
+    _name: &str,
+  ) -> Result<Self::UniformBuffer, Self::Err> {
+    Ok(shader.scarce_clone())
+  }
+
+  fn update_uniform_buffer(
+    _uniform_buffer: &Self::UniformBuffer,
+    bytes: &[u8],
+  ) -> Result<(), Self::Err> {
+    LAST_UNIFORM_BUFFER_BYTES.with(|last| *last.borrow_mut() = bytes.to_owned());
+    Ok(())
+  }
+
+  fn uniform_buffer_layout(
+    _uniform_buffer: &Self::UniformBuffer,
+  ) -> Result<UniformBlockLayout, Self::Err> {
+    // the layout of the camera block
+    Ok(UniformBlockLayout {
+      size: 80,
+      members: vec![
+        UniformBlockMember {
+          name: "view".to_owned(),
+          offset: 0,
+          array_stride: None,
+          matrix_stride: Some(16),
+        },
+        UniformBlockMember {
+          name: "exposure".to_owned(),
+          offset: 64,
+          array_stride: None,
+          matrix_stride: None,
+        },
+      ],
+    })
+  }
+
+  fn get_texture_binding_point(
+    &self,
+    index: usize,
+  ) -> Result<Self::TextureBindingPoint, Self::Err> {
+    Ok(DummyResourceBindingPoint(index))
+  }
+
+  fn get_uniform_buffer_binding_point(
+    &self,
+    index: usize,
+  ) -> Result<Self::UniformBufferBindingPoint, Self::Err> {
+    Ok(DummyResourceBindingPoint(index))
+  }
+
+  fn get_storage_buffer_binding_point(
+    &self,
+    index: usize,
+  ) -> Result<Self::StorageBufferBindingPoint, Self::Err> {
+    Ok(DummyResourceBindingPoint(index))
+  }
+
+  fn get_shader_texture_binding_point(
+    _shader: &Self::Shader,
+    _name: &str,
+  ) -> Result<Self::ShaderTextureBindingPoint, Self::Err> {
+    SHADER_BINDING_LOOKUPS.with(|lookups| lookups.set(lookups.get() + 1));
+    Ok(DummyShaderBindingPoint)
+  }
+
+  /// Get a uniform buffer binding point from a shader.
+  fn get_shader_uniform_buffer_binding_point(
+    _shader: &Self::Shader,
+    _name: &str,
+  ) -> Result<Self::ShaderUniformBufferBindingPoint, Self::Err> {
+    SHADER_BINDING_LOOKUPS.with(|lookups| lookups.set(lookups.get() + 1));
+    Ok(DummyShaderBindingPoint)
+  }
+
+  fn get_shader_storage_buffer_binding_point(
+    _shader: &Self::Shader,
+    _name: &str,
+  ) -> Result<Self::ShaderStorageBufferBindingPoint, Self::Err> {
+    Ok(DummyShaderBindingPoint)
+  }
+
+  fn new_storage_buffer(&self, _size: usize) -> Result<Self::StorageBuffer, Self::Err> {
+    Ok(self.new_resource())
+  }
+
+  fn drop_storage_buffer(_storage_buffer: &Self::StorageBuffer) -> Result<(), Self::Err> {
+    Ok(())
+  }
+
+  fn update_storage_buffer(
+    _storage_buffer: &Self::StorageBuffer,
+    offset: usize,
+    bytes: &[u8],
+  ) -> Result<(), Self::Err> {
+    LAST_STORAGE_BUFFER_UPDATE.with(|last| last.replace(Some((offset, bytes.to_owned()))));
+    Ok(())
+  }
+
+  fn map_storage_buffer_bytes(
+    _storage_buffer: &Self::StorageBuffer,
+    byte_range: Range<usize>,
+    _access: MapAccess,
+  ) -> Result<Self::StorageBufferMappedBytes, Self::Err> {
+    Ok(vec![0; byte_range.len()])
+  }
+
+  fn unmap_storage_buffer_bytes(
+    mapped_bytes: &Self::StorageBufferMappedBytes,
+  ) -> Result<(), Self::Err> {
+    LAST_UNMAPPED_BYTES.with(|last| last.replace(mapped_bytes.clone()));
+    Ok(())
+  }
+
+  fn storage_buffer_bytes_data(bytes: &Self::StorageBufferMappedBytes) -> (*const u8, usize) {
+    (bytes.as_ptr(), bytes.len())
+  }
+
+  fn storage_buffer_bytes_data_mut(bytes: &mut Self::StorageBufferMappedBytes) -> (*mut u8, usize) {
+    (bytes.as_mut_ptr(), bytes.len())
+  }
+
+  fn new_texture(
+    &self,
+    _storage: piksels_backend::texture::Storage,
+    _pixel: piksels_backend::pixel::Pixel,
+    _sampling: piksels_backend::texture::Sampling,
+  ) -> Result<Self::Texture, Self::Err> {
+    let recycled = RECYCLED_TEXTURE_INDICES.with(|indices| indices.borrow_mut().as_mut()?.pop());
+    Ok(recycled.map_or_else(|| self.new_resource(), DummyResource))
+  }
+
+  fn drop_texture(texture: &Self::Texture) -> Result<(), Self::Err> {
+    DROPPED_TEXTURES.with(|dropped| dropped.set(dropped.get() + 1));
+    RECYCLED_TEXTURE_INDICES.with(|indices| {
+      if let Some(indices) = indices.borrow_mut().as_mut() {
+        indices.push(texture.0);
+      }
+    });
+    Ok(())
+  }
+
+  fn set_texture_name(texture: &Self::Texture, name: &str) -> Result<(), Self::Err> {
+    NAMES.with(|names| {
+      names
+        .borrow_mut()
+        .push((texture.scarce_index(), name.to_owned()))
+    });
+    Ok(())
+  }
+
+  fn resize_texture(
+    _texture: &Self::Texture,
+    _size: piksels_backend::texture::Size,
+  ) -> Result<(), Self::Err> {
+    Err(DummyBackendError::Unimplemented)
+  }
+
+  fn set_texture_levels(
+    _texture: &Self::Texture,
+    _base_level: usize,
+    _max_level: usize,
+  ) -> Result<(), Self::Err> {
+    Ok(())
+  }
+
+  fn set_texels(
+    _texture: &Self::Texture,
+    rect: Rect,
+    _mipmaps: bool,
+    _level: usize,
+    texels: *const u8,
+  ) -> Result<(), Self::Err> {
+    // only the first texel byte is recorded, as the length is not known
+    let first = unsafe { *texels };
+    LAST_TEXELS_UPLOAD.with(|upload| upload.set(Some((rect, first))));
+    Ok(())
+  }
+
+  fn clear_texels(
+    _texture: &Self::Texture,
+    _rect: piksels_backend::texture::Rect,
+    _mipmaps: bool,
+    _value: *const u8,
+  ) -> Result<(), Self::Err> {
+    Err(DummyBackendError::Unimplemented)
+  }
+
+  fn new_cmd_buf(&self) -> Result<Self::CmdBuf, Self::Err> {
+    Ok(self.new_resource())
+  }
+
+  fn new_deferred_cmd_buf(&self) -> Result<Self::CmdBuf, Self::Err> {
+    Ok(self.new_resource())
+  }
+
+  fn drop_cmd_buf(_cmd_buf: &Self::CmdBuf) -> Result<(), Self::Err> {
+    DROPPED_CMD_BUFS.with(|dropped| dropped.set(dropped.get() + 1));
+    Ok(())
+  }
+
+  fn set_cmd_buf_name(cmd_buf: &Self::CmdBuf, name: &str) -> Result<(), Self::Err> {
+    NAMES.with(|names| {
+      names
+        .borrow_mut()
+        .push((cmd_buf.scarce_index(), name.to_owned()))
+    });
+    Ok(())
+  }
+
+  fn cmd_buf_blending(
+    _cmd_buf: &Self::CmdBuf,
+    _blending: piksels_backend::blending::BlendingMode,
+  ) -> Result<(), Self::Err> {
+    Ok(())
+  }
+
+  fn cmd_buf_depth_test(
+    _cmd_buf: &Self::CmdBuf,
+    _depth_test: piksels_backend::depth_stencil::DepthTest,
+  ) -> Result<(), Self::Err> {
+    Ok(())
+  }
+
+  fn cmd_buf_depth_write(
+    _cmd_buf: &Self::CmdBuf,
+    _depth_write: piksels_backend::depth_stencil::DepthWrite,
+  ) -> Result<(), Self::Err> {
+    Ok(())
+  }
+
+  fn cmd_buf_stencil_test(
+    _cmd_buf: &Self::CmdBuf,
+    _stencil_test: piksels_backend::depth_stencil::StencilTest,
+  ) -> Result<(), Self::Err> {
+    Ok(())
+  }
+
+  fn cmd_buf_face_culling(
+    _cmd_buf: &Self::CmdBuf,
+    _face_culling: piksels_backend::face_culling::FaceCulling,
+  ) -> Result<(), Self::Err> {
+    Ok(())
+  }
+
+  fn cmd_buf_viewport(_cmd_buf: &Self::CmdBuf, viewport: Viewport) -> Result<(), Self::Err> {
+    LAST_VIEWPORT.with(|last| last.set(Some(viewport)));
+    Ok(())
+  }
+
+  fn cmd_buf_scissor(_cmd_buf: &Self::CmdBuf, scissor: Scissor) -> Result<(), Self::Err> {
+    LAST_SCISSOR.with(|last| last.set(Some(scissor)));
+    Ok(())
+  }
+
+  fn cmd_buf_clear_color(_cmd_buf: &Self::CmdBuf, _clear_color: RGBA32F) -> Result<(), Self::Err> {
+    Ok(())
+  }
+
+  fn cmd_buf_clear_depth(_cmd_buf: &Self::CmdBuf, _clear_depth: f32) -> Result<(), Self::Err> {
+    Ok(())
+  }
+
+  fn cmd_buf_srgb(_cmd_buf: &Self::CmdBuf, srgb: bool) -> Result<(), Self::Err> {
+    SRGB_SWITCHES.with(|switches| switches.borrow_mut().push(srgb));
+    Ok(())
+  }
+
+  fn cmd_buf_primitive_restart(
+    _cmd_buf: &Self::CmdBuf,
+    _primitive_restart: bool,
+  ) -> Result<(), Self::Err> {
+    Ok(())
+  }
+
+  fn cmd_buf_set_uniform(
+    _cmd_buf: &Self::CmdBuf,
+    _uniform: &Self::Uniform,
+    value: &[u8],
+  ) -> Result<(), Self::Err> {
+    LAST_UNIFORM_BYTES.with(|bytes| *bytes.borrow_mut() = value.to_owned());
+    Ok(())
+  }
+
+  fn cmd_buf_set_uniform_array(
+    _cmd_buf: &Self::CmdBuf,
+    _uniform: &Self::Uniform,
+    first_element: usize,
+    values: &[u8],
+  ) -> Result<(), Self::Err> {
+    LAST_UNIFORM_ARRAY_UPDATE
+      .with(|update| *update.borrow_mut() = Some((first_element, values.to_owned())));
+    Ok(())
+  }
+
+  fn cmd_buf_bind_texture(
+    _cmd_buf: &Self::CmdBuf,
+    texture: &Self::Texture,
+    binding_point: &Self::TextureBindingPoint,
+  ) -> Result<(), Self::Err> {
+    TEXTURE_BINDS.with(|binds| binds.borrow_mut().push((binding_point.0, texture.0)));
+    Ok(())
+  }
+
+  fn cmd_buf_bind_color_attachment(
+    _cmd_buf: &Self::CmdBuf,
+    _color_attachment: &Self::ColorAttachment,
+    _binding_point: &Self::TextureBindingPoint,
+  ) -> Result<(), Self::Err> {
+    Ok(())
+  }
+
+  fn cmd_buf_bind_depth_stencil_attachment(
+    _cmd_buf: &Self::CmdBuf,
+    _depth_stencil_attachment: &Self::DepthStencilAttachment,
+    _binding_point: &Self::TextureBindingPoint,
+  ) -> Result<(), Self::Err> {
+    Ok(())
+  }
+
+  fn cmd_buf_associate_texture_binding_point(
+    _cmd_buf: &Self::CmdBuf,
+    _texture_binding_point: &Self::TextureBindingPoint,
+    _shader_binding_point: &Self::ShaderTextureBindingPoint,
+  ) -> Result<(), Self::Err> {
+    Ok(())
+  }
+
+  fn cmd_buf_bind_uniform_buffer(
+    _cmd_buf: &Self::CmdBuf,
+    _uniform_buffer: &Self::UniformBuffer,
+    _binding_point: &Self::UniformBufferBindingPoint,
+  ) -> Result<(), Self::Err> {
+    Ok(())
+  }
+
+  fn cmd_buf_associate_uniform_buffer_binding_point(
+    _cmd_buf: &Self::CmdBuf,
+    _uniform_buffer_binding_point: &Self::UniformBufferBindingPoint,
+    _shader_uniform_buffer_binding_point: &Self::ShaderUniformBufferBindingPoint,
+  ) -> Result<(), Self::Err> {
+    Ok(())
+  }
+
+  fn cmd_buf_bind_storage_buffer(
+    _cmd_buf: &Self::CmdBuf,
+    _storage_buffer: &Self::StorageBuffer,
+    _binding_point: &Self::StorageBufferBindingPoint,
+  ) -> Result<(), Self::Err> {
+    Ok(())
+  }
+
+  fn cmd_buf_associate_storage_buffer_binding_point(
+    _cmd_buf: &Self::CmdBuf,
+    _storage_buffer_binding_point: &Self::StorageBufferBindingPoint,
+    _shader_storage_buffer_binding_point: &Self::ShaderStorageBufferBindingPoint,
+  ) -> Result<(), Self::Err> {
+    Ok(())
+  }
+
+  fn cmd_buf_resolve_texture(
+    _cmd_buf: &Self::CmdBuf,
+    _src: &Self::Texture,
+    _dst: &Self::Texture,
+  ) -> Result<(), Self::Err> {
+    Err(DummyBackendError::Unimplemented)
+  }
+
+  fn cmd_buf_resolve_render_targets(
+    _cmd_buf: &Self::CmdBuf,
+    _src: &Self::RenderTargets,
+    _dst: &Self::RenderTargets,
+  ) -> Result<(), Self::Err> {
+    Ok(())
+  }
+
+  fn cmd_buf_bind_render_targets(
+    _cmd_buf: &Self::CmdBuf,
+    _render_targets: &Self::RenderTargets,
+  ) -> Result<(), Self::Err> {
+    Ok(())
+  }
+
+  fn cmd_buf_bind_shader(_cmd_buf: &Self::CmdBuf, _shader: &Self::Shader) -> Result<(), Self::Err> {
+    Ok(())
+  }
+
+  fn cmd_buf_draw_vertex_array(
+    _cmd_buf: &Self::CmdBuf,
+    _vertex_array: &Self::VertexArray,
+    _restart_index: u32,
+  ) -> Result<(), Self::Err> {
+    if OPEN_TIMER_QUERIES.with(Cell::get) > 0 {
+      TIMED_DRAWS.with(|draws| draws.set(draws.get() + 1));
+    }
+
+    Ok(())
+  }
+
+  fn cmd_buf_draw_vertex_array_range(
+    _cmd_buf: &Self::CmdBuf,
+    _vertex_array: &Self::VertexArray,
+    _start_vertex: usize,
+    _vertex_count: usize,
+    _start_instance: usize,
+    _instance_count: usize,
+    _restart_index: u32,
+  ) -> Result<(), Self::Err> {
+    Ok(())
+  }
+
+  fn cmd_buf_finish(_cmd_buf: &Self::CmdBuf) -> Result<(), Self::Err> {
+    Ok(())
+  }
+
+  fn cmd_buf_execute(_cmd_buf: &Self::CmdBuf, deferred: &Self::CmdBuf) -> Result<(), Self::Err> {
+    EXECUTED_CMD_BUFS.with(|executed| executed.borrow_mut().push(deferred.0));
+    Ok(())
+  }
+
+  fn new_swap_chain(
+    &self,
+    width: u32,
+    height: u32,
+    _mode: piksels_backend::swap_chain::SwapChainMode,
+    _image_count: usize,
+    _format: SwapChainFormat,
+  ) -> Result<Self::SwapChain, Self::Err> {
+    SWAP_CHAIN_EXTENT.with(|extent| extent.set((width, height)));
+    Ok(self.new_resource())
+  }
+
+  fn drop_swap_chain(_swap_chain: &Self::SwapChain) -> Result<(), Self::Err> {
+    Ok(())
+  }
+
+  fn swap_chain_wait_idle(_swap_chain: &Self::SwapChain) -> Result<(), Self::Err> {
+    SWAP_CHAIN_WAITS.with(|waits| waits.set(waits.get() + 1));
+    Ok(())
+  }
+
+  fn resize_swap_chain(
+    _swap_chain: &Self::SwapChain,
+    width: u32,
+    height: u32,
+  ) -> Result<(), Self::Err> {
+    SWAP_CHAIN_EXTENT.with(|extent| extent.set((width, height)));
+    Ok(())
+  }
+
+  fn swap_chain_render_targets(
+    swap_chain: &Self::SwapChain,
+  ) -> Result<Self::RenderTargets, Self::Err> {
+    Ok(DummyResource(swap_chain.0))
+  }
+
+  fn present_render_targets(
+    _swap_chain: &Self::SwapChain,
+    _render_targets: &Self::RenderTargets,
+  ) -> Result<PresentStatus, Self::Err> {
+    let status = NEXT_PRESENT_STATUS.with(|status| status.take());
+    if status == PresentStatus::Presented {
+      PRESENTED_FRAMES.with(|presented| presented.set(presented.get() + 1));
+    }
+
+    Ok(status)
+  }
+}
+
+pub fn dummy_device() -> Device<DummyBackend> {
+  let (device, _) = DeviceBuilder::new()
+    .logger(LoggerExt::new(LogLevel::Trace, DummyLogger))
+    .build()
+    .unwrap();
+  device
+}
+
+pub fn dummy_sampling() -> Sampling {
+  Sampling {
+    wrap_r: Wrap::ClampToEdge,
+    wrap_s: Wrap::ClampToEdge,
+    wrap_t: Wrap::ClampToEdge,
+    min_filter: MinFilter::Nearest,
+    mag_filter: MagFilter::Nearest,
+    depth_comparison: None,
+  }
+}
+
+pub fn dummy_limits() -> DeviceLimits {
+  DeviceLimits {
+    max_texture_size: 16384,
+    max_color_attachments: 8,
+    max_texture_units: 32,
+    max_stage_texture_units: BTreeMap::new(),
+    max_uniform_buffer_units: 16,
+    max_samples: 8,
+    max_uniform_buffer_size: 65536,
+    max_vertex_attributes: 16,
+    max_workgroup_count: [65535; 3],
+    max_workgroup_size: [1024, 1024, 64],
+    max_workgroup_invocations: 1024,
+  }
+}
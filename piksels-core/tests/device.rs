@@ -0,0 +1,517 @@
+use std::{cell::Cell, collections::HashSet, sync::Arc, thread};
+
+use piksels_backend::{
+  error::Error,
+  extension::{
+    logger::{LogLevel, LoggerExt},
+    ExtensionKind, Extensions,
+  },
+  pixel::{ChannelBits, Format, Pixel, Type},
+  scarce::ScarceIndexAllocator,
+  shader::ShaderSources,
+  texture::{MagFilter, MinFilter, Sampling, Storage},
+  viewport::Viewport,
+  Backend, BackendInfo,
+};
+use piksels_core::{
+  cmd_buf::CmdBuf,
+  device::{Device, DeviceBuilder},
+  error_policy::ErrorPolicy,
+  gc::{GcBudget, GcMode},
+  pipeline::{PipelineDefaults, PipelineState},
+  render_targets::RenderTargets,
+  shader::Shader,
+  swap_chain::SwapChain,
+  texture::Texture,
+  vertex_array::VertexArray,
+};
+
+mod common;
+
+use common::*;
+
+#[test]
+fn diagnostic_report() {
+  let (device, extensions) = DeviceBuilder::new()
+    .logger(LoggerExt::new(LogLevel::Trace, DummyLogger))
+    .extension(ExtensionKind::SparseTexture)
+    .build::<DummyBackend>()
+    .unwrap();
+  assert_eq!(device.extensions(), &extensions);
+
+  let report = device.diagnostic_report().unwrap();
+  assert_eq!(report.name, "DummyBackend");
+  assert_eq!(report.version, "v1.0.0-super-dummy");
+  assert_eq!(report.shading_lang_version, "v1.0.0");
+  assert_eq!(report.limits, dummy_limits());
+  assert_eq!(report.features, device.features());
+  assert!(report.extensions.is_accepted(ExtensionKind::Logger));
+  assert!(report
+    .extensions
+    .rejected()
+    .eq([ExtensionKind::SparseTexture]));
+
+  #[cfg(feature = "serde")]
+  {
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["name"], "DummyBackend");
+    assert_eq!(json["limits"]["max_texture_units"], 32);
+    assert_eq!(json["extensions"]["rejected"][0], "SparseTexture");
+  }
+}
+
+#[test]
+fn extension_negotiation() {
+  let (_, extensions) = DeviceBuilder::new()
+    .logger(LoggerExt::new(LogLevel::Trace, DummyLogger))
+    .extension(ExtensionKind::Readback)
+    .extension(ExtensionKind::SparseTexture)
+    .build::<DummyBackend>()
+    .unwrap();
+
+  assert!(extensions.is_accepted(ExtensionKind::Logger));
+  assert_eq!(
+    extensions.is_accepted(ExtensionKind::Readback),
+    cfg!(feature = "ext-readback")
+  );
+  assert!(!extensions.is_accepted(ExtensionKind::FrameStats));
+  assert!(extensions.rejected().eq(
+    cfg!(not(feature = "ext-readback"))
+      .then_some(ExtensionKind::Readback)
+      .into_iter()
+      .chain([ExtensionKind::SparseTexture])
+  ));
+
+  // a logger is needed to accept the logger extension
+  let mut extensions = Extensions::new().with(ExtensionKind::Logger);
+  DummyBackend::build(&mut extensions).unwrap();
+  assert!(extensions
+    .negotiated()
+    .rejected()
+    .eq([ExtensionKind::Logger]));
+}
+
+#[test]
+fn device_threading() {
+  fn assert_send_sync<T: Send + Sync>() {}
+  fn assert_send<T: Send>() {}
+  assert_send_sync::<Device<DummyBackend>>();
+  assert_send_sync::<Texture<DummyBackend>>();
+  assert_send_sync::<VertexArray<DummyBackend>>();
+  assert_send_sync::<Shader<DummyBackend>>();
+  assert_send_sync::<RenderTargets<DummyBackend>>();
+  assert_send_sync::<SwapChain<DummyBackend>>();
+  assert_send::<CmdBuf<DummyBackend>>();
+
+  let device = Arc::new(dummy_device());
+  let texture = thread::spawn({
+    let device = device.clone();
+    move || {
+      let context = device.make_current().unwrap();
+      assert!(CURRENT_CONTEXT.with(Cell::get));
+
+      let texture = device
+        .new_texture(
+          Storage::Flat2D {
+            width: 4,
+            height: 4,
+          },
+          Pixel {
+            encoding: Type::NormUnsigned,
+            format: Format::RGBA(
+              ChannelBits::Eight,
+              ChannelBits::Eight,
+              ChannelBits::Eight,
+              ChannelBits::Eight,
+            ),
+          },
+          dummy_sampling(),
+        )
+        .unwrap();
+
+      drop(context);
+      assert!(!CURRENT_CONTEXT.with(Cell::get));
+      texture
+    }
+  })
+  .join()
+  .unwrap();
+
+  // the context was only current on the other thread
+  assert!(!CURRENT_CONTEXT.with(Cell::get));
+  let _context = device.make_current().unwrap();
+  assert!(CURRENT_CONTEXT.with(Cell::get));
+  assert_eq!(
+    texture.storage(),
+    Storage::Flat2D {
+      width: 4,
+      height: 4
+    }
+  );
+}
+
+#[test]
+fn device_limits() {
+  let queries = LIMITS_QUERIES.with(Cell::get);
+  let device = dummy_device();
+  assert_eq!(device.limits(), &dummy_limits());
+  assert_eq!(device.limits().max_color_attachments, 8);
+
+  // limits are queried once, when the device is created
+  assert_eq!(LIMITS_QUERIES.with(Cell::get), queries + 1);
+}
+
+#[test]
+fn device_features() {
+  let features = dummy_device().features();
+  assert!(features.compute && features.tessellation && features.geometry_shaders);
+  assert!(features.anisotropy && features.srgb);
+  assert!(!features.multi_draw_indirect);
+  assert!(!features.bindless);
+}
+
+#[test]
+fn dummy_backend_info() {
+  let device = dummy_device();
+
+  assert_eq!(
+    device.author(),
+    Ok("Dimitri 'phaazon' Sabadie <dimitri.sabadie@gmail.com>".to_owned())
+  );
+  assert_eq!(device.name(), Ok("DummyBackend".to_owned()));
+  assert_eq!(device.version(), Ok("v1.0.0-super-dummy".to_owned()));
+  assert_eq!(device.shading_lang_version(), Ok("v1.0.0".to_owned()));
+  assert_eq!(
+    device.info(),
+    Ok(BackendInfo {
+      version: env!("CARGO_PKG_VERSION"),
+      git_commit_hash: "HEAD"
+    })
+  );
+}
+
+#[test]
+fn dummy_backend_resource_by_id() {
+  let device = dummy_device();
+
+  let texture = device
+    .new_texture(
+      Storage::Flat2D {
+        width: 4,
+        height: 4,
+      },
+      Pixel {
+        encoding: Type::NormUnsigned,
+        format: Format::RGBA(
+          ChannelBits::Eight,
+          ChannelBits::Eight,
+          ChannelBits::Eight,
+          ChannelBits::Eight,
+        ),
+      },
+      dummy_sampling(),
+    )
+    .unwrap();
+  let id = texture.id();
+
+  assert_eq!(
+    device
+      .texture_by_id(&id)
+      .unwrap()
+      .map(|texture| texture.id()),
+    Some(id)
+  );
+  assert!(device.texture_by_id(&(id + 1)).unwrap().is_none());
+}
+
+#[test]
+fn accumulated_errors() {
+  FAILING_SHADER_DROPS.with(|failing| failing.set(true));
+  let device = dummy_device().with_error_policy(ErrorPolicy::Accumulate);
+
+  let shader = device.new_shader(ShaderSources::default()).unwrap();
+  device.drop_shader(shader).unwrap();
+  assert_eq!(device.drain_errors().unwrap().len(), 1);
+
+  let device = device.with_gc_mode(GcMode::TimeSliced);
+  for _ in 0..2 {
+    let shader = device.new_shader(ShaderSources::default()).unwrap();
+    device.drop_shader(shader).unwrap();
+  }
+
+  assert!(device.drain_errors().unwrap().is_empty());
+  assert_eq!(device.collect_garbage(GcBudget::unlimited()).unwrap(), 2);
+  assert_eq!(
+    device.drain_errors().unwrap(),
+    [DummyBackendError::Injected, DummyBackendError::Injected]
+  );
+  assert!(device.drain_errors().unwrap().is_empty());
+}
+
+#[test]
+fn logged_errors() {
+  FAILING_SHADER_DROPS.with(|failing| failing.set(true));
+  let device = dummy_device().with_error_policy(ErrorPolicy::LogAndContinue);
+
+  let errors = LOGGED_ERRORS.with(Cell::get);
+  let shader = device.new_shader(ShaderSources::default()).unwrap();
+  device.drop_shader(shader).unwrap();
+  assert_eq!(LOGGED_ERRORS.with(Cell::get), errors + 1);
+  assert!(device.drain_errors().unwrap().is_empty());
+}
+
+#[test]
+#[should_panic(expected = "non-fatal backend error")]
+fn panicking_errors() {
+  FAILING_SHADER_DROPS.with(|failing| failing.set(true));
+  let device = dummy_device();
+  let shader = device.new_shader(ShaderSources::default()).unwrap();
+  let _ = device.drop_shader(shader);
+}
+
+#[test]
+fn frame_counter() {
+  let device = dummy_device().with_gc_mode(GcMode::TimeSliced);
+  assert_eq!(device.frame().unwrap().get(), 0);
+
+  let frame = device.begin_frame().unwrap();
+  assert_eq!(frame.get(), 1);
+  assert!(matches!(
+    device.begin_frame(),
+    Err(DummyBackendError::Common(Error::InvalidFrame { .. }))
+  ));
+
+  let triangle = device.new_empty_vertex_array(3).unwrap();
+  device.drop_vertex_array(triangle).unwrap();
+  assert_eq!(device.end_frame().unwrap(), frame);
+  assert!(matches!(
+    device.end_frame(),
+    Err(DummyBackendError::Common(Error::InvalidFrame { .. }))
+  ));
+
+  // the vertex array is kept around while frames might still use it
+  let budget = GcBudget::unlimited().min_age(2);
+  assert_eq!(device.collect_garbage(budget).unwrap(), 0);
+
+  let next = device.begin_frame().unwrap();
+  assert!(next > frame);
+  assert_eq!(next.frames_since(frame), 1);
+  device.end_frame().unwrap();
+  assert_eq!(device.collect_garbage(budget).unwrap(), 0);
+
+  device.begin_frame().unwrap();
+  assert_eq!(device.collect_garbage(budget).unwrap(), 1);
+  assert_eq!(device.garbage_len().unwrap(), 0);
+}
+
+#[test]
+fn resource_names() {
+  let device = dummy_device();
+  let pixel = Pixel {
+    encoding: Type::NormUnsigned,
+    format: Format::R(ChannelBits::Eight),
+  };
+  let storage = Storage::Flat2D {
+    width: 4,
+    height: 4,
+  };
+  let albedo = device
+    .new_texture(storage, pixel, dummy_sampling())
+    .unwrap();
+  let shader = device.new_shader(ShaderSources::default()).unwrap();
+  let cmd_buf = device.new_cmd_buf().unwrap();
+
+  NAMES.with(|names| names.borrow_mut().clear());
+  device.set_name(&albedo, "albedo").unwrap();
+  device.set_name(&shader, "gbuffer").unwrap();
+  assert_eq!(device.resource_name(&cmd_buf).unwrap(), None);
+  device.set_name(&cmd_buf, "main pass").unwrap();
+  device.set_name(&albedo, "albedo map").unwrap();
+
+  assert_eq!(
+    device.resource_name(&albedo).unwrap().as_deref(),
+    Some("albedo map")
+  );
+  assert_eq!(
+    device.resource_name(&shader).unwrap().as_deref(),
+    Some("gbuffer")
+  );
+  assert_eq!(
+    device.resource_name(&cmd_buf).unwrap().as_deref(),
+    Some("main pass")
+  );
+  assert_eq!(
+    NAMES.with(|names| names.take()),
+    [
+      (albedo.id(), "albedo".to_owned()),
+      (shader.id(), "gbuffer".to_owned()),
+      (cmd_buf.id(), "main pass".to_owned()),
+      (albedo.id(), "albedo map".to_owned()),
+    ]
+  );
+
+  // names are forgotten with their resources, even if their index is given to another resource
+  RECYCLED_TEXTURE_INDICES.with(|indices| *indices.borrow_mut() = Some(Vec::new()));
+  let albedo_copy = device.texture_by_id(&albedo.id()).unwrap().unwrap();
+  device.drop_texture(albedo).unwrap();
+  assert_eq!(
+    device.resource_name(&albedo_copy).unwrap().as_deref(),
+    Some("albedo map")
+  );
+
+  let id = albedo_copy.id();
+  drop(albedo_copy);
+  let recycled = device
+    .new_texture(storage, pixel, dummy_sampling())
+    .unwrap();
+  assert_eq!(recycled.id(), id);
+  assert_eq!(device.resource_name(&recycled).unwrap(), None);
+}
+
+#[test]
+fn device_defaults() {
+  let device = dummy_device();
+  let viewport = Viewport::Specific {
+    x: 0,
+    y: 0,
+    width: 400,
+    height: 300,
+  };
+  let sampling = Sampling {
+    min_filter: MinFilter::Nearest,
+    mag_filter: MagFilter::Nearest,
+    ..Sampling::default()
+  };
+
+  assert_eq!(device.defaults().unwrap(), PipelineDefaults::default());
+  let cmd_buf = device.new_cmd_buf().unwrap();
+  assert_eq!(cmd_buf.state_change_count(), 0);
+
+  device
+    .set_defaults(PipelineDefaults {
+      state: PipelineState {
+        viewport,
+        ..PipelineState::default()
+      },
+      sampling,
+    })
+    .unwrap();
+  assert_eq!(device.defaults().unwrap().sampling, sampling);
+
+  // new command buffers are seeded with the defaults
+  LAST_VIEWPORT.with(|last| last.set(None));
+  let cmd_buf = device.new_cmd_buf().unwrap();
+  let state_changes = cmd_buf.state_change_count();
+  assert!(state_changes > 0);
+  assert_eq!(LAST_VIEWPORT.with(Cell::get), Some(viewport));
+
+  // every command buffer is seeded, whatever the others set
+  let cmd_buf = device.new_cmd_buf().unwrap();
+  assert_eq!(cmd_buf.state_change_count(), state_changes);
+
+  // the state is cached, so seeding again doesn’t change anything
+  cmd_buf.clear_state().unwrap();
+  assert_eq!(cmd_buf.state_change_count(), state_changes);
+
+  // clearing the state resets to the defaults
+  cmd_buf.viewport(Viewport::Whole).unwrap();
+  cmd_buf.clear_state().unwrap();
+  assert_eq!(LAST_VIEWPORT.with(Cell::get), Some(viewport));
+
+  // deferred command buffers are seeded as well
+  LAST_VIEWPORT.with(|last| last.set(None));
+  let deferred = device.create_deferred_context().new_cmd_buf().unwrap();
+  assert!(deferred.is_deferred());
+  assert_eq!(LAST_VIEWPORT.with(Cell::get), Some(viewport));
+}
+
+#[test]
+fn cache_debug_dump() {
+  let device = dummy_device();
+  let pixel = Pixel {
+    encoding: Type::NormUnsigned,
+    format: Format::R(ChannelBits::Eight),
+  };
+  let storage = Storage::Flat2D {
+    width: 4,
+    height: 4,
+  };
+  let albedo = device
+    .new_texture(storage, pixel, dummy_sampling())
+    .unwrap();
+  let normal = device
+    .new_texture(storage, pixel, dummy_sampling())
+    .unwrap();
+  let shader = device.new_shader(ShaderSources::default()).unwrap();
+  let albedo_point = shader.texture_binding_point("albedo").unwrap();
+  let render_targets = device
+    .new_render_targets(HashSet::new(), None, storage)
+    .unwrap();
+  device.set_name(&albedo, "albedo").unwrap();
+
+  device
+    .new_layers()
+    .unwrap()
+    .render_targets(&render_targets)
+    .unwrap()
+    .shader(&shader)
+    .unwrap()
+    .texture(&albedo, &albedo_point)
+    .unwrap()
+    .done()
+    .unwrap()
+    .done()
+    .done()
+    .unwrap();
+
+  let dump = device.debug_dump().unwrap();
+  assert_eq!(
+    dump
+      .textures
+      .iter()
+      .map(|texture| (texture.id, texture.name.as_deref()))
+      .collect::<Vec<_>>(),
+    [(albedo.id(), Some("albedo")), (normal.id(), None)]
+  );
+  assert_eq!(dump.shaders.len(), 1);
+  assert_eq!(dump.render_targets.len(), 1);
+
+  // the albedo texture is still bound, the normal one never was
+  assert_eq!(dump.texture_units.len(), 1);
+  assert_eq!(dump.texture_units[0].scarce_index, albedo.id());
+  assert!(!dump.texture_units[0].in_use);
+
+  let text = dump.to_string();
+  assert!(text.contains("textures: 2"));
+  assert!(text.contains("\"albedo\""));
+}
+
+#[test]
+fn scarce_index_allocator() {
+  let allocator = ScarceIndexAllocator::new();
+  let indices: Vec<_> = (0..4).map(|_| allocator.allocate()).collect();
+  assert_eq!(indices, [0, 1, 2, 3]);
+  assert_eq!(allocator.allocated(), 4);
+
+  // freed indices are reused oldest first
+  allocator.free(2);
+  allocator.free(0);
+  assert_eq!(allocator.allocated(), 2);
+  assert_eq!(allocator.allocate(), 2);
+  assert_eq!(allocator.allocate(), 0);
+  assert_eq!(allocator.allocate(), 4);
+
+  // indices are unique across threads
+  let allocator = Arc::new(ScarceIndexAllocator::new());
+  let workers: Vec<_> = (0..4)
+    .map(|_| {
+      let allocator = allocator.clone();
+      thread::spawn(move || (0..100).map(|_| allocator.allocate()).collect::<Vec<_>>())
+    })
+    .collect();
+  let indices: HashSet<_> = workers
+    .into_iter()
+    .flat_map(|worker| worker.join().unwrap())
+    .collect();
+  assert_eq!(indices.len(), 400);
+}
@@ -0,0 +1,348 @@
+use std::{cell::Cell, collections::HashSet};
+
+use piksels_backend::{
+  pixel::{ChannelBits, Format, Pixel, Type},
+  render_targets::{self, ColorAttachmentPoint, ColorType},
+  shader::ShaderSources,
+  swap_chain::SwapChainFormat,
+  texture::Storage,
+};
+use piksels_core::{
+  error_policy::ErrorPolicy,
+  gc::{GcBudget, GcMode},
+};
+
+mod common;
+
+use common::*;
+
+#[test]
+fn time_sliced_gc() {
+  let device = dummy_device().with_gc_mode(GcMode::TimeSliced);
+  let pixel = Pixel {
+    encoding: Type::NormUnsigned,
+    format: Format::R(ChannelBits::Eight),
+  };
+  let storage = Storage::Flat2D {
+    width: 4,
+    height: 4,
+  };
+
+  for _ in 0..5 {
+    let texture = device
+      .new_texture(storage, pixel, dummy_sampling())
+      .unwrap();
+    let id = texture.id();
+    device.drop_texture(texture).unwrap();
+    assert!(device.texture_by_id(&id).unwrap().is_none());
+  }
+
+  assert_eq!(DROPPED_TEXTURES.with(Cell::get), 0);
+  assert_eq!(device.garbage_len().unwrap(), 5);
+
+  assert_eq!(
+    device
+      .collect_garbage(GcBudget::default().count(2))
+      .unwrap(),
+    2
+  );
+  assert_eq!(DROPPED_TEXTURES.with(Cell::get), 2);
+  assert_eq!(device.garbage_len().unwrap(), 3);
+
+  assert_eq!(device.collect_garbage(GcBudget::unlimited()).unwrap(), 3);
+  assert_eq!(DROPPED_TEXTURES.with(Cell::get), 5);
+  assert_eq!(device.garbage_len().unwrap(), 0);
+}
+
+#[test]
+fn deferred_gc() {
+  let device = dummy_device().with_gc_mode(GcMode::Deferred);
+  let pixel = Pixel {
+    encoding: Type::NormUnsigned,
+    format: Format::R(ChannelBits::Eight),
+  };
+  let storage = Storage::Flat2D {
+    width: 4,
+    height: 4,
+  };
+  let albedo = device
+    .new_texture(storage, pixel, dummy_sampling())
+    .unwrap();
+  let unit = device.get_texture_binding_point(0).unwrap();
+
+  // the texture is dropped while a command buffer using it is still recording
+  let cmd_buf = device.new_cmd_buf().unwrap();
+  cmd_buf.use_texture(&albedo, &unit).unwrap();
+  device.drop_texture(albedo).unwrap();
+  assert_eq!(device.collect_garbage(GcBudget::unlimited()).unwrap(), 0);
+  assert_eq!(DROPPED_TEXTURES.with(Cell::get), 0);
+
+  cmd_buf.finish().unwrap();
+  assert_eq!(device.collect_garbage(GcBudget::unlimited()).unwrap(), 1);
+  assert_eq!(DROPPED_TEXTURES.with(Cell::get), 1);
+  assert_eq!(device.garbage_len().unwrap(), 0);
+
+  // a presentation is a submission as well
+  let swap_chain = device
+    .new_swap_chain(
+      800,
+      600,
+      piksels_backend::swap_chain::SwapChainMode::Fifo,
+      2,
+      SwapChainFormat::SRGBA8,
+    )
+    .unwrap();
+  let texture = device
+    .new_texture(storage, pixel, dummy_sampling())
+    .unwrap();
+  let render_targets = swap_chain.acquire().unwrap();
+  device.drop_texture(texture).unwrap();
+  assert_eq!(device.collect_garbage(GcBudget::unlimited()).unwrap(), 0);
+  swap_chain.present(&render_targets).unwrap();
+  assert_eq!(device.collect_garbage(GcBudget::unlimited()).unwrap(), 1);
+  assert_eq!(DROPPED_TEXTURES.with(Cell::get), 2);
+}
+
+#[test]
+fn shader_drop() {
+  let device = dummy_device().with_error_policy(ErrorPolicy::Accumulate);
+
+  let dropped = DROPPED_SHADERS.with(Cell::get);
+  let shader = device.new_shader(ShaderSources::default()).unwrap();
+  device.drop_shader(shader).unwrap();
+  assert_eq!(DROPPED_SHADERS.with(Cell::get), dropped + 1);
+  assert!(device.drain_errors().unwrap().is_empty());
+}
+
+#[test]
+fn drop_order_resources_first() {
+  let device = dummy_device();
+  let pixel = Pixel {
+    encoding: Type::NormUnsigned,
+    format: Format::R(ChannelBits::Eight),
+  };
+  let texture = device
+    .new_texture(
+      Storage::Flat2D {
+        width: 4,
+        height: 4,
+      },
+      pixel,
+      dummy_sampling(),
+    )
+    .unwrap();
+  let other_handle = device.texture_by_id(&texture.id()).unwrap().unwrap();
+
+  // dropping the texture through both handles only destroys it once
+  device.drop_texture(texture).unwrap();
+  device.drop_texture(other_handle).unwrap();
+  assert_eq!(DROPPED_TEXTURES.with(Cell::get), 1);
+
+  drop(device);
+  assert_eq!(DROPPED_TEXTURES.with(Cell::get), 1);
+}
+
+#[test]
+fn drop_order_device_first() {
+  let device = dummy_device().with_gc_mode(GcMode::TimeSliced);
+  let pixel = Pixel {
+    encoding: Type::NormUnsigned,
+    format: Format::R(ChannelBits::Eight),
+  };
+  let storage = Storage::Flat2D {
+    width: 4,
+    height: 4,
+  };
+  let dropped = device
+    .new_texture(storage, pixel, dummy_sampling())
+    .unwrap();
+  let alive = device
+    .new_texture(storage, pixel, dummy_sampling())
+    .unwrap();
+  let render_targets = device
+    .new_render_targets(
+      HashSet::from([ColorAttachmentPoint::new(
+        0,
+        "color",
+        ColorType::IRGBA {
+          red_bits: render_targets::ChannelBits::Eight,
+          green_bits: render_targets::ChannelBits::Eight,
+          blue_bits: render_targets::ChannelBits::Eight,
+          alpha_bits: render_targets::ChannelBits::Eight,
+        },
+      )]),
+      None,
+      storage,
+    )
+    .unwrap();
+  let cmd_buf = device.new_cmd_buf().unwrap();
+
+  device.drop_texture(dropped).unwrap();
+  assert_eq!(DROPPED_TEXTURES.with(Cell::get), 0);
+
+  // pending garbage is destroyed with the device, and so are resources still alive
+  drop(device);
+  assert_eq!(DROPPED_TEXTURES.with(Cell::get), 2);
+
+  // handles outliving the device can still be dropped, without destroying anything twice
+  drop((alive, render_targets, cmd_buf));
+  assert_eq!(DROPPED_TEXTURES.with(Cell::get), 2);
+}
+
+#[test]
+fn drop_order_all_resources() {
+  let dropped = || {
+    [
+      DROPPED_TEXTURES.with(Cell::get),
+      DROPPED_SHADERS.with(Cell::get),
+      DROPPED_VERTEX_ARRAYS.with(Cell::get),
+      DROPPED_CMD_BUFS.with(Cell::get),
+    ]
+  };
+  let pixel = Pixel {
+    encoding: Type::NormUnsigned,
+    format: Format::R(ChannelBits::Eight),
+  };
+  let storage = Storage::Flat2D {
+    width: 4,
+    height: 4,
+  };
+
+  // handles dropped without the device: resources are destroyed once, when dropping their handles
+  let device = dummy_device();
+  let texture = device
+    .new_texture(storage, pixel, dummy_sampling())
+    .unwrap();
+  let shader = device.new_shader(ShaderSources::default()).unwrap();
+  let vertex_array = device.new_empty_vertex_array(3).unwrap();
+  let cmd_buf = device.new_cmd_buf().unwrap();
+  drop((texture, shader, vertex_array, cmd_buf));
+  assert_eq!(dropped(), [1; 4]);
+
+  drop(device);
+  assert_eq!(dropped(), [1; 4]);
+
+  // handles dropped with the device, and then the device: nothing is destroyed twice
+  let device = dummy_device().with_gc_mode(GcMode::TimeSliced);
+  let texture = device
+    .new_texture(storage, pixel, dummy_sampling())
+    .unwrap();
+  let shader = device.new_shader(ShaderSources::default()).unwrap();
+  let vertex_array = device.new_empty_vertex_array(3).unwrap();
+  let cmd_buf = device.new_cmd_buf().unwrap();
+  let shared_shader = device.shader_by_id(&shader.id()).unwrap().unwrap();
+  device.drop_texture(texture).unwrap();
+  device.drop_shader(shader).unwrap();
+  device.drop_shader(shared_shader).unwrap();
+  device.drop_vertex_array(vertex_array).unwrap();
+  device.drop_cmd_buf(cmd_buf).unwrap();
+
+  drop(device);
+  assert_eq!(dropped(), [2; 4]);
+
+  // handles dropped after the device: the device destroys the resources once
+  let device = dummy_device();
+  let texture = device
+    .new_texture(storage, pixel, dummy_sampling())
+    .unwrap();
+  let shader = device.new_shader(ShaderSources::default()).unwrap();
+  let vertex_array = device.new_empty_vertex_array(3).unwrap();
+  let cmd_buf = device.new_cmd_buf().unwrap();
+
+  drop(device);
+  assert_eq!(dropped(), [3; 4]);
+
+  drop((texture, shader, vertex_array, cmd_buf));
+  assert_eq!(dropped(), [3; 4]);
+}
+
+#[test]
+fn drop_last_handle() {
+  let device = dummy_device();
+  let pixel = Pixel {
+    encoding: Type::NormUnsigned,
+    format: Format::R(ChannelBits::Eight),
+  };
+  let storage = Storage::Flat2D {
+    width: 4,
+    height: 4,
+  };
+  let texture = device
+    .new_texture(storage, pixel, dummy_sampling())
+    .unwrap();
+  let shader = device.new_shader(ShaderSources::default()).unwrap();
+  let other_texture = device.texture_by_id(&texture.id()).unwrap().unwrap();
+  let other_shader = device.new_shader(ShaderSources::default()).unwrap();
+
+  // the resources stay alive until their last handle is dropped
+  drop((texture, shader));
+  assert_eq!(DROPPED_TEXTURES.with(Cell::get), 0);
+  assert_eq!(DROPPED_SHADERS.with(Cell::get), 0);
+  assert_eq!(device.debug_dump().unwrap().shaders.len(), 1);
+
+  drop((other_texture, other_shader));
+  assert_eq!(DROPPED_TEXTURES.with(Cell::get), 1);
+  assert_eq!(DROPPED_SHADERS.with(Cell::get), 1);
+  assert!(device.debug_dump().unwrap().shaders.is_empty());
+
+  drop(device);
+  assert_eq!(DROPPED_TEXTURES.with(Cell::get), 1);
+  assert_eq!(DROPPED_SHADERS.with(Cell::get), 1);
+}
+
+#[test]
+fn drop_last_handle_gc_mode() {
+  let device = dummy_device().with_gc_mode(GcMode::TimeSliced);
+  let vertex_array = device.new_empty_vertex_array(3).unwrap();
+
+  // dropped handles are garbage collected as any dropped resource
+  drop(vertex_array);
+  assert_eq!(DROPPED_VERTEX_ARRAYS.with(Cell::get), 0);
+  assert_eq!(device.collect_garbage(GcBudget::unlimited()).unwrap(), 1);
+  assert_eq!(DROPPED_VERTEX_ARRAYS.with(Cell::get), 1);
+}
+
+#[test]
+fn drop_order_recycled_index() {
+  RECYCLED_TEXTURE_INDICES.with(|indices| *indices.borrow_mut() = Some(Vec::new()));
+
+  let device = dummy_device();
+  let pixel = Pixel {
+    encoding: Type::NormUnsigned,
+    format: Format::R(ChannelBits::Eight),
+  };
+  let storage = Storage::Flat2D {
+    width: 4,
+    height: 4,
+  };
+  let texture = device
+    .new_texture(storage, pixel, dummy_sampling())
+    .unwrap();
+  let id = texture.id();
+  device.drop_texture(texture).unwrap();
+  assert_eq!(DROPPED_TEXTURES.with(Cell::get), 1);
+
+  // the new texture gets the index of the dropped one, and is a resource of its own
+  let recycled = device
+    .new_texture(storage, pixel, dummy_sampling())
+    .unwrap();
+  assert_eq!(recycled.id(), id);
+  assert!(device.texture_by_id(&id).unwrap().is_some());
+  assert_eq!(DROPPED_TEXTURES.with(Cell::get), 1);
+
+  device.drop_texture(recycled).unwrap();
+  assert_eq!(DROPPED_TEXTURES.with(Cell::get), 2);
+}
+
+#[test]
+fn shader_by_id_shares_variant() {
+  let device = dummy_device().with_gc_mode(GcMode::TimeSliced);
+  let shader = device.new_shader(ShaderSources::default()).unwrap();
+  let other_handle = device.shader_by_id(&shader.id()).unwrap().unwrap();
+
+  // the shader stays alive until both handles are dropped
+  device.drop_shader(shader).unwrap();
+  assert_eq!(device.debug_dump().unwrap().shaders.len(), 1);
+  device.drop_shader(other_handle).unwrap();
+  assert!(device.debug_dump().unwrap().shaders.is_empty());
+}
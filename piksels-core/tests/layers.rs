@@ -0,0 +1,1132 @@
+use std::{cell::Cell, collections::HashSet};
+
+use piksels_backend::{
+  blending::BlendingMode,
+  color::RGBA32F,
+  depth_stencil::DepthTest,
+  error::Error,
+  pixel::{ChannelBits, Format, Pixel, Type},
+  shader::{ShaderSources, UniformTypeBase},
+  texture::{MagFilter, MinFilter, Sampling, Storage, Wrap},
+  vertex_array::{MemoryLayout, VertexArrayData},
+  viewport::Viewport,
+  Backend,
+};
+use piksels_core::{
+  device::Device,
+  layers::{LayerCommons, LayerShader, Layers},
+  units::SpillStrategy,
+  vertex_array::View,
+};
+
+mod common;
+
+use common::*;
+
+#[test]
+fn layers() {
+  let device = dummy_device();
+  let pixel = Pixel {
+    encoding: Type::NormUnsigned,
+    format: Format::R(ChannelBits::Eight),
+  };
+  let storage = Storage::Flat2D {
+    width: 4,
+    height: 4,
+  };
+  let albedo = device
+    .new_texture(storage, pixel, dummy_sampling())
+    .unwrap();
+  let normal = device
+    .new_texture(storage, pixel, dummy_sampling())
+    .unwrap();
+  let shader = device.new_shader(ShaderSources::default()).unwrap();
+  let albedo_point = shader.texture_binding_point("albedo").unwrap();
+  let normal_point = shader.texture_binding_point("normal").unwrap();
+  let vertex_array = device
+    .new_vertex_array(
+      VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+      VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+      Vec::new(),
+    )
+    .unwrap();
+  let render_targets = device
+    .new_render_targets(HashSet::new(), None, storage)
+    .unwrap();
+
+  TEXTURE_BINDS.with(|binds| binds.borrow_mut().clear());
+  let layers = device
+    .new_layers()
+    .unwrap()
+    .render_targets(&render_targets)
+    .unwrap()
+    .shader(&shader)
+    .unwrap()
+    .texture(&albedo, &albedo_point)
+    .unwrap()
+    .draw(&vertex_array)
+    .unwrap()
+    .done()
+    .unwrap()
+    .shader(&shader)
+    .unwrap()
+    .texture(&normal, &normal_point)
+    .unwrap()
+    .texture(&albedo, &albedo_point)
+    .unwrap()
+    .draw(&vertex_array)
+    .unwrap()
+    .done()
+    .unwrap()
+    .done();
+
+  // albedo is still bound to the unit given back by the first layer, so it is not bound again
+  let binds = TEXTURE_BINDS.with(|binds| binds.take());
+  assert_eq!(binds, [(0, albedo.id()), (1, normal.id())]);
+
+  // dropping a texture forgets its unit; other textures stay bound
+  device.drop_texture(albedo).unwrap();
+  let cmd_buf = layers
+    .render_targets(&render_targets)
+    .unwrap()
+    .shader(&shader)
+    .unwrap()
+    .texture(&normal, &normal_point)
+    .unwrap()
+    .done()
+    .unwrap()
+    .done()
+    .done()
+    .unwrap();
+  assert!(TEXTURE_BINDS.with(|binds| binds.take()).is_empty());
+  device.drop_cmd_buf(cmd_buf).unwrap();
+
+  // ranged and instanced draws
+  let view = vertex_array.view(..).unwrap().with_instances(0..4).unwrap();
+  let layer = device
+    .new_layers()
+    .unwrap()
+    .render_targets(&render_targets)
+    .unwrap()
+    .shader(&shader)
+    .unwrap()
+    .texture(&normal, &normal_point)
+    .unwrap()
+    .draw_view(&view)
+    .unwrap()
+    .draw_view(&view)
+    .unwrap();
+  assert_eq!(layer.cmd_buf().draw_count(), 2);
+  let cmd_buf = layer.done().unwrap().done().done().unwrap();
+  device.drop_cmd_buf(cmd_buf).unwrap();
+
+  // binding points looked up by name
+  SHADER_BINDING_LOOKUPS.with(|lookups| lookups.set(0));
+  TEXTURE_BINDS.with(|binds| binds.take());
+  let cmd_buf = device
+    .new_layers()
+    .unwrap()
+    .render_targets(&render_targets)
+    .unwrap()
+    .shader(&shader)
+    .unwrap()
+    .texture_named("emissive", &normal)
+    .unwrap()
+    .draw(&vertex_array)
+    .unwrap()
+    .done()
+    .unwrap()
+    .done()
+    .done()
+    .unwrap();
+  assert_eq!(SHADER_BINDING_LOOKUPS.with(Cell::get), 1);
+  // normal was bound by other command buffers, so it is bound again
+  let bound = TEXTURE_BINDS.with(|binds| binds.take());
+  assert_eq!(
+    bound
+      .iter()
+      .map(|(_, texture)| *texture)
+      .collect::<Vec<_>>(),
+    [normal.id()]
+  );
+  device.drop_cmd_buf(cmd_buf).unwrap();
+
+  // units are exhausted past the limit of the device
+  let textures: Vec<_> = (0..=device.limits().max_texture_units)
+    .map(|_| {
+      device
+        .new_texture(storage, pixel, dummy_sampling())
+        .unwrap()
+    })
+    .collect();
+  let mut layer = device
+    .new_layers()
+    .unwrap()
+    .render_targets(&render_targets)
+    .unwrap()
+    .shader(&shader)
+    .unwrap();
+
+  for texture in &textures[1..] {
+    layer = layer.texture(texture, &albedo_point).unwrap();
+  }
+
+  assert!(matches!(
+    layer.texture(&textures[0], &albedo_point),
+    Err(DummyBackendError::Common(Error::NoMoreUnits))
+  ));
+}
+
+#[test]
+fn layers_units_per_cmd_buf() {
+  let device = dummy_device();
+  let pixel = Pixel {
+    encoding: Type::NormUnsigned,
+    format: Format::R(ChannelBits::Eight),
+  };
+  let storage = Storage::Flat2D {
+    width: 4,
+    height: 4,
+  };
+  let albedo = device
+    .new_texture(storage, pixel, dummy_sampling())
+    .unwrap();
+  let shader = device
+    .new_shader(ShaderSources::default().fragment("units per cmd buf"))
+    .unwrap();
+  let albedo_point = shader.texture_binding_point("albedo").unwrap();
+  let render_targets = device
+    .new_render_targets(HashSet::new(), None, storage)
+    .unwrap();
+  let bind_albedo = |layers: Layers<DummyBackend>| {
+    layers
+      .render_targets(&render_targets)
+      .unwrap()
+      .shader(&shader)
+      .unwrap()
+      .texture(&albedo, &albedo_point)
+      .unwrap()
+      .done()
+      .unwrap()
+      .done()
+  };
+  let albedo_binds = || {
+    TEXTURE_BINDS
+      .with(|binds| binds.take())
+      .iter()
+      .filter(|(_, texture)| *texture == albedo.id())
+      .count()
+  };
+
+  // a texture bound by a command buffer is not bound in the others, which can be executed in any order
+  TEXTURE_BINDS.with(|binds| binds.take());
+  let first = bind_albedo(device.new_layers().unwrap());
+  let second = bind_albedo(device.new_layers().unwrap());
+  assert_eq!(albedo_binds(), 2);
+
+  let first = bind_albedo(first);
+  assert_eq!(albedo_binds(), 1);
+  let first = bind_albedo(first);
+  assert_eq!(albedo_binds(), 0);
+
+  for layers in [first, second] {
+    let cmd_buf = layers.done().unwrap();
+    device.drop_cmd_buf(cmd_buf).unwrap();
+  }
+}
+
+#[test]
+fn layers_stolen_units() {
+  let device = dummy_device();
+  let pixel = Pixel {
+    encoding: Type::NormUnsigned,
+    format: Format::R(ChannelBits::Eight),
+  };
+  let storage = Storage::Flat2D {
+    width: 4,
+    height: 4,
+  };
+  let textures: Vec<_> = (0..device.limits().max_texture_units)
+    .map(|_| {
+      device
+        .new_texture(storage, pixel, dummy_sampling())
+        .unwrap()
+    })
+    .collect();
+  let thief = device
+    .new_texture(storage, pixel, dummy_sampling())
+    .unwrap();
+  let shader = device.new_shader(ShaderSources::default()).unwrap();
+  let albedo_point = shader.texture_binding_point("albedo").unwrap();
+  let vertex_array = device
+    .new_vertex_array(
+      VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+      VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+      Vec::new(),
+    )
+    .unwrap();
+  let render_targets = device
+    .new_render_targets(HashSet::new(), None, storage)
+    .unwrap();
+  device
+    .set_units_spill_strategy(SpillStrategy::Steal)
+    .unwrap();
+
+  let mut layer = device
+    .new_layers()
+    .unwrap()
+    .render_targets(&render_targets)
+    .unwrap()
+    .shader(&shader)
+    .unwrap();
+  for texture in &textures {
+    layer = layer.texture(texture, &albedo_point).unwrap();
+  }
+
+  // another layer steals the unit of the first texture, then gives it back
+  let other = device
+    .new_layers()
+    .unwrap()
+    .render_targets(&render_targets)
+    .unwrap()
+    .shader(&shader)
+    .unwrap()
+    .texture(&thief, &albedo_point)
+    .unwrap()
+    .done()
+    .unwrap()
+    .done()
+    .done()
+    .unwrap();
+
+  // the first texture is rebound before drawing
+  TEXTURE_BINDS.with(|binds| binds.borrow_mut().clear());
+  let cmd_buf = layer
+    .draw(&vertex_array)
+    .unwrap()
+    .done()
+    .unwrap()
+    .done()
+    .done()
+    .unwrap();
+  assert_eq!(
+    TEXTURE_BINDS.with(|binds| binds.take()),
+    [(0, textures[0].id())]
+  );
+
+  let dump = device.debug_dump().unwrap();
+  assert_eq!(dump.texture_units.len(), textures.len());
+  assert!(dump.texture_units.iter().all(|binding| !binding.in_use));
+  assert_eq!(dump.texture_units[0].scarce_index, textures[0].id());
+
+  // a layer using more textures than there are units cannot draw
+  let mut layer = device
+    .new_layers()
+    .unwrap()
+    .render_targets(&render_targets)
+    .unwrap()
+    .shader(&shader)
+    .unwrap();
+  for texture in textures.iter().chain([&thief]) {
+    layer = layer.texture(texture, &albedo_point).unwrap();
+  }
+  assert!(matches!(
+    layer.draw(&vertex_array),
+    Err(DummyBackendError::Common(Error::NoMoreUnits))
+  ));
+
+  device.drop_cmd_buf(cmd_buf).unwrap();
+  device.drop_cmd_buf(other).unwrap();
+}
+
+#[test]
+fn layers_sorted_draws() {
+  let device = dummy_device();
+  let pixel = Pixel {
+    encoding: Type::NormUnsigned,
+    format: Format::R(ChannelBits::Eight),
+  };
+  let storage = Storage::Flat2D {
+    width: 4,
+    height: 4,
+  };
+  let albedo = device
+    .new_texture(storage, pixel, dummy_sampling())
+    .unwrap();
+  let normal = device
+    .new_texture(storage, pixel, dummy_sampling())
+    .unwrap();
+  let opaque = device
+    .new_shader(ShaderSources::default().fragment("opaque"))
+    .unwrap();
+  let tinted = device
+    .new_shader(ShaderSources::default().fragment("tinted"))
+    .unwrap();
+  let albedo_point = opaque.texture_binding_point("albedo").unwrap();
+  let normal_point = tinted.texture_binding_point("normal").unwrap();
+  let tint = tinted.uniform("tint", UniformTypeBase::Float3).unwrap();
+  let vertex_arrays: Vec<_> = (0..3)
+    .map(|_| {
+      device
+        .new_vertex_array(
+          VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+          VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+          Vec::new(),
+        )
+        .unwrap()
+    })
+    .collect();
+  let render_targets = device
+    .new_render_targets(HashSet::new(), None, storage)
+    .unwrap();
+
+  TEXTURE_BINDS.with(|binds| binds.borrow_mut().clear());
+  let layers = device.new_layers().unwrap().sort_draws();
+  layers.cmd_buf().record_bindings();
+  let layers = layers
+    .render_targets(&render_targets)
+    .unwrap()
+    .shader(&tinted)
+    .unwrap()
+    .uniform(&tint, &[1f32, 0., 0.])
+    .unwrap()
+    .texture(&normal, &normal_point)
+    .unwrap()
+    .draw(&vertex_arrays[0])
+    .unwrap()
+    .done()
+    .unwrap()
+    .shader(&opaque)
+    .unwrap()
+    .texture(&albedo, &albedo_point)
+    .unwrap()
+    .draw(&vertex_arrays[1])
+    .unwrap()
+    .done()
+    .unwrap()
+    .shader(&tinted)
+    .unwrap()
+    .uniform(&tint, &[0f32, 1., 0.])
+    .unwrap()
+    .texture(&normal, &normal_point)
+    .unwrap()
+    .draw_view(&vertex_arrays[2].view(..).unwrap())
+    .unwrap()
+    .done()
+    .unwrap()
+    .done();
+
+  // nothing is recorded until the layers are done
+  assert_eq!(layers.cmd_buf().draw_count(), 0);
+  assert!(TEXTURE_BINDS.with(|binds| binds.take()).is_empty());
+
+  let cmd_buf = layers.done().unwrap();
+  let report = cmd_buf.take_binding_report().unwrap();
+  assert_eq!(
+    report
+      .draws()
+      .iter()
+      .map(|draw| (draw.vertex_array, draw.shader))
+      .collect::<Vec<_>>(),
+    [
+      (vertex_arrays[1].id(), Some(opaque.id())),
+      (vertex_arrays[0].id(), Some(tinted.id())),
+      (vertex_arrays[2].id(), Some(tinted.id())),
+    ]
+  );
+
+  // draws sharing their shader and textures don’t bind them again, but keep their own uniform values
+  assert_eq!(
+    TEXTURE_BINDS.with(|binds| binds.take()),
+    [(0, albedo.id()), (1, normal.id())]
+  );
+  assert_eq!(
+    LAST_UNIFORM_BYTES.with(|bytes| bytes.borrow().clone()),
+    [0f32, 1., 0.]
+      .iter()
+      .flat_map(|x| x.to_ne_bytes())
+      .collect::<Vec<_>>()
+  );
+
+  device.drop_cmd_buf(cmd_buf).unwrap();
+}
+
+#[test]
+fn layers_sorted_draws_per_pass() {
+  let device = dummy_device();
+  let storage = Storage::Flat2D {
+    width: 4,
+    height: 4,
+  };
+  let opaque = device
+    .new_shader(ShaderSources::default().fragment("opaque"))
+    .unwrap();
+  let tinted = device
+    .new_shader(ShaderSources::default().fragment("tinted"))
+    .unwrap();
+  let vertex_arrays: Vec<_> = (0..4)
+    .map(|_| device.new_empty_vertex_array(3).unwrap())
+    .collect();
+  let main = device
+    .new_render_targets(HashSet::new(), None, storage)
+    .unwrap();
+  let shadow_map = device
+    .new_render_targets(HashSet::new(), None, storage)
+    .unwrap();
+
+  // the shadow map pass comes first, even though the main render targets were created first
+  let layers = device.new_layers().unwrap().sort_draws();
+  layers.cmd_buf().record_bindings();
+  let cmd_buf = layers
+    .render_targets(&shadow_map)
+    .unwrap()
+    .shader(&tinted)
+    .unwrap()
+    .draw(&vertex_arrays[0])
+    .unwrap()
+    .done()
+    .unwrap()
+    .shader(&opaque)
+    .unwrap()
+    .draw(&vertex_arrays[1])
+    .unwrap()
+    .done()
+    .unwrap()
+    .done()
+    .render_targets(&main)
+    .unwrap()
+    .shader(&tinted)
+    .unwrap()
+    .draw(&vertex_arrays[2])
+    .unwrap()
+    .done()
+    .unwrap()
+    .shader(&opaque)
+    .unwrap()
+    .draw(&vertex_arrays[3])
+    .unwrap()
+    .done()
+    .unwrap()
+    .done()
+    .done()
+    .unwrap();
+
+  // draws are only sorted within their pass
+  let report = cmd_buf.take_binding_report().unwrap();
+  assert_eq!(
+    report
+      .draws()
+      .iter()
+      .map(|draw| draw.vertex_array)
+      .collect::<Vec<_>>(),
+    [
+      vertex_arrays[1].id(),
+      vertex_arrays[0].id(),
+      vertex_arrays[3].id(),
+      vertex_arrays[2].id(),
+    ]
+  );
+
+  device.drop_cmd_buf(cmd_buf).unwrap();
+}
+
+#[test]
+fn layers_uniforms() {
+  let device = dummy_device();
+  let storage = Storage::Flat2D {
+    width: 4,
+    height: 4,
+  };
+  let shader = device
+    .new_shader(ShaderSources::default().fragment("layers_uniforms"))
+    .unwrap();
+  let color = shader.uniform("color", UniformTypeBase::Float3).unwrap();
+  let render_targets = device
+    .new_render_targets(HashSet::new(), None, storage)
+    .unwrap();
+  let last_bytes = || LAST_UNIFORM_BYTES.with(|bytes| bytes.take());
+
+  last_bytes();
+  let layer = device
+    .new_layers()
+    .unwrap()
+    .render_targets(&render_targets)
+    .unwrap()
+    .shader(&shader)
+    .unwrap()
+    .uniform(&color, &[1i32, 0, 1])
+    .unwrap();
+  assert_eq!(
+    last_bytes(),
+    [1f32, 0., 1.]
+      .iter()
+      .flat_map(|x| x.to_ne_bytes())
+      .collect::<Vec<_>>()
+  );
+
+  // the same value is cached
+  let layer = layer.uniform(&color, &[1f32, 0., 1.]).unwrap();
+  assert!(last_bytes().is_empty());
+
+  // values are type-checked
+  assert!(matches!(
+    layer.uniform(&color, &[1f32, 0.]),
+    Err(DummyBackendError::Common(Error::UniformTypeMismatch { .. }))
+  ));
+}
+
+#[test]
+fn layers_deeper() {
+  let device = dummy_device();
+  let main_storage = Storage::Flat2D {
+    width: 8,
+    height: 8,
+  };
+  let shadow_storage = Storage::Flat2D {
+    width: 4,
+    height: 4,
+  };
+  let main = device
+    .new_render_targets(HashSet::new(), None, main_storage)
+    .unwrap();
+  let shadow = device
+    .new_render_targets(HashSet::new(), None, shadow_storage)
+    .unwrap();
+  let main_shader = device
+    .new_shader(ShaderSources::default().fragment("main"))
+    .unwrap();
+  let shadow_shader = device
+    .new_shader(ShaderSources::default().fragment("shadow"))
+    .unwrap();
+  let main_viewport = Viewport::Specific {
+    x: 0,
+    y: 0,
+    width: 8,
+    height: 8,
+  };
+
+  let layer = device
+    .new_layers()
+    .unwrap()
+    .render_targets(&main)
+    .unwrap()
+    .shader(&main_shader)
+    .unwrap()
+    .done()
+    .unwrap();
+  layer.cmd_buf().viewport(main_viewport).unwrap();
+  let before = layer.cmd_buf().snapshot().unwrap();
+
+  let layer = layer
+    .deeper(&shadow, |shadow_layer| {
+      shadow_layer.cmd_buf().viewport(Viewport::Whole)?;
+      assert_eq!(
+        shadow_layer.cmd_buf().snapshot()?.render_targets,
+        Some(shadow.id())
+      );
+      shadow_layer.shader(&shadow_shader)?.done()
+    })
+    .unwrap();
+
+  // the state of the main pass is back
+  let after = layer.cmd_buf().snapshot().unwrap();
+  assert_eq!(after.render_targets, Some(main.id()));
+  assert_eq!(after.shader, Some(main_shader.id()));
+  assert_eq!(after.state, before.state);
+  assert_eq!(LAST_VIEWPORT.with(Cell::get), before.state.viewport);
+
+  let cmd_buf = layer.done().done().unwrap();
+  device.drop_cmd_buf(cmd_buf).unwrap();
+}
+
+#[test]
+fn layers_deeper_unset_state() {
+  let device = dummy_device();
+  let storage = Storage::Flat2D {
+    width: 4,
+    height: 4,
+  };
+  let main = device
+    .new_render_targets(HashSet::new(), None, storage)
+    .unwrap();
+  let shadow = device
+    .new_render_targets(HashSet::new(), None, storage)
+    .unwrap();
+  let shader = device.new_shader(ShaderSources::default()).unwrap();
+  let vertex_array = device.new_empty_vertex_array(3).unwrap();
+  let shadow_viewport = Viewport::Specific {
+    x: 0,
+    y: 0,
+    width: 2,
+    height: 2,
+  };
+
+  for sort in [false, true] {
+    let layers = device.new_layers().unwrap();
+    let layers = if sort { layers.sort_draws() } else { layers };
+
+    // the main pass never sets the viewport
+    let layer = layers
+      .render_targets(&main)
+      .unwrap()
+      .deeper(&shadow, |shadow_layer| {
+        shadow_layer
+          .viewport(shadow_viewport)?
+          .shader(&shader)?
+          .draw(&vertex_array)?
+          .done()
+      })
+      .unwrap();
+
+    if !sort {
+      assert_eq!(LAST_VIEWPORT.with(Cell::get), Some(Viewport::Whole));
+    }
+
+    // the viewport of the shadow pass is reset for the draws of the main pass, even when they are deferred
+    let cmd_buf = layer
+      .shader(&shader)
+      .unwrap()
+      .draw(&vertex_array)
+      .unwrap()
+      .done()
+      .unwrap()
+      .done()
+      .done()
+      .unwrap();
+    assert_eq!(LAST_VIEWPORT.with(Cell::get), Some(Viewport::Whole));
+
+    device.drop_cmd_buf(cmd_buf).unwrap();
+  }
+}
+
+#[test]
+fn layers_recorded() {
+  let device = dummy_device();
+  let storage = Storage::Flat2D {
+    width: 4,
+    height: 4,
+  };
+  let shader = device
+    .new_shader(ShaderSources::default().fragment("recorded"))
+    .unwrap();
+  let tint = shader.uniform("tint", UniformTypeBase::Float3).unwrap();
+  let vertex_array = device
+    .new_vertex_array(
+      VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+      VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+      Vec::new(),
+    )
+    .unwrap();
+  let render_targets = device
+    .new_render_targets(HashSet::new(), None, storage)
+    .unwrap();
+  let tint_bytes = |tint: [f32; 3]| {
+    tint
+      .iter()
+      .flat_map(|x| x.to_ne_bytes())
+      .collect::<Vec<_>>()
+  };
+
+  let mut layers = device
+    .new_layers()
+    .unwrap()
+    .sort_draws()
+    .render_targets(&render_targets)
+    .unwrap()
+    .shader(&shader)
+    .unwrap()
+    .uniform(&tint, &[1f32, 0., 0.])
+    .unwrap()
+    .draw(&vertex_array)
+    .unwrap()
+    .draw(&vertex_array)
+    .unwrap()
+    .done()
+    .unwrap()
+    .done();
+  let mut recorded = layers.freeze();
+  assert_eq!(recorded.draw_count(), 2);
+
+  // frozen draws are not recorded by the layers they come from anymore
+  layers.cmd_buf().record_bindings();
+  let cmd_buf = layers.done().unwrap();
+  assert!(cmd_buf.take_binding_report().unwrap().draws().is_empty());
+  device.drop_cmd_buf(cmd_buf).unwrap();
+
+  // submitted every frame, possibly with new uniform values
+  for frame_tint in [[1f32, 0., 0.], [0., 0., 1.]] {
+    recorded.uniform(&tint, &frame_tint).unwrap();
+
+    let layers = device.new_layers().unwrap();
+    layers.cmd_buf().record_bindings();
+    LAST_UNIFORM_BYTES.with(|bytes| bytes.take());
+    let layers = recorded.submit(layers).unwrap();
+    assert_eq!(
+      LAST_UNIFORM_BYTES.with(|bytes| bytes.take()),
+      tint_bytes(frame_tint)
+    );
+
+    let cmd_buf = layers.done().unwrap();
+    let report = cmd_buf.take_binding_report().unwrap();
+    assert_eq!(report.draws().len(), 2);
+    assert!(report
+      .draws()
+      .iter()
+      .all(|draw| draw.shader == Some(shader.id())));
+    device.drop_cmd_buf(cmd_buf).unwrap();
+  }
+}
+
+#[test]
+fn layers_when() {
+  let device = dummy_device();
+  let storage = Storage::Flat2D {
+    width: 4,
+    height: 4,
+  };
+  let shader = device
+    .new_shader(ShaderSources::default().fragment("when"))
+    .unwrap();
+  let overlay = device
+    .new_shader(ShaderSources::default().fragment("when overlay"))
+    .unwrap();
+  let vertex_array = device
+    .new_vertex_array(
+      VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+      VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+      Vec::new(),
+    )
+    .unwrap();
+  let render_targets = device
+    .new_render_targets(HashSet::new(), None, storage)
+    .unwrap();
+
+  for show_overlay in [false, true] {
+    let layers = device.new_layers().unwrap();
+    layers.cmd_buf().record_bindings();
+    let cmd_buf = layers
+      .render_targets(&render_targets)
+      .unwrap()
+      .shader(&shader)
+      .unwrap()
+      .draw(&vertex_array)
+      .unwrap()
+      .when(false, |layer| layer.draw(&vertex_array))
+      .unwrap()
+      .done()
+      .unwrap()
+      .when(show_overlay, |layer| {
+        layer.shader(&overlay)?.draw(&vertex_array)?.done()
+      })
+      .unwrap()
+      .done()
+      .when_with(
+        |layers| layers.cmd_buf().draw_count() > 0,
+        |layers| {
+          layers
+            .render_targets(&render_targets)?
+            .shader(&shader)?
+            .draw(&vertex_array)?
+            .done()
+            .map(|layer| layer.done())
+        },
+      )
+      .unwrap()
+      .done()
+      .unwrap();
+
+    let report = cmd_buf.take_binding_report().unwrap();
+    let shaders = report
+      .draws()
+      .iter()
+      .map(|draw| draw.shader)
+      .collect::<Vec<_>>();
+    let expected = if show_overlay {
+      vec![Some(shader.id()), Some(overlay.id()), Some(shader.id())]
+    } else {
+      vec![Some(shader.id()), Some(shader.id())]
+    };
+    assert_eq!(shaders, expected);
+    device.drop_cmd_buf(cmd_buf).unwrap();
+  }
+}
+
+#[test]
+fn layers_when_frozen() {
+  let device = dummy_device();
+  let storage = Storage::Flat2D {
+    width: 4,
+    height: 4,
+  };
+  let shader = device.new_shader(ShaderSources::default()).unwrap();
+  let vertex_array = device.new_empty_vertex_array(3).unwrap();
+  let render_targets = device
+    .new_render_targets(HashSet::new(), None, storage)
+    .unwrap();
+  let show_overlay = Cell::new(true);
+
+  let mut layers = device
+    .new_layers()
+    .unwrap()
+    .sort_draws()
+    .render_targets(&render_targets)
+    .unwrap()
+    .shader(&shader)
+    .unwrap()
+    .draw(&vertex_array)
+    .unwrap()
+    .when_with(|_| show_overlay.get(), |layer| layer.draw(&vertex_array))
+    .unwrap()
+    .done()
+    .unwrap()
+    .done();
+  let recorded = layers.freeze();
+  assert_eq!(recorded.draw_count(), 2);
+
+  // the predicate was evaluated while recording: submitting doesn’t evaluate it again
+  show_overlay.set(false);
+  layers.cmd_buf().record_bindings();
+  let cmd_buf = recorded.submit(layers).unwrap().done().unwrap();
+  let report = cmd_buf.take_binding_report().unwrap();
+  assert_eq!(report.draws().len(), 2);
+
+  device.drop_cmd_buf(cmd_buf).unwrap();
+}
+
+#[test]
+fn simple_layers() {
+  fn run<B>(device: &Device<B>) -> Result<(), B::Err>
+  where
+    B: Backend,
+  {
+    let storage = Storage::Flat1D { width: 10 };
+    let pixel = Pixel {
+      encoding: Type::NormUnsigned,
+      format: Format::R(ChannelBits::Eight),
+    };
+    let render_targets = device.new_render_targets(HashSet::default(), None, storage)?;
+    let shader = device.new_shader(ShaderSources::default())?;
+    let uni_0 = shader.uniform("uni_0", UniformTypeBase::Float)?;
+    let texture_point = shader.texture_binding_point("tex")?;
+
+    // vertex arrays
+    let foo = device.new_vertex_array(
+      VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+      VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+      Vec::new(),
+    )?;
+    let bar = device.new_vertex_array(
+      VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+      VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+      Vec::new(),
+    )?;
+
+    // textures
+    let sampling = Sampling {
+      wrap_r: Wrap::ClampToEdge,
+      wrap_s: Wrap::ClampToEdge,
+      wrap_t: Wrap::ClampToEdge,
+      min_filter: MinFilter::Nearest,
+      mag_filter: MagFilter::Nearest,
+      depth_comparison: None,
+    };
+    let texture_foo = device.new_texture(storage, pixel, sampling)?;
+    let texture_bar = device.new_texture(storage, pixel, sampling)?;
+
+    let cmd_buf = device
+      .new_layers()?
+      .clear_color(RGBA32F::new(0., 0., 0., 1.))?
+      .render_targets(&render_targets)?
+      .depth_test(DepthTest::Off)?
+      .shader(&shader)?
+      .blending(BlendingMode::Off)?
+      .uniform(&uni_0, &1f32)?
+      .group()
+      .texture(&texture_foo, &texture_point)?
+      .draw(&foo)?
+      .done()?
+      .group()
+      .texture(&texture_bar, &texture_point)?
+      .draw(&bar)?
+      .done()?
+      .done()?
+      .done()
+      .done()?;
+
+    device.drop_cmd_buf(cmd_buf)
+  }
+
+  run(&dummy_device()).unwrap();
+}
+
+#[test]
+fn layers_groups() {
+  let device = dummy_device();
+  let pixel = Pixel {
+    encoding: Type::NormUnsigned,
+    format: Format::R(ChannelBits::Eight),
+  };
+  let storage = Storage::Flat2D {
+    width: 4,
+    height: 4,
+  };
+  let shader = device
+    .new_shader(ShaderSources::default().fragment("groups"))
+    .unwrap();
+  let albedo_point = shader.texture_binding_point("albedo").unwrap();
+  let vertex_array = device
+    .new_vertex_array(
+      VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+      VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+      Vec::new(),
+    )
+    .unwrap();
+  let render_targets = device
+    .new_render_targets(HashSet::new(), None, storage)
+    .unwrap();
+  let textures: Vec<_> = (0..=device.limits().max_texture_units)
+    .map(|_| {
+      device
+        .new_texture(storage, pixel, dummy_sampling())
+        .unwrap()
+    })
+    .collect();
+
+  // more textures than units, each bound in its own group
+  let mut layer = device
+    .new_layers()
+    .unwrap()
+    .render_targets(&render_targets)
+    .unwrap()
+    .shader(&shader)
+    .unwrap();
+
+  for texture in &textures {
+    layer = layer
+      .group()
+      .texture(texture, &albedo_point)
+      .unwrap()
+      .draw(&vertex_array)
+      .unwrap()
+      .done()
+      .unwrap();
+  }
+
+  assert_eq!(layer.cmd_buf().draw_count(), textures.len());
+  let cmd_buf = layer.done().unwrap().done().done().unwrap();
+  device.drop_cmd_buf(cmd_buf).unwrap();
+
+  // resources bound in a group are not used by the deferred draws of the layer after it
+  let layers = device.new_layers().unwrap().sort_draws();
+  layers.cmd_buf().record_bindings();
+  let cmd_buf = layers
+    .render_targets(&render_targets)
+    .unwrap()
+    .shader(&shader)
+    .unwrap()
+    .group()
+    .texture(&textures[0], &albedo_point)
+    .unwrap()
+    .draw(&vertex_array)
+    .unwrap()
+    .done()
+    .unwrap()
+    .draw(&vertex_array)
+    .unwrap()
+    .done()
+    .unwrap()
+    .done()
+    .done()
+    .unwrap();
+  let report = cmd_buf.take_binding_report().unwrap();
+  let texture_counts = report
+    .draws()
+    .iter()
+    .map(|draw| draw.textures.len())
+    .collect::<Vec<_>>();
+  assert_eq!(texture_counts, [0, 1]);
+  device.drop_cmd_buf(cmd_buf).unwrap();
+}
+
+#[test]
+fn layers_pipeline_state() {
+  let device = dummy_device();
+  let storage = Storage::Flat2D {
+    width: 8,
+    height: 8,
+  };
+  let shader = device
+    .new_shader(ShaderSources::default().fragment("pipeline state"))
+    .unwrap();
+  let other_shader = device
+    .new_shader(ShaderSources::default().fragment("pipeline state other"))
+    .unwrap();
+  let vertex_array = device
+    .new_vertex_array(
+      VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+      VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+      Vec::new(),
+    )
+    .unwrap();
+  let render_targets = device
+    .new_render_targets(HashSet::new(), None, storage)
+    .unwrap();
+  let small = Viewport::Specific {
+    x: 0,
+    y: 0,
+    width: 4,
+    height: 4,
+  };
+
+  // changed right away
+  let layer = device
+    .new_layers()
+    .unwrap()
+    .render_targets(&render_targets)
+    .unwrap()
+    .viewport(small)
+    .unwrap();
+  assert_eq!(LAST_VIEWPORT.with(Cell::get), Some(small));
+  assert_eq!(
+    layer.cmd_buf().snapshot().unwrap().state.viewport,
+    Some(small)
+  );
+  let cmd_buf = layer.done().done().unwrap();
+  device.drop_cmd_buf(cmd_buf).unwrap();
+
+  // when sorting draws, changed along with the draws recorded after it, whatever their order once sorted
+  LAST_VIEWPORT.with(|last| last.set(None));
+  let layers = device
+    .new_layers()
+    .unwrap()
+    .sort_draws()
+    .render_targets(&render_targets)
+    .unwrap()
+    .shader(&other_shader)
+    .unwrap()
+    .viewport(small)
+    .unwrap()
+    .draw(&vertex_array)
+    .unwrap()
+    .done()
+    .unwrap()
+    .shader(&shader)
+    .unwrap()
+    .viewport(Viewport::Whole)
+    .unwrap()
+    .draw(&vertex_array)
+    .unwrap()
+    .done()
+    .unwrap()
+    .done();
+  assert_eq!(LAST_VIEWPORT.with(Cell::get), None);
+
+  let cmd_buf = layers.done().unwrap();
+  let last_draw_viewport = if shader.id() > other_shader.id() {
+    Viewport::Whole
+  } else {
+    small
+  };
+  assert_eq!(LAST_VIEWPORT.with(Cell::get), Some(last_draw_viewport));
+  device.drop_cmd_buf(cmd_buf).unwrap();
+}
@@ -0,0 +1,32 @@
+use piksels_backend::{
+  blending::BlendingMode,
+  depth_stencil::{DepthTest, DepthWrite},
+};
+use piksels_core::{
+  cache::CachedPipelineState,
+  pipeline::{PipelineState, PipelineStateDiff},
+};
+
+#[test]
+fn pipeline_diff_uncached() {
+  let cached = CachedPipelineState::default();
+  let state = PipelineState::default();
+
+  assert_eq!(cached.diff(state.clone()), PipelineStateDiff::from(state));
+}
+
+#[test]
+fn pipeline_diff_partially_cached() {
+  let mut cached = CachedPipelineState::default();
+  cached.blending.set(BlendingMode::Off);
+  cached.depth_test.set(DepthTest::Off);
+  cached.depth_write.set(DepthWrite::On);
+
+  let diff = cached.diff(PipelineState::default());
+
+  assert_eq!(diff.blending, None);
+  assert_eq!(diff.depth_test, Some(PipelineState::default().depth_test));
+  assert_eq!(diff.depth_write, None);
+  assert_eq!(diff.viewport, Some(PipelineState::default().viewport));
+  assert!(!diff.is_empty());
+}
@@ -0,0 +1,70 @@
+use piksels_backend::{error::Error, shader::ShaderSources};
+use piksels_core::preprocessor::ShaderLibrary;
+
+#[test]
+fn includes_and_defines() {
+  let library = ShaderLibrary::new()
+    .with_file(
+      "common.glsl",
+      "#include \"consts.glsl\"\nfloat sq(float x) { return x * x; }",
+    )
+    .with_file("consts.glsl", "const float PI = 3.14159;")
+    .with_define("MAX_LIGHTS", "16")
+    .with_define("SHADOWS", "");
+
+  assert_eq!(
+    library.preprocess("#version 330 core\n#include \"common.glsl\"\nvoid main() {}"),
+    Ok(
+      "#version 330 core\n#define MAX_LIGHTS 16\n#define SHADOWS\nconst float PI = 3.14159;\nfloat sq(float x) { \
+       return x * x; }\nvoid main() {}\n"
+        .to_owned()
+    )
+  );
+
+  // without #version, defines go at the top
+  assert_eq!(
+    library.preprocess("void main() {}"),
+    Ok("#define MAX_LIGHTS 16\n#define SHADOWS\nvoid main() {}\n".to_owned())
+  );
+
+  let sources = library
+    .preprocess_sources(
+      &ShaderSources::default()
+        .compute("  #include \"consts.glsl\"")
+        .define("LOCAL_SIZE", "64"),
+    )
+    .unwrap();
+  let sources = sources.sources();
+  assert!(sources.is_compute());
+  assert!(sources.vertex_stage().is_empty());
+  assert_eq!(
+    sources.compute_stage(),
+    "#define MAX_LIGHTS 16\n#define SHADOWS\n#define LOCAL_SIZE 64\nconst float PI = 3.14159;\n"
+  );
+  assert!(sources.defines().is_empty());
+}
+
+#[test]
+fn include_errors() {
+  let library = ShaderLibrary::new()
+    .with_file("a.glsl", "#include \"b.glsl\"")
+    .with_file("b.glsl", "#include \"a.glsl\"");
+  let error = |reason: &str| {
+    Err(Error::ShaderPreprocessing {
+      reason: reason.to_owned(),
+    })
+  };
+
+  assert_eq!(
+    library.preprocess("#include \"a.glsl\""),
+    error("include cycle: a.glsl -> b.glsl -> a.glsl")
+  );
+  assert_eq!(
+    library.preprocess("#include \"c.glsl\""),
+    error("unknown include file \"c.glsl\"")
+  );
+  assert_eq!(
+    library.preprocess("#include <a.glsl>"),
+    error("malformed include directive: #include <a.glsl>")
+  );
+}
@@ -0,0 +1,135 @@
+#![cfg(feature = "ext-profiler")]
+
+use std::{cell::Cell, collections::HashSet};
+
+use piksels_backend::{
+  shader::ShaderSources,
+  texture::Storage,
+  vertex_array::{MemoryLayout, VertexArrayData},
+};
+
+mod common;
+
+use common::*;
+
+#[test]
+fn cmd_buf_timed() {
+  use piksels_core::profiler::Timing;
+
+  let device = dummy_device();
+  let shader = device.new_shader(ShaderSources::default()).unwrap();
+  let vertex_array = device
+    .new_vertex_array(
+      VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+      VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+      Vec::new(),
+    )
+    .unwrap();
+
+  let cmd_buf = device.new_cmd_buf().unwrap();
+  cmd_buf
+    .shader(&shader)
+    .unwrap()
+    .draw(&vertex_array)
+    .unwrap()
+    .timed("opaque", |cmd_buf| {
+      cmd_buf.draw(&vertex_array)?.draw(&vertex_array)
+    })
+    .unwrap()
+    .finish()
+    .unwrap();
+
+  // only the enclosed draws are timed
+  assert_eq!(OPEN_TIMER_QUERIES.with(Cell::get), 0);
+  assert_eq!(TIMED_DRAWS.with(Cell::get), 2);
+
+  // the device hasn’t executed the timed draws yet
+  assert!(device.timings().unwrap().is_empty());
+  assert_eq!(DROPPED_TIMER_QUERIES.with(Cell::get), 0);
+
+  TIMER_QUERIES_READY.with(|ready| ready.set(true));
+  assert_eq!(
+    device.timings().unwrap(),
+    [Timing {
+      name: "opaque".to_owned(),
+      duration: std::time::Duration::from_millis(1),
+    }]
+  );
+  assert!(device.timings().unwrap().is_empty());
+  assert_eq!(DROPPED_TIMER_QUERIES.with(Cell::get), 1);
+}
+
+#[test]
+fn layers_timed() {
+  use piksels_core::profiler::Timing;
+
+  let device = dummy_device();
+  let storage = Storage::Flat2D {
+    width: 4,
+    height: 4,
+  };
+  let shader = device.new_shader(ShaderSources::default()).unwrap();
+  let vertex_array = device
+    .new_vertex_array(
+      VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+      VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+      Vec::new(),
+    )
+    .unwrap();
+  let render_targets = device
+    .new_render_targets(HashSet::new(), None, storage)
+    .unwrap();
+
+  device
+    .new_layers()
+    .unwrap()
+    .render_targets(&render_targets)
+    .unwrap()
+    .shader(&shader)
+    .unwrap()
+    .draw(&vertex_array)
+    .unwrap()
+    .timed("opaque", |layer| {
+      layer.draw(&vertex_array)?.draw(&vertex_array)
+    })
+    .unwrap()
+    .done()
+    .unwrap()
+    .done()
+    .done()
+    .unwrap();
+
+  // only the enclosed draws are timed
+  assert_eq!(OPEN_TIMER_QUERIES.with(Cell::get), 0);
+  assert_eq!(TIMED_DRAWS.with(Cell::get), 2);
+
+  TIMER_QUERIES_READY.with(|ready| ready.set(true));
+  assert_eq!(
+    device.timings().unwrap(),
+    [Timing {
+      name: "opaque".to_owned(),
+      duration: std::time::Duration::from_millis(1),
+    }]
+  );
+
+  // sorted draws are recorded without timing them
+  let warnings = LOGGED_WARNINGS.with(Cell::get);
+  device
+    .new_layers()
+    .unwrap()
+    .sort_draws()
+    .render_targets(&render_targets)
+    .unwrap()
+    .shader(&shader)
+    .unwrap()
+    .timed("sorted", |layer| layer.draw(&vertex_array))
+    .unwrap()
+    .done()
+    .unwrap()
+    .done()
+    .done()
+    .unwrap();
+  assert_eq!(LOGGED_WARNINGS.with(Cell::get), warnings + 1);
+  assert_eq!(TIMED_DRAWS.with(Cell::get), 2);
+  assert!(device.timings().unwrap().is_empty());
+}
@@ -0,0 +1,976 @@
+use std::{cell::Cell, collections::HashSet};
+
+use piksels_backend::{
+  error::Error,
+  pixel::{ChannelBits, Format, Pixel, Type},
+  render_targets::{
+    self, AttachmentUsage, BlitFilter, BlitMask, ColorAttachmentPoint, ColorType,
+    RenderTargetsError,
+  },
+  texture::{CubeFace, MagFilter, MinFilter, Offset, Rect, Sampling, Size, Storage, Wrap},
+};
+
+mod common;
+
+use common::*;
+
+#[test]
+fn shared_depth_stencil_attachment() {
+  let device = dummy_device();
+  let storage = Storage::Flat2D {
+    width: 800,
+    height: 600,
+  };
+
+  let gbuffer = device
+    .new_render_targets(HashSet::default(), None, storage)
+    .unwrap();
+  let depth_stencil = gbuffer.depth_stencil_attachment(0).unwrap();
+
+  assert!(device
+    .new_render_targets_with_depth_stencil_attachment(HashSet::default(), &depth_stencil, storage)
+    .is_ok());
+  assert!(matches!(
+    device.new_render_targets_with_depth_stencil_attachment(
+      HashSet::default(),
+      &depth_stencil,
+      Storage::Flat2D {
+        width: 400,
+        height: 300
+      }
+    ),
+    Err(DummyBackendError::Common(
+      Error::IncompatibleDepthStencilAttachment { .. }
+    ))
+  ));
+}
+
+#[test]
+fn attachment_levels_and_layers() {
+  let device = dummy_device();
+  let color = ColorType::IRGBA {
+    red_bits: render_targets::ChannelBits::Eight,
+    green_bits: render_targets::ChannelBits::Eight,
+    blue_bits: render_targets::ChannelBits::Eight,
+    alpha_bits: render_targets::ChannelBits::Eight,
+  };
+  let depth = render_targets::DepthStencilType::Depth {
+    depth_bits: render_targets::ChannelBits::ThirtyTwo,
+  };
+  let cascades = Storage::Layered2D {
+    width: 1024,
+    height: 1024,
+    layers: 4,
+  };
+
+  // one render targets per shadow cascade, rendering to the mip 1 of the color attachment
+  for layer in 0..4 {
+    let color = ColorAttachmentPoint::new(0, "color", color)
+      .with_level(1)
+      .with_layer(layer);
+    let depth =
+      render_targets::DepthStencilAttachmentPoint::new(0, "depth", depth).with_layer(layer);
+    assert!(device
+      .new_render_targets(HashSet::from([color]), Some(depth), cascades)
+      .is_ok());
+  }
+
+  assert_eq!(
+    device
+      .new_render_targets(
+        HashSet::from([ColorAttachmentPoint::new(0, "color", color).with_layer(4)]),
+        None,
+        cascades
+      )
+      .err(),
+    Some(DummyBackendError::Common(Error::InvalidAttachmentPoint {
+      reason: format!("\"color\" renders to layer 4, but {cascades:?} has 4 layers")
+    }))
+  );
+  assert_eq!(
+    device
+      .new_render_targets(
+        HashSet::default(),
+        Some(render_targets::DepthStencilAttachmentPoint::new(0, "depth", depth).with_level(11)),
+        cascades
+      )
+      .err(),
+    Some(DummyBackendError::Common(Error::InvalidAttachmentPoint {
+      reason: format!("\"depth\" renders to level 11, but {cascades:?} has 11 levels")
+    }))
+  );
+
+  let flat = Storage::Flat2D {
+    width: 800,
+    height: 600,
+  };
+  assert!(matches!(
+    device.new_render_targets(
+      HashSet::from([ColorAttachmentPoint::new(0, "color", color).with_layer(0)]),
+      None,
+      flat
+    ),
+    Err(DummyBackendError::Common(
+      Error::InvalidAttachmentPoint { .. }
+    ))
+  ));
+
+  // cubemap faces are layers
+  assert_eq!(Storage::FlatCubemap { size: 512 }.layers(), Some(6));
+  assert_eq!(
+    Storage::LayeredCubemap {
+      size: 512,
+      layers: 2
+    }
+    .layers(),
+    Some(12)
+  );
+}
+
+#[test]
+fn layered_render_targets() {
+  let device = dummy_device();
+  let depth = render_targets::DepthStencilAttachmentPoint::new(
+    0,
+    "depth",
+    render_targets::DepthStencilType::Depth {
+      depth_bits: render_targets::ChannelBits::ThirtyTwo,
+    },
+  );
+  let cubemap = Storage::FlatCubemap { size: 512 };
+
+  // point-light shadows, rendered to all faces in a single pass
+  let shadows = device
+    .new_render_targets(HashSet::default(), Some(depth), cubemap)
+    .unwrap();
+  assert_eq!(shadows.layers(), Some(6));
+  assert_eq!(CubeFace::NegY.layer(), 3);
+
+  let face = device
+    .new_render_targets(HashSet::default(), Some(depth.with_layer(3)), cubemap)
+    .unwrap();
+  assert_eq!(face.layers(), None);
+
+  let flat = Storage::Flat2D {
+    width: 800,
+    height: 600,
+  };
+  let flat = device
+    .new_render_targets(HashSet::default(), Some(depth), flat)
+    .unwrap();
+  assert_eq!(flat.layers(), None);
+
+  let color = ColorAttachmentPoint::new(
+    0,
+    "color",
+    ColorType::IR {
+      red_bits: render_targets::ChannelBits::Sixteen,
+    },
+  );
+  assert_eq!(
+    device
+      .new_render_targets(HashSet::from([color.with_layer(0)]), Some(depth), cubemap)
+      .err(),
+    Some(DummyBackendError::Common(Error::InvalidAttachmentPoint {
+      reason: "attachments must either all select a layer, or all be layered".to_owned()
+    }))
+  );
+
+  // sharing a layered depth attachment requires layered color attachments
+  let depth = shadows.depth_stencil_attachment(0).unwrap();
+  assert_eq!(depth.layers(), Some(6));
+  let shared = device
+    .new_render_targets_with_depth_stencil_attachment(HashSet::from([color]), &depth, cubemap)
+    .unwrap();
+  assert_eq!(shared.layers(), Some(6));
+  assert!(matches!(
+    device.new_render_targets_with_depth_stencil_attachment(
+      HashSet::from([color.with_layer(1)]),
+      &depth,
+      cubemap
+    ),
+    Err(DummyBackendError::Common(
+      Error::IncompatibleDepthStencilAttachment { .. }
+    ))
+  ));
+}
+
+#[test]
+fn shadow_map_render_targets() {
+  let device = dummy_device();
+  let shadow_map = device
+    .new_depth_only_render_targets(
+      1024,
+      1024,
+      render_targets::ChannelBits::ThirtyTwo,
+      piksels_backend::depth_stencil::Comparison::LessOrEqual,
+    )
+    .unwrap();
+
+  assert_eq!(
+    shadow_map.storage(),
+    Storage::Flat2D {
+      width: 1024,
+      height: 1024
+    }
+  );
+  assert_eq!(shadow_map.color_attachments().len(), 0);
+  assert_eq!(
+    LAST_DEPTH_STENCIL_SAMPLING.with(Cell::get),
+    Some(Sampling {
+      wrap_r: Wrap::ClampToEdge,
+      wrap_s: Wrap::ClampToEdge,
+      wrap_t: Wrap::ClampToEdge,
+      min_filter: MinFilter::Linear,
+      mag_filter: MagFilter::Linear,
+      depth_comparison: Some(piksels_backend::depth_stencil::Comparison::LessOrEqual),
+    })
+  );
+
+  let cmd_buf = device.new_cmd_buf().unwrap();
+  let binding_point = device.get_texture_binding_point(0).unwrap();
+  assert!(cmd_buf
+    .use_depth_stencil_attachment(
+      &shadow_map.depth_stencil_attachment(0).unwrap(),
+      &binding_point
+    )
+    .is_ok());
+}
+
+#[test]
+fn render_only_attachments() {
+  let device = dummy_device();
+  let storage = Storage::Flat2D {
+    width: 800,
+    height: 600,
+  };
+  let color = ColorAttachmentPoint::new(
+    0,
+    "color",
+    ColorType::IRGBA {
+      red_bits: render_targets::ChannelBits::Eight,
+      green_bits: render_targets::ChannelBits::Eight,
+      blue_bits: render_targets::ChannelBits::Eight,
+      alpha_bits: render_targets::ChannelBits::Eight,
+    },
+  );
+  let depth = render_targets::DepthStencilAttachmentPoint::new(
+    0,
+    "depth",
+    render_targets::DepthStencilType::Depth {
+      depth_bits: render_targets::ChannelBits::ThirtyTwo,
+    },
+  )
+  .with_usage(AttachmentUsage::RenderOnly);
+
+  let render_targets = device
+    .new_render_targets(HashSet::from([color]), Some(depth), storage)
+    .unwrap();
+  let color_attachment = render_targets.color_attachment(0).unwrap();
+  assert_eq!(color_attachment.usage(), AttachmentUsage::Sampled);
+
+  let cmd_buf = device.new_cmd_buf().unwrap();
+  let binding_point = device.get_texture_binding_point(0).unwrap();
+  assert!(cmd_buf
+    .use_color_attachment(&color_attachment, &binding_point)
+    .is_ok());
+
+  // render-only color attachments cannot be sampled
+  let render_targets = device
+    .new_render_targets(
+      HashSet::from([color.with_usage(AttachmentUsage::RenderOnly)]),
+      Some(depth),
+      storage,
+    )
+    .unwrap();
+  let color_attachment = render_targets.color_attachment(0).unwrap();
+  assert!(matches!(
+    cmd_buf.use_color_attachment(&color_attachment, &binding_point),
+    Err(DummyBackendError::Common(
+      Error::NonSampleableAttachment { .. }
+    ))
+  ));
+
+  assert_eq!(
+    device
+      .new_render_targets(
+        HashSet::default(),
+        Some(depth),
+        Storage::FlatCubemap { size: 512 }
+      )
+      .err(),
+    Some(DummyBackendError::Common(Error::InvalidAttachmentPoint {
+      reason: "\"depth\" is render-only, but FlatCubemap { size: 512 } has layers".to_owned()
+    }))
+  );
+  assert!(matches!(
+    device.new_render_targets(HashSet::default(), Some(depth.with_level(1)), storage),
+    Err(DummyBackendError::Common(
+      Error::InvalidAttachmentPoint { .. }
+    ))
+  ));
+}
+
+#[test]
+fn resizable_render_targets() {
+  let device = dummy_device();
+  let color = ColorAttachmentPoint::new(
+    0,
+    "color",
+    ColorType::IRGBA {
+      red_bits: render_targets::ChannelBits::Eight,
+      green_bits: render_targets::ChannelBits::Eight,
+      blue_bits: render_targets::ChannelBits::Eight,
+      alpha_bits: render_targets::ChannelBits::Eight,
+    },
+  );
+  let mut render_targets = device
+    .new_render_targets(
+      HashSet::from([color]),
+      None,
+      Storage::Flat2DMultiSample {
+        width: 800,
+        height: 600,
+        samples: 4,
+      },
+    )
+    .unwrap();
+  let id = render_targets.id();
+
+  render_targets.resize(1920, 1080).unwrap();
+  let resized = Storage::Flat2DMultiSample {
+    width: 1920,
+    height: 1080,
+    samples: 4,
+  };
+  assert_eq!(render_targets.id(), id);
+  assert_eq!(render_targets.storage(), resized);
+  assert_eq!(
+    device.render_targets_by_id(&id).unwrap().unwrap().storage(),
+    resized
+  );
+
+  let mut cubemap = device
+    .new_render_targets(HashSet::default(), None, Storage::FlatCubemap { size: 512 })
+    .unwrap();
+  assert_eq!(
+    cubemap.resize(1024, 512).err(),
+    Some(DummyBackendError::Common(
+      Error::InvalidRenderTargetsResize {
+        reason: "FlatCubemap { size: 512 } cannot be resized to 1024×512".to_owned()
+      }
+    ))
+  );
+  cubemap.resize(1024, 1024).unwrap();
+  assert_eq!(cubemap.storage(), Storage::FlatCubemap { size: 1024 });
+
+  // mip 3 of a 8×8 storage doesn’t exist in a 4×4 one
+  let mut mips = device
+    .new_render_targets(
+      HashSet::from([color.with_level(3)]),
+      None,
+      Storage::Flat2D {
+        width: 8,
+        height: 8,
+      },
+    )
+    .unwrap();
+  assert!(matches!(
+    mips.resize(4, 4),
+    Err(DummyBackendError::Common(
+      Error::InvalidRenderTargetsResize { .. }
+    ))
+  ));
+}
+
+#[test]
+fn multisample_render_targets_resolve() {
+  let device = dummy_device();
+  let color = ColorAttachmentPoint::new(
+    0,
+    "color",
+    ColorType::IRGBA {
+      red_bits: render_targets::ChannelBits::Eight,
+      green_bits: render_targets::ChannelBits::Eight,
+      blue_bits: render_targets::ChannelBits::Eight,
+      alpha_bits: render_targets::ChannelBits::Eight,
+    },
+  );
+  let storage = Storage::Flat2D {
+    width: 800,
+    height: 600,
+  };
+  let msaa = storage.multisampled(4).unwrap();
+  assert_eq!(msaa.samples(), Some(4));
+  assert_eq!(msaa.resolved(), Some(storage));
+  assert_eq!(Storage::FlatCubemap { size: 512 }.multisampled(4), None);
+
+  let main_pass = device
+    .new_render_targets(HashSet::from([color]), None, msaa)
+    .unwrap();
+  let resolved = device
+    .new_render_targets(HashSet::from([color]), None, storage)
+    .unwrap();
+  let cmd_buf = device.new_cmd_buf().unwrap();
+  assert!(cmd_buf
+    .resolve_render_targets(&main_pass, &resolved)
+    .is_ok());
+
+  // the other way around
+  assert!(matches!(
+    cmd_buf.resolve_render_targets(&resolved, &main_pass),
+    Err(DummyBackendError::Common(Error::IncompatibleResolve { .. }))
+  ));
+
+  let missing_color = device
+    .new_render_targets(HashSet::default(), None, storage)
+    .unwrap();
+  assert!(matches!(
+    cmd_buf.resolve_render_targets(&main_pass, &missing_color),
+    Err(DummyBackendError::Common(Error::IncompatibleResolve { .. }))
+  ));
+}
+
+#[cfg(feature = "ext-readback")]
+#[test]
+fn color_attachment_read() {
+  let device = dummy_device();
+  let color = ColorAttachmentPoint::new(
+    0,
+    "color",
+    ColorType::IRGBA {
+      red_bits: render_targets::ChannelBits::Eight,
+      green_bits: render_targets::ChannelBits::Eight,
+      blue_bits: render_targets::ChannelBits::Eight,
+      alpha_bits: render_targets::ChannelBits::Eight,
+    },
+  );
+  let storage = Storage::Flat2D {
+    width: 4,
+    height: 4,
+  };
+  let render_targets = device
+    .new_render_targets(HashSet::from([color]), None, storage)
+    .unwrap();
+  let color_attachment = render_targets.color_attachment(0).unwrap();
+
+  let texels = color_attachment
+    .read(Rect::new(
+      Offset::Dim2 { x: 1, y: 1 },
+      Size::Dim2 {
+        width: 2,
+        height: 3,
+      },
+    ))
+    .unwrap();
+  assert_eq!(texels, [255; 4 * 2 * 3]);
+
+  let out_of_bounds = Rect::new(
+    Offset::Dim2 { x: 3, y: 0 },
+    Size::Dim2 {
+      width: 2,
+      height: 1,
+    },
+  );
+  assert_eq!(
+    color_attachment.read(out_of_bounds).err(),
+    Some(DummyBackendError::Common(Error::InvalidReadback {
+      reason: format!("{out_of_bounds:?} out of bounds of {storage:?}")
+    }))
+  );
+
+  // multisample attachments must be resolved first
+  let msaa = device
+    .new_render_targets(
+      HashSet::from([color]),
+      None,
+      storage.multisampled(4).unwrap(),
+    )
+    .unwrap();
+  assert!(matches!(
+    msaa.color_attachment(0).unwrap().read(Rect::new(
+      Offset::Dim2 { x: 0, y: 0 },
+      Size::Dim2 {
+        width: 1,
+        height: 1
+      }
+    )),
+    Err(DummyBackendError::Common(Error::InvalidReadback { .. }))
+  ));
+}
+
+#[test]
+fn render_targets_errors() {
+  let device = dummy_device();
+  let storage = Storage::Flat2D {
+    width: 800,
+    height: 600,
+  };
+  let color = |index, name| {
+    ColorAttachmentPoint::new(
+      index,
+      name,
+      ColorType::IR {
+        red_bits: render_targets::ChannelBits::Eight,
+      },
+    )
+  };
+
+  assert_eq!(
+    device
+      .new_render_targets(
+        HashSet::from([color(0, "albedo"), color(1, "normal"), color(1, "emissive")]),
+        None,
+        storage
+      )
+      .err(),
+    Some(DummyBackendError::Common(Error::IncompleteRenderTargets(
+      RenderTargetsError::DuplicateIndex {
+        index: 1,
+        attachments: ["emissive", "normal"]
+      }
+    )))
+  );
+
+  let points = ["a", "b", "c", "d", "e", "f", "g", "h", "i"]
+    .into_iter()
+    .enumerate()
+    .map(|(index, name)| color(index, name))
+    .collect::<HashSet<_>>();
+  let err =
+    Error::IncompleteRenderTargets(RenderTargetsError::TooManyAttachments { count: 9, max: 8 });
+  assert_eq!(
+    err.to_string(),
+    "incomplete render targets: 9 color attachments, but at most 8 are supported"
+  );
+  assert_eq!(
+    device.new_render_targets(points, None, storage).err(),
+    Some(DummyBackendError::Common(err))
+  );
+
+  // reported by the backend
+  let srgb = ColorAttachmentPoint::new(
+    0,
+    "srgb",
+    ColorType::UintSRGB {
+      red_bits: render_targets::ChannelBits::Eight,
+      green_bits: render_targets::ChannelBits::Eight,
+      blue_bits: render_targets::ChannelBits::Eight,
+    },
+  );
+  assert_eq!(
+    device
+      .new_render_targets(HashSet::from([srgb]), None, storage)
+      .err(),
+    Some(DummyBackendError::Common(Error::IncompleteRenderTargets(
+      RenderTargetsError::UnsupportedFormat { attachment: "srgb" }
+    )))
+  );
+}
+
+#[test]
+fn active_attachments() {
+  let device = dummy_device();
+  let points = ["albedo", "normal", "emissive"]
+    .into_iter()
+    .enumerate()
+    .map(|(index, name)| {
+      ColorAttachmentPoint::new(
+        index,
+        name,
+        ColorType::IRGBA {
+          red_bits: render_targets::ChannelBits::Eight,
+          green_bits: render_targets::ChannelBits::Eight,
+          blue_bits: render_targets::ChannelBits::Eight,
+          alpha_bits: render_targets::ChannelBits::Eight,
+        },
+      )
+    })
+    .collect();
+  let gbuffer = device
+    .new_render_targets(
+      points,
+      None,
+      Storage::Flat2D {
+        width: 800,
+        height: 600,
+      },
+    )
+    .unwrap();
+
+  gbuffer.set_active_attachments(&[2, 0]).unwrap();
+  gbuffer.set_active_attachments(&[0, 2, 2]).unwrap();
+  device
+    .render_targets_by_id(&gbuffer.id())
+    .unwrap()
+    .unwrap()
+    .set_active_attachments(&[0, 2])
+    .unwrap();
+  gbuffer.set_active_attachments(&[0, 1, 2]).unwrap();
+  assert_eq!(
+    ACTIVE_ATTACHMENTS.with(|active| active.borrow().clone()),
+    [vec![0, 2], vec![0, 1, 2]]
+  );
+
+  assert_eq!(
+    gbuffer.set_active_attachments(&[3]).err(),
+    Some(DummyBackendError::Common(Error::InvalidAttachmentPoint {
+      reason: "no color attachment at index 3".to_owned()
+    }))
+  );
+}
+
+#[test]
+fn render_targets_blit() {
+  let device = dummy_device();
+  let color = ColorAttachmentPoint::new(
+    0,
+    "color",
+    ColorType::IRGBA {
+      red_bits: render_targets::ChannelBits::Eight,
+      green_bits: render_targets::ChannelBits::Eight,
+      blue_bits: render_targets::ChannelBits::Eight,
+      alpha_bits: render_targets::ChannelBits::Eight,
+    },
+  );
+  let new_render_targets = |storage| {
+    device
+      .new_render_targets(HashSet::from([color]), None, storage)
+      .unwrap()
+  };
+  let rect = |width, height| Rect::new(Offset::Dim2 { x: 0, y: 0 }, Size::Dim2 { width, height });
+  let blit_error = |reason: &str| {
+    Some(DummyBackendError::Common(Error::InvalidBlit {
+      reason: reason.to_owned(),
+    }))
+  };
+
+  let full = new_render_targets(Storage::Flat2D {
+    width: 800,
+    height: 600,
+  });
+  let half = new_render_targets(Storage::Flat2D {
+    width: 400,
+    height: 300,
+  });
+
+  // downsampling
+  full
+    .blit(
+      &half,
+      rect(800, 600),
+      rect(400, 300),
+      BlitFilter::Linear,
+      BlitMask::COLOR,
+    )
+    .unwrap();
+  assert_eq!(
+    LAST_BLIT.with(Cell::get),
+    Some((
+      rect(800, 600),
+      rect(400, 300),
+      BlitFilter::Linear,
+      BlitMask::COLOR
+    ))
+  );
+
+  assert_eq!(
+    full
+      .blit(
+        &half,
+        rect(800, 600),
+        rect(400, 300),
+        BlitFilter::Linear,
+        BlitMask::COLOR | BlitMask::DEPTH,
+      )
+      .err(),
+    blit_error("BlitMask { color: true, depth: true, stencil: false } cannot be linearly filtered")
+  );
+  assert_eq!(
+    full
+      .blit(
+        &half,
+        rect(800, 600),
+        rect(800, 600),
+        BlitFilter::Nearest,
+        BlitMask::DEPTH_STENCIL,
+      )
+      .err(),
+    blit_error(
+      "Rect { offset: Dim2 { x: 0, y: 0 }, size: Dim2 { width: 800, height: 600 } } out of bounds of Flat2D { width: \
+       400, height: 300 }"
+    )
+  );
+
+  // the storage is looked up in the cache, so resizing through another handle is seen
+  device
+    .render_targets_by_id(&half.id())
+    .unwrap()
+    .unwrap()
+    .resize(800, 600)
+    .unwrap();
+  full
+    .blit(
+      &half,
+      rect(800, 600),
+      rect(800, 600),
+      BlitFilter::Nearest,
+      BlitMask::DEPTH_STENCIL,
+    )
+    .unwrap();
+
+  let msaa = new_render_targets(Storage::Flat2DMultiSample {
+    width: 800,
+    height: 600,
+    samples: 4,
+  });
+  msaa
+    .blit(
+      &full,
+      rect(800, 600),
+      rect(800, 600),
+      BlitFilter::Nearest,
+      BlitMask::COLOR,
+    )
+    .unwrap();
+  assert_eq!(
+    msaa
+      .blit(
+        &full,
+        rect(800, 600),
+        rect(400, 300),
+        BlitFilter::Linear,
+        BlitMask::COLOR,
+      )
+      .err(),
+    blit_error(
+      "multisample Rect { offset: Dim2 { x: 0, y: 0 }, size: Dim2 { width: 800, height: 600 } } must be resolved to a \
+       region of the same size, not Rect { offset: Dim2 { x: 0, y: 0 }, size: Dim2 { width: 400, height: 300 } }"
+    )
+  );
+  assert_eq!(
+    full
+      .blit(
+        &msaa,
+        rect(800, 600),
+        rect(800, 600),
+        BlitFilter::Nearest,
+        BlitMask::COLOR,
+      )
+      .err(),
+    blit_error(
+      "cannot blit to multisample Flat2DMultiSample { width: 800, height: 600, samples: 4 }"
+    )
+  );
+
+  // empty masks are ignored
+  LAST_BLIT.with(|blit| blit.set(None));
+  full
+    .blit(
+      &msaa,
+      rect(800, 600),
+      rect(800, 600),
+      BlitFilter::Nearest,
+      BlitMask::default(),
+    )
+    .unwrap();
+  assert_eq!(LAST_BLIT.with(Cell::get), None);
+}
+
+#[test]
+fn render_targets_texture_swapping() {
+  let device = dummy_device();
+  let storage = Storage::Flat2D {
+    width: 800,
+    height: 600,
+  };
+  let color = ColorAttachmentPoint::new(
+    0,
+    "color",
+    ColorType::IRGBA {
+      red_bits: render_targets::ChannelBits::Eight,
+      green_bits: render_targets::ChannelBits::Eight,
+      blue_bits: render_targets::ChannelBits::Eight,
+      alpha_bits: render_targets::ChannelBits::Eight,
+    },
+  );
+  let render_targets = device
+    .new_render_targets(HashSet::from([color]), None, storage)
+    .unwrap();
+  let new_texture = |storage| {
+    device
+      .new_texture(
+        storage,
+        Pixel {
+          encoding: Type::NormUnsigned,
+          format: Format::RGBA(
+            ChannelBits::Eight,
+            ChannelBits::Eight,
+            ChannelBits::Eight,
+            ChannelBits::Eight,
+          ),
+        },
+        dummy_sampling(),
+      )
+      .unwrap()
+  };
+  let ping = new_texture(storage);
+  let pong = new_texture(storage);
+
+  render_targets.attach_texture(0, &ping).unwrap();
+  render_targets.attach_texture(0, &pong).unwrap();
+  render_targets.detach_texture(0).unwrap();
+  assert_eq!(
+    ATTACHED_TEXTURES.with(|attached| attached.borrow().clone()),
+    [(0, Some(ping.id())), (0, Some(pong.id())), (0, None)]
+  );
+
+  assert_eq!(
+    render_targets.attach_texture(1, &ping).err(),
+    Some(DummyBackendError::Common(Error::InvalidAttachmentPoint {
+      reason: "no color attachment at index 1".to_owned()
+    }))
+  );
+  assert_eq!(
+    render_targets
+      .attach_texture(
+        0,
+        &new_texture(Storage::Flat2D {
+          width: 400,
+          height: 300
+        })
+      )
+      .err(),
+    Some(DummyBackendError::Common(Error::InvalidAttachmentPoint {
+      reason: "texture storage is Flat2D { width: 400, height: 300 }, but render targets storage is Flat2D { width: \
+               800, height: 600 }"
+        .to_owned()
+    }))
+  );
+}
+
+#[test]
+fn srgb_encoding_per_attachment() {
+  let device = dummy_device();
+  let storage = Storage::Flat2D {
+    width: 800,
+    height: 600,
+  };
+  let linear = ColorAttachmentPoint::new(
+    0,
+    "normal",
+    ColorType::IRGBA {
+      red_bits: render_targets::ChannelBits::Sixteen,
+      green_bits: render_targets::ChannelBits::Sixteen,
+      blue_bits: render_targets::ChannelBits::Sixteen,
+      alpha_bits: render_targets::ChannelBits::Sixteen,
+    },
+  );
+  let srgb = ColorAttachmentPoint::new(
+    1,
+    "albedo",
+    ColorType::ISRGBA {
+      red_bits: render_targets::ChannelBits::Eight,
+      green_bits: render_targets::ChannelBits::Eight,
+      blue_bits: render_targets::ChannelBits::Eight,
+      alpha_bits: render_targets::ChannelBits::Eight,
+    },
+  );
+
+  let gbuffer = device
+    .new_render_targets(HashSet::from([linear, srgb]), None, storage)
+    .unwrap();
+  let output = device
+    .new_render_targets(
+      HashSet::from([srgb.with_srgb_encoding(true)]),
+      None,
+      storage,
+    )
+    .unwrap();
+  let scratch = device
+    .new_render_targets(HashSet::from([linear]), None, storage)
+    .unwrap();
+  assert_eq!(gbuffer.srgb_encoding(), Some(false));
+  assert_eq!(output.srgb_encoding(), Some(true));
+  assert_eq!(scratch.srgb_encoding(), None);
+
+  // encoding is only switched when it changes, and left alone by render targets without sRGB attachments
+  let cmd_buf = device.new_cmd_buf().unwrap();
+  cmd_buf
+    .render_targets(&gbuffer)
+    .unwrap()
+    .render_targets(&gbuffer)
+    .unwrap()
+    .render_targets(&output)
+    .unwrap()
+    .render_targets(&scratch)
+    .unwrap()
+    .render_targets(&output)
+    .unwrap();
+  assert_eq!(
+    SRGB_SWITCHES.with(|switches| switches.borrow().clone()),
+    [false, true]
+  );
+
+  assert_eq!(
+    device
+      .new_render_targets(
+        HashSet::from([linear.with_srgb_encoding(true)]),
+        None,
+        storage
+      )
+      .err(),
+    Some(DummyBackendError::Common(Error::InvalidAttachmentPoint {
+      reason: "\"normal\" encodes to sRGB, but IRGBA { red_bits: Sixteen, green_bits: Sixteen, blue_bits: Sixteen, \
+               alpha_bits: Sixteen } is not sRGB"
+        .to_owned()
+    }))
+  );
+  assert_eq!(
+    device
+      .new_render_targets(
+        HashSet::from([
+          srgb,
+          ColorAttachmentPoint::new(2, "emissive", srgb.ty()).with_srgb_encoding(true)
+        ]),
+        None,
+        storage
+      )
+      .err(),
+    Some(DummyBackendError::Common(Error::InvalidAttachmentPoint {
+      reason: "sRGB attachments [\"albedo\", \"emissive\"] must all encode to sRGB, or none"
+        .to_owned()
+    }))
+  );
+}
+
+#[test]
+fn render_targets_color_attachments() {
+  let device = dummy_device();
+  let storage = Storage::Flat2D {
+    width: 800,
+    height: 600,
+  };
+  let rgba = ColorType::IRGBA {
+    red_bits: render_targets::ChannelBits::Eight,
+    green_bits: render_targets::ChannelBits::Eight,
+    blue_bits: render_targets::ChannelBits::Eight,
+    alpha_bits: render_targets::ChannelBits::Eight,
+  };
+  let normal = ColorType::IRGB {
+    red_bits: render_targets::ChannelBits::Sixteen,
+    green_bits: render_targets::ChannelBits::Sixteen,
+    blue_bits: render_targets::ChannelBits::Sixteen,
+  };
+  let points = HashSet::from([
+    ColorAttachmentPoint::new(1, "normal", normal),
+    ColorAttachmentPoint::new(0, "albedo", rgba),
+  ]);
+
+  let gbuffer = device.new_render_targets(points, None, storage).unwrap();
+  let expected = [(0, "albedo", rgba), (1, "normal", normal)];
+  assert!(gbuffer.color_attachments().eq(expected));
+
+  let gbuffer = device.render_targets_by_id(&gbuffer.id()).unwrap().unwrap();
+  assert!(gbuffer.color_attachments().eq(expected));
+}
@@ -0,0 +1,408 @@
+use std::cell::Cell;
+
+use piksels_backend::{
+  error::Error,
+  shader::{InterfaceVariable, ShaderInterface, ShaderSources, UniformType, UniformTypeBase},
+  vertex::{self, Normalized, VertexAttr},
+  vertex_array::MapAccess,
+};
+use piksels_core::{error_policy::ErrorPolicy, gc::GcMode, interface::InterfaceMismatch};
+
+mod common;
+
+use common::*;
+
+#[test]
+fn shader_binding_points_resolved_at_creation() {
+  let device = dummy_device();
+  let shader = device.new_shader(ShaderSources::default()).unwrap();
+  let lookups = SHADER_BINDING_LOOKUPS.with(Cell::get);
+  assert_eq!(lookups, 3);
+
+  assert!(shader.texture_binding_point("albedo").is_ok());
+  assert!(shader.texture_binding_point("normal").is_ok());
+  assert!(shader.uniform_buffer_binding_point("camera").is_ok());
+
+  let shader = device.shader_by_id(&shader.id()).unwrap().unwrap();
+  assert!(shader.texture_binding_point("albedo").is_ok());
+  assert_eq!(SHADER_BINDING_LOOKUPS.with(Cell::get), lookups);
+
+  // unknown bindings are still looked up by the backend
+  assert!(shader.texture_binding_point("unknown").is_ok());
+  assert_eq!(SHADER_BINDING_LOOKUPS.with(Cell::get), lookups + 1);
+}
+
+#[test]
+fn compute_shader_sources() {
+  let device = dummy_device();
+
+  let sources = ShaderSources::default().compute("void main() {}");
+  assert!(sources.is_compute());
+  assert!(device.new_shader(sources).is_ok());
+
+  assert_eq!(
+    device
+      .new_shader(
+        ShaderSources::default()
+          .vertex("void main() {}")
+          .fragment("void main() {}")
+          .compute("void main() {}")
+      )
+      .err(),
+    Some(DummyBackendError::Common(Error::InvalidShaderSources {
+      reason: "compute stage mixed with rasterization stages: vertex, fragment".to_owned()
+    }))
+  );
+}
+
+#[test]
+fn typed_uniforms() {
+  let device = dummy_device();
+  let shader = device.new_shader(ShaderSources::default()).unwrap();
+  let cmd_buf = device.new_cmd_buf().unwrap();
+
+  let color = shader.uniform("color", UniformTypeBase::Float3).unwrap();
+  cmd_buf.uniform(&color, &[1i32, 0, 1]).unwrap();
+  assert_eq!(
+    LAST_UNIFORM_BYTES.with(|bytes| bytes.borrow().clone()),
+    [1f32, 0., 1.]
+      .iter()
+      .flat_map(|x| x.to_ne_bytes())
+      .collect::<Vec<_>>()
+  );
+
+  assert_eq!(
+    cmd_buf.uniform(&color, &1f32).err(),
+    Some(DummyBackendError::Common(Error::UniformTypeMismatch {
+      expected: UniformTypeBase::Float3.into(),
+      got: UniformTypeBase::Float.into(),
+    }))
+  );
+}
+
+#[test]
+fn uniform_array_ranges() {
+  let device = dummy_device();
+  let shader = device.new_shader(ShaderSources::default()).unwrap();
+  let cmd_buf = device.new_cmd_buf().unwrap();
+
+  let bones = shader
+    .uniform("bones", UniformType::new(UniformTypeBase::FloatMat44, 128))
+    .unwrap();
+  let bone = [
+    [1f32, 0., 0., 0.],
+    [0., 1., 0., 0.],
+    [0., 0., 1., 0.],
+    [0., 0., 0., 1.],
+  ];
+  cmd_buf.uniform_array(&bones, 126, &[bone; 2]).unwrap();
+
+  let (first_element, bytes) = LAST_UNIFORM_ARRAY_UPDATE
+    .with(|update| update.borrow().clone())
+    .unwrap();
+  assert_eq!(first_element, 126);
+  assert_eq!(bytes.len(), 2 * 64);
+
+  assert_eq!(
+    cmd_buf.uniform_array(&bones, 127, &[bone; 2]).err(),
+    Some(DummyBackendError::Common(Error::InvalidUniformArrayRange {
+      reason: "elements 127..129 out of bounds of an array of 128".to_owned()
+    }))
+  );
+
+  let scale = shader.uniform("scale", UniformTypeBase::Float).unwrap();
+  assert!(cmd_buf.uniform_array(&scale, 0, &[1f32]).is_err());
+}
+
+#[test]
+fn storage_buffers() {
+  let device = dummy_device().with_gc_mode(GcMode::TimeSliced);
+  let storage_buffer = device.new_storage_buffer(64).unwrap();
+  assert_eq!(storage_buffer.size(), 64);
+
+  storage_buffer.update(16, &[1, 2, 3, 4]).unwrap();
+  assert_eq!(
+    LAST_STORAGE_BUFFER_UPDATE.with(|last| last.borrow().clone()),
+    Some((16, vec![1, 2, 3, 4]))
+  );
+  assert_eq!(
+    storage_buffer.update(62, &[0; 4]).err(),
+    Some(DummyBackendError::Common(
+      Error::InvalidStorageBufferRange {
+        reason: "bytes 62..66 out of bounds of a storage buffer of 64 bytes".to_owned()
+      }
+    ))
+  );
+
+  let mut mapped = storage_buffer.map(8..12, MapAccess::Write).unwrap();
+  mapped.copy_from_slice(&[5, 6, 7, 8]);
+  drop(mapped);
+  assert_eq!(
+    LAST_UNMAPPED_BYTES.with(|last| last.borrow().clone()),
+    [5, 6, 7, 8]
+  );
+  assert!(storage_buffer.map(60..72, MapAccess::Read).is_err());
+
+  let shader = device
+    .new_shader(ShaderSources::default().compute("void main() {}"))
+    .unwrap();
+  let binding_point = device.get_storage_buffer_binding_point(2).unwrap();
+  let shader_binding_point = shader.storage_buffer_binding_point("particles").unwrap();
+  let cmd_buf = device.new_cmd_buf().unwrap();
+  cmd_buf.record_bindings();
+  cmd_buf
+    .associate_storage_buffer(&binding_point, &shader_binding_point)
+    .unwrap()
+    .use_storage_buffer(&storage_buffer, &binding_point)
+    .unwrap();
+
+  let vertex_array = device.new_empty_vertex_array(3).unwrap();
+  cmd_buf.draw(&vertex_array).unwrap();
+  let report = cmd_buf.take_binding_report().unwrap();
+  assert_eq!(
+    report.draws()[0].storage_buffers.get(&2),
+    Some(&storage_buffer.id())
+  );
+
+  let id = storage_buffer.id();
+  assert!(device.storage_buffer_by_id(&id).unwrap().is_some());
+  device.drop_storage_buffer(storage_buffer).unwrap();
+  assert!(device.storage_buffer_by_id(&id).unwrap().is_none());
+  assert_eq!(device.garbage_len().unwrap(), 1);
+}
+
+#[derive(piksels_derive::UniformBlock)]
+struct Camera {
+  view: [[f32; 4]; 4],
+  exposure: f32,
+}
+
+#[test]
+fn uniform_buffer_upload() {
+  let device = dummy_device();
+  let shader = device.new_shader(ShaderSources::default()).unwrap();
+  let uniform_buffer = shader.uniform_buffer("Camera").unwrap();
+
+  let camera = Camera {
+    view: [
+      [1., 0., 0., 0.],
+      [0., 1., 0., 0.],
+      [0., 0., 1., 0.],
+      [0., 0., 0., 1.],
+    ],
+    exposure: 2.,
+  };
+  uniform_buffer.upload(&camera).unwrap();
+
+  let bytes = LAST_UNIFORM_BUFFER_BYTES.with(|bytes| bytes.borrow().clone());
+  assert_eq!(bytes.len(), 80);
+  assert_eq!(bytes[64..68], 2f32.to_ne_bytes());
+}
+
+#[derive(piksels_derive::UniformBlock)]
+struct PackedCamera {
+  exposure: f32,
+  view: [[f32; 4]; 4],
+}
+
+#[derive(piksels_derive::UniformBlock)]
+struct Exposure {
+  exposure: f32,
+  gamma: f32,
+}
+
+#[test]
+fn uniform_buffer_layout() {
+  let device = dummy_device();
+  let shader = device.new_shader(ShaderSources::default()).unwrap();
+  let layout = shader.uniform_buffer("Camera").unwrap().layout().unwrap();
+
+  assert_eq!(layout.size, 80);
+  assert_eq!(layout.member("view").unwrap().matrix_stride, Some(16));
+  assert_eq!(layout.check::<Camera>(), Ok(()));
+  assert_eq!(
+    layout.check::<PackedCamera>(),
+    Err(Error::UniformBlockLayoutMismatch {
+      reason: "member \"exposure\" is at offset 64, but at offset 0 on the CPU side".to_owned()
+    })
+  );
+  assert_eq!(
+    layout.check::<Exposure>(),
+    Err(Error::UniformBlockLayoutMismatch {
+      reason: "block is 80 bytes, but 16 bytes on the CPU side".to_owned()
+    })
+  );
+}
+
+#[test]
+fn shader_variants() {
+  let device = dummy_device().with_error_policy(ErrorPolicy::Accumulate);
+  let vs = "#version 330 core\nvoid main() {}";
+
+  let shader = device
+    .new_shader(
+      ShaderSources::default()
+        .vertex(vs)
+        .define("SHADOWS", "")
+        .define("MAX_LIGHTS", "16"),
+    )
+    .unwrap();
+  assert_eq!(
+    LAST_VERTEX_STAGE.with(|stage| stage.borrow().clone()),
+    "#version 330 core\n#define SHADOWS\n#define MAX_LIGHTS 16\nvoid main() {}"
+  );
+
+  // same variant, whatever the order of the defines
+  let same = device
+    .new_shader(
+      ShaderSources::default()
+        .vertex(vs)
+        .define("MAX_LIGHTS", "16")
+        .define("SHADOWS", ""),
+    )
+    .unwrap();
+  assert_eq!(same.id(), shader.id());
+
+  let other = device
+    .new_shader(
+      ShaderSources::default()
+        .vertex(vs)
+        .define("MAX_LIGHTS", "8"),
+    )
+    .unwrap();
+  assert_ne!(other.id(), shader.id());
+
+  // the variant is shared: it stays alive until its last handle is dropped
+  let id = shader.id();
+  device.drop_shader(shader).unwrap();
+  assert_eq!(device.garbage_len().unwrap(), 0);
+  let shader = device
+    .new_shader(
+      ShaderSources::default()
+        .vertex(vs)
+        .define("SHADOWS", "")
+        .define("MAX_LIGHTS", "16"),
+    )
+    .unwrap();
+  assert_eq!(shader.id(), id);
+
+  device.drop_shader(shader).unwrap();
+  device.drop_shader(same).unwrap();
+  let shader = device
+    .new_shader(
+      ShaderSources::default()
+        .vertex(vs)
+        .define("SHADOWS", "")
+        .define("MAX_LIGHTS", "16"),
+    )
+    .unwrap();
+  assert_ne!(shader.id(), id);
+}
+
+#[test]
+fn shader_variants_compiled_concurrently() {
+  let device = dummy_device().with_error_policy(ErrorPolicy::Accumulate);
+  let sources = ShaderSources::default().vertex(RACING_VERTEX_STAGE);
+
+  // both threads miss the variant, and compile it at the same time
+  let (a, b) = std::thread::scope(|scope| {
+    let a = scope.spawn(|| device.new_shader(sources.clone()).unwrap());
+    let b = scope.spawn(|| device.new_shader(sources.clone()).unwrap());
+    (a.join().unwrap(), b.join().unwrap())
+  });
+
+  // the first one tracked is shared, and the duplicate is destroyed
+  assert_eq!(a.id(), b.id());
+  assert_eq!(device.debug_dump().unwrap().shaders.len(), 1);
+  assert!(device.drain_errors().unwrap().is_empty());
+
+  // the variant is shared by both handles
+  device.drop_shader(a).unwrap();
+  assert_eq!(device.debug_dump().unwrap().shaders.len(), 1);
+  device.drop_shader(b).unwrap();
+  assert!(device.debug_dump().unwrap().shaders.is_empty());
+}
+
+fn interface_variable(name: &str, location: Option<usize>, ty: vertex::Type) -> InterfaceVariable {
+  InterfaceVariable {
+    name: name.to_owned(),
+    location,
+    ty,
+    array: None,
+  }
+}
+
+#[test]
+fn shader_interface_validation() {
+  SHADER_INTERFACE.with(|interface| {
+    *interface.borrow_mut() = ShaderInterface {
+      attributes: vec![
+        interface_variable("position", Some(0), vertex::Type::Float3),
+        interface_variable("color", None, vertex::Type::Float4),
+      ],
+      varying_outputs: vec![interface_variable("v_color", None, vertex::Type::Float4)],
+      varying_inputs: vec![interface_variable("v_color", None, vertex::Type::Float4)],
+    }
+  });
+
+  let device = dummy_device();
+  let shader = device.new_shader(ShaderSources::default()).unwrap();
+  let position = VertexAttr {
+    index: 0,
+    name: "position",
+    ty: vertex::Type::Float3,
+    array: None,
+  };
+
+  // normalized integral attributes are floating-point in shaders
+  let color = VertexAttr {
+    index: 1,
+    name: "color",
+    ty: vertex::Type::Uint4(Normalized::Yes),
+    array: None,
+  };
+  assert!(shader.validate(&[position, color]).unwrap().is_valid());
+
+  let report = shader
+    .validate(&[
+      VertexAttr {
+        index: 2,
+        ..position
+      },
+      VertexAttr {
+        ty: vertex::Type::Uint4(Normalized::No),
+        ..color
+      },
+    ])
+    .unwrap();
+  assert_eq!(
+    report.mismatches(),
+    [
+      InterfaceMismatch::AttributeLocation {
+        name: "position".to_owned(),
+        shader: 0,
+        vertex: 2
+      },
+      InterfaceMismatch::AttributeType {
+        name: "color".to_owned(),
+        shader: (vertex::Type::Float4, None),
+        vertex: (vertex::Type::Uint4(Normalized::No), None)
+      },
+    ]
+  );
+
+  SHADER_INTERFACE.with(|interface| {
+    let mut interface = interface.borrow_mut();
+    interface.varying_outputs[0].ty = vertex::Type::Float3;
+    interface
+      .varying_inputs
+      .push(interface_variable("v_uv", Some(1), vertex::Type::Float2));
+  });
+  let report = shader.validate(&[position]).unwrap();
+  assert_eq!(
+    report.to_string(),
+    "missing vertex attribute \"color\"\n\
+     fragment input \"v_color\" is (Float4, None), but output as (Float3, None) by the previous stage\n\
+     fragment input \"v_uv\" is not output by the previous stage\n"
+  );
+}
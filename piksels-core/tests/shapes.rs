@@ -0,0 +1,68 @@
+use piksels_backend::vertex_array::MemoryLayout;
+use piksels_core::shapes::{self, Shape};
+
+/// Positions and normals of the vertices of a shape.
+fn positions_and_normals(shape: &Shape) -> Vec<([f32; 3], [f32; 3])> {
+  let MemoryLayout::Interleaved { data } = shape.vertices().layout() else {
+    panic!("shapes are interleaved");
+  };
+
+  data
+    .chunks_exact(32)
+    .map(|vertex| {
+      let component = |i: usize| f32::from_ne_bytes(vertex[4 * i..4 * i + 4].try_into().unwrap());
+      (
+        [component(0), component(1), component(2)],
+        [component(3), component(4), component(5)],
+      )
+    })
+    .collect()
+}
+
+/// Check the indices, the bounds of the shape and that triangles face the same side as their vertex normals.
+fn check_shape(shape: &Shape, vertex_count: usize, triangle_count: usize, bound: f32) {
+  let vertices = positions_and_normals(shape);
+  assert_eq!(vertices.len(), vertex_count);
+  assert_eq!(shape.vertices().len(), vertex_count);
+  assert_eq!(shape.indices().len(), 3 * triangle_count);
+
+  for (position, normal) in &vertices {
+    assert!(position.iter().all(|x| x.abs() <= bound + 1e-6));
+
+    let len = normal.iter().map(|x| x * x).sum::<f32>().sqrt();
+    assert!((len - 1.).abs() < 1e-5);
+  }
+
+  for triangle in shape.indices().chunks_exact(3) {
+    let [a, b, c] = [0, 1, 2].map(|i| vertices[triangle[i] as usize]);
+    let e1 = [0, 1, 2].map(|i| b.0[i] - a.0[i]);
+    let e2 = [0, 1, 2].map(|i| c.0[i] - a.0[i]);
+    let face_normal = [
+      e1[1] * e2[2] - e1[2] * e2[1],
+      e1[2] * e2[0] - e1[0] * e2[2],
+      e1[0] * e2[1] - e1[1] * e2[0],
+    ];
+
+    // degenerate triangles at the poles of spheres have no orientation
+    let area = face_normal.iter().map(|x| x * x).sum::<f32>();
+    if area < 1e-12 {
+      continue;
+    }
+
+    let normal = [0, 1, 2].map(|i| a.1[i] + b.1[i] + c.1[i]);
+    let dot: f32 = (0..3).map(|i| face_normal[i] * normal[i]).sum();
+    assert!(dot > 0., "triangle {triangle:?} is not counter-clockwise");
+  }
+}
+
+#[test]
+fn procedural_shapes() {
+  check_shape(&shapes::quad(), 4, 2, 0.5);
+  check_shape(&shapes::cube(), 24, 12, 0.5);
+  check_shape(&shapes::uv_sphere(16, 8), 17 * 9, 2 * 16 * 8, 0.5);
+  check_shape(&shapes::cylinder(12), 2 * 13 + 2 * 14, 2 * 12 + 2 * 12, 0.5);
+  check_shape(&shapes::fullscreen_triangle(), 3, 1, 3.);
+
+  // degenerate subdivisions are clamped
+  check_shape(&shapes::uv_sphere(0, 0), 4 * 3, 2 * 3 * 2, 0.5);
+}
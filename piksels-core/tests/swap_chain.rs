@@ -0,0 +1,358 @@
+use std::cell::Cell;
+
+use piksels_backend::{
+  error::Error,
+  swap_chain::{PresentStatus, SwapChainFormat, SwapChainMode},
+  texture::Storage,
+};
+use piksels_core::{cmd_buf::CmdBuf, frame_loop::FrameLoop, vertex_array::VertexArray};
+
+mod common;
+
+use common::*;
+
+#[test]
+fn swap_chain_frame_loop() {
+  /// Frame-structured code, agnostic of where frames end up.
+  fn render(
+    frame_loop: &mut impl FrameLoop<DummyBackend>,
+    cmd_buf: &CmdBuf<DummyBackend>,
+    triangle: &VertexArray<DummyBackend>,
+  ) -> Result<(), DummyBackendError> {
+    frame_loop.frame(cmd_buf, |cmd_buf, _| cmd_buf.draw(triangle).map(|_| ()))
+  }
+
+  let device = dummy_device();
+  let mut swap_chain = device
+    .new_swap_chain(
+      800,
+      600,
+      piksels_backend::swap_chain::SwapChainMode::Fifo,
+      2,
+      SwapChainFormat::SRGBA8,
+    )
+    .unwrap();
+  let cmd_buf = device.new_cmd_buf().unwrap();
+  let triangle = device.new_empty_vertex_array(3).unwrap();
+
+  let presented = PRESENTED_FRAMES.with(Cell::get);
+  render(&mut swap_chain, &cmd_buf, &triangle).unwrap();
+  render(&mut swap_chain, &cmd_buf, &triangle).unwrap();
+  assert_eq!(PRESENTED_FRAMES.with(Cell::get), presented + 2);
+
+  // the command buffer is finished at the end of every frame
+  assert_eq!(cmd_buf.draw_count(), 0);
+}
+
+#[test]
+fn swap_chain_resize() {
+  let device = dummy_device();
+  let mut swap_chain = device
+    .new_swap_chain(
+      800,
+      600,
+      piksels_backend::swap_chain::SwapChainMode::Fifo,
+      2,
+      SwapChainFormat::SRGBA8,
+    )
+    .unwrap();
+  let cmd_buf = device.new_cmd_buf().unwrap();
+  let render_targets = swap_chain.acquire().unwrap();
+  assert!(!render_targets.is_stale().unwrap());
+
+  swap_chain.resize(1920, 1080).unwrap();
+  let resized = Storage::Flat2D {
+    width: 1920,
+    height: 1080,
+  };
+  assert_eq!(swap_chain.storage(), resized);
+
+  // render targets obtained before resizing cannot be used anymore
+  let stale = Some(DummyBackendError::Common(Error::StaleRenderTargets {
+    reason: "the swap chain was resized since the render targets were obtained".to_owned(),
+  }));
+  assert!(render_targets.is_stale().unwrap());
+  assert_eq!(cmd_buf.render_targets(&render_targets).err(), stale);
+  assert_eq!(swap_chain.present(&render_targets).err(), stale);
+
+  let render_targets = swap_chain.acquire().unwrap();
+  assert_eq!(render_targets.storage(), resized);
+  assert!(cmd_buf.render_targets(&render_targets).is_ok());
+  assert!(swap_chain.present(&render_targets).is_ok());
+
+  // resizing through another handle is seen by all handles
+  let mut other = device.swap_chain_by_id(&swap_chain.id()).unwrap().unwrap();
+  other.resize(800, 600).unwrap();
+  assert!(render_targets.is_stale().unwrap());
+  assert_eq!(
+    swap_chain.acquire().unwrap().storage(),
+    Storage::Flat2D {
+      width: 800,
+      height: 600
+    }
+  );
+}
+
+#[test]
+fn swap_chain_frames_in_flight() {
+  let device = dummy_device();
+  let swap_chain = device
+    .new_swap_chain(
+      800,
+      600,
+      piksels_backend::swap_chain::SwapChainMode::Fifo,
+      3,
+      SwapChainFormat::SRGBA8,
+    )
+    .unwrap();
+  assert_eq!(swap_chain.image_count(), 3);
+  assert_eq!(
+    device
+      .swap_chain_by_id(&swap_chain.id())
+      .unwrap()
+      .unwrap()
+      .image_count(),
+    3
+  );
+
+  swap_chain.wait_idle().unwrap();
+  assert_eq!(SWAP_CHAIN_WAITS.with(Cell::get), 1);
+
+  assert_eq!(
+    device
+      .new_swap_chain(
+        800,
+        600,
+        piksels_backend::swap_chain::SwapChainMode::Fifo,
+        0,
+        SwapChainFormat::SRGBA8
+      )
+      .err(),
+    Some(DummyBackendError::Common(Error::InvalidSwapChain {
+      reason: "a swap chain needs at least one image".to_owned()
+    }))
+  );
+}
+
+#[cfg(feature = "ext-frame-stats")]
+#[test]
+fn swap_chain_frame_stats() {
+  let device = dummy_device();
+  let swap_chain = device
+    .new_swap_chain(800, 600, SwapChainMode::Fifo, 2, SwapChainFormat::SRGBA8)
+    .unwrap();
+
+  let stats = swap_chain.frame_stats().unwrap();
+  assert_eq!(stats.present_timestamps.len(), 4);
+  assert_eq!(stats.missed_vblanks, 1);
+
+  // the refresh rate is estimated from the timestamps, despite the missed vertical blank
+  let refresh_rate = stats.refresh_rate.unwrap();
+  assert!((refresh_rate - 60.).abs() < 1e-3, "{refresh_rate}");
+}
+
+#[test]
+fn swap_chain_present_recovery() {
+  let device = dummy_device();
+  let mut swap_chain = device
+    .new_swap_chain(800, 600, SwapChainMode::Fifo, 2, SwapChainFormat::SRGBA8)
+    .unwrap();
+
+  // the window was resized: the image is given back, and resizing recovers
+  NEXT_PRESENT_STATUS.with(|status| status.set(PresentStatus::OutOfDate));
+  let render_targets = swap_chain.acquire().unwrap();
+  assert_eq!(
+    swap_chain.present(&render_targets).err(),
+    Some(DummyBackendError::Common(Error::SwapChainOutOfDate))
+  );
+  swap_chain.resize(1024, 768).unwrap();
+  let render_targets = swap_chain.acquire().unwrap();
+  assert!(swap_chain.present(&render_targets).is_ok());
+
+  // the display is gone: the swap chain cannot be used anymore
+  NEXT_PRESENT_STATUS.with(|status| status.set(PresentStatus::SurfaceLost));
+  let render_targets = swap_chain.acquire().unwrap();
+  let lost = Some(DummyBackendError::Common(Error::SurfaceLost));
+  assert_eq!(swap_chain.present(&render_targets).err(), lost);
+  assert_eq!(swap_chain.acquire().err(), lost);
+  assert_eq!(swap_chain.resize(800, 600).err(), lost);
+
+  let swap_chain = device
+    .new_swap_chain(800, 600, SwapChainMode::Fifo, 2, SwapChainFormat::SRGBA8)
+    .unwrap();
+  let render_targets = swap_chain.acquire().unwrap();
+  assert!(swap_chain.present(&render_targets).is_ok());
+}
+
+#[cfg(feature = "ext-readback")]
+#[test]
+fn swap_chain_capture() {
+  let device = dummy_device();
+  let mut swap_chain = device
+    .new_swap_chain(4, 2, SwapChainMode::Fifo, 2, SwapChainFormat::SRGBA8)
+    .unwrap();
+  let nothing_presented = Some(DummyBackendError::Common(Error::InvalidReadback {
+    reason: "no image was presented since the swap chain was created or resized".to_owned(),
+  }));
+  assert_eq!(swap_chain.capture().err(), nothing_presented);
+
+  let render_targets = swap_chain.acquire().unwrap();
+  swap_chain.present(&render_targets).unwrap();
+  assert_eq!(swap_chain.capture().unwrap(), [255; 4 * 4 * 2]);
+
+  // the last presented image is gone once resized
+  swap_chain.resize(8, 8).unwrap();
+  assert_eq!(swap_chain.capture().err(), nothing_presented);
+  let render_targets = swap_chain.acquire().unwrap();
+  swap_chain.present(&render_targets).unwrap();
+  assert_eq!(swap_chain.capture().unwrap().len(), 4 * 8 * 8);
+}
+
+#[test]
+fn swap_chain_capabilities() {
+  let device = dummy_device();
+  let new_swap_chain = |(width, height), mode, image_count| {
+    device
+      .new_swap_chain(width, height, mode, image_count, SwapChainFormat::SRGBA8)
+      .err()
+  };
+  let invalid = |reason: &str| {
+    Some(DummyBackendError::Common(Error::InvalidSwapChain {
+      reason: reason.to_owned(),
+    }))
+  };
+
+  assert_eq!(
+    new_swap_chain((4096, 4096), SwapChainMode::Mailbox, 3),
+    None
+  );
+  assert_eq!(
+    new_swap_chain((8192, 4096), SwapChainMode::Fifo, 2),
+    invalid("8192×4096 is out of the supported extents, from 1×1 to 4096×4096")
+  );
+  assert_eq!(
+    new_swap_chain((800, 0), SwapChainMode::Fifo, 2),
+    invalid("800×0 is out of the supported extents, from 1×1 to 4096×4096")
+  );
+  assert_eq!(
+    new_swap_chain((800, 600), SwapChainMode::Fifo, 4),
+    invalid("4 images is out of the supported image counts, from 1 to 3")
+  );
+  assert_eq!(
+    new_swap_chain((800, 600), SwapChainMode::Immediate, 2),
+    invalid("Immediate is not supported")
+  );
+}
+
+#[test]
+fn hdr_swap_chain() {
+  let device = dummy_device();
+  let formats = device.swap_chain_capabilities().unwrap().formats;
+  assert_eq!(formats, [SwapChainFormat::SRGBA8, SwapChainFormat::RGBA16F]);
+
+  let swap_chain = device
+    .new_swap_chain(
+      1920,
+      1080,
+      piksels_backend::swap_chain::SwapChainMode::Fifo,
+      2,
+      SwapChainFormat::RGBA16F,
+    )
+    .unwrap();
+  assert_eq!(swap_chain.format(), SwapChainFormat::RGBA16F);
+  assert_eq!(
+    device
+      .swap_chain_by_id(&swap_chain.id())
+      .unwrap()
+      .unwrap()
+      .format(),
+    SwapChainFormat::RGBA16F
+  );
+
+  assert_eq!(
+    device
+      .new_swap_chain(
+        1920,
+        1080,
+        piksels_backend::swap_chain::SwapChainMode::Fifo,
+        2,
+        SwapChainFormat::RGB10A2,
+      )
+      .err(),
+    Some(DummyBackendError::Common(Error::InvalidSwapChain {
+      reason: "RGB10A2 is not supported".to_owned()
+    }))
+  );
+}
+
+#[test]
+fn multiple_swap_chains() {
+  let device = dummy_device();
+  let main_window = device
+    .new_swap_chain(
+      1920,
+      1080,
+      piksels_backend::swap_chain::SwapChainMode::Fifo,
+      2,
+      SwapChainFormat::SRGBA8,
+    )
+    .unwrap();
+  let tool_window = device
+    .new_swap_chain(
+      640,
+      480,
+      piksels_backend::swap_chain::SwapChainMode::Mailbox,
+      3,
+      SwapChainFormat::SRGBA8,
+    )
+    .unwrap();
+  let cmd_buf = device.new_cmd_buf().unwrap();
+  let triangle = device.new_empty_vertex_array(3).unwrap();
+
+  // images of both windows can be acquired at once, and presented in any order
+  let presented = PRESENTED_FRAMES.with(Cell::get);
+  let main_targets = main_window.acquire().unwrap();
+  let tool_targets = tool_window.acquire().unwrap();
+  assert_eq!(
+    tool_targets.storage(),
+    Storage::Flat2D {
+      width: 640,
+      height: 480
+    }
+  );
+  for render_targets in [&main_targets, &tool_targets] {
+    cmd_buf
+      .render_targets(render_targets)
+      .unwrap()
+      .draw(&triangle)
+      .unwrap();
+  }
+  cmd_buf.finish().unwrap();
+  tool_window.present(&tool_targets).unwrap();
+
+  // render targets are presented to the swap chain they were acquired from
+  assert_eq!(
+    tool_window.present(&main_targets).err(),
+    Some(DummyBackendError::Common(Error::InvalidPresent {
+      reason: "render targets were not acquired from this swap chain".to_owned()
+    }))
+  );
+  main_window.present(&main_targets).unwrap();
+  assert_eq!(PRESENTED_FRAMES.with(Cell::get), presented + 2);
+
+  // an image must be presented before acquiring the next one, and presented only once
+  let main_targets = main_window.acquire().unwrap();
+  assert_eq!(
+    main_window.acquire().err(),
+    Some(DummyBackendError::Common(Error::InvalidAcquire {
+      reason: "the previously acquired image was not presented".to_owned()
+    }))
+  );
+  main_window.present(&main_targets).unwrap();
+  assert_eq!(
+    main_window.present(&main_targets).err(),
+    Some(DummyBackendError::Common(Error::InvalidPresent {
+      reason: "no image is acquired".to_owned()
+    }))
+  );
+}
@@ -0,0 +1,106 @@
+use piksels_backend::{
+  error::Error,
+  pixel::{ChannelBits, Format, Pixel, Type},
+  texture::{Offset, Rect, Size, Storage},
+};
+
+mod common;
+
+use common::*;
+
+#[test]
+fn texture_3d_uploads() {
+  let device = dummy_device();
+  let pixel = Pixel {
+    encoding: Type::NormUnsigned,
+    format: Format::RGBA(
+      ChannelBits::Eight,
+      ChannelBits::Eight,
+      ChannelBits::Eight,
+      ChannelBits::Eight,
+    ),
+  };
+  let volume = device
+    .new_texture(
+      Storage::Flat3D {
+        width: 4,
+        height: 4,
+        depth: 4,
+      },
+      pixel,
+      dummy_sampling(),
+    )
+    .unwrap();
+
+  assert!(volume.set_slice(0, 3, &[0; 4 * 4 * 4]).is_ok());
+  assert!(volume.set_slice(1, 1, &[0; 2 * 2 * 4]).is_ok());
+  assert!(matches!(
+    volume.set_slice(0, 4, &[0; 4 * 4 * 4]),
+    Err(DummyBackendError::Common(Error::InvalidTexelsUpload { .. }))
+  ));
+  assert!(matches!(
+    volume.set_slice(0, 0, &[0; 4 * 4 * 3]),
+    Err(DummyBackendError::Common(Error::InvalidTexelsUpload { .. }))
+  ));
+
+  let rect = Rect::new(
+    Offset::Dim3 { x: 1, y: 1, z: 1 },
+    Size::Dim3 {
+      width: 2,
+      height: 2,
+      depth: 2,
+    },
+  );
+
+  // rows padded to 16 bytes, slices padded to 40 bytes
+  assert!(volume
+    .set_region_3d(0, rect, &[0; 40 + 16 + 8], 16, 40)
+    .is_ok());
+  assert!(matches!(
+    volume.set_region_3d(0, rect, &[0; 40 + 16 + 7], 16, 40),
+    Err(DummyBackendError::Common(Error::InvalidTexelsUpload { .. }))
+  ));
+  assert!(matches!(
+    volume.set_region_3d(0, rect, &[0; 64], 4, 40),
+    Err(DummyBackendError::Common(Error::InvalidTexelsUpload { .. }))
+  ));
+  assert!(matches!(
+    volume.set_region_3d(2, rect, &[0; 64], 8, 16),
+    Err(DummyBackendError::Common(Error::InvalidTexelsUpload { .. }))
+  ));
+}
+
+#[test]
+fn texture_levels() {
+  let device = dummy_device();
+  let pixel = Pixel {
+    encoding: Type::Floating,
+    format: Format::R(ChannelBits::ThirtyTwo),
+  };
+  let texture = device
+    .new_texture(
+      Storage::Flat2D {
+        width: 256,
+        height: 64,
+      },
+      pixel,
+      dummy_sampling(),
+    )
+    .unwrap();
+
+  assert_eq!(texture.storage().levels(), 9);
+  assert!(texture.set_levels(6, 8).is_ok());
+  assert!(texture.set_levels(0, 0).is_ok());
+  assert!(matches!(
+    texture.set_levels(0, 9),
+    Err(DummyBackendError::Common(
+      Error::InvalidTextureLevels { .. }
+    ))
+  ));
+  assert!(matches!(
+    texture.set_levels(4, 3),
+    Err(DummyBackendError::Common(
+      Error::InvalidTextureLevels { .. }
+    ))
+  ));
+}
@@ -0,0 +1,264 @@
+#![cfg(feature = "ext-trace")]
+
+use std::{cell::Cell, collections::HashSet};
+
+use piksels_backend::{
+  error::Error,
+  pixel::{ChannelBits, Format, Pixel, Type},
+  render_targets::{self, ColorAttachmentPoint, ColorType},
+  shader::{ShaderSources, UniformType, UniformTypeBase},
+  texture::Storage,
+  vertex_array::{MemoryLayout, VertexArrayData},
+  viewport::Viewport,
+};
+use piksels_core::{pipeline::PipelineState, vertex_array::View};
+
+mod common;
+
+use common::*;
+
+#[test]
+fn trace_replay() {
+  use piksels_backend::extension::trace::{TraceCall, TraceCallKind};
+  use piksels_core::trace::TraceReplay;
+
+  let device = dummy_device();
+  let pixel = Pixel {
+    encoding: Type::NormUnsigned,
+    format: Format::R(ChannelBits::Eight),
+  };
+  let storage = Storage::Flat2D {
+    width: 4,
+    height: 4,
+  };
+  let texture = device
+    .new_texture(storage, pixel, dummy_sampling())
+    .unwrap();
+  let shader = device.new_shader(ShaderSources::default()).unwrap();
+  let vertex_array = device
+    .new_vertex_array(
+      VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+      VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+      Vec::new(),
+    )
+    .unwrap();
+  let unit = device.get_texture_binding_point(0).unwrap();
+
+  let cmd_buf = device.new_cmd_buf().unwrap();
+  cmd_buf.start_trace();
+  for _ in 0..2 {
+    cmd_buf
+      .viewport(Viewport::Whole)
+      .unwrap()
+      .shader(&shader)
+      .unwrap()
+      .use_texture(&texture, &unit)
+      .unwrap()
+      .draw(&vertex_array)
+      .unwrap()
+      .finish()
+      .unwrap();
+  }
+
+  let trace = cmd_buf.take_trace().unwrap();
+  assert!(cmd_buf.take_trace().is_none());
+  assert_eq!(trace.frame_count(), 2);
+
+  // resources are identified in order of first use; the redundant viewport and shader of the second frame are not
+  // captured
+  let frame = [
+    TraceCall::Viewport(Viewport::Whole),
+    TraceCall::Shader(0),
+    TraceCall::Texture {
+      texture: 1,
+      binding_point: 2,
+    },
+    TraceCall::Draw {
+      vertex_array: 3,
+      restart_index: piksels_backend::primitive::DEFAULT_RESTART_INDEX,
+    },
+    TraceCall::Finish,
+  ];
+  assert_eq!(trace.calls[..5], frame);
+  assert_eq!(trace.calls[5..], frame[2..]);
+
+  // traces can be saved and loaded back, e.g. to be replayed by another program, and so can pipeline states
+  #[cfg(feature = "serde")]
+  {
+    use piksels_backend::extension::trace::Trace;
+
+    let json = serde_json::to_string(&trace).unwrap();
+    assert_eq!(serde_json::from_str::<Trace>(&json).unwrap(), trace);
+
+    let state = PipelineState::default();
+    let json = serde_json::to_string(&state).unwrap();
+    assert_eq!(serde_json::from_str::<PipelineState>(&json).unwrap(), state);
+  }
+
+  // replaying with missing resources fails
+  let replay_cmd_buf = device.new_cmd_buf().unwrap();
+  assert!(matches!(
+    TraceReplay::new().run(&replay_cmd_buf, &trace),
+    Err(DummyBackendError::Common(Error::InvalidTrace { .. }))
+  ));
+
+  let replay_cmd_buf = device.new_cmd_buf().unwrap();
+  let waits = CMD_BUF_WAITS.with(Cell::get);
+  let report = TraceReplay::new()
+    .shader(0, &shader)
+    .texture(1, &texture)
+    .texture_binding_point(2, &unit)
+    .vertex_array(3, &vertex_array)
+    .run(&replay_cmd_buf, &trace)
+    .unwrap();
+
+  assert_eq!(CMD_BUF_WAITS.with(Cell::get), waits + 2);
+  assert_eq!(report.frames().len(), 2);
+  assert_eq!(report.calls()[&TraceCallKind::Draw].count, 2);
+  assert_eq!(report.calls()[&TraceCallKind::Viewport].count, 1);
+  assert_eq!(report.calls()[&TraceCallKind::Shader].count, 1);
+  assert_eq!(report.calls()[&TraceCallKind::Finish].count, 2);
+
+  let table = report.to_string();
+  assert!(table.lines().any(|line| line.starts_with("Draw ")));
+  assert!(table.lines().last().unwrap().starts_with("2 frames in "));
+}
+
+#[test]
+fn trace_replay_all_calls() {
+  use piksels_backend::extension::trace::{Trace, TraceCall, TraceCallKind};
+  use piksels_core::trace::TraceReplay;
+
+  let device = dummy_device();
+  let color = ColorAttachmentPoint::new(
+    0,
+    "color",
+    ColorType::IRGBA {
+      red_bits: render_targets::ChannelBits::Eight,
+      green_bits: render_targets::ChannelBits::Eight,
+      blue_bits: render_targets::ChannelBits::Eight,
+      alpha_bits: render_targets::ChannelBits::Eight,
+    },
+  );
+  let storage = Storage::Flat2D {
+    width: 4,
+    height: 4,
+  };
+  let main_pass = device
+    .new_render_targets(
+      HashSet::from([color]),
+      None,
+      storage.multisampled(4).unwrap(),
+    )
+    .unwrap();
+  let resolved = device
+    .new_render_targets(HashSet::from([color]), None, storage)
+    .unwrap();
+  let color_attachment = resolved.color_attachment(0).unwrap();
+  let shader = device.new_shader(ShaderSources::default()).unwrap();
+  let tint = shader.uniform("tint", UniformTypeBase::Float).unwrap();
+  let bones = shader
+    .uniform("bones", UniformType::new(UniformTypeBase::Float, 4))
+    .unwrap();
+  let storage_buffer = device.new_storage_buffer(64).unwrap();
+  let storage_buffer_binding_point = device.get_storage_buffer_binding_point(0).unwrap();
+  let shader_storage_buffer_binding_point =
+    shader.storage_buffer_binding_point("particles").unwrap();
+  let texture_binding_point = device.get_texture_binding_point(0).unwrap();
+  let vertex_array = device.new_empty_vertex_array(6).unwrap();
+  let deferred = device.create_deferred_context().new_cmd_buf().unwrap();
+  deferred.finish().unwrap();
+
+  let cmd_buf = device.new_cmd_buf().unwrap();
+  cmd_buf.start_trace();
+  cmd_buf
+    .uniform(&tint, &1f32)
+    .unwrap()
+    .uniform_array(&bones, 2, &[1f32, 2.])
+    .unwrap()
+    .associate_storage_buffer(
+      &storage_buffer_binding_point,
+      &shader_storage_buffer_binding_point,
+    )
+    .unwrap()
+    .use_storage_buffer(&storage_buffer, &storage_buffer_binding_point)
+    .unwrap()
+    .draw_view(&vertex_array.view(1..4).unwrap())
+    .unwrap()
+    .resolve_render_targets(&main_pass, &resolved)
+    .unwrap()
+    .use_color_attachment(&color_attachment, &texture_binding_point)
+    .unwrap()
+    .execute(&deferred)
+    .unwrap()
+    .finish()
+    .unwrap();
+
+  // uniforms of the dummy backend share the scarce index of their shader, hence the same trace identifier
+  let trace = cmd_buf.take_trace().unwrap();
+  assert_eq!(
+    trace.calls,
+    [
+      TraceCall::Uniform {
+        uniform: 0,
+        bytes: 1f32.to_ne_bytes().to_vec(),
+      },
+      TraceCall::UniformArray {
+        uniform: 0,
+        first_element: 2,
+        bytes: [1f32.to_ne_bytes(), 2f32.to_ne_bytes()].concat(),
+      },
+      TraceCall::AssociateStorageBuffer {
+        binding_point: 1,
+        shader_binding_point: 2,
+      },
+      TraceCall::StorageBuffer {
+        storage_buffer: 3,
+        binding_point: 1,
+      },
+      TraceCall::DrawRange {
+        vertex_array: 4,
+        start_vertex: 1,
+        vertex_count: 3,
+        start_instance: 0,
+        instance_count: 1,
+        restart_index: piksels_backend::primitive::DEFAULT_RESTART_INDEX,
+      },
+      TraceCall::ResolveRenderTargets { src: 5, dst: 6 },
+      TraceCall::ColorAttachment {
+        color_attachment: 7,
+        binding_point: 8,
+      },
+      TraceCall::Execute(9),
+      TraceCall::Finish,
+    ]
+  );
+
+  let replay_cmd_buf = device.new_cmd_buf().unwrap();
+  let report = TraceReplay::new()
+    .uniform(0, &tint)
+    .storage_buffer_binding_point(1, &storage_buffer_binding_point)
+    .shader_storage_buffer_binding_point(2, &shader_storage_buffer_binding_point)
+    .storage_buffer(3, &storage_buffer)
+    .vertex_array(4, &vertex_array)
+    .render_targets(5, &main_pass)
+    .render_targets(6, &resolved)
+    .color_attachment(7, &color_attachment)
+    .texture_binding_point(8, &texture_binding_point)
+    .cmd_buf(9, &deferred)
+    .run(&replay_cmd_buf, &trace)
+    .unwrap();
+  assert_eq!(report.calls().len(), trace.calls.len());
+  assert_eq!(report.calls()[&TraceCallKind::DrawRange].count, 1);
+
+  // readbacks are only replayed by backends supporting them
+  let readback = Trace {
+    calls: vec![TraceCall::ReadColorAttachment(0)],
+  };
+  assert!(matches!(
+    TraceReplay::new()
+      .color_attachment(0, &color_attachment)
+      .run(&replay_cmd_buf, &readback),
+    Err(DummyBackendError::Common(Error::InvalidTrace { .. }))
+  ));
+}
@@ -0,0 +1,65 @@
+#![cfg(feature = "glsl-translate")]
+
+use piksels_backend::{
+  error::Error,
+  shader::{ShaderDialect, ShaderSources},
+};
+use piksels_core::translate::translate_sources;
+
+const VS: &str = "#version 450 core
+layout(location = 0) in vec2 position;
+layout(location = 0) out vec2 uv;
+
+void main() {
+  uv = position * 0.5 + 0.5;
+  gl_Position = vec4(position * SCALE, 0.0, 1.0);
+}
+";
+
+const FS: &str = "#version 450 core
+layout(location = 0) in vec2 uv;
+layout(location = 0) out vec4 color;
+
+void main() {
+  color = vec4(uv, 0.0, 1.0);
+}
+";
+
+#[test]
+fn glsl_dialects() {
+  let sources = ShaderSources::default()
+    .vertex(VS)
+    .fragment(FS)
+    .define("SCALE", "2.0");
+
+  let glsl = translate_sources(&sources, ShaderDialect::Glsl330).unwrap();
+  let glsl = glsl.sources();
+  assert!(glsl.vertex_stage().starts_with("#version 330 core"));
+  assert!(glsl.fragment_stage().starts_with("#version 330 core"));
+  assert!(glsl.compute_stage().is_empty());
+  assert!(glsl.defines().is_empty());
+
+  let essl = translate_sources(&sources, ShaderDialect::Essl300).unwrap();
+  assert!(essl.sources().vertex_stage().starts_with("#version 300 es"));
+
+  let wgsl = translate_sources(&sources, ShaderDialect::Wgsl).unwrap();
+  assert!(wgsl.sources().vertex_stage().contains("@vertex"));
+  assert!(wgsl.sources().fragment_stage().contains("@fragment"));
+}
+
+#[test]
+fn translation_errors() {
+  // SCALE is not defined
+  let err = translate_sources(&ShaderSources::default().vertex(VS), ShaderDialect::Glsl330);
+  assert!(matches!(err, Err(Error::ShaderTranslation { .. })));
+
+  assert_eq!(
+    translate_sources(
+      &ShaderSources::default().geometry("void main() {}"),
+      ShaderDialect::Wgsl
+    ),
+    Err(Error::ShaderTranslation {
+      reason: "geometry stages cannot be translated".to_owned()
+    })
+  );
+}
@@ -0,0 +1,148 @@
+use piksels_backend::{
+  error::Error,
+  shader::{UniformType, UniformTypeBase},
+  uniform_block::{Std140, UniformBlock},
+};
+use piksels_derive::UniformBlock;
+
+fn f32_bytes(values: &[f32]) -> Vec<u8> {
+  values
+    .iter()
+    .flat_map(|value| value.to_ne_bytes())
+    .collect()
+}
+
+#[test]
+fn exact_uniform_types() {
+  let ty = UniformType::from(UniformTypeBase::Float3);
+  assert_eq!(ty.coerce(&[1f32, 2., 3.]), Ok(f32_bytes(&[1., 2., 3.])));
+
+  let ty = UniformType::from(UniformTypeBase::FloatMat22);
+  assert_eq!(
+    ty.coerce(&[[1f32, 2.], [3., 4.]]),
+    Ok(f32_bytes(&[1., 2., 3., 4.]))
+  );
+}
+
+#[test]
+fn coerced_uniform_types() {
+  let ty = UniformType::from(UniformTypeBase::Int);
+  assert_eq!(ty.coerce(&true), Ok(1i32.to_ne_bytes().to_vec()));
+
+  let ty = UniformType::from(UniformTypeBase::Float2);
+  assert_eq!(ty.coerce(&[3i32, -1]), Ok(f32_bytes(&[3., -1.])));
+
+  let ty = UniformType::from(UniformTypeBase::Double);
+  assert_eq!(ty.coerce(&0.5f32), Ok(0.5f64.to_ne_bytes().to_vec()));
+
+  let ty = UniformType::new(UniformTypeBase::Float, 4);
+  assert_eq!(ty.coerce(&[1f32, 2.][..]), Ok(f32_bytes(&[1., 2.])));
+}
+
+#[test]
+fn mismatching_uniform_types() {
+  let mismatch =
+    |expected: UniformType, got: UniformType| Err(Error::UniformTypeMismatch { expected, got });
+
+  let ty = UniformType::from(UniformTypeBase::Float3);
+  assert_eq!(
+    ty.coerce(&[0f32; 4]),
+    mismatch(ty, UniformTypeBase::Float4.into())
+  );
+
+  let ty = UniformType::from(UniformTypeBase::Int);
+  assert_eq!(
+    ty.coerce(&1f32),
+    mismatch(ty, UniformTypeBase::Float.into())
+  );
+
+  let ty = UniformType::from(UniformTypeBase::Bool);
+  assert_eq!(ty.coerce(&1i32), mismatch(ty, UniformTypeBase::Int.into()));
+
+  let ty = UniformType::from(UniformTypeBase::FloatMat22);
+  assert_eq!(
+    ty.coerce(&[0f32; 4]),
+    mismatch(ty, UniformTypeBase::Float4.into())
+  );
+
+  let ty = UniformType::new(UniformTypeBase::Float, 2);
+  assert_eq!(
+    ty.coerce(&[0f32; 3][..]),
+    mismatch(ty, UniformType::new(UniformTypeBase::Float, 3))
+  );
+  assert_eq!(
+    ty.coerce(&0f32),
+    mismatch(ty, UniformTypeBase::Float.into())
+  );
+}
+
+#[derive(UniformBlock)]
+struct Light {
+  position: [f32; 3],
+  intensity: f32,
+  color: [f32; 3],
+}
+
+#[derive(UniformBlock)]
+struct Scene {
+  ambient: f32,
+  projection: [[f32; 3]; 3],
+  enabled: bool,
+  lights: [Light; 2],
+  count: u32,
+}
+
+#[test]
+fn std140_offsets() {
+  // vec3 + float pack in 16 bytes, and the struct is padded to a multiple of 16
+  assert_eq!(
+    Light::field_offsets(),
+    [("position", 0), ("intensity", 12), ("color", 16)]
+  );
+  assert_eq!(Light::SIZE, 32);
+
+  // mat3 columns are padded to vec4, structs are aligned on 16 bytes
+  assert_eq!(
+    Scene::field_offsets(),
+    [
+      ("ambient", 0),
+      ("projection", 16),
+      ("enabled", 64),
+      ("lights", 80),
+      ("count", 144)
+    ]
+  );
+  assert_eq!(Scene::SIZE, 160);
+}
+
+#[test]
+fn std140_bytes() {
+  let light = |x: f32| Light {
+    position: [x, 0., 0.],
+    intensity: 1.,
+    color: [1., 1., 1.],
+  };
+  let scene = Scene {
+    ambient: 0.5,
+    projection: [[1., 2., 3.], [4., 5., 6.], [7., 8., 9.]],
+    enabled: true,
+    lights: [light(10.), light(20.)],
+    count: 2,
+  };
+
+  let bytes = scene.to_std140_bytes();
+  assert_eq!(bytes.len(), Scene::SIZE);
+  assert_eq!(bytes[0..4], f32_bytes(&[0.5]));
+  assert_eq!(bytes[16..28], f32_bytes(&[1., 2., 3.]));
+  assert_eq!(bytes[28..32], [0; 4]);
+  assert_eq!(bytes[32..44], f32_bytes(&[4., 5., 6.]));
+  assert_eq!(bytes[64..68], 1u32.to_ne_bytes());
+  assert_eq!(bytes[80..84], f32_bytes(&[10.]));
+  assert_eq!(bytes[112..116], f32_bytes(&[20.]));
+  assert_eq!(bytes[144..148], 2u32.to_ne_bytes());
+
+  let mut buffer = vec![0xff; 200];
+  scene.write_to(&mut buffer);
+  assert_eq!(buffer[..Scene::SIZE], bytes);
+  assert_eq!(buffer[Scene::SIZE], 0xff);
+}
@@ -0,0 +1,200 @@
+use piksels_backend::{error::Error, shader::Stage};
+use piksels_core::units::{SpillStrategy, U32Unit, Unit, Units, UnitsTelemetry};
+
+mod common;
+
+use common::*;
+
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+struct DummyUnit(u32);
+
+impl Unit for DummyUnit {
+  fn next_unit(&self) -> Self {
+    DummyUnit(self.0 + 1)
+  }
+
+  fn from_index(index: usize) -> Self {
+    DummyUnit(index as u32)
+  }
+
+  fn index(&self) -> usize {
+    self.0 as usize
+  }
+}
+
+#[test]
+fn u32_units() {
+  assert_eq!(U32Unit::default().next_unit(), U32Unit(1));
+  assert_eq!(U32Unit::from_index(3), U32Unit(3));
+  assert_eq!(U32Unit::from_index(usize::MAX), U32Unit(u32::MAX));
+  assert_eq!(U32Unit(7).index(), 7);
+
+  let mut units: Units<DummyBackend, U32Unit> = Units::new(U32Unit::from_index(1));
+  assert_eq!(
+    units
+      .get_unit(10, Stage::Fragment)
+      .map(|entry| entry.unit)
+      .ok(),
+    Some(U32Unit(0))
+  );
+  assert!(units.get_unit(11, Stage::Fragment).is_err());
+}
+
+#[test]
+fn units_stage_limits() {
+  let mut units: Units<DummyBackend, DummyUnit> = Units::new(DummyUnit(4));
+  units.set_stage_limit(Stage::Vertex, 1);
+
+  assert_eq!(units.stage_limit(Stage::Vertex), Some(1));
+  assert_eq!(units.stage_limit(Stage::Fragment), None);
+  assert_eq!(
+    units
+      .get_unit(10, Stage::Vertex)
+      .ok()
+      .map(|entry| entry.unit),
+    Some(DummyUnit(0))
+  );
+  assert_eq!(
+    units.get_unit(11, Stage::Vertex).err(),
+    Some(DummyBackendError::Common(Error::NoMoreUnits))
+  );
+
+  // the combined limit is shared with the other stages
+  assert!(units.get_unit(11, Stage::Fragment).is_ok());
+  assert!(units.get_unit(12, Stage::Fragment).is_ok());
+
+  // a resource still bound to an idle unit doesn’t get it back past the stage limit
+  units.idle(DummyUnit(1));
+  assert_eq!(units.get_bound_unit(&11, Stage::Vertex), None);
+  assert_eq!(
+    units.get_bound_unit(&11, Stage::Fragment),
+    Some(DummyUnit(1))
+  );
+
+  units.set_spill_strategy(SpillStrategy::Steal);
+  let entry = units.get_unit(13, Stage::Vertex).ok().unwrap();
+  assert_eq!(entry.unit, DummyUnit(0));
+  assert_eq!(entry.current_scarce_index, Some(10));
+}
+
+#[test]
+fn units_spill_fail() {
+  let mut units: Units<DummyBackend, DummyUnit> = Units::new(DummyUnit(2));
+
+  assert_eq!(
+    units
+      .get_unit(10, Stage::Fragment)
+      .ok()
+      .map(|entry| entry.unit),
+    Some(DummyUnit(0))
+  );
+  assert_eq!(
+    units
+      .get_unit(11, Stage::Fragment)
+      .ok()
+      .map(|entry| entry.unit),
+    Some(DummyUnit(1))
+  );
+  assert_eq!(
+    units.get_unit(12, Stage::Fragment).err(),
+    Some(DummyBackendError::Common(Error::NoMoreUnits))
+  );
+
+  // idle units are reused before spilling
+  units.idle(DummyUnit(0));
+  let entry = units.get_unit(12, Stage::Fragment).ok().unwrap();
+  assert_eq!(entry.unit, DummyUnit(0));
+  assert_eq!(entry.current_scarce_index, Some(10));
+}
+
+#[test]
+fn units_spill_diagnose() {
+  let mut units: Units<DummyBackend, DummyUnit> = Units::new(DummyUnit(2));
+  units.set_spill_strategy(SpillStrategy::Diagnose);
+
+  assert!(units.get_unit(10, Stage::Fragment).is_ok());
+  assert!(units.get_unit(11, Stage::Fragment).is_ok());
+
+  assert_eq!(
+    units.get_unit(12, Stage::Fragment).err(),
+    Some(DummyBackendError::Common(Error::NoMoreUnitsInUse {
+      owners: "DummyUnit(0) -> 10, DummyUnit(1) -> 11".to_owned()
+    }))
+  );
+}
+
+#[test]
+fn units_spill_steal() {
+  let mut units: Units<DummyBackend, DummyUnit> = Units::new(DummyUnit(2));
+  units.set_spill_strategy(SpillStrategy::Steal);
+
+  assert!(units.get_unit(10, Stage::Fragment).is_ok());
+  assert!(units.get_unit(11, Stage::Fragment).is_ok());
+
+  let entry = units.get_unit(12, Stage::Fragment).ok().unwrap();
+  assert_eq!(entry.unit, DummyUnit(0));
+  assert_eq!(entry.current_scarce_index, Some(10));
+
+  let entry = units.get_unit(13, Stage::Fragment).ok().unwrap();
+  assert_eq!(entry.unit, DummyUnit(1));
+  assert_eq!(entry.current_scarce_index, Some(11));
+}
+
+#[test]
+fn units_telemetry() {
+  let mut units: Units<DummyBackend, DummyUnit> = Units::new(DummyUnit(2));
+  units.set_spill_strategy(SpillStrategy::Steal);
+
+  assert!(units.get_unit(10, Stage::Fragment).is_ok());
+  assert!(units.get_unit(11, Stage::Fragment).is_ok());
+  units.idle(DummyUnit(0));
+  assert!(units.get_unit(12, Stage::Fragment).is_ok());
+  assert!(units.get_unit(13, Stage::Fragment).is_ok());
+  assert_eq!(
+    units.telemetry(),
+    UnitsTelemetry {
+      high_water_mark: 2,
+      reuses: 1,
+      exhaustions: 1,
+    }
+  );
+
+  units.idle(DummyUnit(0));
+  units.reset_telemetry();
+  assert_eq!(
+    units.telemetry(),
+    UnitsTelemetry {
+      high_water_mark: 1,
+      reuses: 0,
+      exhaustions: 0,
+    }
+  );
+}
+
+#[test]
+fn units_lru() {
+  let mut units: Units<DummyBackend, DummyUnit> = Units::new(DummyUnit(3));
+
+  for scarce_index in 10..13 {
+    assert!(units.get_unit(scarce_index, Stage::Fragment).is_ok());
+  }
+
+  units.idle(DummyUnit(1));
+  units.idle(DummyUnit(0));
+  units.idle(DummyUnit(2));
+
+  // the least recently used unit is evicted first
+  let entry = units.get_unit(13, Stage::Fragment).ok().unwrap();
+  assert_eq!(entry.unit, DummyUnit(1));
+  assert_eq!(entry.current_scarce_index, Some(11));
+
+  // units without any resource bound are reused before evicting anything
+  units.forget(&12);
+  let entry = units.get_unit(14, Stage::Fragment).ok().unwrap();
+  assert_eq!(entry.unit, DummyUnit(2));
+  assert_eq!(entry.current_scarce_index, None);
+
+  let entry = units.get_unit(15, Stage::Fragment).ok().unwrap();
+  assert_eq!(entry.unit, DummyUnit(0));
+  assert_eq!(entry.current_scarce_index, Some(10));
+}
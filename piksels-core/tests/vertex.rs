@@ -0,0 +1,157 @@
+use bytemuck::{Pod, Zeroable};
+use piksels_backend::{
+  assert_vertex_layout,
+  error::Error,
+  field_offset,
+  vertex::{Normalized, Type, Vertex, VertexAttr},
+  vertex_array::{MemoryLayout, VertexArrayData},
+};
+use piksels_derive::Vertex;
+
+#[derive(Clone, Copy, Debug, Pod, Vertex, Zeroable)]
+#[repr(C)]
+struct TestVertex {
+  #[vertex(name = "position")]
+  pos: [f32; 3],
+
+  #[vertex(normalized)]
+  color: [u32; 4],
+
+  weight: f32,
+}
+
+assert_vertex_layout!(TestVertex {
+  pos: Type::Float3,
+  color: Type::Uint4(Normalized::Yes),
+  weight: Type::Float,
+});
+
+#[derive(Clone, Copy, Debug, Pod, Vertex, Zeroable)]
+#[repr(C)]
+struct InstanceVertex {
+  model: [[f32; 4]; 4],
+  normal_matrix: [[f32; 3]; 3],
+  tint: [f32; 4],
+}
+
+assert_vertex_layout!(InstanceVertex {
+  model: Type::FloatMat4,
+  normal_matrix: Type::FloatMat3,
+  tint: Type::Float4,
+});
+
+#[test]
+fn derive_vertex_attrs() {
+  assert_eq!(
+    TestVertex::vertex_attrs(),
+    vec![
+      VertexAttr {
+        index: 0,
+        name: "position",
+        ty: Type::Float3,
+        array: None,
+      },
+      VertexAttr {
+        index: 1,
+        name: "color",
+        ty: Type::Uint4(Normalized::Yes),
+        array: None,
+      },
+      VertexAttr {
+        index: 2,
+        name: "weight",
+        ty: Type::Float,
+        array: None,
+      },
+    ]
+  );
+}
+
+#[test]
+fn derive_vertex_as_bytes() {
+  let vertices = [
+    TestVertex {
+      pos: [1., 2., 3.],
+      color: [255, 0, 0, 255],
+      weight: 0.5,
+    },
+    TestVertex {
+      pos: [4., 5., 6.],
+      color: [0, 255, 0, 255],
+      weight: 1.,
+    },
+  ];
+
+  assert_eq!(&vertices[0].as_bytes()[..4], &1f32.to_ne_bytes());
+  assert_eq!(vertices[0].as_bytes().len(), 32);
+
+  let data = VertexArrayData::from_vertices(&vertices);
+  assert_eq!(data.len(), 2);
+  assert!(matches!(data.layout(), MemoryLayout::Interleaved { data } if data.len() == 64));
+}
+
+#[test]
+fn vertex_data_from_slice() {
+  let vertices = [TestVertex {
+    pos: [1., 2., 3.],
+    color: [255, 0, 0, 255],
+    weight: 0.5,
+  }; 3];
+
+  let data = VertexArrayData::from_slice(&vertices).unwrap();
+  assert_eq!(data, VertexArrayData::from_vertices(&vertices));
+  assert_eq!(data.len(), 3);
+}
+
+#[test]
+fn vertex_data_deinterleaved() {
+  let data = VertexArrayData::deinterleaved()
+    .attr("position", &[[0f32; 3]; 4])
+    .normalized_attr("color", &[[0u32; 4]; 4])
+    .build()
+    .unwrap();
+
+  assert_eq!(data.len(), 4);
+  assert_eq!(data.attrs()[1].index, 1);
+  assert_eq!(data.attrs()[1].ty, Type::Uint4(Normalized::Yes));
+
+  assert!(matches!(
+    VertexArrayData::deinterleaved()
+      .attr("position", &[[0f32; 3]; 4])
+      .attr("weight", &[0f32; 3])
+      .build(),
+    Err(Error::InvalidVertexData { .. })
+  ));
+}
+
+#[test]
+fn field_offsets() {
+  const WEIGHT_OFFSET: usize = field_offset!(TestVertex, weight);
+
+  assert_eq!(field_offset!(TestVertex, pos), 0);
+  assert_eq!(field_offset!(TestVertex, color), 12);
+  assert_eq!(WEIGHT_OFFSET, 28);
+}
+
+#[test]
+fn matrix_vertex_attrs() {
+  assert_eq!(Type::FloatMat4.size(), 64);
+  assert_eq!(Type::FloatMat4.align(), 4);
+  assert_eq!(Type::FloatMat3.vector_dim(), 3);
+  assert_eq!(Type::FloatMat2.locations(), 2);
+  assert_eq!(Type::FloatMat4.location_type(), Type::Float4);
+
+  let indices: Vec<_> = InstanceVertex::vertex_attrs()
+    .iter()
+    .map(|attr| attr.index)
+    .collect();
+  assert_eq!(indices, [0, 4, 7]);
+
+  let data = VertexArrayData::deinterleaved()
+    .attr("model", &[[[0f32; 4]; 4]; 2])
+    .attr("tint", &[[0f32; 4]; 2])
+    .build()
+    .unwrap();
+  assert_eq!(data.len(), 2);
+  assert_eq!(data.attrs()[1].index, 4);
+}
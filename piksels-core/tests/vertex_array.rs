@@ -0,0 +1,353 @@
+use std::cell::RefCell;
+
+use piksels_backend::{
+  error::Error,
+  render_targets::{self, ColorAttachmentPoint, ColorType},
+  texture::Storage,
+  vertex::Vertex,
+  vertex_array::{
+    DataSelector, MapAccess, MemoryLayout, StepRate, VertexArrayData, VertexArrayUpdate,
+    VertexStream,
+  },
+};
+use piksels_core::{
+  streaming::StreamingVertexArray,
+  transient::{TransientKey, TransientPool},
+  vertex_array::{VertexArrayView, View},
+};
+
+mod common;
+
+use common::*;
+
+#[test]
+fn vertex_array_map_range() {
+  let device = dummy_device();
+  let vertex_array = device
+    .new_vertex_array(
+      VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+      VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+      Vec::new(),
+    )
+    .unwrap();
+
+  let mapped = vertex_array.map_range(DataSelector::InterleavedVertices, 64..128, MapAccess::Write);
+  assert!(mapped.is_ok());
+  drop(mapped);
+  assert_eq!(
+    LAST_MAP_RANGE.with(RefCell::take),
+    Some((DataSelector::InterleavedVertices, 64..128, MapAccess::Write))
+  );
+
+  let mapped = vertex_array.map_range(DataSelector::Indices, 16..16, MapAccess::Read);
+  assert!(matches!(
+    mapped,
+    Err(DummyBackendError::Common(Error::InvalidMapRange { .. }))
+  ));
+  assert_eq!(LAST_MAP_RANGE.with(RefCell::take), None);
+}
+
+#[test]
+fn vertex_array_partial_updates() {
+  let device = dummy_device();
+  let vertex_array = device
+    .new_vertex_array(
+      VertexArrayData::new(
+        Vec::new(),
+        MemoryLayout::Deinterleaved {
+          data_per_attr: Vec::new(),
+        },
+      ),
+      VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+      Vec::new(),
+    )
+    .unwrap();
+
+  vertex_array
+    .update(VertexArrayUpdate::vertex_attr(1, 12, &[1, 2, 3, 4]))
+    .unwrap();
+  assert_eq!(
+    LAST_VERTEX_ARRAY_UPDATE.with(RefCell::take),
+    Some((
+      DataSelector::DeinterleavedVertices { index: 1 },
+      12,
+      vec![1, 2, 3, 4]
+    ))
+  );
+
+  vertex_array
+    .update(VertexArrayUpdate::indices(
+      8,
+      &[u32::from_ne_bytes([5, 6, 7, 8])],
+    ))
+    .unwrap();
+  assert_eq!(
+    LAST_VERTEX_ARRAY_UPDATE.with(RefCell::take),
+    Some((DataSelector::Indices, 8, vec![5, 6, 7, 8]))
+  );
+
+  // empty updates never reach the backend
+  vertex_array
+    .update(VertexArrayUpdate::vertices(0, &[]))
+    .unwrap();
+  assert_eq!(LAST_VERTEX_ARRAY_UPDATE.with(RefCell::take), None);
+
+  assert!(matches!(
+    vertex_array.update(VertexArrayUpdate::new(DataSelector::Indices, 2, &[0; 4])),
+    Err(DummyBackendError::Common(
+      Error::InvalidVertexArrayUpdate { .. }
+    ))
+  ));
+  assert_eq!(LAST_VERTEX_ARRAY_UPDATE.with(RefCell::take), None);
+}
+
+#[derive(Clone, Copy, Debug, piksels_derive::Vertex)]
+#[repr(C)]
+struct LineVertex {
+  position: [f32; 2],
+}
+
+#[test]
+fn streaming_vertex_array() {
+  let device = dummy_device();
+  let mut stream = StreamingVertexArray::<_, LineVertex>::new(&device, 4, 2).unwrap();
+  let line = [
+    LineVertex { position: [0., 0.] },
+    LineVertex { position: [1., 1.] },
+    LineVertex { position: [2., 0.] },
+  ];
+
+  let view = stream.push(&line).unwrap();
+  assert_eq!((view.start_vertex(), view.vertex_count()), (0, 3));
+  assert_eq!(
+    LAST_UNMAPPED_BYTES.with(RefCell::take),
+    LineVertex::slice_as_bytes(&line)
+  );
+
+  let view = stream.push(&line[..1]).unwrap();
+  assert_eq!((view.start_vertex(), view.vertex_count()), (3, 1));
+  assert_eq!(
+    LAST_MAP_RANGE.with(RefCell::take),
+    Some((DataSelector::InterleavedVertices, 24..32, MapAccess::Write))
+  );
+
+  assert!(matches!(
+    stream.push(&line[..1]),
+    Err(DummyBackendError::Common(Error::StreamingOverflow { .. }))
+  ));
+  assert_eq!(stream.len(), 4);
+
+  stream.next_frame();
+  let view = stream.push(&line[..2]).unwrap();
+  assert_eq!((view.start_vertex(), view.vertex_count()), (4, 2));
+
+  let cmd_buf = device.new_cmd_buf().unwrap();
+  assert!(cmd_buf.draw_view(&view).is_ok());
+
+  // the ring wraps around
+  stream.next_frame();
+  let view = stream.push(&line[..2]).unwrap();
+  assert_eq!((view.start_vertex(), view.vertex_count()), (0, 2));
+}
+
+#[test]
+fn transient_pool() {
+  let device = dummy_device();
+  let mut pool = TransientPool::new();
+  let color = ColorType::IRGBA {
+    red_bits: render_targets::ChannelBits::Eight,
+    green_bits: render_targets::ChannelBits::Eight,
+    blue_bits: render_targets::ChannelBits::Eight,
+    alpha_bits: render_targets::ChannelBits::Eight,
+  };
+  let half = TransientKey::new(
+    Storage::Flat2D {
+      width: 400,
+      height: 300,
+    },
+    [ColorAttachmentPoint::new(0, "color", color)],
+    None,
+  );
+  let full = TransientKey::new(
+    Storage::Flat2D {
+      width: 800,
+      height: 600,
+    },
+    [ColorAttachmentPoint::new(0, "color", color)],
+    None,
+  );
+
+  let (blur_id, full_id) = {
+    let blur = pool.acquire(&device, &half).unwrap();
+    let bright = pool.acquire(&device, &half).unwrap();
+    let full = pool.acquire(&device, &full).unwrap();
+    assert_ne!(blur.id(), bright.id());
+    assert_eq!(
+      full.storage(),
+      Storage::Flat2D {
+        width: 800,
+        height: 600
+      }
+    );
+    (blur.id(), full.id())
+  };
+  assert_eq!(pool.free_len(), 3);
+
+  // released render targets are reused within the frame
+  let blur = pool.acquire(&device, &half).unwrap();
+  assert_eq!(blur.id(), blur_id);
+  assert_eq!(pool.free_len(), 2);
+  drop(blur);
+
+  // render targets not used during a frame are destroyed at the end of the next one
+  pool.end_frame(&device).unwrap();
+  assert_eq!(pool.free_len(), 3);
+  drop(pool.acquire(&device, &half).unwrap());
+  pool.end_frame(&device).unwrap();
+  assert_eq!(pool.free_len(), 1);
+  assert!(device.render_targets_by_id(&full_id).unwrap().is_none());
+
+  pool.clear(&device).unwrap();
+  assert_eq!(pool.free_len(), 0);
+}
+
+#[test]
+fn vertex_streams() {
+  let device = dummy_device();
+  let positions = VertexArrayData::deinterleaved()
+    .attr("position", &[[0f32; 3]; 4])
+    .build()
+    .unwrap();
+  let joints = VertexArrayData::deinterleaved()
+    .attr("joints", &[[0u32; 4]; 4])
+    .attr("weights", &[[0f32; 4]; 4])
+    .build()
+    .unwrap();
+  let offsets = VertexArrayData::deinterleaved()
+    .attr("offset", &[[0f32; 3]; 2])
+    .build()
+    .unwrap();
+
+  let vertex_array = device
+    .new_vertex_array_with_streams(
+      vec![
+        VertexStream::per_vertex(positions.clone()),
+        VertexStream::per_vertex(joints),
+        VertexStream::new(offsets.clone(), StepRate::PerInstance { divisor: 2 }),
+      ],
+      Vec::new(),
+    )
+    .unwrap();
+  assert_eq!(vertex_array.vertex_count(), 4);
+  assert_eq!(
+    LAST_VERTEX_STREAM_RATES.with(|rates| rates.borrow().clone()),
+    [
+      StepRate::PerVertex,
+      StepRate::PerVertex,
+      StepRate::PerInstance { divisor: 2 }
+    ]
+  );
+
+  // per-vertex streams must agree on the number of vertices
+  assert_eq!(
+    device
+      .new_vertex_array_with_streams(
+        vec![
+          VertexStream::per_vertex(positions.clone()),
+          VertexStream::per_vertex(offsets.clone()),
+        ],
+        Vec::new(),
+      )
+      .err(),
+    Some(DummyBackendError::Common(Error::InvalidVertexData {
+      reason: "stream 1 has 2 vertices, expected 4".to_owned()
+    }))
+  );
+
+  assert!(device
+    .new_vertex_array_with_streams(
+      vec![VertexStream::new(
+        offsets,
+        StepRate::PerInstance { divisor: 0 }
+      )],
+      Vec::new(),
+    )
+    .is_err());
+
+  // empty instance data doesn’t make a stream
+  device
+    .new_vertex_array(
+      positions,
+      VertexArrayData::new(Vec::new(), MemoryLayout::Interleaved { data: Vec::new() }),
+      Vec::new(),
+    )
+    .unwrap();
+  assert_eq!(
+    LAST_VERTEX_STREAM_RATES.with(|rates| rates.borrow().clone()),
+    [StepRate::PerVertex]
+  );
+}
+
+#[test]
+fn vertex_array_views() {
+  let device = dummy_device();
+  let positions = VertexArrayData::deinterleaved()
+    .attr("position", &[[0f32; 3]; 4])
+    .build()
+    .unwrap();
+  let offsets = VertexArrayData::deinterleaved()
+    .attr("offset", &[[0f32; 3]; 3])
+    .build()
+    .unwrap();
+
+  let vertex_array = device
+    .new_vertex_array_with_streams(
+      vec![VertexStream::per_vertex(positions.clone())],
+      Vec::new(),
+    )
+    .unwrap();
+  assert_eq!(vertex_array.instance_count(), None);
+
+  let range = |view: VertexArrayView<DummyBackend>| (view.start_vertex(), view.vertex_count());
+  assert_eq!(vertex_array.view(..).map(range), Ok((0, 4)));
+  assert_eq!(vertex_array.view(1..3).map(range), Ok((1, 2)));
+  assert_eq!(vertex_array.view(2..).map(range), Ok((2, 2)));
+  assert_eq!(vertex_array.view(..3).map(range), Ok((0, 3)));
+  assert_eq!(vertex_array.view(..=3).map(range), Ok((0, 4)));
+  assert_eq!(vertex_array.view(4..4).map(range), Ok((4, 0)));
+
+  assert_eq!(
+    vertex_array.view(2..5).err(),
+    Some(DummyBackendError::Common(Error::InvalidVertexArrayView {
+      reason: "vertex range 2..5 is out of bounds (count is 4)".to_owned()
+    }))
+  );
+  assert!(vertex_array.view(..=4).is_err());
+  assert!(vertex_array.view(5..).is_err());
+
+  // without per-instance data, instance ranges must be bounded
+  let view = vertex_array.view(..).unwrap();
+  assert_eq!((view.start_instance(), view.instance_count()), (0, 1));
+  let view = view.with_instances(10..42).unwrap();
+  assert_eq!((view.start_instance(), view.instance_count()), (10, 32));
+  assert!(vertex_array.view(..).unwrap().with_instances(1..).is_err());
+
+  // with per-instance data, instance ranges must fit in it
+  let vertex_array = device
+    .new_vertex_array_with_streams(
+      vec![
+        VertexStream::per_vertex(positions),
+        VertexStream::new(offsets, StepRate::PerInstance { divisor: 2 }),
+      ],
+      Vec::new(),
+    )
+    .unwrap();
+  assert_eq!(vertex_array.instance_count(), Some(6));
+
+  let view = vertex_array.view(..).unwrap().with_instances(2..).unwrap();
+  assert_eq!((view.start_instance(), view.instance_count()), (2, 4));
+  assert!(vertex_array.view(..).unwrap().with_instances(..7).is_err());
+
+  let cmd_buf = device.new_cmd_buf().unwrap();
+  assert!(cmd_buf.draw_view(&view).is_ok());
+}
@@ -0,0 +1,240 @@
+//! Derive macros for piksels.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, spanned::Spanned, Data, DeriveInput, Error, Fields, LitStr};
+
+/// Derive `piksels_backend::vertex::Vertex` for a struct.
+///
+/// The struct must be `#[repr(C)]`, have named fields and no padding. Each field becomes a vertex attribute, indexed
+/// by its position in the struct; matrix fields use one index per column, shifting the index of the next fields
+/// accordingly. Fields can be annotated with:
+///
+/// - `#[vertex(name = "…")]` to use a different attribute name than the field name.
+/// - `#[vertex(normalized)]` to normalize integral attributes.
+#[proc_macro_derive(Vertex, attributes(vertex))]
+pub fn derive_vertex(input: TokenStream) -> TokenStream {
+  let input = parse_macro_input!(input as DeriveInput);
+  derive_vertex_impl(input)
+    .unwrap_or_else(Error::into_compile_error)
+    .into()
+}
+
+fn derive_vertex_impl(input: DeriveInput) -> Result<TokenStream2, Error> {
+  let ident = &input.ident;
+
+  if !input.generics.params.is_empty() {
+    return Err(Error::new(
+      input.generics.span(),
+      "vertex types cannot be generic",
+    ));
+  }
+
+  if !has_repr_c(&input) {
+    return Err(Error::new(ident.span(), "vertex types must be #[repr(C)]"));
+  }
+
+  let fields = match input.data {
+    Data::Struct(ref data) => match data.fields {
+      Fields::Named(ref fields) => &fields.named,
+      _ => {
+        return Err(Error::new(
+          ident.span(),
+          "vertex types must have named fields",
+        ))
+      }
+    },
+    _ => return Err(Error::new(ident.span(), "vertex types must be structs")),
+  };
+
+  let mut attrs = Vec::with_capacity(fields.len());
+  let mut field_tys = Vec::with_capacity(fields.len());
+
+  for field in fields {
+    let field_ty = &field.ty;
+    let mut name = field
+      .ident
+      .as_ref()
+      .map(ToString::to_string)
+      .unwrap_or_default();
+    let mut normalized = false;
+
+    for attr in field
+      .attrs
+      .iter()
+      .filter(|attr| attr.path().is_ident("vertex"))
+    {
+      attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("normalized") {
+          normalized = true;
+          Ok(())
+        } else if meta.path.is_ident("name") {
+          name = meta.value()?.parse::<LitStr>()?.value();
+          Ok(())
+        } else {
+          Err(meta.error("unknown vertex attribute; expected `name` or `normalized`"))
+        }
+      })?;
+    }
+
+    let ty = if normalized {
+      quote! { <#field_ty as ::piksels_backend::vertex::VertexAttrType>::TYPE.normalize() }
+    } else {
+      quote! { <#field_ty as ::piksels_backend::vertex::VertexAttrType>::TYPE }
+    };
+
+    let index = quote! {
+      0 #(+ <#field_tys as ::piksels_backend::vertex::VertexAttrType>::TYPE.locations())*
+    };
+
+    attrs.push(quote! {
+      ::piksels_backend::vertex::VertexAttr {
+        index: #index,
+        name: #name,
+        ty: #ty,
+        array: None,
+      }
+    });
+    field_tys.push(field_ty);
+  }
+
+  let padding_msg = format!("{ident} has padding; reorder or resize its fields");
+  let attr_size_msgs = field_tys.iter().map(|field_ty| {
+    format!(
+      "the size of {} doesn’t match its vertex attribute type",
+      quote!(#field_ty)
+    )
+  });
+
+  Ok(quote! {
+    const _: () = assert!(
+      ::std::mem::size_of::<#ident>() == 0 #(+ ::std::mem::size_of::<#field_tys>())*,
+      #padding_msg
+    );
+
+    #(
+      const _: () = assert!(
+        ::std::mem::size_of::<#field_tys>()
+          == <#field_tys as ::piksels_backend::vertex::VertexAttrType>::TYPE.size(),
+        #attr_size_msgs
+      );
+    )*
+
+    unsafe impl ::piksels_backend::vertex::Vertex for #ident {
+      fn vertex_attrs() -> ::std::vec::Vec<::piksels_backend::vertex::VertexAttr> {
+        ::std::vec![#(#attrs),*]
+      }
+    }
+  })
+}
+
+/// Derive `piksels_backend::uniform_block::UniformBlock` for a struct.
+///
+/// The struct must have named fields, all implementing `piksels_backend::uniform_block::Std140`. Fields are laid out
+/// in declaration order according to the std140 rules; the layout of the Rust struct itself doesn’t matter.
+#[proc_macro_derive(UniformBlock)]
+pub fn derive_uniform_block(input: TokenStream) -> TokenStream {
+  let input = parse_macro_input!(input as DeriveInput);
+  derive_uniform_block_impl(input)
+    .unwrap_or_else(Error::into_compile_error)
+    .into()
+}
+
+fn derive_uniform_block_impl(input: DeriveInput) -> Result<TokenStream2, Error> {
+  let ident = &input.ident;
+
+  if !input.generics.params.is_empty() {
+    return Err(Error::new(
+      input.generics.span(),
+      "uniform blocks cannot be generic",
+    ));
+  }
+
+  let fields = match input.data {
+    Data::Struct(ref data) => match data.fields {
+      Fields::Named(ref fields) => &fields.named,
+      _ => {
+        return Err(Error::new(
+          ident.span(),
+          "uniform blocks must have named fields",
+        ))
+      }
+    },
+    _ => return Err(Error::new(ident.span(), "uniform blocks must be structs")),
+  };
+
+  if fields.is_empty() {
+    return Err(Error::new(ident.span(), "uniform blocks cannot be empty"));
+  }
+
+  let field_idents = fields.iter().map(|field| &field.ident).collect::<Vec<_>>();
+  let field_names = field_idents
+    .iter()
+    .map(|ident| ident.as_ref().map(ToString::to_string).unwrap_or_default());
+  let field_tys = fields.iter().map(|field| &field.ty).collect::<Vec<_>>();
+
+  Ok(quote! {
+    impl ::piksels_backend::uniform_block::Std140 for #ident {
+      // structs are aligned like a vec4
+      const ALIGN: usize = 16;
+
+      const SIZE: usize = {
+        let mut offset = 0;
+        #(
+          offset = ::piksels_backend::uniform_block::align_up(
+            offset,
+            <#field_tys as ::piksels_backend::uniform_block::Std140>::ALIGN,
+          ) + <#field_tys as ::piksels_backend::uniform_block::Std140>::SIZE;
+        )*
+        ::piksels_backend::uniform_block::align_up(offset, 16)
+      };
+
+      fn write_std140(&self, bytes: &mut [u8]) {
+        let mut offset = 0;
+        #(
+          offset = ::piksels_backend::uniform_block::align_up(
+            offset,
+            <#field_tys as ::piksels_backend::uniform_block::Std140>::ALIGN,
+          );
+          ::piksels_backend::uniform_block::Std140::write_std140(&self.#field_idents, &mut bytes[offset..]);
+          offset += <#field_tys as ::piksels_backend::uniform_block::Std140>::SIZE;
+        )*
+        let _ = offset;
+      }
+    }
+
+    impl ::piksels_backend::uniform_block::UniformBlock for #ident {
+      fn field_offsets() -> ::std::vec::Vec<(&'static str, usize)> {
+        let mut offsets = ::std::vec::Vec::new();
+        let mut offset = 0;
+        #(
+          offset = ::piksels_backend::uniform_block::align_up(
+            offset,
+            <#field_tys as ::piksels_backend::uniform_block::Std140>::ALIGN,
+          );
+          offsets.push((#field_names, offset));
+          offset += <#field_tys as ::piksels_backend::uniform_block::Std140>::SIZE;
+        )*
+        let _ = offset;
+        offsets
+      }
+    }
+  })
+}
+
+/// Check whether a type is `#[repr(C)]`.
+fn has_repr_c(input: &DeriveInput) -> bool {
+  input
+    .attrs
+    .iter()
+    .filter(|attr| attr.path().is_ident("repr"))
+    .any(|attr| {
+      let mut repr_c = false;
+      let _ = attr.parse_nested_meta(|meta| {
+        repr_c |= meta.path.is_ident("C");
+        Ok(())
+      });
+      repr_c
+    })
+}
@@ -0,0 +1,209 @@
+//! Bloom.
+//!
+//! [`Bloom`] makes the bright parts of a frame bleed into their surroundings. The bright parts are extracted into a
+//! half-resolution buffer, blurred with a separable gaussian kernel, and added back to the frame.
+//!
+//! piksels has no floating-point color attachments yet, so the intermediate buffers use 16-bit channels.
+
+use std::collections::HashSet;
+
+use piksels_backend::{
+  render_targets::{ChannelBits, ColorAttachmentPoint, ColorType},
+  shader::UniformTypeBase,
+  texture::Storage,
+  Backend,
+};
+use piksels_core::{
+  cmd_buf::CmdBuf,
+  device::Device,
+  render_targets::{ColorAttachment, RenderTargets},
+  shader::Uniform,
+};
+
+use crate::fullscreen::FullscreenPass;
+
+const BRIGHT_FRAGMENT_STAGE: &str = r#"#version 330 core
+
+uniform sampler2D source;
+uniform float threshold;
+
+in vec2 v_uv;
+
+out vec4 frag;
+
+void main() {
+  vec3 color = texture(source, v_uv).rgb;
+  float luma = dot(color, vec3(.2126, .7152, .0722));
+  frag = vec4(color * max(luma - threshold, 0.) / max(luma, 1e-4), 1.);
+}
+"#;
+
+const BLUR_FRAGMENT_STAGE: &str = r#"#version 330 core
+
+uniform sampler2D source;
+uniform vec2 direction;
+
+in vec2 v_uv;
+
+out vec4 frag;
+
+const float weights[5] = float[](.227027, .1945946, .1216216, .054054, .016216);
+
+void main() {
+  vec3 color = texture(source, v_uv).rgb * weights[0];
+
+  for (int i = 1; i < 5; ++i) {
+    color += texture(source, v_uv + direction * float(i)).rgb * weights[i];
+    color += texture(source, v_uv - direction * float(i)).rgb * weights[i];
+  }
+
+  frag = vec4(color, 1.);
+}
+"#;
+
+const COMPOSITE_FRAGMENT_STAGE: &str = r#"#version 330 core
+
+uniform sampler2D source;
+uniform sampler2D bloom;
+uniform float intensity;
+
+in vec2 v_uv;
+
+out vec4 frag;
+
+void main() {
+  frag = vec4(texture(source, v_uv).rgb + texture(bloom, v_uv).rgb * intensity, 1.);
+}
+"#;
+
+/// Color type of the intermediate buffers.
+const BUFFER_COLOR_TYPE: ColorType = ColorType::IRGBA {
+  red_bits: ChannelBits::Sixteen,
+  green_bits: ChannelBits::Sixteen,
+  blue_bits: ChannelBits::Sixteen,
+  alpha_bits: ChannelBits::Sixteen,
+};
+
+/// Bloom parameters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BloomParams {
+  /// Luminance above which colors bloom.
+  pub threshold: f32,
+
+  /// Factor applied to the bloom when adding it back to the frame.
+  pub intensity: f32,
+
+  /// Number of horizontal and vertical blur passes; the more passes, the wider the bloom.
+  pub blur_passes: usize,
+}
+
+impl Default for BloomParams {
+  fn default() -> Self {
+    Self {
+      threshold: 1.,
+      intensity: 0.8,
+      blur_passes: 2,
+    }
+  }
+}
+
+/// Bloom effect.
+#[derive(Debug)]
+pub struct Bloom<B>
+where
+  B: Backend,
+{
+  bright: FullscreenPass<B, 1>,
+  threshold: Uniform<B>,
+  blur: FullscreenPass<B, 1>,
+  direction: Uniform<B>,
+  composite: FullscreenPass<B, 2>,
+  intensity: Uniform<B>,
+  buffers: [(RenderTargets<B>, ColorAttachment<B>); 2],
+  texel_size: [f32; 2],
+}
+
+impl<B> Bloom<B>
+where
+  B: Backend,
+{
+  /// Create a bloom effect for frames of `width × height` texels.
+  pub fn new(device: &Device<B>, width: u32, height: u32) -> Result<Self, B::Err> {
+    let bright = FullscreenPass::new(device, BRIGHT_FRAGMENT_STAGE, ["source"])?;
+    let threshold = bright
+      .shader()
+      .uniform("threshold", UniformTypeBase::Float)?;
+    let blur = FullscreenPass::new(device, BLUR_FRAGMENT_STAGE, ["source"])?;
+    let direction = blur
+      .shader()
+      .uniform("direction", UniformTypeBase::Float2)?;
+    let composite = FullscreenPass::new(device, COMPOSITE_FRAGMENT_STAGE, ["source", "bloom"])?;
+    let intensity = composite
+      .shader()
+      .uniform("intensity", UniformTypeBase::Float)?;
+
+    // bloom is computed at half resolution
+    let (width, height) = ((width / 2).max(1), (height / 2).max(1));
+    let storage = Storage::Flat2D { width, height };
+    let buffer = || {
+      let render_targets = device.new_render_targets(
+        HashSet::from([ColorAttachmentPoint::new(0, "color", BUFFER_COLOR_TYPE)]),
+        None,
+        storage,
+      )?;
+      let color_attachment = render_targets.color_attachment(0)?;
+      Ok::<_, B::Err>((render_targets, color_attachment))
+    };
+    let buffers = [buffer()?, buffer()?];
+    let texel_size = [1. / width as f32, 1. / height as f32];
+
+    Ok(Self {
+      bright,
+      threshold,
+      blur,
+      direction,
+      composite,
+      intensity,
+      buffers,
+      texel_size,
+    })
+  }
+
+  /// Apply bloom to `input`, writing the result into `output`.
+  pub fn apply(
+    &self,
+    cmd_buf: &CmdBuf<B>,
+    input: &ColorAttachment<B>,
+    output: &RenderTargets<B>,
+    params: BloomParams,
+  ) -> Result<(), B::Err> {
+    let [(ping, ping_color), (pong, pong_color)] = &self.buffers;
+
+    self.bright.draw(cmd_buf, ping, [input], |cmd_buf| {
+      cmd_buf.uniform(&self.threshold, &params.threshold)?;
+      Ok(())
+    })?;
+
+    let [texel_width, texel_height] = self.texel_size;
+    for _ in 0..params.blur_passes {
+      let horizontal = [texel_width, 0.];
+      self.blur.draw(cmd_buf, pong, [ping_color], |cmd_buf| {
+        cmd_buf.uniform(&self.direction, &horizontal)?;
+        Ok(())
+      })?;
+
+      let vertical = [0., texel_height];
+      self.blur.draw(cmd_buf, ping, [pong_color], |cmd_buf| {
+        cmd_buf.uniform(&self.direction, &vertical)?;
+        Ok(())
+      })?;
+    }
+
+    self
+      .composite
+      .draw(cmd_buf, output, [input, ping_color], |cmd_buf| {
+        cmd_buf.uniform(&self.intensity, &params.intensity)?;
+        Ok(())
+      })
+  }
+}
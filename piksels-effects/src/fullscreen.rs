@@ -0,0 +1,144 @@
+//! Fullscreen passes.
+//!
+//! A [`FullscreenPass`] runs a fragment shader over every texel of some render targets, by drawing a single triangle
+//! covering them. This is the building block of all the effects of this crate.
+
+use piksels_backend::{shader::ShaderSources, viewport::Viewport, Backend};
+use piksels_core::{
+  cmd_buf::CmdBuf,
+  device::Device,
+  render_targets::{ColorAttachment, RenderTargets},
+  shader::{Shader, ShaderTextureBindingPoint},
+  texture::{Texture, TextureBindingPoint},
+  vertex_array::VertexArray,
+};
+
+/// Vertex stage of fullscreen passes.
+///
+/// It generates a triangle covering the whole clip space from the vertex indices, and exposes the texture
+/// coordinates of the fragment being shaded as `v_uv`.
+pub const VERTEX_STAGE: &str = r#"#version 330 core
+
+out vec2 v_uv;
+
+void main() {
+  vec2 co = vec2(float((gl_VertexID & 1) << 2) - 1., float((gl_VertexID & 2) << 1) - 1.);
+  v_uv = co * .5 + .5;
+  gl_Position = vec4(co, 0., 1.);
+}
+"#;
+
+/// Resources a fullscreen pass can sample.
+pub trait PassInput<B>
+where
+  B: Backend,
+{
+  /// Bind the input to a texture binding point.
+  fn bind(&self, cmd_buf: &CmdBuf<B>, binding_point: &TextureBindingPoint<B>)
+    -> Result<(), B::Err>;
+}
+
+impl<B> PassInput<B> for ColorAttachment<B>
+where
+  B: Backend,
+{
+  fn bind(
+    &self,
+    cmd_buf: &CmdBuf<B>,
+    binding_point: &TextureBindingPoint<B>,
+  ) -> Result<(), B::Err> {
+    cmd_buf.use_color_attachment(self, binding_point)?;
+    Ok(())
+  }
+}
+
+impl<B> PassInput<B> for Texture<B>
+where
+  B: Backend,
+{
+  fn bind(
+    &self,
+    cmd_buf: &CmdBuf<B>,
+    binding_point: &TextureBindingPoint<B>,
+  ) -> Result<(), B::Err> {
+    cmd_buf.use_texture(self, binding_point)?;
+    Ok(())
+  }
+}
+
+/// A fragment shader run over the whole render targets, sampling `N` inputs.
+#[derive(Debug)]
+pub struct FullscreenPass<B, const N: usize>
+where
+  B: Backend,
+{
+  shader: Shader<B>,
+  triangle: VertexArray<B>,
+  inputs: Vec<(TextureBindingPoint<B>, ShaderTextureBindingPoint<B>)>,
+}
+
+impl<B, const N: usize> FullscreenPass<B, N>
+where
+  B: Backend,
+{
+  /// Create a fullscreen pass.
+  ///
+  /// `inputs` are the names of the samplers of `fragment_stage`, which are bound to the texture binding points `0`
+  /// to `N - 1`, in order.
+  pub fn new(device: &Device<B>, fragment_stage: &str, inputs: [&str; N]) -> Result<Self, B::Err> {
+    let shader = device.new_shader(
+      ShaderSources::default()
+        .vertex(VERTEX_STAGE)
+        .fragment(fragment_stage),
+    )?;
+
+    let triangle = device.new_empty_vertex_array(3)?;
+
+    let inputs = inputs
+      .iter()
+      .enumerate()
+      .map(|(index, name)| {
+        Ok((
+          device.get_texture_binding_point(index)?,
+          shader.texture_binding_point(name)?,
+        ))
+      })
+      .collect::<Result<_, B::Err>>()?;
+
+    Ok(Self {
+      shader,
+      triangle,
+      inputs,
+    })
+  }
+
+  pub fn shader(&self) -> &Shader<B> {
+    &self.shader
+  }
+
+  /// Run the pass into `output`, sampling `inputs`.
+  ///
+  /// `uniforms` is called once the shader is bound, to set the uniforms of the pass.
+  pub fn draw(
+    &self,
+    cmd_buf: &CmdBuf<B>,
+    output: &RenderTargets<B>,
+    inputs: [&dyn PassInput<B>; N],
+    uniforms: impl FnOnce(&CmdBuf<B>) -> Result<(), B::Err>,
+  ) -> Result<(), B::Err> {
+    cmd_buf
+      .render_targets(output)?
+      .viewport(Viewport::Whole)?
+      .shader(&self.shader)?;
+
+    for ((binding_point, shader_binding_point), input) in self.inputs.iter().zip(inputs) {
+      cmd_buf.associate_texture(binding_point, shader_binding_point)?;
+      input.bind(cmd_buf, binding_point)?;
+    }
+
+    uniforms(cmd_buf)?;
+    cmd_buf.draw(&self.triangle)?;
+
+    Ok(())
+  }
+}
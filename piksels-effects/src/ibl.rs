@@ -0,0 +1,337 @@
+//! Image-based lighting.
+//!
+//! [`IblBaker`] convolves an environment cubemap into the two cubemaps needed by image-based lighting: an irradiance
+//! cubemap for diffuse lighting, and a prefiltered cubemap for specular lighting, which mipmap levels hold the
+//! environment convolved with increasing roughness.
+//!
+//! Render targets cannot be attached to a face or a mipmap level of a texture yet, so every face of every level is
+//! rendered into intermediate render targets, read back, and uploaded into the destination cubemap. This requires the
+//! `ext-readback` feature, and limits the baked cubemaps to 8-bit channels.
+
+use std::collections::HashSet;
+
+use piksels_backend::{
+  extension::readback::BackendReadback,
+  pixel::{ChannelBits, Format, Pixel, Type},
+  render_targets::{self, ColorAttachmentPoint, ColorType},
+  shader::UniformTypeBase,
+  texture::{CubeFace, MagFilter, MinFilter, Offset, Rect, Sampling, Size, Storage, Wrap},
+};
+use piksels_core::{
+  cmd_buf::CmdBuf, device::Device, render_targets::RenderTargets, shader::Uniform, texture::Texture,
+};
+
+use crate::fullscreen::FullscreenPass;
+
+/// Direction of the texel being shaded, from the face index and the texture coordinates.
+const FACE_DIRECTION: &str = r#"
+uniform int face;
+
+vec3 face_direction(vec2 uv) {
+  vec2 st = uv * 2. - 1.;
+
+  switch (face) {
+    case 0: return normalize(vec3(1., -st.y, -st.x));
+    case 1: return normalize(vec3(-1., -st.y, st.x));
+    case 2: return normalize(vec3(st.x, 1., st.y));
+    case 3: return normalize(vec3(st.x, -1., -st.y));
+    case 4: return normalize(vec3(st.x, -st.y, 1.));
+    default: return normalize(vec3(-st.x, -st.y, -1.));
+  }
+}
+"#;
+
+const IRRADIANCE_FRAGMENT_STAGE: &str = r#"
+const float PI = 3.14159265359;
+
+uniform samplerCube environment;
+
+in vec2 v_uv;
+
+out vec4 frag;
+
+void main() {
+  vec3 n = face_direction(v_uv);
+  vec3 up = abs(n.y) < .999 ? vec3(0., 1., 0.) : vec3(1., 0., 0.);
+  vec3 right = normalize(cross(up, n));
+  up = cross(n, right);
+
+  vec3 irradiance = vec3(0.);
+  float samples = 0.;
+
+  for (float phi = 0.; phi < 2. * PI; phi += .025) {
+    for (float theta = 0.; theta < .5 * PI; theta += .025) {
+      vec3 t = vec3(sin(theta) * cos(phi), sin(theta) * sin(phi), cos(theta));
+      irradiance += texture(environment, t.x * right + t.y * up + t.z * n).rgb * cos(theta) * sin(theta);
+      samples += 1.;
+    }
+  }
+
+  frag = vec4(PI * irradiance / samples, 1.);
+}
+"#;
+
+const PREFILTER_FRAGMENT_STAGE: &str = r#"
+const float PI = 3.14159265359;
+const uint SAMPLES = 1024u;
+
+uniform samplerCube environment;
+uniform float roughness;
+
+in vec2 v_uv;
+
+out vec4 frag;
+
+float radical_inverse(uint bits) {
+  bits = (bits << 16u) | (bits >> 16u);
+  bits = ((bits & 0x55555555u) << 1u) | ((bits & 0xAAAAAAAAu) >> 1u);
+  bits = ((bits & 0x33333333u) << 2u) | ((bits & 0xCCCCCCCCu) >> 2u);
+  bits = ((bits & 0x0F0F0F0Fu) << 4u) | ((bits & 0xF0F0F0F0u) >> 4u);
+  bits = ((bits & 0x00FF00FFu) << 8u) | ((bits & 0xFF00FF00u) >> 8u);
+  return float(bits) * 2.3283064365386963e-10;
+}
+
+vec3 importance_sample_ggx(uint i, vec3 n) {
+  vec2 xi = vec2(float(i) / float(SAMPLES), radical_inverse(i));
+  float a = roughness * roughness;
+  float phi = 2. * PI * xi.x;
+  float cos_theta = sqrt((1. - xi.y) / (1. + (a * a - 1.) * xi.y));
+  float sin_theta = sqrt(1. - cos_theta * cos_theta);
+
+  vec3 up = abs(n.z) < .999 ? vec3(0., 0., 1.) : vec3(1., 0., 0.);
+  vec3 tangent = normalize(cross(up, n));
+  vec3 bitangent = cross(n, tangent);
+
+  return normalize(tangent * cos(phi) * sin_theta + bitangent * sin(phi) * sin_theta + n * cos_theta);
+}
+
+void main() {
+  vec3 n = face_direction(v_uv);
+  vec3 color = vec3(0.);
+  float weight = 0.;
+
+  for (uint i = 0u; i < SAMPLES; ++i) {
+    vec3 h = importance_sample_ggx(i, n);
+    vec3 l = normalize(2. * dot(n, h) * h - n);
+    float n_dot_l = dot(n, l);
+
+    if (n_dot_l > 0.) {
+      color += texture(environment, l).rgb * n_dot_l;
+      weight += n_dot_l;
+    }
+  }
+
+  frag = vec4(color / max(weight, 1e-4), 1.);
+}
+"#;
+
+/// Faces of a cubemap, in the order expected by [`FACE_DIRECTION`].
+const FACES: [CubeFace; 6] = [
+  CubeFace::PosX,
+  CubeFace::NegX,
+  CubeFace::PosY,
+  CubeFace::NegY,
+  CubeFace::PosZ,
+  CubeFace::NegZ,
+];
+
+/// Pixel of the baked cubemaps; it must match the format of readbacks.
+const PIXEL: Pixel = Pixel {
+  encoding: Type::NormUnsigned,
+  format: Format::RGBA(
+    ChannelBits::Eight,
+    ChannelBits::Eight,
+    ChannelBits::Eight,
+    ChannelBits::Eight,
+  ),
+};
+
+const COLOR_TYPE: ColorType = ColorType::IRGBA {
+  red_bits: render_targets::ChannelBits::Eight,
+  green_bits: render_targets::ChannelBits::Eight,
+  blue_bits: render_targets::ChannelBits::Eight,
+  alpha_bits: render_targets::ChannelBits::Eight,
+};
+
+/// Convolution of environment cubemaps for image-based lighting.
+#[derive(Debug)]
+pub struct IblBaker<B>
+where
+  B: BackendReadback,
+{
+  irradiance: FullscreenPass<B, 1>,
+  irradiance_face: Uniform<B>,
+  prefilter: FullscreenPass<B, 1>,
+  prefilter_face: Uniform<B>,
+  roughness: Uniform<B>,
+}
+
+impl<B> IblBaker<B>
+where
+  B: BackendReadback,
+{
+  pub fn new(device: &Device<B>) -> Result<Self, B::Err> {
+    let irradiance = FullscreenPass::new(
+      device,
+      &fragment_stage(IRRADIANCE_FRAGMENT_STAGE),
+      ["environment"],
+    )?;
+    let irradiance_face = irradiance.shader().uniform("face", UniformTypeBase::Int)?;
+    let prefilter = FullscreenPass::new(
+      device,
+      &fragment_stage(PREFILTER_FRAGMENT_STAGE),
+      ["environment"],
+    )?;
+    let prefilter_face = prefilter.shader().uniform("face", UniformTypeBase::Int)?;
+    let roughness = prefilter
+      .shader()
+      .uniform("roughness", UniformTypeBase::Float)?;
+
+    Ok(Self {
+      irradiance,
+      irradiance_face,
+      prefilter,
+      prefilter_face,
+      roughness,
+    })
+  }
+
+  /// Convolve `environment` into an irradiance cubemap of `size × size` faces.
+  pub fn irradiance(
+    &self,
+    device: &Device<B>,
+    environment: &Texture<B>,
+    size: u32,
+  ) -> Result<Texture<B>, B::Err> {
+    let cubemap = new_cubemap(device, size, MinFilter::Linear)?;
+    bake(
+      device,
+      &self.irradiance,
+      &self.irradiance_face,
+      environment,
+      &cubemap,
+      0,
+      |_| Ok(()),
+    )?;
+
+    Ok(cubemap)
+  }
+
+  /// Convolve `environment` into a prefiltered cubemap of `size × size` faces.
+  ///
+  /// Roughness goes linearly from `0` at the first mipmap level to `1` at the last one.
+  pub fn prefiltered(
+    &self,
+    device: &Device<B>,
+    environment: &Texture<B>,
+    size: u32,
+  ) -> Result<Texture<B>, B::Err> {
+    let cubemap = new_cubemap(device, size, MinFilter::LinearMipmapLinear)?;
+    let levels = cubemap.storage().levels();
+
+    for level in 0..levels {
+      let roughness = level as f32 / (levels - 1).max(1) as f32;
+      bake(
+        device,
+        &self.prefilter,
+        &self.prefilter_face,
+        environment,
+        &cubemap,
+        level,
+        |cmd_buf| {
+          cmd_buf.uniform(&self.roughness, &roughness)?;
+          Ok(())
+        },
+      )?;
+    }
+
+    Ok(cubemap)
+  }
+}
+
+/// Render the six faces of a mipmap level of `cubemap` with a pass.
+///
+/// `uniforms` sets the uniforms of the pass besides the face.
+fn bake<B>(
+  device: &Device<B>,
+  pass: &FullscreenPass<B, 1>,
+  face_uniform: &Uniform<B>,
+  environment: &Texture<B>,
+  cubemap: &Texture<B>,
+  level: usize,
+  uniforms: impl Fn(&CmdBuf<B>) -> Result<(), B::Err>,
+) -> Result<(), B::Err>
+where
+  B: BackendReadback,
+{
+  let size = match cubemap.storage() {
+    Storage::FlatCubemap { size } => (size >> level).max(1),
+    storage => unreachable!("baking into {storage:?}"),
+  };
+  let render_targets = new_face_render_targets(device, size)?;
+  let mut texels = vec![0; size as usize * size as usize * 4];
+
+  for (index, face) in FACES.into_iter().enumerate() {
+    let face_index = index as i32;
+    let cmd_buf = device.new_cmd_buf()?;
+
+    pass.draw(&cmd_buf, &render_targets, [environment], |cmd_buf| {
+      cmd_buf.uniform(face_uniform, &face_index)?;
+      uniforms(cmd_buf)
+    })?;
+    let readback = cmd_buf.read_color_attachment(&render_targets, 0)?;
+    cmd_buf.finish()?;
+    readback.read(&mut texels)?;
+    device.drop_cmd_buf(cmd_buf)?;
+
+    cubemap.set(
+      Rect::new(Offset::Cubemap { x: 0, y: 0, face }, Size::Cubemap { size }),
+      false,
+      level,
+      &texels,
+    )?;
+  }
+
+  device.drop_render_targets(render_targets)
+}
+
+/// Prepend the GLSL version and the face direction helper to a fragment stage.
+fn fragment_stage(body: &str) -> String {
+  format!("#version 330 core\n{FACE_DIRECTION}{body}")
+}
+
+fn new_cubemap<B>(
+  device: &Device<B>,
+  size: u32,
+  min_filter: MinFilter,
+) -> Result<Texture<B>, B::Err>
+where
+  B: BackendReadback,
+{
+  device.new_texture(
+    Storage::FlatCubemap { size },
+    PIXEL,
+    Sampling {
+      wrap_r: Wrap::ClampToEdge,
+      wrap_s: Wrap::ClampToEdge,
+      wrap_t: Wrap::ClampToEdge,
+      min_filter,
+      mag_filter: MagFilter::Linear,
+      depth_comparison: None,
+    },
+  )
+}
+
+fn new_face_render_targets<B>(device: &Device<B>, size: u32) -> Result<RenderTargets<B>, B::Err>
+where
+  B: BackendReadback,
+{
+  device.new_render_targets(
+    HashSet::from([ColorAttachmentPoint::new(0, "color", COLOR_TYPE)]),
+    None,
+    Storage::Flat2D {
+      width: size,
+      height: size,
+    },
+  )
+}
@@ -0,0 +1,13 @@
+//! Reference post effects for piksels.
+//!
+//! The effects of this crate are implemented purely against the public API of `piksels-core`: they are both drop-in
+//! building blocks and a check that the API is sufficient to write real effects. Each effect reads the color
+//! attachment of some render targets and writes into other render targets, so effects can be chained.
+//!
+//! Shaders are written in GLSL 3.30, and hence require a backend accepting such sources.
+
+pub mod bloom;
+pub mod fullscreen;
+#[cfg(feature = "ext-readback")]
+pub mod ibl;
+pub mod tonemap;
@@ -0,0 +1,116 @@
+//! Tonemapping.
+//!
+//! [`Tonemap`] maps the high dynamic range colors of a frame to displayable colors, after scaling them by an exposure.
+
+use piksels_backend::{shader::UniformTypeBase, Backend};
+use piksels_core::{
+  cmd_buf::CmdBuf,
+  device::Device,
+  render_targets::{ColorAttachment, RenderTargets},
+  shader::Uniform,
+};
+
+use crate::fullscreen::FullscreenPass;
+
+const FRAGMENT_STAGE: &str = r#"#version 330 core
+
+uniform sampler2D source;
+uniform float exposure;
+uniform int operator;
+
+in vec2 v_uv;
+
+out vec4 frag;
+
+vec3 reinhard(vec3 color) {
+  return color / (1. + color);
+}
+
+// Krzysztof Narkowicz’s fit of the ACES filmic curve
+vec3 aces(vec3 color) {
+  return clamp((color * (2.51 * color + .03)) / (color * (2.43 * color + .59) + .14), 0., 1.);
+}
+
+void main() {
+  vec3 color = texture(source, v_uv).rgb * exposure;
+  frag = vec4(operator == 0 ? reinhard(color) : aces(color), 1.);
+}
+"#;
+
+/// Tonemapping operator.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Operator {
+  /// Reinhard operator; simple, but washes out highlights.
+  Reinhard,
+
+  /// Fit of the ACES filmic curve.
+  #[default]
+  Aces,
+}
+
+/// Tonemapping parameters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TonemapParams {
+  /// Factor applied to colors before tonemapping them.
+  pub exposure: f32,
+
+  pub operator: Operator,
+}
+
+impl Default for TonemapParams {
+  fn default() -> Self {
+    Self {
+      exposure: 1.,
+      operator: Operator::default(),
+    }
+  }
+}
+
+/// Tonemapping effect.
+#[derive(Debug)]
+pub struct Tonemap<B>
+where
+  B: Backend,
+{
+  pass: FullscreenPass<B, 1>,
+  exposure: Uniform<B>,
+  operator: Uniform<B>,
+}
+
+impl<B> Tonemap<B>
+where
+  B: Backend,
+{
+  pub fn new(device: &Device<B>) -> Result<Self, B::Err> {
+    let pass = FullscreenPass::new(device, FRAGMENT_STAGE, ["source"])?;
+    let exposure = pass.shader().uniform("exposure", UniformTypeBase::Float)?;
+    let operator = pass.shader().uniform("operator", UniformTypeBase::Int)?;
+
+    Ok(Self {
+      pass,
+      exposure,
+      operator,
+    })
+  }
+
+  /// Tonemap `input` into `output`.
+  pub fn apply(
+    &self,
+    cmd_buf: &CmdBuf<B>,
+    input: &ColorAttachment<B>,
+    output: &RenderTargets<B>,
+    params: TonemapParams,
+  ) -> Result<(), B::Err> {
+    let operator: i32 = match params.operator {
+      Operator::Reinhard => 0,
+      Operator::Aces => 1,
+    };
+
+    self.pass.draw(cmd_buf, output, [input], |cmd_buf| {
+      cmd_buf
+        .uniform(&self.exposure, &params.exposure)?
+        .uniform(&self.operator, &operator)?;
+      Ok(())
+    })
+  }
+}
@@ -1 +1,9 @@
-//mod units;
+//! Low-level strongly typed graphics system.
+//!
+//! This crate gathers the piksels crates behind a single dependency: the backend-agnostic API of `piksels-core` is
+//! re-exported at the root (e.g. [`device::Device`]), and the interface backends implement, along with the types they
+//! share with it (pixel formats, pipeline state, etc.), lives in [`backend`]. Features enable the same features of the
+//! underlying crates.
+
+pub use piksels_backend as backend;
+pub use piksels_core::*;